@@ -0,0 +1,23 @@
+use std::process::Command;
+
+// Exposes build metadata to `zen version`; ZEN_GIT_HASH may be set by packagers
+fn main() {
+    let target = std::env::var("TARGET").unwrap_or_default();
+    println!("cargo:rustc-env=ZEN_HOST_TARGET={}", target);
+
+    let hash = std::env::var("ZEN_GIT_HASH").ok().or_else(|| {
+        Command::new("git")
+            .args(["rev-parse", "--short", "HEAD"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| String::from_utf8(output.stdout).ok())
+    });
+    if let Some(hash) = hash {
+        println!("cargo:rustc-env=ZEN_GIT_HASH={}", hash.trim());
+    }
+
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+    println!("cargo:rerun-if-env-changed=ZEN_GIT_HASH");
+}
@@ -1,10 +1,12 @@
+use crate::ast::stmt::Stmt;
 use crate::token::*;
+use crate::types::Type;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub enum StringPart {
     Text(String),
     Variable(String),
-    Expression(String), // For function calls like add(result, result)
+    Expr(Box<Expr>), // Any other expression, e.g. `add(a, b)` or `x + y`
 }
 
 impl StringPart {
@@ -46,6 +48,9 @@ pub enum Expr {
         value: bool,
         token: Token,
     },
+    NullLiteral {
+        token: Token,
+    },
     Identifier {
         name: String,
         token: Token,
@@ -73,6 +78,15 @@ pub enum Expr {
         is_mutable: bool,
         token: Token,
     },
+    Deref {
+        expr: Box<Expr>,
+        token: Token,
+    },
+    Cast {
+        expr: Box<Expr>,
+        target_type: Type,
+        token: Token,
+    },
     FieldAccess {
         object: Box<Expr>,
         field: String,
@@ -83,6 +97,35 @@ pub enum Expr {
         index: Box<Expr>,
         token: Token,
     },
+    ArrayRepeat {
+        value: Box<Expr>,
+        count: usize,
+        token: Token,
+    },
+    ArrayLiteral {
+        elements: Vec<Expr>,
+        token: Token,
+    },
+    /// `match value { pattern => expr, _ => expr }` producing the selected arm's value
+    Match {
+        value: Box<Expr>,
+        arms: Vec<(Expr, Expr)>,
+        default: Option<Box<Expr>>,
+        token: Token,
+    },
+    /// `if condition { then_expr } else { else_expr }` producing the taken branch's value
+    IfExpr {
+        condition: Box<Expr>,
+        then_expr: Box<Expr>,
+        else_expr: Box<Expr>,
+        token: Token,
+    },
+    /// `{ statements; tail }`, which runs its statements and yields the value of `tail`
+    Block {
+        statements: Vec<Stmt>,
+        tail: Box<Expr>,
+        token: Token,
+    },
     StructLiteral {
         struct_name: String,
         fields: Vec<(String, Expr)>,
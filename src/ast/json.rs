@@ -0,0 +1,516 @@
+//! JSON rendering of the AST for external tools.
+//!
+//! Every node is an object whose `kind` names the `Stmt`/`Expr` variant. Nodes that carry
+//! a token also have `line` and `column`; the remaining keys are the variant's fields, with
+//! types written in their source syntax. New keys may be added, but existing ones keep
+//! their meaning.
+
+use crate::ast::expr::{Expr, StringPart};
+use crate::ast::program::Program;
+use crate::ast::stmt::Stmt;
+use crate::token::Token;
+use crate::types::Type;
+
+/// Render a whole program as `{"kind": "Program", "statements": [...]}`
+pub fn program_to_json(program: &Program) -> String {
+    Node::new("Program", None)
+        .raw("statements", stmts(&program.statements))
+        .finish()
+}
+
+pub fn stmt_to_json(stmt: &Stmt) -> String {
+    match stmt {
+        Stmt::VariableDecl {
+            name,
+            type_annotation,
+            initializer,
+            is_mutable,
+            token,
+        } => Node::new("VariableDecl", Some(token))
+            .str("name", name)
+            .raw("type", optional_type(type_annotation.as_ref()))
+            .raw("initializer", optional_expr(initializer.as_ref()))
+            .bool("is_mutable", *is_mutable)
+            .finish(),
+        Stmt::Assignment {
+            target,
+            value,
+            token,
+        } => Node::new("Assignment", Some(token))
+            .raw("target", expr_to_json(target))
+            .raw("value", expr_to_json(value))
+            .finish(),
+        Stmt::FunctionDecl {
+            name,
+            type_params,
+            params,
+            return_type,
+            body,
+            is_public,
+            doc,
+            token,
+        } => Node::new("FunctionDecl", Some(token))
+            .str("name", name)
+            .raw(
+                "type_params",
+                array(type_params.iter().map(|param| string(param))),
+            )
+            .raw("params", typed_names(params))
+            .str("return_type", &return_type.to_string())
+            .bool("is_public", *is_public)
+            .raw("doc", optional_str(doc.as_deref()))
+            .raw("body", stmts(body))
+            .finish(),
+        Stmt::Return { value, token } => Node::new("Return", Some(token))
+            .raw("value", optional_expr(value.as_ref()))
+            .finish(),
+        Stmt::If {
+            condition,
+            then_branch,
+            else_if_branches,
+            else_branch,
+            token,
+        } => {
+            let else_ifs: Vec<String> = else_if_branches
+                .iter()
+                .map(|branch| {
+                    Node::new("ElseIf", Some(&branch.token))
+                        .raw("condition", expr_to_json(&branch.condition))
+                        .raw("body", stmts(&branch.body))
+                        .finish()
+                })
+                .collect();
+            Node::new("If", Some(token))
+                .raw("condition", expr_to_json(condition))
+                .raw("then_branch", stmts(then_branch))
+                .raw("else_if_branches", array(else_ifs))
+                .raw(
+                    "else_branch",
+                    else_branch.as_deref().map_or("null".to_string(), stmts),
+                )
+                .finish()
+        }
+        Stmt::While {
+            condition,
+            body,
+            token,
+        } => Node::new("While", Some(token))
+            .raw("condition", expr_to_json(condition))
+            .raw("body", stmts(body))
+            .finish(),
+        Stmt::Loop { body, token } => Node::new("Loop", Some(token))
+            .raw("body", stmts(body))
+            .finish(),
+        Stmt::For {
+            init,
+            condition,
+            increment,
+            body,
+            token,
+        } => Node::new("For", Some(token))
+            .raw(
+                "init",
+                init.as_deref().map_or("null".to_string(), stmt_to_json),
+            )
+            .raw("condition", optional_expr(condition.as_ref()))
+            .raw("increment", optional_expr(increment.as_ref()))
+            .raw("body", stmts(body))
+            .finish(),
+        Stmt::ForIn {
+            variable,
+            iterable,
+            body,
+            token,
+        } => Node::new("ForIn", Some(token))
+            .str("variable", variable)
+            .raw("iterable", expr_to_json(iterable))
+            .raw("body", stmts(body))
+            .finish(),
+        Stmt::Break { token } => Node::new("Break", Some(token)).finish(),
+        Stmt::Continue { token } => Node::new("Continue", Some(token)).finish(),
+        Stmt::Match {
+            value,
+            arms,
+            default,
+            token,
+        } => {
+            let arms: Vec<String> = arms
+                .iter()
+                .map(|(pattern, body)| {
+                    Node::new("MatchArm", None)
+                        .raw("pattern", expr_to_json(pattern))
+                        .raw("body", stmts(body))
+                        .finish()
+                })
+                .collect();
+            Node::new("Match", Some(token))
+                .raw("value", expr_to_json(value))
+                .raw("arms", array(arms))
+                .raw(
+                    "default",
+                    default.as_deref().map_or("null".to_string(), stmts),
+                )
+                .finish()
+        }
+        Stmt::Use { path, alias, token } => Node::new("Use", Some(token))
+            .raw("path", array(path.iter().map(|segment| string(segment))))
+            .raw("alias", optional_str(alias.as_deref()))
+            .finish(),
+        Stmt::Mod {
+            name,
+            items,
+            is_public,
+            token,
+        } => Node::new("Mod", Some(token))
+            .str("name", name)
+            .bool("is_public", *is_public)
+            .raw("items", stmts(items))
+            .finish(),
+        Stmt::StructDecl {
+            name,
+            fields,
+            derives,
+            is_public,
+            doc,
+            token,
+        } => Node::new("StructDecl", Some(token))
+            .str("name", name)
+            .raw("fields", typed_names(fields))
+            .raw(
+                "derives",
+                array(derives.iter().map(|derive| string(derive))),
+            )
+            .bool("is_public", *is_public)
+            .raw("doc", optional_str(doc.as_deref()))
+            .finish(),
+        Stmt::ImplBlock {
+            struct_name,
+            methods,
+            token,
+        } => Node::new("ImplBlock", Some(token))
+            .str("struct_name", struct_name)
+            .raw("methods", stmts(methods))
+            .finish(),
+        Stmt::ConstDecl {
+            name,
+            type_annotation,
+            initializer,
+            is_public,
+            token,
+        } => Node::new("ConstDecl", Some(token))
+            .str("name", name)
+            .raw("type", optional_type(type_annotation.as_ref()))
+            .raw("initializer", expr_to_json(initializer))
+            .bool("is_public", *is_public)
+            .finish(),
+        Stmt::ExprStmt { expr } => Node::new("ExprStmt", None)
+            .raw("expr", expr_to_json(expr))
+            .finish(),
+        Stmt::Block { statements } => Node::new("Block", None)
+            .raw("statements", stmts(statements))
+            .finish(),
+    }
+}
+
+pub fn expr_to_json(expr: &Expr) -> String {
+    match expr {
+        Expr::IntegerLiteral { value, token } => Node::new("IntegerLiteral", Some(token))
+            .str("value", value)
+            .finish(),
+        Expr::FloatLiteral { value, token } => Node::new("FloatLiteral", Some(token))
+            .raw("value", float(*value))
+            .finish(),
+        Expr::StringLiteral { value, token } => Node::new("StringLiteral", Some(token))
+            .str("value", value)
+            .finish(),
+        Expr::InterpolatedString { parts, token } => {
+            let parts = parts.iter().map(|part| match part {
+                StringPart::Text(text) => Node::new("Text", None).str("value", text).finish(),
+                StringPart::Variable(name) => {
+                    Node::new("Variable", None).str("value", name).finish()
+                }
+                StringPart::Expr(expr) => Node::new("Expr", None)
+                    .raw("value", expr_to_json(expr))
+                    .finish(),
+            });
+            Node::new("InterpolatedString", Some(token))
+                .raw("parts", array(parts))
+                .finish()
+        }
+        Expr::CharLiteral { value, token } => Node::new("CharLiteral", Some(token))
+            .str("value", &value.to_string())
+            .finish(),
+        Expr::BooleanLiteral { value, token } => Node::new("BooleanLiteral", Some(token))
+            .bool("value", *value)
+            .finish(),
+        Expr::NullLiteral { token } => Node::new("NullLiteral", Some(token)).finish(),
+        Expr::Identifier { name, token } => Node::new("Identifier", Some(token))
+            .str("name", name)
+            .finish(),
+        Expr::BinaryOp { left, op, right } => Node::new("BinaryOp", Some(op))
+            .str("op", &op.lexeme)
+            .raw("left", expr_to_json(left))
+            .raw("right", expr_to_json(right))
+            .finish(),
+        Expr::UnaryOp { op, operand } => Node::new("UnaryOp", Some(op))
+            .str("op", &op.lexeme)
+            .raw("operand", expr_to_json(operand))
+            .finish(),
+        Expr::Call {
+            callee,
+            args,
+            token,
+        } => Node::new("Call", Some(token))
+            .raw("callee", expr_to_json(callee))
+            .raw("args", array(args.iter().map(expr_to_json)))
+            .finish(),
+        Expr::OwnershipTransfer { expr, token } => Node::new("OwnershipTransfer", Some(token))
+            .raw("expr", expr_to_json(expr))
+            .finish(),
+        Expr::Borrow {
+            expr,
+            is_mutable,
+            token,
+        } => Node::new("Borrow", Some(token))
+            .bool("is_mutable", *is_mutable)
+            .raw("expr", expr_to_json(expr))
+            .finish(),
+        Expr::Deref { expr, token } => Node::new("Deref", Some(token))
+            .raw("expr", expr_to_json(expr))
+            .finish(),
+        Expr::Cast {
+            expr,
+            target_type,
+            token,
+        } => Node::new("Cast", Some(token))
+            .raw("expr", expr_to_json(expr))
+            .str("target_type", &target_type.to_string())
+            .finish(),
+        Expr::FieldAccess {
+            object,
+            field,
+            token,
+        } => Node::new("FieldAccess", Some(token))
+            .raw("object", expr_to_json(object))
+            .str("field", field)
+            .finish(),
+        Expr::ArrayAccess {
+            array: target,
+            index,
+            token,
+        } => Node::new("ArrayAccess", Some(token))
+            .raw("array", expr_to_json(target))
+            .raw("index", expr_to_json(index))
+            .finish(),
+        Expr::ArrayRepeat {
+            value,
+            count,
+            token,
+        } => Node::new("ArrayRepeat", Some(token))
+            .raw("value", expr_to_json(value))
+            .raw("count", count.to_string())
+            .finish(),
+        Expr::ArrayLiteral { elements, token } => Node::new("ArrayLiteral", Some(token))
+            .raw("elements", array(elements.iter().map(expr_to_json)))
+            .finish(),
+        Expr::Match {
+            value,
+            arms,
+            default,
+            token,
+        } => {
+            let arms = arms.iter().map(|(pattern, arm_value)| {
+                Node::new("MatchArm", None)
+                    .raw("pattern", expr_to_json(pattern))
+                    .raw("value", expr_to_json(arm_value))
+                    .finish()
+            });
+            Node::new("Match", Some(token))
+                .raw("value", expr_to_json(value))
+                .raw("arms", array(arms))
+                .raw("default", optional_expr(default.as_deref()))
+                .finish()
+        }
+        Expr::IfExpr {
+            condition,
+            then_expr,
+            else_expr,
+            token,
+        } => Node::new("IfExpr", Some(token))
+            .raw("condition", expr_to_json(condition))
+            .raw("then", expr_to_json(then_expr))
+            .raw("else", expr_to_json(else_expr))
+            .finish(),
+        Expr::Block {
+            statements,
+            tail,
+            token,
+        } => Node::new("Block", Some(token))
+            .raw("statements", stmts(statements))
+            .raw("tail", expr_to_json(tail))
+            .finish(),
+        Expr::StructLiteral {
+            struct_name,
+            fields,
+            token,
+        } => {
+            let fields = fields.iter().map(|(name, value)| {
+                Node::new("FieldInit", None)
+                    .str("name", name)
+                    .raw("value", expr_to_json(value))
+                    .finish()
+            });
+            Node::new("StructLiteral", Some(token))
+                .str("struct_name", struct_name)
+                .raw("fields", array(fields))
+                .finish()
+        }
+        Expr::ModuleAccess {
+            module,
+            item,
+            token,
+        } => Node::new("ModuleAccess", Some(token))
+            .str("module", module)
+            .str("item", item)
+            .finish(),
+    }
+}
+
+// Builds one JSON object, keeping keys in insertion order
+struct Node {
+    fields: Vec<(&'static str, String)>,
+}
+
+impl Node {
+    fn new(kind: &str, token: Option<&Token>) -> Self {
+        let mut node = Node { fields: Vec::new() }.str("kind", kind);
+        if let Some(token) = token {
+            node = node
+                .raw("line", token.line.to_string())
+                .raw("column", token.column.to_string());
+        }
+        node
+    }
+
+    fn str(self, key: &'static str, value: &str) -> Self {
+        self.raw(key, string(value))
+    }
+
+    fn bool(self, key: &'static str, value: bool) -> Self {
+        self.raw(key, value.to_string())
+    }
+
+    fn raw(mut self, key: &'static str, json: String) -> Self {
+        self.fields.push((key, json));
+        self
+    }
+
+    fn finish(self) -> String {
+        let fields: Vec<String> = self
+            .fields
+            .into_iter()
+            .map(|(key, value)| format!("\"{}\":{}", key, value))
+            .collect();
+        format!("{{{}}}", fields.join(","))
+    }
+}
+
+fn stmts(statements: &[Stmt]) -> String {
+    array(statements.iter().map(stmt_to_json))
+}
+
+fn array(items: impl IntoIterator<Item = String>) -> String {
+    format!("[{}]", items.into_iter().collect::<Vec<_>>().join(","))
+}
+
+fn typed_names(names: &[(String, Type)]) -> String {
+    array(names.iter().map(|(name, ty)| {
+        format!(
+            "{{\"name\":{},\"type\":{}}}",
+            string(name),
+            string(&ty.to_string())
+        )
+    }))
+}
+
+fn optional_expr(expr: Option<&Expr>) -> String {
+    expr.map_or("null".to_string(), expr_to_json)
+}
+
+fn optional_type(ty: Option<&Type>) -> String {
+    ty.map_or("null".to_string(), |ty| string(&ty.to_string()))
+}
+
+fn optional_str(text: Option<&str>) -> String {
+    text.map_or("null".to_string(), string)
+}
+
+// JSON has no NaN or infinity, so those become null
+fn float(value: f64) -> String {
+    if value.is_finite() {
+        format!("{:?}", value)
+    } else {
+        "null".to_string()
+    }
+}
+
+/// `text` as a JSON string literal
+pub fn string(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + 2);
+    escaped.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::lexer::Lexer;
+    use crate::parser::parser::Parser;
+
+    fn to_json(code: &str) -> serde_json::Value {
+        let tokens = Lexer::new(code).tokenize().unwrap();
+        let program = Parser::new(tokens).parse_strict().unwrap();
+        serde_json::from_str(&program_to_json(&program)).unwrap()
+    }
+
+    #[test]
+    fn test_root_is_function_declaration() {
+        let json = to_json("fn add(a: i32, b: &mut i32) -> i32 {\n    return a + *b\n}");
+
+        assert_eq!(json["kind"], "Program");
+        let function = &json["statements"][0];
+        assert_eq!(function["kind"], "FunctionDecl");
+        assert_eq!(function["name"], "add");
+        assert_eq!(function["params"][1]["type"], "&mut i32");
+        assert_eq!(function["return_type"], "i32");
+
+        let returned = &function["body"][0]["value"];
+        assert_eq!(returned["kind"], "BinaryOp");
+        assert_eq!(returned["op"], "+");
+        assert_eq!(returned["line"], 2);
+        assert_eq!(returned["right"]["kind"], "Deref");
+    }
+
+    #[test]
+    fn test_strings_are_escaped() {
+        let json = to_json("let s = \"say \\\"hi\\\"\"");
+
+        assert_eq!(
+            json["statements"][0]["initializer"]["kind"],
+            "StringLiteral"
+        );
+        assert_eq!(json["statements"][0]["initializer"]["value"], "say \"hi\"");
+    }
+}
@@ -1,4 +1,5 @@
 pub mod expr;
+pub mod json;
 pub mod program;
 pub mod stmt;
 
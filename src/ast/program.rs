@@ -15,6 +15,14 @@ impl Program {
     pub fn add_statement(&mut self, stmt: Stmt) {
         self.statements.push(stmt);
     }
+
+    /// Top-level items, with the methods of each `impl` block in place of the block
+    pub fn items(&self) -> impl Iterator<Item = &Stmt> {
+        self.statements.iter().flat_map(|stmt| match stmt {
+            Stmt::ImplBlock { methods, .. } => methods.as_slice(),
+            _ => std::slice::from_ref(stmt),
+        })
+    }
 }
 
 impl Default for Program {
@@ -1,5 +1,6 @@
 use crate::ast::expr::*;
 use crate::token::*;
+use crate::types::Type;
 
 #[derive(Debug, Clone)]
 pub struct ElseIfBranch {
@@ -8,11 +9,14 @@ pub struct ElseIfBranch {
     pub token: Token,
 }
 
+/// Builtins that never return to their caller
+pub const DIVERGING_BUILTINS: &[&str] = &["exit", "abort", "todo"];
+
 #[derive(Debug, Clone)]
 pub enum Stmt {
     VariableDecl {
         name: String,
-        type_annotation: Option<String>,
+        type_annotation: Option<Type>,
         initializer: Option<Expr>,
         is_mutable: bool,
         token: Token,
@@ -24,10 +28,13 @@ pub enum Stmt {
     },
     FunctionDecl {
         name: String,
-        params: Vec<(String, String)>,
-        return_type: String,
+        /// Names of the type parameters, e.g. `T` in `fn id<T>(x: T) -> T`
+        type_params: Vec<String>,
+        params: Vec<(String, Type)>,
+        return_type: Type,
         body: Vec<Stmt>,
         is_public: bool,
+        doc: Option<String>,
         token: Token,
     },
     Return {
@@ -46,6 +53,11 @@ pub enum Stmt {
         body: Vec<Stmt>,
         token: Token,
     },
+    /// `loop { ... }`, repeated until a `break` or `return` leaves it
+    Loop {
+        body: Vec<Stmt>,
+        token: Token,
+    },
     For {
         init: Option<Box<Stmt>>,
         condition: Option<Expr>,
@@ -53,6 +65,20 @@ pub enum Stmt {
         body: Vec<Stmt>,
         token: Token,
     },
+    /// `for x in array { ... }`, binding `x` to each element in turn
+    ForIn {
+        variable: String,
+        iterable: Expr,
+        body: Vec<Stmt>,
+        token: Token,
+    },
+    /// Leaves the innermost loop
+    Break {
+        token: Token,
+    },
+    Continue {
+        token: Token,
+    },
     Match {
         value: Expr,
         arms: Vec<(Expr, Vec<Stmt>)>,
@@ -72,13 +98,23 @@ pub enum Stmt {
     },
     StructDecl {
         name: String,
-        fields: Vec<(String, String)>,
+        fields: Vec<(String, Type)>,
+        /// Traits named in `#[derive(...)]`
+        derives: Vec<String>,
         is_public: bool,
+        doc: Option<String>,
+        token: Token,
+    },
+    /// `impl Name { fn method(self, ...) -> T { ... } }`; each method is kept as the
+    /// function it compiles to, `Name_method`, whose first parameter `self` is a `&Name`
+    ImplBlock {
+        struct_name: String,
+        methods: Vec<Stmt>,
         token: Token,
     },
     ConstDecl {
         name: String,
-        type_annotation: Option<String>,
+        type_annotation: Option<Type>,
         initializer: Expr,
         is_public: bool,
         token: Token,
@@ -90,3 +126,186 @@ pub enum Stmt {
         statements: Vec<Stmt>,
     },
 }
+
+impl Stmt {
+    /// Name and token of the builtin if this statement is a call that never returns
+    pub fn diverging_call(&self) -> Option<(&str, &Token)> {
+        if let Stmt::ExprStmt {
+            expr: Expr::Call { callee, .. },
+        } = self
+        {
+            if let Expr::Identifier { name, token } = callee.as_ref() {
+                if DIVERGING_BUILTINS.contains(&name.as_str()) {
+                    return Some((name, token));
+                }
+            }
+        }
+        None
+    }
+
+    /// Whether control never falls through past this statement: a `return`, a diverging
+    /// call, a `loop` with no `break` out of it, or an `if` with an `else` (or a block)
+    /// where every path ends that way
+    pub fn always_returns(&self) -> bool {
+        match self {
+            Stmt::Return { .. } => true,
+            Stmt::Loop { body, .. } => !Stmt::body_breaks(body),
+            Stmt::If {
+                then_branch,
+                else_if_branches,
+                else_branch: Some(else_branch),
+                ..
+            } => {
+                Stmt::body_always_returns(then_branch)
+                    && else_if_branches
+                        .iter()
+                        .all(|branch| Stmt::body_always_returns(&branch.body))
+                    && Stmt::body_always_returns(else_branch)
+            }
+            Stmt::Block { statements } => Stmt::body_always_returns(statements),
+            _ => self.diverging_call().is_some(),
+        }
+    }
+
+    pub fn body_always_returns(body: &[Stmt]) -> bool {
+        body.iter().any(Stmt::always_returns)
+    }
+
+    // Whether a `break` in `body` leaves the loop it belongs to; one inside a nested loop
+    // only leaves that loop
+    fn body_breaks(body: &[Stmt]) -> bool {
+        body.iter().any(|stmt| match stmt {
+            Stmt::Break { .. } => true,
+            Stmt::If {
+                then_branch,
+                else_if_branches,
+                else_branch,
+                ..
+            } => {
+                Stmt::body_breaks(then_branch)
+                    || else_if_branches
+                        .iter()
+                        .any(|branch| Stmt::body_breaks(&branch.body))
+                    || else_branch.as_deref().is_some_and(Stmt::body_breaks)
+            }
+            Stmt::Match { arms, default, .. } => {
+                arms.iter().any(|(_, body)| Stmt::body_breaks(body))
+                    || default.as_deref().is_some_and(Stmt::body_breaks)
+            }
+            Stmt::Block { statements } => Stmt::body_breaks(statements),
+            _ => false,
+        })
+    }
+
+    /// The value this statement leaves when it ends a body: an expression statement's
+    /// expression, or an `if` with an `else` (or a `match` with a `_` arm) as the
+    /// expression form of it, when every branch ends in such a value
+    pub fn tail_value(&self) -> Option<Expr> {
+        match self {
+            Stmt::ExprStmt { expr } => Some(expr.clone()),
+            Stmt::If {
+                condition,
+                then_branch,
+                else_if_branches,
+                else_branch: Some(else_branch),
+                token,
+            } => {
+                let mut else_expr = Stmt::body_value(else_branch, token)?;
+                for branch in else_if_branches.iter().rev() {
+                    else_expr = Expr::IfExpr {
+                        condition: Box::new(branch.condition.clone()),
+                        then_expr: Box::new(Stmt::body_value(&branch.body, &branch.token)?),
+                        else_expr: Box::new(else_expr),
+                        token: branch.token.clone(),
+                    };
+                }
+                Some(Expr::IfExpr {
+                    condition: Box::new(condition.clone()),
+                    then_expr: Box::new(Stmt::body_value(then_branch, token)?),
+                    else_expr: Box::new(else_expr),
+                    token: token.clone(),
+                })
+            }
+            Stmt::Match {
+                value,
+                arms,
+                default: Some(default),
+                token,
+            } => {
+                let arms = arms
+                    .iter()
+                    .map(|(pattern, body)| Some((pattern.clone(), Stmt::body_value(body, token)?)))
+                    .collect::<Option<Vec<_>>>()?;
+                Some(Expr::Match {
+                    value: Box::new(value.clone()),
+                    arms,
+                    default: Some(Box::new(Stmt::body_value(default, token)?)),
+                    token: token.clone(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    // A branch body as the block expression yielding its last statement's value
+    fn body_value(body: &[Stmt], token: &Token) -> Option<Expr> {
+        let (last, statements) = body.split_last()?;
+        let tail = last.tail_value()?;
+        if statements.is_empty() {
+            return Some(tail);
+        }
+        Some(Expr::Block {
+            statements: statements.to_vec(),
+            tail: Box::new(tail),
+            token: token.clone(),
+        })
+    }
+
+    /// Whether the statement after this one can never run: this one always returns, or
+    /// leaves the enclosing loop body through `break` or `continue` on every path
+    pub fn ends_block(&self) -> bool {
+        match self {
+            Stmt::Break { .. } | Stmt::Continue { .. } => true,
+            Stmt::If {
+                then_branch,
+                else_if_branches,
+                else_branch: Some(else_branch),
+                ..
+            } => {
+                Stmt::body_ends_block(then_branch)
+                    && else_if_branches
+                        .iter()
+                        .all(|branch| Stmt::body_ends_block(&branch.body))
+                    && Stmt::body_ends_block(else_branch)
+            }
+            Stmt::Block { statements } => Stmt::body_ends_block(statements),
+            _ => self.always_returns(),
+        }
+    }
+
+    pub fn body_ends_block(body: &[Stmt]) -> bool {
+        body.iter().any(Stmt::ends_block)
+    }
+
+    /// A bare `name = value` for-loop initializer reuses `name` when it is already
+    /// defined and otherwise declares a new mutable loop variable
+    pub fn resolve_for_init(&self, is_defined: impl Fn(&str) -> bool) -> Stmt {
+        if let Stmt::Assignment {
+            target: Expr::Identifier { name, .. },
+            value,
+            token,
+        } = self
+        {
+            if !is_defined(name) {
+                return Stmt::VariableDecl {
+                    name: name.clone(),
+                    type_annotation: None,
+                    initializer: Some(value.clone()),
+                    is_mutable: true,
+                    token: token.clone(),
+                };
+            }
+        }
+        self.clone()
+    }
+}
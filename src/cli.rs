@@ -1,33 +1,150 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+
+use crate::compiler::{
+    CompilationStats, Compiler, Emit, DEFAULT_OPT_LEVEL, MAX_OPT_LEVEL, STDIN_INPUT,
+};
 
 #[derive(Parser)]
 #[command(name = "zen")]
-#[command(about = "Zen Programming Language Compiler", long_about = None)]
+#[command(version, about = "Zen Programming Language Compiler", long_about = None)]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+    /// Treat every warning as an error
+    #[arg(long, alias = "Werror", global = true)]
+    pub warnings_as_errors: bool,
+    /// Abort with a message on out-of-range array indices
+    #[arg(long, global = true)]
+    pub debug_bounds: bool,
+    /// llc command to run (defaults to $ZEN_LLC, then `llc`)
+    #[arg(long, global = true)]
+    pub llc: Option<String>,
+    /// C compiler to link with (defaults to $ZEN_CC, then gcc or clang)
+    #[arg(long, global = true)]
+    pub cc: Option<String>,
 }
 
 #[derive(Subcommand)]
 pub enum Commands {
     /// Compile a Zen file to native binary
     Compile {
-        /// Input Zen file
-        input: String,
-        /// Output file name (optional)
+        /// Input Zen file (`-` reads from stdin)
+        #[arg(required_unless_present = "stdin")]
+        input: Option<String>,
+        /// Output file name (defaults to the input name, or `a.out` for stdin)
         #[arg(short, long)]
         output: Option<String>,
+        /// Read source from standard input
+        #[arg(long)]
+        stdin: bool,
+        /// Function to start the program at (defaults to `main`)
+        #[arg(long)]
+        entry: Option<String>,
+        /// Stop after writing this output instead of linking a binary
+        #[arg(long, value_enum, default_value_t = Emit::Link)]
+        emit: Emit,
+        /// Optimization level for llc and the linker (0-3)
+        #[arg(short = 'O', long, default_value_t = DEFAULT_OPT_LEVEL, value_parser = opt_level_parser())]
+        opt_level: u8,
+        /// Target triple to build for, such as x86_64-pc-windows-gnu (defaults to the host)
+        #[arg(long)]
+        target: Option<String>,
+        /// Keep the intermediate .ll and .o files and print their paths
+        #[arg(long)]
+        keep_temps: bool,
+        /// Write compilation statistics as JSON to this file (`-` for stdout)
+        #[arg(long, value_name = "PATH")]
+        stats_json: Option<String>,
     },
     /// Compile and run a Zen file
     Run {
+        /// Input Zen file (`-` reads from stdin)
+        input: String,
+        /// Function to start the program at (defaults to `main`)
+        #[arg(long)]
+        entry: Option<String>,
+        /// Optimization level for llc and the linker (0-3)
+        #[arg(short = 'O', long, default_value_t = DEFAULT_OPT_LEVEL, value_parser = opt_level_parser())]
+        opt_level: u8,
+        /// Target triple to build for; the program only runs when it matches the host
+        #[arg(long)]
+        target: Option<String>,
+        /// Arguments for the program, after `--`
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+    /// Type and ownership check a Zen file without compiling it
+    Check {
+        /// Input Zen file
+        input: String,
+        /// Function to start the program at (defaults to `main`)
+        #[arg(long)]
+        entry: Option<String>,
+    },
+    /// Compile once and time repeated runs of a Zen file
+    Bench {
         /// Input Zen file
         input: String,
+        /// Number of times to run the program
+        #[arg(short = 'n', long, default_value_t = 10)]
+        iterations: usize,
     },
     /// Show tokens from a Zen file
     Tokenize {
         /// Input Zen file
         input: String,
     },
+    /// Print the syntax tree of a Zen file
+    Ast {
+        /// Input Zen file
+        input: String,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = AstFormat::Text)]
+        format: AstFormat,
+    },
+    /// Generate markdown documentation for public functions and structs
+    Doc {
+        /// Input Zen file
+        input: String,
+        /// Output markdown file (defaults to the input name with a .md extension)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Print the compiler version and build information
+    Version,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+pub enum AstFormat {
+    /// Rust debug tree
+    Text,
+    /// JSON with node kinds and positions
+    Json,
+}
+
+fn opt_level_parser() -> clap::builder::RangedI64ValueParser<u8> {
+    clap::value_parser!(u8).range(0..=i64::from(MAX_OPT_LEVEL))
+}
+
+// Write `stats` as JSON to `path`, or to stdout for `-`
+fn write_stats_json(path: &str, stats: &CompilationStats) -> anyhow::Result<()> {
+    let json = stats.to_json();
+    if path == "-" {
+        println!("{}", json);
+        return Ok(());
+    }
+    std::fs::write(path, json + "\n")
+        .map_err(|e| anyhow::anyhow!("Failed to write stats to '{}': {}", path, e))
+}
+
+/// Version, git commit (when known at build time) and host target triple
+pub fn version_info() -> String {
+    let mut info = format!("zen {}", env!("CARGO_PKG_VERSION"));
+    if let Some(hash) = option_env!("ZEN_GIT_HASH") {
+        info.push_str(&format!(" ({})", hash));
+    }
+    info.push_str(&format!("\nhost: {}", env!("ZEN_HOST_TARGET")));
+    info
 }
 
 impl Cli {
@@ -40,16 +157,46 @@ impl Cli {
         println!("Commands:");
         println!("  compile   Compile a Zen file to native binary");
         println!("  run       Compile and run a Zen file");
+        println!("  check     Type and ownership check without compiling");
+        println!("  bench     Time repeated runs of a compiled Zen file");
         println!("  tokenize  Show tokens from a Zen file");
+        println!("  ast       Print the syntax tree (--format text|json)");
+        println!("  doc       Generate markdown docs for public items");
+        println!("  version   Print version and build information");
         println!();
         println!("Options:");
         println!("  -o, --output <file>  Specify output file");
+        println!("      --warnings-as-errors  Fail compilation on any warning (alias: --Werror)");
+        println!("      --debug-bounds   Abort on out-of-range array indices at runtime");
+        println!("      --stdin          Read source from stdin (same as `-` as input)");
+        println!("      --entry <name>   Start the program at <name> instead of main");
+        println!("      --emit <kind>    Stop at llvm-ir, asm or obj instead of linking");
+        println!("  -O, --opt-level <n>  Optimization level 0-3 (default: 2)");
+        println!("      --target <triple> Build for another target (e.g. x86_64-pc-windows-gnu)");
+        println!("      --keep-temps     Keep the intermediate .ll and .o files");
+        println!("      --stats-json <path> Write compile statistics as JSON (`-` for stdout)");
+        println!("      --llc <cmd>      llc to run (default: $ZEN_LLC, then llc)");
+        println!(
+            "      --cc <cmd>       C compiler to link with (default: $ZEN_CC, then gcc or clang)"
+        );
+        println!("  -n, --iterations <n> Number of benchmark runs (default: 10)");
+        println!("  -V, --version        Print version information");
         println!();
         println!("Examples:");
         println!("  zen compile examples/hello.zen");
         println!("  zen compile examples/hello.zen -o /tmp/hello");
+        println!("  cat hello.zen | zen compile - -o /tmp/hello");
+        println!("  cat hello.zen | zen run -");
+        println!("  zen compile examples/hello.zen --emit llvm-ir");
+        println!("  zen compile examples/hello.zen --target x86_64-pc-windows-gnu");
         println!("  zen run examples/hello.zen");
+        println!("  zen run tool.zen --entry start");
+        println!("  zen run tool.zen -- input.txt --flag");
+        println!("  zen check examples/hello.zen");
+        println!("  zen bench examples/algorithms.zen -n 20");
         println!("  zen tokenize input.zen");
+        println!("  zen ast input.zen --format json");
+        println!("  zen doc examples/math.zen -o math.md");
     }
 
     pub fn from_args(args: Vec<String>) -> Result<Self, String> {
@@ -62,11 +209,168 @@ impl Cli {
 
     pub fn run(self) -> anyhow::Result<()> {
         match self.command {
-            Commands::Compile { input, output } => {
-                crate::compiler::Compiler::compile(&input, output.as_deref())
+            Commands::Compile {
+                input,
+                output,
+                stdin,
+                entry,
+                emit,
+                opt_level,
+                target,
+                keep_temps,
+                stats_json,
+            } => {
+                if stats_json.is_some() && emit != Emit::Link {
+                    anyhow::bail!("--stats-json needs a linked binary; drop --emit");
+                }
+                // The compile log would corrupt statistics written to stdout
+                let verbose = stats_json.as_deref() != Some("-");
+                let mut compiler = Compiler::new()
+                    .with_verbose(verbose)
+                    .with_warnings_as_errors(self.warnings_as_errors)
+                    .with_debug_bounds(self.debug_bounds)
+                    .with_entry(entry)
+                    .with_emit(emit)
+                    .with_opt_level(opt_level)
+                    .with_target(target)
+                    .with_llc(self.llc)
+                    .with_cc(self.cc)
+                    .with_keep_temps(keep_temps);
+                match input.as_deref() {
+                    Some(input) if !stdin && input != STDIN_INPUT => {
+                        compiler.compile_file(input, output.as_deref())?
+                    }
+                    _ => compiler.compile_stdin(output.as_deref())?,
+                }
+                match (stats_json, compiler.get_stats()) {
+                    (Some(path), Some(stats)) => write_stats_json(&path, stats),
+                    _ => Ok(()),
+                }
+            }
+            Commands::Run {
+                input,
+                entry,
+                opt_level,
+                target,
+                args,
+            } => {
+                let code = Compiler::new()
+                    .with_warnings_as_errors(self.warnings_as_errors)
+                    .with_debug_bounds(self.debug_bounds)
+                    .with_entry(entry)
+                    .with_opt_level(opt_level)
+                    .with_target(target)
+                    .with_llc(self.llc)
+                    .with_cc(self.cc)
+                    .with_run_args(args)
+                    .run_file(&input)?;
+                // Exit like the program did, so `zen run` is transparent to scripts
+                if code != 0 {
+                    std::process::exit(code);
+                }
+                Ok(())
+            }
+            Commands::Check { input, entry } => {
+                Compiler::new()
+                    .with_warnings_as_errors(self.warnings_as_errors)
+                    .with_entry(entry)
+                    .check(&input)?;
+                println!("success: checks passed");
+                Ok(())
+            }
+            Commands::Bench { input, iterations } => Compiler::new()
+                .with_warnings_as_errors(self.warnings_as_errors)
+                .with_debug_bounds(self.debug_bounds)
+                .with_llc(self.llc)
+                .with_cc(self.cc)
+                .bench(&input, iterations),
+            Commands::Tokenize { input } => Compiler::tokenize(&input),
+            Commands::Ast { input, format } => Compiler::ast(&input, format == AstFormat::Json),
+            Commands::Doc { input, output } => {
+                let path = Compiler::doc(&input, output.as_deref())?;
+                println!("Wrote documentation to {}", path.display());
+                Ok(())
+            }
+            Commands::Version => {
+                println!("{}", version_info());
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_version_matches_crate_version() {
+        let info = version_info();
+        let first_line = info.lines().next().unwrap();
+
+        assert!(first_line.starts_with(&format!("zen {}", env!("CARGO_PKG_VERSION"))));
+        assert!(info.contains("\nhost: "));
+        assert_eq!(
+            Cli::command().get_version(),
+            Some(env!("CARGO_PKG_VERSION"))
+        );
+    }
+
+    #[test]
+    fn test_version_subcommand_parses() {
+        let cli = Cli::from_args(vec!["zen".to_string(), "version".to_string()]).unwrap();
+        assert!(matches!(cli.command, Commands::Version));
+    }
+
+    #[test]
+    fn test_opt_level_flag_is_validated() {
+        let parse =
+            |args: &[&str]| Cli::from_args(args.iter().map(|arg| arg.to_string()).collect());
+
+        let cli = parse(&["zen", "compile", "main.zen", "-O0"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Compile { opt_level: 0, .. }
+        ));
+        let cli = parse(&["zen", "run", "main.zen", "--opt-level", "3"]).unwrap();
+        assert!(matches!(cli.command, Commands::Run { opt_level: 3, .. }));
+
+        let Err(err) = parse(&["zen", "compile", "main.zen", "-O5"]) else {
+            panic!("-O5 should be rejected");
+        };
+        assert!(err.contains("5 is not in 0..=3"), "{}", err);
+    }
+
+    #[test]
+    fn test_run_forwards_arguments_after_double_dash() {
+        let args = ["zen", "run", "main.zen", "-O1", "--", "first", "--flag"];
+        let cli = Cli::from_args(args.iter().map(|arg| arg.to_string()).collect()).unwrap();
+        match cli.command {
+            Commands::Run {
+                input,
+                opt_level,
+                args,
+                ..
+            } => {
+                assert_eq!(input, "main.zen");
+                assert_eq!(opt_level, 1);
+                assert_eq!(args, ["first", "--flag"]);
             }
-            Commands::Run { input } => crate::compiler::Compiler::run(&input),
-            Commands::Tokenize { input } => crate::compiler::Compiler::tokenize(&input),
+            _ => panic!("Expected the run command"),
         }
     }
+
+    #[test]
+    fn test_ast_format_flag_parses() {
+        let args = ["zen", "ast", "main.zen", "--format", "json"];
+        let cli = Cli::from_args(args.iter().map(|arg| arg.to_string()).collect()).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Ast {
+                format: AstFormat::Json,
+                ..
+            }
+        ));
+    }
 }
@@ -1,55 +1,153 @@
 use crate::ast::expr::Expr;
 use crate::ast::stmt::Stmt;
+use crate::codegen::constant::Constant;
 use crate::codegen::ir::StringGenerator;
-use crate::token::TokenType;
+use crate::codegen::monomorphize::{instance_name, specialize};
+use crate::const_fold::fold_program;
+use crate::error::ZenError;
+use crate::token::{Token, TokenType};
+use crate::types::{FloatKind, IntKind, Type};
+use std::cell::RefCell;
 use std::collections::HashMap;
 
+// printf format for a failed `--debug-bounds` check, given the index and the length
+const BOUNDS_MESSAGE: &str = "index %d out of bounds for array of length %d\n";
+
+// Written to stderr when a null pointer is dereferenced
+const NULL_DEREF_MESSAGE: &str = "null pointer dereference\n";
+
+// Written to stderr when `todo()` is reached
+const TODO_MESSAGE: &str = "not yet implemented\n";
+
+// printf format for a failed `assert_eq`, given the line and both values; `{}` is the
+// conversion for the compared type
+const ASSERT_EQ_MESSAGE: &str =
+    "assertion failed at line %d: left != right\n  left: {}\n right: {}\n";
+
+// Branch targets for loop control statements inside the innermost loop
+#[derive(Debug, Clone)]
+struct LoopLabels {
+    continue_label: String,
+    break_label: String,
+}
+
 #[derive(Default)]
 pub struct CodeGenerator {
-    functions: HashMap<String, (Vec<String>, String)>,
-    variables: HashMap<String, (String, bool, usize)>,
-    structs: HashMap<String, Vec<(String, String)>>, // struct_name -> [(field_name, field_type)]
+    functions: HashMap<String, (Vec<Type>, Type)>,
+    variables: HashMap<String, (Type, bool, usize)>,
+    // Module-level values stored in `@NAME` globals, visible wherever no local shadows them
+    globals: HashMap<String, Type>,
+    // Values of the top-level `const`s, for folding constants that refer to them
+    constants: HashMap<String, Constant>,
+    structs: HashMap<String, Vec<(String, Type)>>, // struct_name -> [(field_name, field_type)]
     current_function: Option<String>,
     counter: usize,
     label_counter: usize,
     string_gen: StringGenerator,
     last_register: Option<usize>,
+    loop_stack: Vec<LoopLabels>,
+    errors: Vec<ZenError>,
+    debug_bounds: bool,
+    // Conversions used by `assert_eq` failure messages, one format global each
+    assert_formats: Vec<&'static str>,
+    // printf formats of interpolated strings, emitted as `@interp_fmt.N`
+    interpolation_formats: Vec<String>,
+    // Function the C `main` calls, when it is not `main` itself
+    entry: Option<String>,
+    // Generic function declarations, instantiated on demand for each set of type arguments
+    generic_functions: HashMap<String, Stmt>,
+    pending_instances: Vec<Stmt>,
+    // Locals of block expressions whose type is inferred before the block is generated
+    block_locals: RefCell<Vec<(String, Type)>>,
 }
 
-const VOID_TYPE: &str = "void";
-const I32_TYPE: &str = "i32";
-
 impl CodeGenerator {
     pub fn new() -> Self {
         Self {
             functions: HashMap::new(),
             variables: HashMap::new(),
+            globals: HashMap::new(),
+            constants: HashMap::new(),
             structs: HashMap::new(),
             current_function: None,
             counter: 0,
             label_counter: 0,
             string_gen: StringGenerator::new(),
             last_register: None,
+            loop_stack: Vec::new(),
+            errors: Vec::new(),
+            debug_bounds: false,
+            assert_formats: Vec::new(),
+            interpolation_formats: Vec::new(),
+            entry: None,
+            generic_functions: HashMap::new(),
+            pending_instances: Vec::new(),
+            block_locals: RefCell::new(Vec::new()),
         }
     }
 
-    pub fn generate(&mut self, program: &crate::ast::program::Program) -> String {
+    /// Emit a runtime check that aborts on out-of-range indices into fixed-size arrays
+    pub fn with_debug_bounds(mut self, enabled: bool) -> Self {
+        self.debug_bounds = enabled;
+        self
+    }
+
+    /// Make the program start at `entry` instead of `main`
+    pub fn with_entry(mut self, entry: Option<String>) -> Self {
+        self.entry = entry.filter(|name| name != "main");
+        self
+    }
+
+    pub fn generate(&mut self, program: &crate::ast::program::Program) -> Result<String, ZenError> {
+        // Operators on literals are computed here rather than at run time
+        let program = &fold_program(program);
         let mut ir = String::new();
 
         ir.push_str("declare i32 @puts(i8*)\n");
         ir.push_str("declare i32 @printf(i8*, ...)\n");
         ir.push_str("declare i32 @sprintf(i8*, i8*, ...)\n");
+        ir.push_str("declare void @exit(i32) noreturn\n");
+        ir.push_str("declare void @abort() noreturn\n");
+        ir.push_str("declare i32 @fflush(i8*)\n");
+        ir.push_str("declare i64 @write(i32, i8*, i64)\n");
+        ir.push_str("declare i32 @strcmp(i8*, i8*)\n");
+        ir.push_str("declare double @llvm.pow.f64(double, double)\n");
+        ir.push_str(&format!(
+            "@todo_msg = private unnamed_addr constant [{} x i8] c\"{}\"\n",
+            TODO_MESSAGE.len() + 1,
+            TODO_MESSAGE.replace('\n', "\\0A") + "\\00"
+        ));
+        ir.push_str(&format!(
+            "@null_deref_msg = private unnamed_addr constant [{} x i8] c\"{}\"\n",
+            NULL_DEREF_MESSAGE.len() + 1,
+            NULL_DEREF_MESSAGE.replace('\n', "\\0A") + "\\00"
+        ));
+        if self.debug_bounds {
+            ir.push_str(&format!(
+                "@bounds_fmt = private unnamed_addr constant [{} x i8] c\"{}\"\n",
+                BOUNDS_MESSAGE.len() + 1,
+                BOUNDS_MESSAGE.replace('\n', "\\0A") + "\\00"
+            ));
+        }
         ir.push_str("@int_fmt = private unnamed_addr constant [4 x i8] c\"%d\\0A\\00\"\n");
+        ir.push_str("@int64_fmt = private unnamed_addr constant [6 x i8] c\"%lld\\0A\\00\"\n");
+        ir.push_str("@uint_fmt = private unnamed_addr constant [4 x i8] c\"%u\\0A\\00\"\n");
+        ir.push_str("@uint64_fmt = private unnamed_addr constant [6 x i8] c\"%llu\\0A\\00\"\n");
         ir.push_str("@int_fmt_no_nl = private unnamed_addr constant [3 x i8] c\"%d\\00\"\n");
+        ir.push_str("@char_fmt = private unnamed_addr constant [4 x i8] c\"%c\\0A\\00\"\n");
+        ir.push_str("@char_fmt_no_nl = private unnamed_addr constant [3 x i8] c\"%c\\00\"\n");
         ir.push_str("@float_fmt = private unnamed_addr constant [4 x i8] c\"%f\\0A\\00\"\n");
         ir.push_str("@float_fmt_no_nl = private unnamed_addr constant [3 x i8] c\"%f\\00\"\n\n");
 
-        for stmt in &program.statements {
+        for stmt in program.items() {
             self.register_functions(stmt);
             self.register_structs(stmt);
         }
 
-        for stmt in &program.statements {
+        // `println` of a bool selects one of these
+        self.string_gen.add_string("true");
+        self.string_gen.add_string("false");
+        for stmt in program.items() {
             self.string_gen.generate_strings(stmt);
         }
 
@@ -72,11 +170,249 @@ impl CodeGenerator {
         // Generate struct type definitions
         self.generate_struct_types(&mut ir);
 
-        for stmt in &program.statements {
+        for stmt in program.items() {
+            if let Stmt::ConstDecl {
+                name,
+                type_annotation: Some(const_type),
+                initializer,
+                ..
+            } = stmt
+            {
+                self.generate_constant(name, const_type, initializer, &mut ir);
+            }
+        }
+        let global_decls: Vec<&Stmt> = program
+            .items()
+            .filter(|stmt| matches!(stmt, Stmt::VariableDecl { .. }))
+            .collect();
+        self.generate_globals(&global_decls, &mut ir);
+
+        for stmt in program.items() {
             self.generate_statement(stmt, &mut ir);
         }
+        // Instances may call further generic functions, queueing more instances
+        while !self.pending_instances.is_empty() {
+            for instance in std::mem::take(&mut self.pending_instances) {
+                self.generate_statement(&instance, &mut ir);
+            }
+        }
+        self.generate_entry_shim(&mut ir);
+        for (i, conversion) in self.assert_formats.iter().enumerate() {
+            let message = ASSERT_EQ_MESSAGE.replace("{}", conversion);
+            ir.push_str(&format!(
+                "@assert_eq_fmt.{} = private unnamed_addr constant [{} x i8] c\"{}\"\n",
+                i,
+                message.len() + 1,
+                message.replace('\n', "\\0A") + "\\00"
+            ));
+        }
+
+        for (i, format) in self.interpolation_formats.iter().enumerate() {
+            ir.push_str(&format!(
+                "@interp_fmt.{} = private unnamed_addr constant [{} x i8] c\"{}\\00\"\n",
+                i,
+                format.len() + 1,
+                self.escape_for_llvm(format)
+            ));
+        }
+
+        // The IR is incomplete after any error, so report the first one instead
+        match self.errors.drain(..).next() {
+            Some(error) => Err(error),
+            None => Ok(ir),
+        }
+    }
+
+    // `const NAME: T = value` becomes `@NAME = constant T value`, folded at compile time
+    fn generate_constant(
+        &mut self,
+        name: &str,
+        const_type: &Type,
+        initializer: &Expr,
+        ir: &mut String,
+    ) {
+        let constants = &self.constants;
+        let Some(value) = Constant::evaluate(initializer, &|name| constants.get(name).cloned())
+        else {
+            self.error(format!(
+                "Initializer of constant '{}' cannot be evaluated at compile time",
+                name
+            ));
+            return;
+        };
+        let llvm_value = match &value {
+            Constant::Str(text) => match self.string_gen.get_string_literal(text) {
+                Ok((_, idx)) => Some(format!(
+                    "getelementptr inbounds ([{} x i8], [{} x i8]* @.str.{}, i64 0, i64 0)",
+                    text.len() + 1,
+                    text.len() + 1,
+                    idx
+                )),
+                Err(e) => {
+                    self.error(e);
+                    return;
+                }
+            },
+            _ => value.to_llvm(const_type),
+        };
+        let Some(llvm_value) = llvm_value else {
+            self.error(format!(
+                "Constant '{}' of type '{}' cannot hold {:?}",
+                name, const_type, value
+            ));
+            return;
+        };
+
+        ir.push_str(&format!(
+            "@{} = constant {} {}\n",
+            name,
+            self.get_llvm_type(const_type),
+            llvm_value
+        ));
+        self.globals.insert(name.to_string(), const_type.clone());
+        self.constants.insert(name.to_string(), value);
+    }
+
+    // Top-level `let`s become zeroed `@NAME` globals that a constructor fills in before
+    // `main` runs. Each initializer is lowered like a local declaration, then copied over.
+    fn generate_globals(&mut self, decls: &[&Stmt], ir: &mut String) {
+        if decls.is_empty() {
+            return;
+        }
+        self.counter = 0;
+        self.label_counter = 0;
+        let mut body = String::from("define internal void @zen.init_globals() {\nentry:\n");
+        for decl in decls {
+            let Stmt::VariableDecl { name, .. } = decl else {
+                continue;
+            };
+            self.generate_function_statement(decl, &mut body);
+            let Some((zen_type, _, alloc_id)) = self.variables.remove(name) else {
+                continue;
+            };
+            let llvm_type = self.get_llvm_type(&zen_type);
+            ir.push_str(&format!(
+                "@{} = global {} {}\n",
+                name,
+                llvm_type,
+                zero_value(&zen_type)
+            ));
+            let value_id = self.fresh_id();
+            body.push_str(&format!(
+                "  %{} = load {}, {}* %{}\n  store {} %{}, {}* @{}\n",
+                value_id, llvm_type, llvm_type, alloc_id, llvm_type, value_id, llvm_type, name
+            ));
+            self.globals.insert(name.clone(), zen_type);
+        }
+        body.push_str("  ret void\n}\n\n");
+        ir.push_str("@llvm.global_ctors = appending global [1 x { i32, void ()*, i8* }] ");
+        ir.push_str(
+            "[{ i32, void ()*, i8* } { i32 65535, void ()* @zen.init_globals, i8* null }]\n\n",
+        );
+        ir.push_str(&body);
+    }
+
+    // The type of a local variable, or of a global when no local shadows it
+    fn variable_type(&self, name: &str) -> Option<&Type> {
+        self.variables
+            .get(name)
+            .map(|(zen_type, _, _)| zen_type)
+            .or_else(|| self.globals.get(name))
+    }
+
+    // Innermost type of `name` among the block locals of an inference in progress
+    fn block_local_type(&self, name: &str) -> Option<Type> {
+        self.block_locals
+            .borrow()
+            .iter()
+            .rev()
+            .find(|(local, _)| local == name)
+            .map(|(_, zen_type)| zen_type.clone())
+    }
+
+    // Pointer to a variable's storage: its local `alloca`, or its global
+    fn variable_pointer(&self, name: &str) -> Option<String> {
+        match self.variables.get(name) {
+            Some((_, _, alloc_id)) => Some(format!("%{}", alloc_id)),
+            None => self
+                .globals
+                .contains_key(name)
+                .then(|| format!("@{}", name)),
+        }
+    }
+
+    // The type of a bare function name used as a value; generic functions have none
+    fn function_pointer_type(&self, name: &str) -> Option<Type> {
+        let (params, return_type) = self.functions.get(name)?;
+        Some(Type::Function(
+            params.clone(),
+            Box::new(return_type.clone()),
+        ))
+    }
+
+    // The function type of a callee that is a variable holding a function pointer
+    fn indirect_callee_type(&self, callee: &Expr) -> Option<Type> {
+        match callee {
+            Expr::Identifier { name, .. } => match self.variable_type(name)? {
+                function_type @ Type::Function(..) => Some(function_type.clone()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    // The mangled `Struct_method` function that `object.method` names
+    fn method_function(&self, callee: &Expr) -> Option<String> {
+        let Expr::FieldAccess { object, field, .. } = callee else {
+            return None;
+        };
+        let object_type = self.infer_expression_type(object);
+        let struct_name = self.get_struct_name_from_type(&object_type)?;
+        let method = format!("{}_{}", struct_name, field);
+        self.functions.contains_key(&method).then_some(method)
+    }
+
+    // A C `main` that calls the `--entry` function and exits with its result
+    fn generate_entry_shim(&mut self, ir: &mut String) {
+        let Some(entry) = self.entry.clone() else {
+            return;
+        };
+        let Some((_, return_type)) = self.functions.get(&entry) else {
+            self.error(format!("Entry point '{}' is not defined", entry));
+            return;
+        };
+        ir.push_str("\ndefine i32 @main() {\nentry:\n");
+        if *return_type == Type::Void {
+            ir.push_str(&format!("  call void @{}()\n  ret i32 0\n}}\n", entry));
+        } else {
+            ir.push_str(&format!(
+                "  %status = call i32 @{}()\n  ret i32 %status\n}}\n",
+                entry
+            ));
+        }
+    }
 
-        ir
+    fn error(&mut self, message: String) {
+        let context = self
+            .current_function
+            .as_ref()
+            .map(|name| format!("in function '{}'", name));
+        self.errors
+            .push(ZenError::CodegenError { message, context });
+    }
+
+    // Declared types must only name known structs; the IR would reference an undefined type
+    fn check_declared_type(&mut self, zen_type: &Type) {
+        match zen_type {
+            Type::Struct(name) if !self.structs.contains_key(name) => {
+                self.error(format!("Unknown type '{}'", name));
+            }
+            Type::Array(inner, _)
+            | Type::Slice(inner)
+            | Type::Ref(inner, _)
+            | Type::Nullable(inner) => self.check_declared_type(inner),
+            _ => {}
+        }
     }
 
     fn escape_for_llvm(&self, s: &str) -> String {
@@ -101,20 +437,25 @@ impl CodeGenerator {
     fn register_functions(&mut self, stmt: &Stmt) {
         if let Stmt::FunctionDecl {
             name,
+            type_params,
             params,
             return_type,
             ..
         } = stmt
         {
-            let param_types: Vec<String> = params.iter().map(|(_, t)| t.clone()).collect();
+            if !type_params.is_empty() {
+                self.generic_functions.insert(name.clone(), stmt.clone());
+                return;
+            }
+            let param_types: Vec<Type> = params.iter().map(|(_, t)| t.clone()).collect();
             self.functions
-                .insert(name.to_string(), (param_types, return_type.to_string()));
+                .insert(name.to_string(), (param_types, return_type.clone()));
         }
     }
 
     fn register_structs(&mut self, stmt: &Stmt) {
         if let Stmt::StructDecl { name, fields, .. } = stmt {
-            let field_types: Vec<(String, String)> = fields
+            let field_types: Vec<(String, Type)> = fields
                 .iter()
                 .map(|(field_name, field_type)| (field_name.clone(), field_type.clone()))
                 .collect();
@@ -122,12 +463,22 @@ impl CodeGenerator {
         }
     }
 
-    fn generate_struct_types(&self, ir: &mut String) {
+    fn generate_struct_types(&mut self, ir: &mut String) {
+        let field_types: Vec<Type> = self
+            .structs
+            .values()
+            .flatten()
+            .map(|(_, field_type)| field_type.clone())
+            .collect();
+        for field_type in &field_types {
+            self.check_declared_type(field_type);
+        }
+
         for (struct_name, fields) in &self.structs {
             ir.push_str(&format!("%struct.{} = type {{ ", struct_name));
             let field_types: Vec<String> = fields
                 .iter()
-                .map(|(_, field_type)| self.get_llvm_type(field_type).to_string())
+                .map(|(_, field_type)| self.get_llvm_type(field_type))
                 .collect();
             ir.push_str(&field_types.join(", "));
             ir.push_str(" }\n");
@@ -150,52 +501,142 @@ impl CodeGenerator {
         label
     }
 
-    fn get_llvm_type(&self, zen_type: &str) -> String {
+    // Unknown structs are reported by check_declared_type where the type is written
+    fn get_llvm_type(&self, zen_type: &Type) -> String {
         match zen_type {
-            "i8" => "i8".to_string(),
-            "i16" => "i16".to_string(),
-            I32_TYPE => "i32".to_string(),
-            "i64" => "i64".to_string(),
-            "u8" => "i8".to_string(),
-            "u16" => "i16".to_string(),
-            "u32" => "i32".to_string(),
-            "u64" => "i64".to_string(),
-            "f32" => "float".to_string(),
-            "f64" => "double".to_string(),
-            "bool" => "i1".to_string(),
-            "str" => "i8*".to_string(),
-            "char" => "i8".to_string(),
-            VOID_TYPE => "void".to_string(),
-            _ => {
-                // Check if it's a struct type
-                if self.structs.contains_key(zen_type) {
-                    format!("%struct.{}", zen_type)
-                } else {
-                    eprintln!("Warning: Unknown type '{}', defaulting to i32", zen_type);
-                    I32_TYPE.to_string()
-                }
+            Type::Struct(name) if !self.structs.contains_key(name) => Type::I32.to_llvm(),
+            Type::Nullable(inner) => self.get_llvm_type(inner),
+            _ => zen_type.to_llvm(),
+        }
+    }
+
+    // `a + b` on structs is a call to the struct's operator function, e.g. `Vec2_add(a, b)`
+    fn operator_call(&self, left: &Expr, op: &Token, right: &Expr) -> Option<Expr> {
+        let Type::Struct(struct_name) = self.infer_expression_type(left) else {
+            return None;
+        };
+        let method = format!("{}_{}", struct_name, op.kind.operator_method()?);
+        if !self.functions.contains_key(&method) {
+            return None;
+        }
+        Some(Expr::Call {
+            callee: Box::new(Expr::Identifier {
+                name: method,
+                token: op.clone(),
+            }),
+            args: vec![left.clone(), right.clone()],
+            token: op.clone(),
+        })
+    }
+
+    // The type both operands of an arithmetic or comparison operator are brought to:
+    // integer literals take the other operand's type, otherwise the wider integer wins
+    fn binary_operand_type(&self, left: &Expr, right: &Expr) -> Type {
+        let left_type = self.infer_expression_type(left);
+        let right_type = self.infer_expression_type(right);
+        match (left, right) {
+            // `null` is compared as the reference on the other side
+            (Expr::NullLiteral { .. }, _) => right_type,
+            (Expr::IntegerLiteral { .. }, _) if integer_bits(&right_type).is_some() => right_type,
+            (_, Expr::IntegerLiteral { .. }) => left_type,
+            _ => match (integer_bits(&left_type), integer_bits(&right_type)) {
+                (Some(left_bits), Some(right_bits)) if right_bits > left_bits => right_type,
+                _ => left_type,
+            },
+        }
+    }
+
+    // An operand widened to the operator's operand type; literals are already written in it
+    fn widen_operand(
+        &mut self,
+        value: &str,
+        operand: &Expr,
+        from: &Type,
+        to: &Type,
+        ir: &mut String,
+    ) -> String {
+        if matches!(operand, Expr::IntegerLiteral { .. }) {
+            return value.to_string();
+        }
+        self.convert_integer(value, from, to, ir)
+    }
+
+    // What each type parameter of a generic function stands for in a call, inferred from
+    // the argument types
+    fn infer_type_args(&self, name: &str, args: &[Expr]) -> Result<HashMap<String, Type>, String> {
+        let Some(Stmt::FunctionDecl {
+            type_params,
+            params,
+            ..
+        }) = self.generic_functions.get(name)
+        else {
+            return Err(format!("'{}' is not a generic function", name));
+        };
+        let mut bindings = HashMap::new();
+        for ((_, param_type), arg) in params.iter().zip(args) {
+            let arg_type = self.infer_expression_type(arg);
+            if !param_type.bind(&arg_type, &mut bindings) {
+                return Err(format!(
+                    "Conflicting types for a type parameter of '{}': '{}' and '{}'",
+                    name,
+                    param_type.substitute(&bindings),
+                    arg_type
+                ));
             }
         }
+        match type_params
+            .iter()
+            .find(|param| !bindings.contains_key(*param))
+        {
+            Some(param) => Err(format!(
+                "Cannot infer type parameter '{}' in call to '{}'",
+                param, name
+            )),
+            None => Ok(bindings),
+        }
+    }
+
+    // Symbol of the instance of a generic function for this call, queueing its
+    // generation the first time those type arguments are seen
+    fn instantiate(&mut self, name: &str, args: &[Expr]) -> Option<String> {
+        let decl = self.generic_functions.get(name)?.clone();
+        let bindings = match self.infer_type_args(name, args) {
+            Ok(bindings) => bindings,
+            Err(message) => {
+                self.error(message);
+                return None;
+            }
+        };
+        let Stmt::FunctionDecl { type_params, .. } = &decl else {
+            return None;
+        };
+        let type_args: Vec<Type> = type_params
+            .iter()
+            .map(|param| bindings[param].clone())
+            .collect();
+        let symbol = instance_name(name, &type_args);
+        if !self.functions.contains_key(&symbol) {
+            let instance = specialize(&decl, &symbol, &bindings);
+            self.register_functions(&instance);
+            self.pending_instances.push(instance);
+        }
+        Some(symbol)
     }
 
-    fn infer_expression_type(&self, expr: &Expr) -> String {
+    fn infer_expression_type(&self, expr: &Expr) -> Type {
         match expr {
-            Expr::IntegerLiteral { .. } => "i32".to_string(),
-            Expr::FloatLiteral { .. } => "f64".to_string(),
-            Expr::BooleanLiteral { .. } => "bool".to_string(),
-            Expr::CharLiteral { .. } => "char".to_string(),
-            Expr::StringLiteral { .. } => "str".to_string(),
+            Expr::IntegerLiteral { .. } => Type::I32,
+            Expr::FloatLiteral { .. } => Type::F64,
+            Expr::BooleanLiteral { .. } => Type::Bool,
+            Expr::CharLiteral { .. } => Type::Char,
+            Expr::StringLiteral { .. } => Type::Str,
+            Expr::NullLiteral { .. } => Type::Null,
             Expr::Identifier { name, .. } => self
-                .variables
-                .get(name)
-                .map(|(t, _, _)| t.clone())
-                .unwrap_or_else(|| {
-                    eprintln!(
-                        "Warning: Cannot infer type for undefined variable '{}'",
-                        name
-                    );
-                    "i32".to_string()
-                }),
+                .block_local_type(name)
+                .or_else(|| self.variable_type(name).cloned())
+                .or_else(|| self.function_pointer_type(name))
+                // Undefined variables are reported when the expression is generated
+                .unwrap_or(Type::I32),
             Expr::BinaryOp { left, op, right } => {
                 match op.kind {
                     TokenType::EqualEqual
@@ -205,34 +646,116 @@ impl CodeGenerator {
                     | TokenType::GreaterThan
                     | TokenType::GreaterEqual
                     | TokenType::And
-                    | TokenType::Or => "bool".to_string(),
+                    | TokenType::Or => Type::Bool,
                     _ => {
+                        if let Some(call) = self.operator_call(left, op, right) {
+                            return self.infer_expression_type(&call);
+                        }
                         // For arithmetic operations, return the "higher" type
                         let left_type = self.infer_expression_type(left);
                         let right_type = self.infer_expression_type(right);
 
-                        if left_type == "f64" || right_type == "f64" {
-                            "f64".to_string()
-                        } else if left_type == "f32" || right_type == "f32" {
-                            "f32".to_string()
+                        let f32_type = Type::Float(FloatKind::F32);
+                        if left_type == Type::F64 || right_type == Type::F64 {
+                            Type::F64
+                        } else if left_type == f32_type || right_type == f32_type {
+                            f32_type
                         } else {
-                            left_type
+                            self.binary_operand_type(left, right)
                         }
                     }
                 }
             }
             Expr::UnaryOp { operand, .. } => self.infer_expression_type(operand),
-            Expr::Call { callee, .. } => {
-                if let Expr::Identifier { name, .. } = callee.as_ref() {
+            Expr::Call { callee, args, .. } => {
+                if let Some(Type::Function(_, return_type)) = self.indirect_callee_type(callee) {
+                    return *return_type;
+                }
+                if let Some(method) = self.method_function(callee) {
+                    return self
+                        .functions
+                        .get(&method)
+                        .map(|(_, return_type)| return_type.clone())
+                        .unwrap_or(Type::I32);
+                }
+                if let Some(name) = callee_name(callee) {
+                    if let Some(Stmt::FunctionDecl { return_type, .. }) =
+                        self.generic_functions.get(name)
+                    {
+                        return self
+                            .infer_type_args(name, args)
+                            .map(|bindings| return_type.substitute(&bindings))
+                            .unwrap_or(Type::I32);
+                    }
                     self.functions
                         .get(name)
                         .map(|(_, ret_type)| ret_type.clone())
-                        .unwrap_or_else(|| "i32".to_string())
+                        .unwrap_or(Type::I32)
                 } else {
-                    "i32".to_string()
+                    Type::I32
+                }
+            }
+            Expr::StructLiteral { struct_name, .. } => Type::Struct(struct_name.clone()),
+            Expr::Cast { target_type, .. } => target_type.clone(),
+            Expr::ArrayRepeat { value, count, .. } => {
+                Type::Array(Box::new(self.infer_expression_type(value)), *count)
+            }
+            Expr::ArrayLiteral { elements, .. } => Type::Array(
+                Box::new(
+                    elements
+                        .first()
+                        .map(|element| self.infer_expression_type(element))
+                        .unwrap_or(Type::I32),
+                ),
+                elements.len(),
+            ),
+            Expr::ArrayAccess { array, .. } => self
+                .array_element_type(&self.infer_expression_type(array))
+                .unwrap_or(Type::I32),
+            Expr::IfExpr { then_expr, .. } => self.infer_expression_type(then_expr),
+            Expr::Block {
+                statements, tail, ..
+            } => {
+                let depth = self.block_locals.borrow().len();
+                for stmt in statements {
+                    if let Stmt::VariableDecl {
+                        name,
+                        type_annotation,
+                        initializer,
+                        ..
+                    } = stmt
+                    {
+                        let zen_type = type_annotation
+                            .clone()
+                            .or_else(|| {
+                                initializer
+                                    .as_ref()
+                                    .map(|init| self.infer_expression_type(init))
+                            })
+                            .unwrap_or(Type::I32);
+                        self.block_locals
+                            .borrow_mut()
+                            .push((name.clone(), zen_type));
+                    }
                 }
+                let tail_type = self.infer_expression_type(tail);
+                self.block_locals.borrow_mut().truncate(depth);
+                tail_type
             }
-            Expr::StructLiteral { struct_name, .. } => struct_name.clone(),
+            Expr::Match { arms, default, .. } => arms
+                .first()
+                .map(|(_, value)| value)
+                .or(default.as_deref())
+                .map(|value| self.infer_expression_type(value))
+                .unwrap_or(Type::I32),
+            Expr::Borrow {
+                expr, is_mutable, ..
+            } => Type::Ref(Box::new(self.infer_expression_type(expr)), *is_mutable),
+            Expr::Deref { expr, .. } => self
+                .infer_expression_type(expr)
+                .pointee()
+                .cloned()
+                .unwrap_or(Type::I32),
             Expr::FieldAccess { object, field, .. } => {
                 let object_type = self.infer_expression_type(object);
                 if let Some(struct_name) = self.get_struct_name_from_type(&object_type) {
@@ -243,16 +766,16 @@ impl CodeGenerator {
                         {
                             field_type.clone()
                         } else {
-                            "i32".to_string()
+                            Type::I32
                         }
                     } else {
-                        "i32".to_string()
+                        Type::I32
                     }
                 } else {
-                    "i32".to_string()
+                    Type::I32
                 }
             }
-            _ => "i32".to_string(),
+            _ => Type::I32,
         }
     }
 
@@ -261,26 +784,26 @@ impl CodeGenerator {
         &mut self,
         left_val: String,
         right_val: String,
-        left_type: &str,
-        right_type: &str,
-        target_type: &str,
+        left_type: &Type,
+        right_type: &Type,
+        target_type: &Type,
         ir: &mut String,
-    ) -> (String, String, String) {
+    ) -> (String, String, Type) {
         if left_type == right_type && left_type == target_type {
-            return (left_val, right_val, target_type.to_string());
+            return (left_val, right_val, target_type.clone());
         }
 
         let mut final_left = left_val;
         let mut final_right = right_val;
-        let mut op_type = target_type.to_string();
+        let mut op_type = target_type.clone();
 
         // Handle numeric promotions
-        if (left_type == "i32" && right_type == "f64")
-            || (left_type == "f64" && right_type == "i32")
+        if (*left_type == Type::I32 && *right_type == Type::F64)
+            || (*left_type == Type::F64 && *right_type == Type::I32)
         {
-            op_type = "f64".to_string();
+            op_type = Type::F64;
 
-            if left_type == "i32" {
+            if *left_type == Type::I32 {
                 let id = self.fresh_id();
                 ir.push_str(&format!(
                     "  %{} = sitofp i32 {} to double\n",
@@ -289,7 +812,7 @@ impl CodeGenerator {
                 final_left = format!("%{}", id);
             }
 
-            if right_type == "i32" {
+            if *right_type == Type::I32 {
                 let id = self.fresh_id();
                 ir.push_str(&format!(
                     "  %{} = sitofp i32 {} to double\n",
@@ -300,50 +823,53 @@ impl CodeGenerator {
         }
 
         // Handle boolean conversions
-        if target_type == "bool" && (left_type != "bool" || right_type != "bool") {
-            if left_type != "bool" {
+        if *target_type == Type::Bool && (*left_type != Type::Bool || *right_type != Type::Bool) {
+            if *left_type != Type::Bool {
                 let id = self.fresh_id();
                 ir.push_str(&format!("  %{} = icmp ne i32 {}, 0\n", id, final_left));
                 final_left = format!("%{}", id);
             }
 
-            if right_type != "bool" {
+            if *right_type != Type::Bool {
                 let id = self.fresh_id();
                 ir.push_str(&format!("  %{} = icmp ne i32 {}, 0\n", id, final_right));
                 final_right = format!("%{}", id);
             }
-            op_type = "bool".to_string();
+            op_type = Type::Bool;
         }
 
         (final_left, final_right, op_type)
     }
 
     #[allow(dead_code)]
-    fn is_compatible_type(&self, target_type: &str, source_type: &str) -> bool {
+    fn is_compatible_type(&self, target_type: &Type, source_type: &Type) -> bool {
         // Enhanced type compatibility checking
         match (target_type, source_type) {
             // Exact matches
             (a, b) if a == b => true,
 
             // Numeric promotions
-            ("f64", "f32") | ("f64", "i32") | ("f64", "i16") | ("f64", "i8") => true,
-            ("f32", "i32") | ("f32", "i16") | ("f32", "i8") => true,
-            ("i64", "i32") | ("i64", "i16") | ("i64", "i8") => true,
-            ("i32", "i16") | ("i32", "i8") => true,
-            ("i16", "i8") => true,
+            (Type::Float(FloatKind::F64), Type::Float(FloatKind::F32)) => true,
+            (Type::Float(_), Type::Int(kind)) => kind.is_signed() && kind.bits() <= 32,
+            (Type::Int(target), Type::Int(source)) if target.is_signed() && source.is_signed() => {
+                source.bits() < target.bits()
+            }
 
             // Unsigned to signed (with warning)
-            ("i32", "u32") | ("i16", "u16") | ("i8", "u8") => {
+            (Type::Int(target), Type::Int(source))
+                if target.is_signed() && source.bits() == target.bits() && target.bits() <= 32 =>
+            {
                 eprintln!("Warning: Implicit conversion from unsigned to signed type");
                 true
             }
 
             // Boolean conversions
-            ("bool", "i32") | ("bool", "i16") | ("bool", "i8") => true,
-            ("i32", "bool") | ("i16", "bool") | ("i8", "bool") => true,
+            (Type::Bool, Type::Int(kind)) | (Type::Int(kind), Type::Bool) => {
+                kind.is_signed() && kind.bits() <= 32
+            }
 
             // Character conversions
-            ("char", "i8") | ("i8", "char") => true,
+            (Type::Char, Type::Int(IntKind::I8)) | (Type::Int(IntKind::I8), Type::Char) => true,
 
             _ => false,
         }
@@ -352,13 +878,15 @@ impl CodeGenerator {
     fn generate_statement(&mut self, stmt: &Stmt, ir: &mut String) {
         #[allow(clippy::single_match)]
         match stmt {
+            // Generic functions are only generated as instances
             Stmt::FunctionDecl {
                 name,
+                type_params,
                 params,
                 return_type,
                 body,
                 ..
-            } => {
+            } if type_params.is_empty() => {
                 self.generate_function(name, params, return_type, body, ir);
             }
             _ => {}
@@ -368,8 +896,8 @@ impl CodeGenerator {
     fn generate_function(
         &mut self,
         name: &str,
-        params: &[(String, String)],
-        return_type: &str,
+        params: &[(String, Type)],
+        return_type: &Type,
         body: &[Stmt],
         ir: &mut String,
     ) {
@@ -380,7 +908,12 @@ impl CodeGenerator {
         self.counter = 0;
         self.label_counter = 0;
 
+        self.check_declared_type(return_type);
+        for (_, param_type) in params {
+            self.check_declared_type(param_type);
+        }
         let llvm_return = self.get_llvm_type(return_type);
+        let start = ir.len();
         ir.push_str(&format!("define {} @{}(", llvm_return, name));
 
         for (i, (param_name, param_type)) in params.iter().enumerate() {
@@ -409,21 +942,34 @@ impl CodeGenerator {
         let mut last_expr_value: Option<String> = None;
         let mut had_return = false;
 
-        for stmt in body {
-            if let Stmt::Return { .. } = stmt {
+        // Statements after one that always returns are dead and have no block to live in
+        for (i, stmt) in body.iter().enumerate() {
+            if stmt.diverging_call().is_some() {
+                self.generate_function_statement(stmt, ir);
+                ir.push_str("  unreachable\n");
                 had_return = true;
+                break;
             }
+            // A trailing `if`/`match` whose branches all end in a value is the result
+            let tail = (*return_type != Type::Void && i + 1 == body.len())
+                .then(|| stmt.tail_value())
+                .flatten();
             if let Stmt::ExprStmt { expr } = stmt {
                 last_expr_value = Some(self.generate_expression(expr, ir));
-                had_return = false;
+            } else if let Some(expr) = tail {
+                last_expr_value = Some(self.generate_expression(&expr, ir));
             } else {
                 last_expr_value = None;
                 self.generate_function_statement(stmt, ir);
+                if stmt.always_returns() {
+                    had_return = true;
+                    break;
+                }
             }
         }
 
         if !had_return {
-            if return_type == VOID_TYPE {
+            if *return_type == Type::Void {
                 ir.push_str("  ret void\n");
             } else if let Some(value) = last_expr_value {
                 ir.push_str(&format!("  ret {} {}\n", llvm_return, value));
@@ -434,6 +980,17 @@ impl CodeGenerator {
 
         ir.push_str("}\n\n");
 
+        // The C runtime reads `main`'s result as the exit code, so a void `main` is
+        // given one: 0, like the entry shim returns for a void `--entry`
+        if name == "main" && *return_type == Type::Void && self.entry.is_none() {
+            let function = ir.split_off(start);
+            ir.push_str(
+                &function
+                    .replacen("define void @main(", "define i32 @main(", 1)
+                    .replace("  ret void\n", "  ret i32 0\n"),
+            );
+        }
+
         self.current_function = old_function;
         self.variables = old_vars;
     }
@@ -448,17 +1005,18 @@ impl CodeGenerator {
                 ..
             } => {
                 let zen_type = if let Some(type_ann) = type_annotation {
+                    self.check_declared_type(type_ann);
                     type_ann.clone()
                 } else if let Some(init) = initializer {
                     // Infer type from initializer
                     match init {
-                        crate::ast::expr::Expr::StringLiteral { .. } => "str".to_string(),
-                        crate::ast::expr::Expr::IntegerLiteral { .. } => I32_TYPE.to_string(),
-                        crate::ast::expr::Expr::FloatLiteral { .. } => "f64".to_string(),
-                        crate::ast::expr::Expr::BooleanLiteral { .. } => "bool".to_string(),
-                        crate::ast::expr::Expr::CharLiteral { .. } => "char".to_string(),
+                        crate::ast::expr::Expr::StringLiteral { .. } => Type::Str,
+                        crate::ast::expr::Expr::IntegerLiteral { .. } => Type::I32,
+                        crate::ast::expr::Expr::FloatLiteral { .. } => Type::F64,
+                        crate::ast::expr::Expr::BooleanLiteral { .. } => Type::Bool,
+                        crate::ast::expr::Expr::CharLiteral { .. } => Type::Char,
                         crate::ast::expr::Expr::StructLiteral { struct_name, .. } => {
-                            struct_name.clone()
+                            Type::Struct(struct_name.clone())
                         }
                         _ => {
                             // Use expression type inference for complex expressions
@@ -466,13 +1024,13 @@ impl CodeGenerator {
                         }
                     }
                 } else {
-                    I32_TYPE.to_string()
+                    Type::I32
                 };
                 let llvm_type = self.get_llvm_type(&zen_type);
 
                 let id = self.fresh_id();
                 // Handle string pointer allocation
-                if zen_type == "str" {
+                if zen_type == Type::Str {
                     ir.push_str(&format!("  %{} = alloca i8*\n", id));
                 } else {
                     ir.push_str(&format!("  %{} = alloca {}\n", id, llvm_type));
@@ -488,7 +1046,9 @@ impl CodeGenerator {
                         ..
                     } = init
                     {
-                        if struct_name == &zen_type && self.structs.contains_key(struct_name) {
+                        if zen_type == Type::Struct(struct_name.clone())
+                            && self.structs.contains_key(struct_name)
+                        {
                             // Generate struct literal directly into the allocated space
                             let struct_fields = self.structs.get(struct_name).unwrap().clone();
 
@@ -518,21 +1078,24 @@ impl CodeGenerator {
                                         field_llvm_type, field_value, field_llvm_type, gep_id
                                     ));
                                 } else {
-                                    eprintln!(
-                                        "Warning: Missing field '{}' in struct '{}' literal",
+                                    self.error(format!(
+                                        "Missing field '{}' in struct '{}' literal",
                                         field_name, struct_name
-                                    );
+                                    ));
                                 }
                             }
                         } else {
-                            eprintln!("Warning: Struct type mismatch or undefined struct");
+                            self.error(format!(
+                                "Cannot initialize '{}' of type '{}' with a '{}' literal",
+                                name, zen_type, struct_name
+                            ));
                         }
                     } else {
                         let init_value = self.generate_expression(init, ir);
                         let init_type = self.infer_expression_type(init);
 
                         // Handle type conversion if needed
-                        if zen_type == "bool" && init_type == "bool" {
+                        if zen_type == Type::Bool && init_type == Type::Bool {
                             // For boolean variables with boolean expressions
                             if init_value.starts_with('%') {
                                 // It's a register - check if it's already i1 or needs conversion
@@ -543,7 +1106,7 @@ impl CodeGenerator {
                                 let bool_val = if init_value == "1" { "true" } else { "false" };
                                 ir.push_str(&format!("  store i1 {}, i1* %{}\n", bool_val, id));
                             }
-                        } else if zen_type == "str" {
+                        } else if zen_type == Type::Str {
                             ir.push_str(&format!("  store i8* {}, i8** %{}\n", init_value, id));
                         } else {
                             ir.push_str(&format!(
@@ -552,30 +1115,61 @@ impl CodeGenerator {
                             ));
                         }
                     }
+                } else {
+                    // Declared without an initializer: start from zero, false or null
+                    ir.push_str(&format!(
+                        "  store {} {}, {}* %{}\n",
+                        llvm_type,
+                        zero_value(&zen_type),
+                        llvm_type,
+                        id
+                    ));
                 }
             }
 
+            // A `const` inside a function is an immutable local
+            Stmt::ConstDecl {
+                name,
+                type_annotation,
+                initializer,
+                token,
+                ..
+            } => {
+                let local = Stmt::VariableDecl {
+                    name: name.clone(),
+                    type_annotation: type_annotation.clone(),
+                    initializer: Some(initializer.clone()),
+                    is_mutable: false,
+                    token: token.clone(),
+                };
+                self.generate_function_statement(&local, ir);
+            }
+
             Stmt::Assignment { target, value, .. } => {
                 #[allow(clippy::collapsible_match)]
                 if let Expr::Identifier { name, .. } = target {
-                    if let Some(var_info) = self.variables.get(name).cloned() {
-                        let (zen_type, _, alloc_id) = var_info;
+                    if let (Some(zen_type), Some(pointer)) = (
+                        self.variable_type(name).cloned(),
+                        self.variable_pointer(name),
+                    ) {
                         let llvm_type = self.get_llvm_type(&zen_type);
                         let value_str = self.generate_expression(value, ir);
-
-                        // Handle string assignment specially
-                        if zen_type == "str" {
-                            ir.push_str(&format!(
-                                "  store i8* {}, i8** %{}\n",
-                                value_str, alloc_id
-                            ));
-                        } else {
-                            ir.push_str(&format!(
-                                "  store {} {}, {}* %{}\n",
-                                llvm_type, value_str, llvm_type, alloc_id
-                            ));
-                        }
+                        ir.push_str(&format!(
+                            "  store {} {}, {}* {}\n",
+                            llvm_type, value_str, llvm_type, pointer
+                        ));
                     }
+                } else if let Expr::Deref { .. }
+                | Expr::ArrayAccess { .. }
+                | Expr::FieldAccess { .. } = target
+                {
+                    let llvm_type = self.get_llvm_type(&self.infer_expression_type(target));
+                    let value_str = self.generate_expression(value, ir);
+                    let pointer = self.generate_address(target, ir);
+                    ir.push_str(&format!(
+                        "  store {} {}, {}* {}\n",
+                        llvm_type, value_str, llvm_type, pointer
+                    ));
                 }
             }
 
@@ -595,12 +1189,12 @@ impl CodeGenerator {
                     let expr_type = self.infer_expression_type(v);
 
                     // Handle type conversion for return values
-                    if return_type == "i1" && expr_type != "bool" {
+                    if return_type == "i1" && expr_type != Type::Bool {
                         // Convert to boolean
                         let bool_id = self.fresh_id();
                         ir.push_str(&format!("  %{} = icmp ne i32 {}, 0\n", bool_id, value_str));
                         ir.push_str(&format!("  ret i1 %{}\n", bool_id));
-                    } else if return_type != "i1" && expr_type == "bool" {
+                    } else if return_type != "i1" && expr_type == Type::Bool {
                         // Convert from boolean to integer
                         let conv_id = self.fresh_id();
                         ir.push_str(&format!("  %{} = zext i1 {} to i32\n", conv_id, value_str));
@@ -608,6 +1202,8 @@ impl CodeGenerator {
                     } else {
                         ir.push_str(&format!("  ret {} {}\n", return_type, value_str));
                     }
+                } else if return_type == "void" {
+                    ir.push_str("  ret void\n");
                 } else {
                     ir.push_str(&format!("  ret {} 0\n", return_type));
                 }
@@ -624,10 +1220,10 @@ impl CodeGenerator {
                 let cond_type = self.infer_expression_type(condition);
 
                 // Convert to i1 for branch condition
-                let bool_cond = if cond_type == "bool" && cond_value.starts_with('%') {
+                let bool_cond = if cond_type == Type::Bool && cond_value.starts_with('%') {
                     // Already i1, use directly
                     cond_value
-                } else if cond_type == "bool" {
+                } else if cond_type == Type::Bool {
                     // Boolean literal, convert to i1
                     let bool_id = self.fresh_id();
                     ir.push_str(&format!("  %{} = icmp ne i32 {}, 0\n", bool_id, cond_value));
@@ -667,13 +1263,7 @@ impl CodeGenerator {
 
                 // Generate then branch
                 ir.push_str(&format!("then.{}:\n", then_label));
-                let mut then_terminated = false;
-                for stmt in then_branch {
-                    if matches!(stmt, Stmt::Return { .. }) {
-                        then_terminated = true;
-                    }
-                    self.generate_function_statement(stmt, ir);
-                }
+                let then_terminated = self.generate_body(then_branch, ir);
                 if !then_terminated {
                     ir.push_str(&format!("  br label %end.{}\n", end_label));
                 }
@@ -690,7 +1280,7 @@ impl CodeGenerator {
                         self.generate_expression(&else_if_branch.condition, ir);
                     let else_if_cond_type = self.infer_expression_type(&else_if_branch.condition);
                     let else_if_bool_cond =
-                        if else_if_cond_type == "bool" && else_if_cond_value.starts_with('%') {
+                        if else_if_cond_type == Type::Bool && else_if_cond_value.starts_with('%') {
                             // Already i1, use directly
                             else_if_cond_value
                         } else {
@@ -730,13 +1320,7 @@ impl CodeGenerator {
 
                     // Generate else if body
                     ir.push_str(&format!("then.{}:\n", else_if_then_label));
-                    let mut else_if_terminated = false;
-                    for stmt in &else_if_branch.body {
-                        if matches!(stmt, Stmt::Return { .. }) {
-                            else_if_terminated = true;
-                        }
-                        self.generate_function_statement(stmt, ir);
-                    }
+                    let else_if_terminated = self.generate_body(&else_if_branch.body, ir);
                     if !else_if_terminated {
                         ir.push_str(&format!("  br label %end.{}\n", end_label));
                     }
@@ -747,13 +1331,7 @@ impl CodeGenerator {
                 // Generate final else branch if present
                 if let Some(else_stmts) = else_branch {
                     ir.push_str(&format!("else.{}:\n", current_label));
-                    let mut else_terminated = false;
-                    for stmt in else_stmts {
-                        if matches!(stmt, Stmt::Return { .. }) {
-                            else_terminated = true;
-                        }
-                        self.generate_function_statement(stmt, ir);
-                    }
+                    let else_terminated = self.generate_body(else_stmts, ir);
                     if !else_terminated {
                         ir.push_str(&format!("  br label %end.{}\n", end_label));
                     }
@@ -765,7 +1343,10 @@ impl CodeGenerator {
                     ir.push_str(&format!("  br label %end.{}\n", end_label));
                 }
 
-                ir.push_str(&format!("end.{}:\n", end_label));
+                // When every branch leaves, nothing jumps to the end block
+                if !stmt.ends_block() {
+                    ir.push_str(&format!("end.{}:\n", end_label));
+                }
             }
 
             Stmt::While {
@@ -782,7 +1363,7 @@ impl CodeGenerator {
                 let cond_type = self.infer_expression_type(condition);
 
                 // Convert to i1 for branch condition
-                let bool_cond = if cond_type == "bool" && cond_value.starts_with('%') {
+                let bool_cond = if cond_type == Type::Bool && cond_value.starts_with('%') {
                     // Already i1, use directly
                     cond_value
                 } else {
@@ -797,14 +1378,49 @@ impl CodeGenerator {
                 ));
 
                 ir.push_str(&format!("body.{}:\n", body_label));
-                for stmt in body {
-                    self.generate_function_statement(stmt, ir);
+                self.loop_stack.push(LoopLabels {
+                    continue_label: format!("cond.{}", cond_label),
+                    break_label: format!("end.{}", end_label),
+                });
+                let body_terminated = self.generate_body(body, ir);
+                self.loop_stack.pop();
+                if !body_terminated {
+                    ir.push_str(&format!("  br label %cond.{}\n", cond_label));
                 }
-                ir.push_str(&format!("  br label %cond.{}\n", cond_label));
 
                 ir.push_str(&format!("end.{}:\n", end_label));
             }
 
+            Stmt::Match {
+                value,
+                arms,
+                default,
+                ..
+            } => self.generate_match_statement(value, arms, default.as_deref(), ir),
+
+            Stmt::Loop { body, .. } => {
+                let body_label = self.fresh_label();
+                let end_label = self.fresh_label();
+
+                ir.push_str(&format!("  br label %body.{}\n", body_label));
+
+                ir.push_str(&format!("body.{}:\n", body_label));
+                self.loop_stack.push(LoopLabels {
+                    continue_label: format!("body.{}", body_label),
+                    break_label: format!("end.{}", end_label),
+                });
+                let body_terminated = self.generate_body(body, ir);
+                self.loop_stack.pop();
+                if !body_terminated {
+                    ir.push_str(&format!("  br label %body.{}\n", body_label));
+                }
+
+                // Without a `break`, nothing jumps to the end block
+                if !stmt.always_returns() {
+                    ir.push_str(&format!("end.{}:\n", end_label));
+                }
+            }
+
             Stmt::For {
                 init,
                 condition,
@@ -813,12 +1429,14 @@ impl CodeGenerator {
                 ..
             } => {
                 if let Some(init_stmt) = init {
-                    self.generate_function_statement(init_stmt, ir);
+                    let init_stmt =
+                        init_stmt.resolve_for_init(|name| self.variable_type(name).is_some());
+                    self.generate_function_statement(&init_stmt, ir);
                 }
 
                 let cond_label = self.fresh_label();
                 let body_label = self.fresh_label();
-                let _inc_label = self.fresh_label();
+                let inc_label = self.fresh_label();
                 let end_label = self.fresh_label();
 
                 ir.push_str(&format!("  br label %cond.{}\n", cond_label));
@@ -829,7 +1447,7 @@ impl CodeGenerator {
                     let cond_type = self.infer_expression_type(cond);
 
                     // Convert to i1 for branch condition
-                    let bool_cond = if cond_type == "bool" && cond_value.starts_with('%') {
+                    let bool_cond = if cond_type == Type::Bool && cond_value.starts_with('%') {
                         // Already i1, use directly
                         cond_value
                     } else {
@@ -847,137 +1465,560 @@ impl CodeGenerator {
                 }
 
                 ir.push_str(&format!("body.{}:\n", body_label));
-                for stmt in body {
-                    self.generate_function_statement(stmt, ir);
+                // `continue` must still run the increment before re-testing the condition
+                self.loop_stack.push(LoopLabels {
+                    continue_label: format!("inc.{}", inc_label),
+                    break_label: format!("end.{}", end_label),
+                });
+                let body_terminated = self.generate_body(body, ir);
+                self.loop_stack.pop();
+                if !body_terminated {
+                    ir.push_str(&format!("  br label %inc.{}\n", inc_label));
                 }
-                if let Some(inc) = increment {
-                    // Handle assignment in increment
-                    if let Expr::BinaryOp { left, op, right } = inc {
-                        if matches!(op.kind, TokenType::Equal) {
-                            if let Expr::Identifier { name, .. } = left.as_ref() {
-                                if let Some(var_info) = self.variables.get(name).cloned() {
-                                    let (zen_type, _, alloc_id) = var_info;
-                                    let llvm_type = self.get_llvm_type(&zen_type);
-                                    let value_str = self.generate_expression(right, ir);
-                                    ir.push_str(&format!(
-                                        "  store {} {}, {}* %{}\n",
-                                        llvm_type, value_str, llvm_type, alloc_id
-                                    ));
-                                }
-                            }
-                        }
-                    } else {
+
+                ir.push_str(&format!("inc.{}:\n", inc_label));
+                match increment {
+                    // An assignment increment (`i = i + 2`, `i += 1`, `a[i] = 0`) is lowered
+                    // exactly like the assignment statement it stands for
+                    Some(Expr::BinaryOp { left, op, right }) if op.kind.is_assignment() => {
+                        let assignment = Stmt::Assignment {
+                            target: left.as_ref().clone(),
+                            value: right.as_ref().clone(),
+                            token: op.clone(),
+                        };
+                        self.generate_function_statement(&assignment, ir);
+                    }
+                    Some(inc) => {
                         self.generate_expression(inc, ir);
                     }
+                    None => {}
                 }
                 ir.push_str(&format!("  br label %cond.{}\n", cond_label));
 
                 ir.push_str(&format!("end.{}:\n", end_label));
             }
 
+            Stmt::ForIn {
+                variable,
+                iterable,
+                body,
+                ..
+            } => self.generate_for_in(variable, iterable, body, ir),
+
             Stmt::ExprStmt { expr } => {
                 self.generate_expression(expr, ir);
             }
 
-            Stmt::Block { statements } => {
-                for stmt in statements {
-                    self.generate_function_statement(stmt, ir);
+            Stmt::Break { .. } | Stmt::Continue { .. } => {
+                if let Some(labels) = self.loop_stack.last() {
+                    let target = if matches!(stmt, Stmt::Break { .. }) {
+                        &labels.break_label
+                    } else {
+                        &labels.continue_label
+                    };
+                    ir.push_str(&format!("  br label %{}\n", target));
                 }
             }
 
+            Stmt::Block { statements } => {
+                self.generate_body(statements, ir);
+            }
+
             _ => {}
         }
     }
 
-    fn generate_expression(&mut self, expr: &Expr, ir: &mut String) -> String {
-        match expr {
-            Expr::IntegerLiteral { value, .. } => {
-                // Enhanced integer literal handling with validation
-                match value.parse::<i64>() {
-                    Ok(val) if val >= i32::MIN as i64 && val <= i32::MAX as i64 => val.to_string(),
-                    Ok(val) => {
-                        eprintln!(
-                            "Warning: Integer literal {} may overflow i32, truncating",
-                            val
-                        );
-                        (val as i32).to_string()
-                    }
-                    Err(_) => {
-                        eprintln!("Error: Invalid integer literal {}", value);
-                        "0".to_string()
-                    }
-                }
-            }
-
-            Expr::FloatLiteral { value, .. } => {
-                // Enhanced float handling with precision control
-                if value.is_finite() {
-                    if value.fract() == 0.0 {
-                        format!("{:.1}", value)
-                    } else {
-                        format!("{:.6}", value)
-                    }
-                } else {
-                    eprintln!("Warning: Non-finite float value, using 0.0");
-                    "0.0".to_string()
-                }
-            }
-
-            Expr::BooleanLiteral { value, .. } => if *value { "1" } else { "0" }.to_string(),
+    // An index loop over a fixed-size array, loading the current element into the loop variable
+    fn generate_for_in(&mut self, variable: &str, iterable: &Expr, body: &[Stmt], ir: &mut String) {
+        let iterable_type = self.infer_expression_type(iterable);
+        let (array_ptr, array_type) = match &iterable_type {
+            Type::Ref(inner, _) => (
+                self.generate_expression(iterable, ir),
+                inner.as_ref().clone(),
+            ),
+            _ => (self.generate_address(iterable, ir), iterable_type.clone()),
+        };
+        let Type::Array(element_type, count) = &array_type else {
+            self.error(format!("Cannot iterate over '{}'", iterable_type));
+            return;
+        };
+        let array_llvm_type = self.get_llvm_type(&array_type);
+        let element_llvm_type = self.get_llvm_type(element_type);
 
-            Expr::CharLiteral { value, .. } => {
-                let ascii_value = *value as u8;
-                // Validate ASCII range
-                if ascii_value <= 127 {
-                    ascii_value.to_string()
-                } else {
-                    eprintln!("Warning: Non-ASCII character, using 0");
-                    "0".to_string()
+        let index_id = self.fresh_id();
+        ir.push_str(&format!("  %{} = alloca i32\n", index_id));
+        ir.push_str(&format!("  store i32 0, i32* %{}\n", index_id));
+        let element_id = self.fresh_id();
+        ir.push_str(&format!(
+            "  %{} = alloca {}\n",
+            element_id, element_llvm_type
+        ));
+        self.variables.insert(
+            variable.to_string(),
+            (element_type.as_ref().clone(), false, element_id),
+        );
+
+        let cond_label = self.fresh_label();
+        let body_label = self.fresh_label();
+        let inc_label = self.fresh_label();
+        let end_label = self.fresh_label();
+
+        ir.push_str(&format!("  br label %cond.{}\n", cond_label));
+        ir.push_str(&format!("cond.{}:\n", cond_label));
+        let index = self.fresh_id();
+        ir.push_str(&format!("  %{} = load i32, i32* %{}\n", index, index_id));
+        let in_bounds = self.fresh_id();
+        ir.push_str(&format!(
+            "  %{} = icmp slt i32 %{}, {}\n",
+            in_bounds, index, count
+        ));
+        ir.push_str(&format!(
+            "  br i1 %{}, label %body.{}, label %end.{}\n",
+            in_bounds, body_label, end_label
+        ));
+
+        ir.push_str(&format!("body.{}:\n", body_label));
+        let slot = self.fresh_id();
+        ir.push_str(&format!(
+            "  %{} = getelementptr inbounds {}, {}* {}, i32 0, i32 %{}\n",
+            slot, array_llvm_type, array_llvm_type, array_ptr, index
+        ));
+        let element = self.fresh_id();
+        ir.push_str(&format!(
+            "  %{} = load {}, {}* %{}\n",
+            element, element_llvm_type, element_llvm_type, slot
+        ));
+        ir.push_str(&format!(
+            "  store {} %{}, {}* %{}\n",
+            element_llvm_type, element, element_llvm_type, element_id
+        ));
+        self.loop_stack.push(LoopLabels {
+            continue_label: format!("inc.{}", inc_label),
+            break_label: format!("end.{}", end_label),
+        });
+        let body_terminated = self.generate_body(body, ir);
+        self.loop_stack.pop();
+        if !body_terminated {
+            ir.push_str(&format!("  br label %inc.{}\n", inc_label));
+        }
+
+        ir.push_str(&format!("inc.{}:\n", inc_label));
+        let current = self.fresh_id();
+        ir.push_str(&format!("  %{} = load i32, i32* %{}\n", current, index_id));
+        let next = self.fresh_id();
+        ir.push_str(&format!("  %{} = add i32 %{}, 1\n", next, current));
+        ir.push_str(&format!("  store i32 %{}, i32* %{}\n", next, index_id));
+        ir.push_str(&format!("  br label %cond.{}\n", cond_label));
+        ir.push_str(&format!("end.{}:\n", end_label));
+    }
+
+    // Trap at runtime instead of loading from or storing to a null pointer
+    fn generate_null_check(&mut self, pointer: &str, llvm_type: &str, ir: &mut String) {
+        let is_null = self.fresh_id();
+        let label = self.fresh_label();
+        ir.push_str(&format!(
+            "  %{} = icmp eq {} {}, null\n",
+            is_null, llvm_type, pointer
+        ));
+        ir.push_str(&format!(
+            "  br i1 %{}, label %null_trap.{}, label %not_null.{}\n",
+            is_null, label, label
+        ));
+        ir.push_str(&format!("null_trap.{}:\n", label));
+        self.generate_trap("@null_deref_msg", NULL_DEREF_MESSAGE, ir);
+        ir.push_str("  unreachable\n");
+        ir.push_str(&format!("not_null.{}:\n", label));
+    }
+
+    // Abort with `message`, stored in `global`, on stderr. abort() does not flush stdio
+    // buffers, so earlier output is flushed first and is not lost when stdout is a pipe.
+    fn generate_trap(&mut self, global: &str, message: &str, ir: &mut String) {
+        let flush_id = self.fresh_id();
+        ir.push_str(&format!("  %{} = call i32 @fflush(i8* null)\n", flush_id));
+        let write_id = self.fresh_id();
+        ir.push_str(&format!(
+            "  %{} = call i64 @write(i32 2, i8* getelementptr inbounds ([{} x i8], [{} x i8]* {}, i64 0, i64 0), i64 {})\n",
+            write_id,
+            message.len() + 1,
+            message.len() + 1,
+            global,
+            message.len()
+        ));
+        ir.push_str("  call void @abort()\n");
+    }
+
+    // Print `value` on its own line in the form its type calls for
+    fn generate_print_value(&mut self, value: &str, value_type: &Type, ir: &mut String) {
+        match value_type {
+            Type::Str => {
+                let call_id = self.fresh_id();
+                ir.push_str(&format!("  %{} = call i32 @puts(i8* {})\n", call_id, value));
+            }
+            Type::Char => self.generate_print_char(value, true, ir),
+            Type::Float(kind) => {
+                // printf takes floats widened to double, as varargs promotion would
+                let value = if *kind == FloatKind::F32 {
+                    let ext_id = self.fresh_id();
+                    ir.push_str(&format!(
+                        "  %{} = fpext float {} to double\n",
+                        ext_id, value
+                    ));
+                    format!("%{}", ext_id)
+                } else {
+                    value.to_string()
+                };
+                let fmt_id = self.fresh_id();
+                ir.push_str(&format!("  %{} = call i32 (i8*, ...) @printf(i8* getelementptr inbounds ([4 x i8], [4 x i8]* @float_fmt, i64 0, i64 0), double {})\n",
+                    fmt_id, value));
+            }
+            _ => self.generate_print_integer(value, value_type, ir),
+        }
+    }
+
+    // Print an integer with the printf conversion for its width and signedness; narrower
+    // values are widened to a C int first, as varargs promotion would
+    fn generate_print_integer(&mut self, value: &str, value_type: &Type, ir: &mut String) {
+        if *value_type == Type::Bool {
+            self.generate_print_bool(value, ir);
+            return;
+        }
+        let kind = match value_type {
+            Type::Int(kind) => *kind,
+            _ => {
+                self.error(format!("Cannot print a value of type '{}'", value_type));
+                return;
+            }
+        };
+        let (format, format_len, llvm_type, value) = match (kind.bits(), kind.is_signed()) {
+            (64, true) => ("@int64_fmt", 6, "i64", value.to_string()),
+            (64, false) => ("@uint64_fmt", 6, "i64", value.to_string()),
+            (_, true) => {
+                let value = self.convert_integer(value, value_type, &Type::I32, ir);
+                ("@int_fmt", 4, "i32", value)
+            }
+            (_, false) => {
+                let value = self.convert_integer(value, value_type, &Type::Int(IntKind::U32), ir);
+                ("@uint_fmt", 4, "i32", value)
+            }
+        };
+        let fmt_id = self.fresh_id();
+        ir.push_str(&format!(
+            "  %{} = call i32 (i8*, ...) @printf(i8* getelementptr inbounds ([{} x i8], [{} x i8]* {}, i64 0, i64 0), {} {})\n",
+            fmt_id, format_len, format_len, format, llvm_type, value
+        ));
+    }
+
+    fn generate_print_bool(&mut self, value: &str, ir: &mut String) {
+        let true_str = self.generate_string_literal("true", ir);
+        let false_str = self.generate_string_literal("false", ir);
+        let text = self.fresh_id();
+        ir.push_str(&format!(
+            "  %{} = select i1 {}, i8* {}, i8* {}\n",
+            text, value, true_str, false_str
+        ));
+        let call_id = self.fresh_id();
+        ir.push_str(&format!("  %{} = call i32 @puts(i8* %{})\n", call_id, text));
+    }
+
+    // Chars print as the character itself; printf takes it widened to an int
+    fn generate_print_char(&mut self, value: &str, newline: bool, ir: &mut String) {
+        let conv_id = self.fresh_id();
+        ir.push_str(&format!("  %{} = zext i8 {} to i32\n", conv_id, value));
+        let (format, format_len) = if newline {
+            ("@char_fmt", 4)
+        } else {
+            ("@char_fmt_no_nl", 3)
+        };
+        let fmt_id = self.fresh_id();
+        ir.push_str(&format!(
+            "  %{} = call i32 (i8*, ...) @printf(i8* getelementptr inbounds ([{} x i8], [{} x i8]* {}, i64 0, i64 0), i32 %{})\n",
+            fmt_id, format_len, format_len, format, conv_id
+        ));
+    }
+
+    // An unsigned compare also catches negative indices
+    fn generate_bounds_check(&mut self, index: &str, length: usize, ir: &mut String) {
+        let out_of_range = self.fresh_id();
+        let label = self.fresh_label();
+        ir.push_str(&format!(
+            "  %{} = icmp uge i32 {}, {}\n",
+            out_of_range, index, length
+        ));
+        ir.push_str(&format!(
+            "  br i1 %{}, label %bounds_trap.{}, label %in_bounds.{}\n",
+            out_of_range, label, label
+        ));
+        ir.push_str(&format!("bounds_trap.{}:\n", label));
+        let call_id = self.fresh_id();
+        let fmt_len = BOUNDS_MESSAGE.len() + 1;
+        ir.push_str(&format!(
+            "  %{} = call i32 (i8*, ...) @printf(i8* getelementptr inbounds ([{} x i8], [{} x i8]* @bounds_fmt, i64 0, i64 0), i32 {}, i32 {})\n",
+            call_id, fmt_len, fmt_len, index, length
+        ));
+        // abort() does not flush stdio buffers
+        let flush_id = self.fresh_id();
+        ir.push_str(&format!("  %{} = call i32 @fflush(i8* null)\n", flush_id));
+        ir.push_str("  call void @abort()\n");
+        ir.push_str("  unreachable\n");
+        ir.push_str(&format!("in_bounds.{}:\n", label));
+    }
+
+    // Generate a statement list, returning true if it ends in a terminator.
+    // Nothing after a diverging call is emitted, since its block ends in `unreachable`.
+    // `assert_eq(a, b)`: compare once, and on a mismatch print both values and abort
+    fn generate_assert_eq(&mut self, args: &[Expr], token: &Token, ir: &mut String) {
+        let [left, right] = args else {
+            self.error("assert_eq expects 2 arguments".to_string());
+            return;
+        };
+        let value_type = self.infer_expression_type(left);
+        let right_type = self.infer_expression_type(right);
+        let llvm_type = self.get_llvm_type(&value_type);
+        let left_val = self.generate_expression(left, ir);
+        let right_val = self.generate_expression(right, ir);
+        let right_val = self.convert_integer(&right_val, &right_type, &value_type, ir);
+
+        let comparison = match &value_type {
+            Type::Float(_) => format!("fcmp une {} {}, {}", llvm_type, left_val, right_val),
+            Type::Str => {
+                let order = self.fresh_id();
+                ir.push_str(&format!(
+                    "  %{} = call i32 @strcmp(i8* {}, i8* {})\n",
+                    order, left_val, right_val
+                ));
+                format!("icmp ne i32 %{}, 0", order)
+            }
+            _ => format!("icmp ne {} {}, {}", llvm_type, left_val, right_val),
+        };
+        let differs = self.fresh_id();
+        ir.push_str(&format!("  %{} = {}\n", differs, comparison));
+        let label = self.fresh_label();
+        ir.push_str(&format!(
+            "  br i1 %{}, label %assert_fail.{}, label %assert_ok.{}\n",
+            differs, label, label
+        ));
+        ir.push_str(&format!("assert_fail.{}:\n", label));
+
+        // printf takes the values widened to int or double
+        let (conversion, printed_type, printed) = match &value_type {
+            Type::Int(kind) if kind.bits() == 64 => {
+                let conversion = if kind.is_signed() { "%lld" } else { "%llu" };
+                (conversion, "i64", vec![left_val, right_val])
+            }
+            Type::Int(kind) => {
+                let (conversion, wide) = if kind.is_signed() {
+                    ("%d", Type::I32)
+                } else {
+                    ("%u", Type::Int(IntKind::U32))
+                };
+                let printed = [left_val, right_val]
+                    .iter()
+                    .map(|value| self.convert_integer(value, &value_type, &wide, ir))
+                    .collect();
+                (conversion, "i32", printed)
+            }
+            Type::Bool | Type::Char => {
+                let conversion = if value_type == Type::Bool { "%d" } else { "%c" };
+                let printed = [left_val, right_val]
+                    .iter()
+                    .map(|value| self.convert_integer(value, &value_type, &Type::I32, ir))
+                    .collect();
+                (conversion, "i32", printed)
+            }
+            Type::Float(FloatKind::F32) => {
+                let printed = [left_val, right_val]
+                    .iter()
+                    .map(|value| {
+                        let id = self.fresh_id();
+                        ir.push_str(&format!("  %{} = fpext float {} to double\n", id, value));
+                        format!("%{}", id)
+                    })
+                    .collect();
+                ("%f", "double", printed)
+            }
+            Type::Float(_) => ("%f", "double", vec![left_val, right_val]),
+            _ => ("%s", "i8*", vec![left_val, right_val]),
+        };
+        let format_index = match self.assert_formats.iter().position(|c| *c == conversion) {
+            Some(index) => index,
+            None => {
+                self.assert_formats.push(conversion);
+                self.assert_formats.len() - 1
+            }
+        };
+        let format_len = ASSERT_EQ_MESSAGE.replace("{}", conversion).len() + 1;
+        let call_id = self.fresh_id();
+        ir.push_str(&format!(
+            "  %{} = call i32 (i8*, ...) @printf(i8* getelementptr inbounds ([{} x i8], [{} x i8]* @assert_eq_fmt.{}, i64 0, i64 0), i32 {}, {} {}, {} {})\n",
+            call_id,
+            format_len,
+            format_len,
+            format_index,
+            token.line,
+            printed_type,
+            printed[0],
+            printed_type,
+            printed[1]
+        ));
+        let flush_id = self.fresh_id();
+        ir.push_str(&format!("  %{} = call i32 @fflush(i8* null)\n", flush_id));
+        ir.push_str("  call void @abort()\n");
+        ir.push_str("  unreachable\n");
+        ir.push_str(&format!("assert_ok.{}:\n", label));
+    }
+
+    // Resize an integer, char or bool value; only signed integers are sign-extended
+    fn convert_integer(&mut self, value: &str, from: &Type, to: &Type, ir: &mut String) -> String {
+        let (Some(from_bits), Some(to_bits)) = (integer_bits(from), integer_bits(to)) else {
+            return value.to_string();
+        };
+        let op = if to_bits < from_bits {
+            "trunc"
+        } else if to_bits == from_bits {
+            return value.to_string();
+        } else if matches!(from, Type::Int(kind) if kind.is_signed()) {
+            "sext"
+        } else {
+            "zext"
+        };
+
+        let id = self.fresh_id();
+        ir.push_str(&format!(
+            "  %{} = {} i{} {} to i{}\n",
+            id, op, from_bits, value, to_bits
+        ));
+        format!("%{}", id)
+    }
+
+    fn generate_body(&mut self, body: &[Stmt], ir: &mut String) -> bool {
+        // Locals declared in the body go out of scope with it, uncovering any they shadowed
+        let outer = self.variables.clone();
+        let terminated = self.generate_statements(body, ir);
+        self.variables = outer;
+        terminated
+    }
+
+    // Returns whether the body ended in a terminator; statements after one are dead and
+    // are not generated, since they would have no block to live in
+    fn generate_statements(&mut self, body: &[Stmt], ir: &mut String) -> bool {
+        for stmt in body {
+            self.generate_function_statement(stmt, ir);
+            if stmt.diverging_call().is_some() {
+                ir.push_str("  unreachable\n");
+                return true;
+            }
+            if stmt.ends_block() {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn generate_expression(&mut self, expr: &Expr, ir: &mut String) -> String {
+        match expr {
+            Expr::IntegerLiteral { value, .. } => {
+                // A folded constant may be wider than i32; llc truncates it to the type it
+                // is used at, so it is only exact where that type is wide enough
+                match crate::lexer::lexer::parse_integer_literal(value) {
+                    Ok(val) => val.to_string(),
+                    Err(_) => {
+                        self.error(format!("Invalid integer literal '{}'", value));
+                        "0".to_string()
+                    }
+                }
+            }
+
+            Expr::FloatLiteral { value, .. } => {
+                // Enhanced float handling with precision control
+                if value.is_finite() {
+                    if value.fract() == 0.0 {
+                        format!("{:.1}", value)
+                    } else {
+                        format!("{:.6}", value)
+                    }
+                } else {
+                    eprintln!("Warning: Non-finite float value, using 0.0");
+                    "0.0".to_string()
                 }
             }
 
+            Expr::BooleanLiteral { value, .. } => if *value { "1" } else { "0" }.to_string(),
+
+            Expr::NullLiteral { .. } => "null".to_string(),
+
+            // A char is one byte; the typechecker rejects code points above 255
+            Expr::CharLiteral { value, .. } => match u8::try_from(*value) {
+                Ok(byte) => (byte as i8).to_string(),
+                Err(_) => {
+                    self.error(format!("Char literal '{}' does not fit in a byte", value));
+                    "0".to_string()
+                }
+            },
+
             Expr::StringLiteral { value, .. } => self.generate_string_literal(value, ir),
 
-            Expr::InterpolatedString { parts, .. } => self.generate_interpolated_string(parts, ir),
+            Expr::InterpolatedString { parts, token } => {
+                self.generate_interpolated_string(parts, token, false, ir)
+            }
 
             Expr::Identifier { name, .. } => {
                 // Enhanced identifier resolution with validation
-                if let Some(var_info) = self.variables.get(name).cloned() {
-                    let (zen_type, _, alloc_id) = var_info;
+                if let (Some(zen_type), Some(pointer)) = (
+                    self.variable_type(name).cloned(),
+                    self.variable_pointer(name),
+                ) {
                     let llvm_type = self.get_llvm_type(&zen_type);
                     let id = self.fresh_id();
 
                     // Enhanced type-specific loading
-                    match zen_type.as_str() {
-                        "str" => {
-                            ir.push_str(&format!("  %{} = load i8*, i8** %{}\n", id, alloc_id));
+                    match zen_type {
+                        Type::Str => {
+                            ir.push_str(&format!("  %{} = load i8*, i8** {}\n", id, pointer));
                         }
-                        "bool" => {
-                            ir.push_str(&format!("  %{} = load i1, i1* %{}\n", id, alloc_id));
+                        Type::Bool => {
+                            ir.push_str(&format!("  %{} = load i1, i1* {}\n", id, pointer));
                         }
-                        "char" => {
-                            ir.push_str(&format!("  %{} = load i8, i8* %{}\n", id, alloc_id));
+                        Type::Char => {
+                            ir.push_str(&format!("  %{} = load i8, i8* {}\n", id, pointer));
                         }
                         _ => {
                             ir.push_str(&format!(
-                                "  %{} = load {}, {}* %{}\n",
-                                id, llvm_type, llvm_type, alloc_id
+                                "  %{} = load {}, {}* {}\n",
+                                id, llvm_type, llvm_type, pointer
                             ));
                         }
                     }
                     format!("%{}", id)
+                } else if self.function_pointer_type(name).is_some() {
+                    format!("@{}", name)
                 } else {
-                    eprintln!("Error: Undefined variable '{}'", name);
-                    format!("%{}", name)
+                    self.error(format!("Undefined variable '{}'", name));
+                    "undef".to_string()
                 }
             }
 
             Expr::BinaryOp { left, op, right } => {
+                if let Some(call) = self.operator_call(left, op, right) {
+                    let result = self.generate_expression(&call, ir);
+                    if op.kind != TokenType::NotEqual {
+                        return result;
+                    }
+                    let id = self.fresh_id();
+                    ir.push_str(&format!("  %{} = xor i1 {}, true\n", id, result));
+                    return format!("%{}", id);
+                }
                 let left_type = self.infer_expression_type(left);
                 let right_type = self.infer_expression_type(right);
+                let operand_type = self.binary_operand_type(left, right);
+                let int_type = operand_type.to_llvm();
+                let unsigned = matches!(operand_type, Type::Int(kind) if !kind.is_signed())
+                    || operand_type == Type::Char;
 
                 let left_val = self.generate_expression(left, ir);
+                let left_val = self.widen_operand(&left_val, left, &left_type, &operand_type, ir);
                 let right_val = self.generate_expression(right, ir);
+                let right_val =
+                    self.widen_operand(&right_val, right, &right_type, &operand_type, ir);
 
                 // Handle comparison operations that return bool
                 let result = match op.kind {
@@ -987,7 +2028,7 @@ impl CodeGenerator {
                     | TokenType::LessEqual
                     | TokenType::GreaterThan
                     | TokenType::GreaterEqual => {
-                        let op_str = if left_type == "f64" || right_type == "f64" {
+                        let op_str = if left_type == Type::F64 || right_type == Type::F64 {
                             match op.kind {
                                 TokenType::EqualEqual => "fcmp oeq double",
                                 TokenType::NotEqual => "fcmp one double",
@@ -998,15 +2039,19 @@ impl CodeGenerator {
                                 _ => "fcmp oeq double",
                             }
                         } else {
-                            match op.kind {
-                                TokenType::EqualEqual => "icmp eq i32",
-                                TokenType::NotEqual => "icmp ne i32",
-                                TokenType::LessThan => "icmp slt i32",
-                                TokenType::LessEqual => "icmp sle i32",
-                                TokenType::GreaterThan => "icmp sgt i32",
-                                TokenType::GreaterEqual => "icmp sge i32",
-                                _ => "icmp eq i32",
-                            }
+                            let predicate = match (op.kind.clone(), unsigned) {
+                                (TokenType::NotEqual, _) => "ne",
+                                (TokenType::LessThan, false) => "slt",
+                                (TokenType::LessEqual, false) => "sle",
+                                (TokenType::GreaterThan, false) => "sgt",
+                                (TokenType::GreaterEqual, false) => "sge",
+                                (TokenType::LessThan, true) => "ult",
+                                (TokenType::LessEqual, true) => "ule",
+                                (TokenType::GreaterThan, true) => "ugt",
+                                (TokenType::GreaterEqual, true) => "uge",
+                                _ => "eq",
+                            };
+                            &format!("icmp {} {}", predicate, int_type)
                         };
                         let id = self.fresh_id();
                         ir.push_str(&format!(
@@ -1023,10 +2068,11 @@ impl CodeGenerator {
                         let result_id = self.fresh_id();
 
                         // Check if operands are already boolean values (i1)
-                        let left_bool_val = if left_type == "bool" && left_val.starts_with('%') {
+                        let left_bool_val = if left_type == Type::Bool && left_val.starts_with('%')
+                        {
                             // Already loaded boolean variable (i1), use directly
                             left_val
-                        } else if left_type == "bool" {
+                        } else if left_type == Type::Bool {
                             // Boolean literal (0 or 1), convert to i1
                             let bool_id = self.fresh_id();
                             ir.push_str(&format!("  %{} = icmp ne i32 {}, 0\n", bool_id, left_val));
@@ -1038,26 +2084,27 @@ impl CodeGenerator {
                             format!("%{}", bool_id)
                         };
 
-                        let right_bool_val = if right_type == "bool" && right_val.starts_with('%') {
-                            // Already loaded boolean variable (i1), use directly
-                            right_val
-                        } else if right_type == "bool" {
-                            // Boolean literal (0 or 1), convert to i1
-                            let bool_id = self.fresh_id();
-                            ir.push_str(&format!(
-                                "  %{} = icmp ne i32 {}, 0\n",
-                                bool_id, right_val
-                            ));
-                            format!("%{}", bool_id)
-                        } else {
-                            // Convert non-boolean to boolean
-                            let bool_id = self.fresh_id();
-                            ir.push_str(&format!(
-                                "  %{} = icmp ne i32 {}, 0\n",
-                                bool_id, right_val
-                            ));
-                            format!("%{}", bool_id)
-                        };
+                        let right_bool_val =
+                            if right_type == Type::Bool && right_val.starts_with('%') {
+                                // Already loaded boolean variable (i1), use directly
+                                right_val
+                            } else if right_type == Type::Bool {
+                                // Boolean literal (0 or 1), convert to i1
+                                let bool_id = self.fresh_id();
+                                ir.push_str(&format!(
+                                    "  %{} = icmp ne i32 {}, 0\n",
+                                    bool_id, right_val
+                                ));
+                                format!("%{}", bool_id)
+                            } else {
+                                // Convert non-boolean to boolean
+                                let bool_id = self.fresh_id();
+                                ir.push_str(&format!(
+                                    "  %{} = icmp ne i32 {}, 0\n",
+                                    bool_id, right_val
+                                ));
+                                format!("%{}", bool_id)
+                            };
 
                         let op_str = match op.kind {
                             TokenType::And => "and i1",
@@ -1073,10 +2120,25 @@ impl CodeGenerator {
                         format!("%{}", result_id)
                     }
 
+                    TokenType::Caret => {
+                        if left_type == Type::F64 || right_type == Type::F64 {
+                            let base = self.convert_to_double(&left_val, &left_type, ir);
+                            let exponent = self.convert_to_double(&right_val, &right_type, ir);
+                            let id = self.fresh_id();
+                            ir.push_str(&format!(
+                                "  %{} = call double @llvm.pow.f64(double {}, double {})\n",
+                                id, base, exponent
+                            ));
+                            format!("%{}", id)
+                        } else {
+                            self.generate_integer_power(&left_val, &right_val, &int_type, ir)
+                        }
+                    }
+
                     _ => {
                         // Arithmetic operations
                         let id = self.fresh_id();
-                        let op_str = if left_type == "f64" || right_type == "f64" {
+                        let op_str = if left_type == Type::F64 || right_type == Type::F64 {
                             match op.kind {
                                 TokenType::Plus => "fadd double",
                                 TokenType::Minus => "fsub double",
@@ -1086,14 +2148,22 @@ impl CodeGenerator {
                                 _ => "fadd double",
                             }
                         } else {
-                            match op.kind {
-                                TokenType::Plus => "add i32",
-                                TokenType::Minus => "sub i32",
-                                TokenType::Star => "mul i32",
-                                TokenType::Slash => "sdiv i32",
-                                TokenType::Percent => "srem i32",
-                                _ => "add i32",
-                            }
+                            let instruction = match op.kind {
+                                TokenType::Minus => "sub",
+                                TokenType::Star => "mul",
+                                TokenType::Slash if unsigned => "udiv",
+                                TokenType::Slash => "sdiv",
+                                TokenType::Percent if unsigned => "urem",
+                                TokenType::Percent => "srem",
+                                TokenType::Pipe => "or",
+                                TokenType::Ampersand => "and",
+                                TokenType::CaretCaret => "xor",
+                                TokenType::ShiftLeft => "shl",
+                                TokenType::ShiftRight if unsigned => "lshr",
+                                TokenType::ShiftRight => "ashr",
+                                _ => "add",
+                            };
+                            &format!("{} {}", instruction, int_type)
                         };
                         ir.push_str(&format!(
                             "  %{} = {} {}, {}\n",
@@ -1111,8 +2181,16 @@ impl CodeGenerator {
 
                 match op.kind {
                     TokenType::Minus => {
+                        let operand_type = self.infer_expression_type(operand);
+                        let llvm_type = match integer_bits(&operand_type) {
+                            Some(_) => operand_type.to_llvm(),
+                            None => "i32".to_string(),
+                        };
                         let id = self.fresh_id();
-                        ir.push_str(&format!("  %{} = sub i32 0, {}\n", id, operand_val));
+                        ir.push_str(&format!(
+                            "  %{} = sub {} 0, {}\n",
+                            id, llvm_type, operand_val
+                        ));
                         format!("%{}", id)
                     }
                     TokenType::Not => {
@@ -1136,11 +2214,26 @@ impl CodeGenerator {
                 }
             }
 
-            Expr::Call { callee, args, .. } => {
-                if let Expr::Identifier { name, .. } = callee.as_ref() {
+            Expr::Call { callee, args, .. } if self.indirect_callee_type(callee).is_some() => {
+                self.generate_indirect_call(callee, args, ir)
+            }
+            Expr::Call { callee, args, .. } if matches!(**callee, Expr::FieldAccess { .. }) => {
+                self.generate_method_call(callee, args, ir)
+            }
+            Expr::Call {
+                callee,
+                args,
+                token,
+            } => {
+                if let Some(name) = callee_name(callee) {
+                    let instance = self.instantiate(name, args);
+                    let name = instance.as_ref().unwrap_or(name);
                     if name == "println" || name == "print" {
                         for arg in args {
                             match arg {
+                                Expr::InterpolatedString { parts, token } => {
+                                    self.generate_interpolated_string(parts, token, true, ir);
+                                }
                                 Expr::StringLiteral { .. } => {
                                     let val = self.generate_expression(arg, ir);
                                     let call_id = self.fresh_id();
@@ -1151,27 +2244,11 @@ impl CodeGenerator {
                                 }
                                 Expr::BooleanLiteral { .. } => {
                                     let val = self.generate_expression(arg, ir);
-                                    // Convert i1 to i32 for printing
-                                    let conv_id = self.fresh_id();
-                                    ir.push_str(&format!(
-                                        "  %{} = zext i1 {} to i32\n",
-                                        conv_id, val
-                                    ));
-                                    let fmt_id = self.fresh_id();
-                                    ir.push_str(&format!("  %{} = call i32 (i8*, ...) @printf(i8* getelementptr inbounds ([4 x i8], [4 x i8]* @int_fmt, i64 0, i64 0), i32 %{})\n",
-                                        fmt_id, conv_id));
+                                    self.generate_print_bool(&val, ir);
                                 }
                                 Expr::CharLiteral { .. } => {
                                     let val = self.generate_expression(arg, ir);
-                                    // Convert i8 to i32 for printing
-                                    let conv_id = self.fresh_id();
-                                    ir.push_str(&format!(
-                                        "  %{} = zext i8 {} to i32\n",
-                                        conv_id, val
-                                    ));
-                                    let fmt_id = self.fresh_id();
-                                    ir.push_str(&format!("  %{} = call i32 (i8*, ...) @printf(i8* getelementptr inbounds ([4 x i8], [4 x i8]* @int_fmt, i64 0, i64 0), i32 %{})\n",
-                                        fmt_id, conv_id));
+                                    self.generate_print_char(&val, true, ir);
                                 }
                                 Expr::IntegerLiteral { .. } | Expr::FloatLiteral { .. } => {
                                     let val = self.generate_expression(arg, ir);
@@ -1185,66 +2262,23 @@ impl CodeGenerator {
                                     ir.push_str(&format!("  %{} = call i32 (i8*, ...) @printf(i8* getelementptr inbounds ([4 x i8], [4 x i8]* {}, i64 0, i64 0), {} {})\n",
                                         fmt_id, fmt_name, val_type, val));
                                 }
-                                Expr::Identifier { name, .. } => {
+                                Expr::Identifier { .. }
+                                | Expr::Deref { .. }
+                                | Expr::ArrayAccess { .. }
+                                | Expr::FieldAccess { .. }
+                                | Expr::IfExpr { .. }
+                                | Expr::Match { .. }
+                                | Expr::Block { .. } => {
+                                    let value_type = self.infer_expression_type(arg);
                                     let val = self.generate_expression(arg, ir);
-                                    let is_float = self
-                                        .variables
-                                        .get(name)
-                                        .is_some_and(|(t, _, _)| t == "f64" || t == "f32");
-                                    let is_bool = self
-                                        .variables
-                                        .get(name)
-                                        .is_some_and(|(t, _, _)| t == "bool");
-                                    let is_string = self
-                                        .variables
-                                        .get(name)
-                                        .is_some_and(|(t, _, _)| t == "str");
-                                    let is_char = self
-                                        .variables
-                                        .get(name)
-                                        .is_some_and(|(t, _, _)| t == "char");
-
-                                    if is_string {
-                                        let call_id = self.fresh_id();
-                                        ir.push_str(&format!(
-                                            "  %{} = call i32 @puts(i8* {})\n",
-                                            call_id, val
-                                        ));
-                                    } else if is_char {
-                                        // Convert i8 to i32 for printing
-                                        let conv_id = self.fresh_id();
-                                        ir.push_str(&format!(
-                                            "  %{} = zext i8 {} to i32\n",
-                                            conv_id, val
-                                        ));
-                                        let fmt_id = self.fresh_id();
-                                        ir.push_str(&format!("  %{} = call i32 (i8*, ...) @printf(i8* getelementptr inbounds ([4 x i8], [4 x i8]* @int_fmt, i64 0, i64 0), i32 %{})\n",
-                                            fmt_id, conv_id));
-                                    } else {
-                                        let (fmt_name, val_type, final_val) = if is_float {
-                                            ("@float_fmt", "double", val)
-                                        } else if is_bool {
-                                            // Convert i1 to i32 for printing
-                                            let conv_id = self.fresh_id();
-                                            ir.push_str(&format!(
-                                                "  %{} = zext i1 {} to i32\n",
-                                                conv_id, val
-                                            ));
-                                            ("@int_fmt", "i32", format!("%{}", conv_id))
-                                        } else {
-                                            ("@int_fmt", "i32", val)
-                                        };
-                                        let fmt_id = self.fresh_id();
-                                        ir.push_str(&format!("  %{} = call i32 (i8*, ...) @printf(i8* getelementptr inbounds ([4 x i8], [4 x i8]* {}, i64 0, i64 0), {} {})\n",
-                                            fmt_id, fmt_name, val_type, final_val));
-                                    }
+                                    self.generate_print_value(&val, &value_type, ir);
                                 }
                                 Expr::BinaryOp { op, .. } => {
                                     let val = self.generate_expression(arg, ir);
                                     let is_float = matches!(arg, Expr::BinaryOp { left, right, .. }
                                         if matches!(left.as_ref(), Expr::FloatLiteral { .. }) || matches!(right.as_ref(), Expr::FloatLiteral { .. }) ||
-                                            matches!(left.as_ref(), Expr::Identifier { name, .. } if self.variables.get(name).is_some_and(|(t,_,_)| t=="f64"||t=="f32")) ||
-                                            matches!(right.as_ref(), Expr::Identifier { name, .. } if self.variables.get(name).is_some_and(|(t,_,_)| t=="f64"||t=="f32")));
+                                            matches!(left.as_ref(), Expr::Identifier { name, .. } if self.variable_type(name).is_some_and(Type::is_float)) ||
+                                            matches!(right.as_ref(), Expr::Identifier { name, .. } if self.variable_type(name).is_some_and(Type::is_float)));
 
                                     let is_bool = matches!(
                                         op.kind,
@@ -1258,29 +2292,28 @@ impl CodeGenerator {
                                             | TokenType::GreaterEqual
                                     );
 
-                                    let (fmt_name, val_type, final_val) = if is_float {
-                                        ("@float_fmt", "double", val)
-                                    } else if is_bool {
-                                        // Convert i1 to i32 for printing
-                                        let conv_id = self.fresh_id();
-                                        ir.push_str(&format!(
-                                            "  %{} = zext i1 {} to i32\n",
-                                            conv_id, val
-                                        ));
-                                        ("@int_fmt", "i32", format!("%{}", conv_id))
+                                    if is_float {
+                                        let fmt_id = self.fresh_id();
+                                        ir.push_str(&format!("  %{} = call i32 (i8*, ...) @printf(i8* getelementptr inbounds ([4 x i8], [4 x i8]* @float_fmt, i64 0, i64 0), double {})\n",
+                                            fmt_id, val));
                                     } else {
-                                        ("@int_fmt", "i32", val)
-                                    };
-                                    let fmt_id = self.fresh_id();
-                                    ir.push_str(&format!("  %{} = call i32 (i8*, ...) @printf(i8* getelementptr inbounds ([4 x i8], [4 x i8]* {}, i64 0, i64 0), {} {})\n",
-                                        fmt_id, fmt_name, val_type, final_val));
+                                        let value_type = if is_bool {
+                                            Type::Bool
+                                        } else {
+                                            self.infer_expression_type(arg)
+                                        };
+                                        self.generate_print_integer(&val, &value_type, ir);
+                                    }
+                                }
+                                Expr::Cast { target_type, .. } => {
+                                    let val = self.generate_expression(arg, ir);
+                                    self.generate_print_value(&val, target_type, ir);
                                 }
                                 Expr::Call { .. } => {
+                                    // The resolved return type, so generic instances print too
+                                    let value_type = self.infer_expression_type(arg);
                                     let val = self.generate_expression(arg, ir);
-                                    // For function calls, assume i32 return type for now
-                                    let fmt_id = self.fresh_id();
-                                    ir.push_str(&format!("  %{} = call i32 (i8*, ...) @printf(i8* getelementptr inbounds ([4 x i8], [4 x i8]* @int_fmt, i64 0, i64 0), i32 {})\n",
-                                        fmt_id, val));
+                                    self.generate_print_value(&val, &value_type, ir);
                                 }
                                 _ => {
                                     self.generate_expression(arg, ir);
@@ -1288,6 +2321,22 @@ impl CodeGenerator {
                             }
                         }
                         String::new()
+                    } else if name == "exit" {
+                        let code = args
+                            .first()
+                            .map(|arg| self.generate_expression(arg, ir))
+                            .unwrap_or_else(|| "0".to_string());
+                        ir.push_str(&format!("  call void @exit(i32 {})\n", code));
+                        String::new()
+                    } else if name == "assert_eq" {
+                        self.generate_assert_eq(args, token, ir);
+                        String::new()
+                    } else if name == "todo" {
+                        self.generate_trap("@todo_msg", TODO_MESSAGE, ir);
+                        String::new()
+                    } else if name == "abort" {
+                        ir.push_str("  call void @abort()\n");
+                        String::new()
                     } else if let Some((params, return_type)) = self.functions.get(name) {
                         let llvm_return = self.get_llvm_type(return_type);
                         let return_type_clone = return_type.clone();
@@ -1298,7 +2347,7 @@ impl CodeGenerator {
                             let arg_value = self.generate_expression(arg, ir);
                             arg_values.push(format!("{} {}", llvm_param_type, arg_value));
                         }
-                        if return_type_clone == VOID_TYPE {
+                        if return_type_clone == Type::Void {
                             ir.push_str(&format!(
                                 "  call void @{}({})\n",
                                 name,
@@ -1318,10 +2367,7 @@ impl CodeGenerator {
                         }
                     } else {
                         // Unknown function - generate a placeholder call that returns 0
-                        eprintln!(
-                            "Warning: Unknown function '{}', generating placeholder",
-                            name
-                        );
+                        self.error(format!("Undefined function '{}'", name));
                         "0".to_string()
                     }
                 } else {
@@ -1331,28 +2377,66 @@ impl CodeGenerator {
 
             Expr::OwnershipTransfer { expr, .. } => self.generate_expression(expr, ir),
 
-            Expr::Borrow {
-                expr, is_mutable, ..
+            // A borrow evaluates to the address of the borrowed place
+            Expr::Borrow { expr, .. } => self.generate_address(expr, ir),
+            Expr::Deref { .. } => {
+                let llvm_type = self.get_llvm_type(&self.infer_expression_type(expr));
+                let pointer = self.generate_address(expr, ir);
+                let id = self.fresh_id();
+                ir.push_str(&format!(
+                    "  %{} = load {}, {}* {}\n",
+                    id, llvm_type, llvm_type, pointer
+                ));
+                format!("%{}", id)
+            }
+            Expr::Cast {
+                expr: operand,
+                target_type,
+                ..
             } => {
-                // For now, treat borrows as the underlying expression
-                // In a full implementation, this would generate pointer types
-                if *is_mutable {
-                    // Mutable borrow - would generate mutable pointer
-                    self.generate_expression(expr, ir)
-                } else {
-                    // Immutable borrow - would generate immutable pointer
-                    self.generate_expression(expr, ir)
-                }
+                let source_type = self.infer_expression_type(operand);
+                let value = self.generate_expression(operand, ir);
+                self.convert_integer(&value, &source_type, target_type, ir)
             }
             Expr::FieldAccess { object, field, .. } => {
                 self.generate_field_access(object, field, ir)
             }
-            Expr::StructLiteral {
-                struct_name,
-                fields,
-                ..
-            } => self.generate_struct_literal(struct_name, fields, ir),
             Expr::ArrayAccess { array, index, .. } => self.generate_array_access(array, index, ir),
+            Expr::StructLiteral { .. } | Expr::ArrayRepeat { .. } | Expr::ArrayLiteral { .. } => {
+                let array_type = self.get_llvm_type(&self.infer_expression_type(expr));
+                let array_ptr = self.generate_address(expr, ir);
+                let id = self.fresh_id();
+                ir.push_str(&format!(
+                    "  %{} = load {}, {}* {}\n",
+                    id, array_type, array_type, array_ptr
+                ));
+                format!("%{}", id)
+            }
+            Expr::Match {
+                value,
+                arms,
+                default,
+                ..
+            } => self.generate_match_expression(value, arms, default.as_deref(), ir),
+            Expr::IfExpr {
+                condition,
+                then_expr,
+                else_expr,
+                ..
+            } => self.generate_if_expression(condition, then_expr, else_expr, ir),
+            Expr::Block {
+                statements, tail, ..
+            } => {
+                // The block's locals go out of scope once its value is computed
+                let outer = self.variables.clone();
+                if self.generate_statements(statements, ir) {
+                    let after_label = self.fresh_label();
+                    ir.push_str(&format!("after.{}:\n", after_label));
+                }
+                let value = self.generate_expression(tail, ir);
+                self.variables = outer;
+                value
+            }
             Expr::ModuleAccess { item, .. } => {
                 // Enhanced but stable module access
                 item.clone()
@@ -1360,66 +2444,191 @@ impl CodeGenerator {
         }
     }
 
-    fn generate_field_access(&mut self, object: &Expr, field: &str, ir: &mut String) -> String {
-        let object_type = self.infer_expression_type(object);
-
-        // For field access, we need the pointer to the struct, not the loaded value
-        let object_ptr = match object {
-            Expr::Identifier { name, .. } => {
-                // For variables, get the pointer directly
-                if let Some((_, _, var_id)) = self.variables.get(name) {
-                    format!("%{}", var_id)
-                } else {
-                    eprintln!("Error: Variable '{}' not found", name);
-                    return "0".to_string();
-                }
-            }
-            _ => {
-                // For other expressions, generate normally (this might need more cases)
-                self.generate_expression(object, ir)
-            }
+    // `f(args)` where `f` holds a function pointer: load it and call through it
+    fn generate_indirect_call(&mut self, callee: &Expr, args: &[Expr], ir: &mut String) -> String {
+        let Some(Type::Function(params, return_type)) = self.indirect_callee_type(callee) else {
+            return "0".to_string();
         };
+        let pointer = self.generate_expression(callee, ir);
+        let mut arg_values = Vec::new();
+        for (arg, param_type) in args.iter().zip(&params) {
+            let arg_value = self.generate_expression(arg, ir);
+            arg_values.push(format!("{} {}", self.get_llvm_type(param_type), arg_value));
+        }
+        if *return_type == Type::Void {
+            ir.push_str(&format!(
+                "  call void {}({})\n",
+                pointer,
+                arg_values.join(", ")
+            ));
+            return String::new();
+        }
+        let id = self.fresh_id();
+        ir.push_str(&format!(
+            "  %{} = call {} {}({})\n",
+            id,
+            self.get_llvm_type(&return_type),
+            pointer,
+            arg_values.join(", ")
+        ));
+        format!("%{}", id)
+    }
 
-        if let Some(struct_name) = self.get_struct_name_from_type(&object_type) {
-            if let Some(struct_fields) = self.structs.get(struct_name) {
-                // Find field index
-                if let Some((field_index, (_, field_type))) = struct_fields
-                    .iter()
-                    .enumerate()
-                    .find(|(_, (field_name, _))| field_name == field)
-                {
-                    let field_llvm_type = self.get_llvm_type(field_type);
-                    let struct_llvm_type = self.get_llvm_type(struct_name);
-
-                    // Generate getelementptr for field access
-                    let gep_id = self.fresh_id();
-                    ir.push_str(&format!(
-                        "  %{} = getelementptr inbounds {}, {}* {}, i32 0, i32 {}\n",
-                        gep_id, struct_llvm_type, struct_llvm_type, object_ptr, field_index
-                    ));
-
-                    // Load the field value
-                    let load_id = self.fresh_id();
-                    ir.push_str(&format!(
-                        "  %{} = load {}, {}* %{}\n",
-                        load_id, field_llvm_type, field_llvm_type, gep_id
-                    ));
+    // `object.method(args)` calls `Struct_method(&object, args)`
+    fn generate_method_call(&mut self, callee: &Expr, args: &[Expr], ir: &mut String) -> String {
+        let Expr::FieldAccess { object, field, .. } = callee else {
+            return "0".to_string();
+        };
+        let Some(method) = self.method_function(callee) else {
+            self.error(format!("Undefined method '{}'", field));
+            return "0".to_string();
+        };
+        let (params, return_type) = self.functions[&method].clone();
 
-                    format!("%{}", load_id)
+        // A reference already holds the receiver's address
+        let receiver = if matches!(self.infer_expression_type(object), Type::Ref(..)) {
+            self.generate_expression(object, ir)
+        } else {
+            self.generate_address(object, ir)
+        };
+        let mut arg_values = vec![format!("{} {}", self.get_llvm_type(&params[0]), receiver)];
+        for (arg, param_type) in args.iter().zip(&params[1..]) {
+            let arg_value = self.generate_expression(arg, ir);
+            arg_values.push(format!("{} {}", self.get_llvm_type(param_type), arg_value));
+        }
+        if return_type == Type::Void {
+            ir.push_str(&format!(
+                "  call void @{}({})\n",
+                method,
+                arg_values.join(", ")
+            ));
+            return String::new();
+        }
+        let id = self.fresh_id();
+        ir.push_str(&format!(
+            "  %{} = call {} @{}({})\n",
+            id,
+            self.get_llvm_type(&return_type),
+            method,
+            arg_values.join(", ")
+        ));
+        format!("%{}", id)
+    }
+
+    fn generate_field_access(&mut self, object: &Expr, field: &str, ir: &mut String) -> String {
+        let Some((field_ptr, field_type)) = self.generate_field_ptr(object, field, ir) else {
+            return "0".to_string();
+        };
+
+        // Load the field value
+        let field_llvm_type = self.get_llvm_type(&field_type);
+        let load_id = self.fresh_id();
+        ir.push_str(&format!(
+            "  %{} = load {}, {}* {}\n",
+            load_id, field_llvm_type, field_llvm_type, field_ptr
+        ));
+
+        format!("%{}", load_id)
+    }
+
+    // Address of `object.field` together with the field's Zen type
+    fn generate_field_ptr(
+        &mut self,
+        object: &Expr,
+        field: &str,
+        ir: &mut String,
+    ) -> Option<(String, Type)> {
+        let object_type = self.infer_expression_type(object);
+
+        // For field access, we need the pointer to the struct, not the loaded value;
+        // a reference already holds that pointer
+        let object_ptr = if matches!(object_type, Type::Ref(..)) {
+            self.generate_expression(object, ir)
+        } else {
+            self.generate_address(object, ir)
+        };
+
+        let Some(struct_name) = self.get_struct_name_from_type(&object_type) else {
+            self.error(format!(
+                "Cannot access field '{}' on non-struct type '{}'",
+                field, object_type
+            ));
+            return None;
+        };
+        let Some(struct_fields) = self.structs.get(struct_name) else {
+            self.error(format!("Undefined struct '{}'", struct_name));
+            return None;
+        };
+
+        // Find field index
+        let Some((field_index, (_, field_type))) = struct_fields
+            .iter()
+            .enumerate()
+            .find(|(_, (field_name, _))| field_name == field)
+        else {
+            self.error(format!(
+                "Field '{}' does not exist in struct '{}'",
+                field, struct_name
+            ));
+            return None;
+        };
+        let field_type = field_type.clone();
+        let struct_llvm_type = self.get_llvm_type(&Type::Struct(struct_name.to_string()));
+
+        // Generate getelementptr for field access
+        let gep_id = self.fresh_id();
+        ir.push_str(&format!(
+            "  %{} = getelementptr inbounds {}, {}* {}, i32 0, i32 {}\n",
+            gep_id, struct_llvm_type, struct_llvm_type, object_ptr, field_index
+        ));
+
+        Some((format!("%{}", gep_id), field_type))
+    }
+
+    // Pointer to the storage of an lvalue; other expressions are spilled to a temporary
+    fn generate_address(&mut self, expr: &Expr, ir: &mut String) -> String {
+        match expr {
+            Expr::Identifier { name, .. } => {
+                if let Some(pointer) = self.variable_pointer(name) {
+                    pointer
                 } else {
-                    eprintln!(
-                        "Error: Field '{}' does not exist in struct '{}'",
-                        field, struct_name
-                    );
-                    "0".to_string()
+                    self.error(format!("Undefined variable '{}'", name));
+                    "undef".to_string()
                 }
-            } else {
-                eprintln!("Error: Struct '{}' not found", struct_name);
-                "0".to_string()
             }
-        } else {
-            eprintln!("Error: Cannot access field '{}' on non-struct type", field);
-            "0".to_string()
+            Expr::FieldAccess { object, field, .. } => self
+                .generate_field_ptr(object, field, ir)
+                .map(|(ptr, _)| ptr)
+                .unwrap_or_else(|| "null".to_string()),
+            Expr::ArrayAccess { array, index, .. } => self.generate_element_ptr(array, index, ir),
+            // The place behind `*p` lives at the address stored in `p`
+            Expr::Deref { expr: pointer, .. } => {
+                let pointer_type = self.infer_expression_type(pointer);
+                let address = self.generate_expression(pointer, ir);
+                if matches!(pointer_type, Type::Nullable(_)) {
+                    self.generate_null_check(&address, &self.get_llvm_type(&pointer_type), ir);
+                }
+                address
+            }
+            Expr::ArrayRepeat { value, count, .. } => self.generate_array_repeat(value, *count, ir),
+            Expr::ArrayLiteral { elements, .. } => self.generate_array_literal(elements, ir),
+            // Struct literals are already built in their own stack slot
+            Expr::StructLiteral {
+                struct_name,
+                fields,
+                ..
+            } => self.generate_struct_literal(struct_name, fields, ir),
+            _ => {
+                let llvm_type = self.get_llvm_type(&self.infer_expression_type(expr));
+                let value = self.generate_expression(expr, ir);
+                let alloc_id = self.fresh_id();
+                ir.push_str(&format!("  %{} = alloca {}\n", alloc_id, llvm_type));
+                ir.push_str(&format!(
+                    "  store {} {}, {}* %{}\n",
+                    llvm_type, value, llvm_type, alloc_id
+                ));
+                format!("%{}", alloc_id)
+            }
         }
     }
 
@@ -1433,7 +2642,7 @@ impl CodeGenerator {
         let struct_fields = if let Some(fields) = self.structs.get(struct_name) {
             fields.clone()
         } else {
-            eprintln!("Error: Undefined struct '{}'", struct_name);
+            self.error(format!("Undefined struct '{}'", struct_name));
             return "null".to_string();
         };
 
@@ -1452,16 +2661,16 @@ impl CodeGenerator {
                 let field_value = self.generate_expression(field_expr, ir);
                 field_values.push(field_value);
             } else {
-                eprintln!(
-                    "Warning: Missing field '{}' in struct '{}' literal",
+                self.error(format!(
+                    "Missing field '{}' in struct '{}' literal",
                     field_name, struct_name
-                );
+                ));
                 field_values.push("0".to_string());
             }
         }
 
         // Create struct constant
-        let struct_llvm_type = self.get_llvm_type(struct_name);
+        let struct_llvm_type = self.get_llvm_type(&Type::Struct(struct_name.to_string()));
 
         // Allocate space for the struct
         let alloc_id = self.fresh_id();
@@ -1487,27 +2696,385 @@ impl CodeGenerator {
     }
 
     fn generate_array_access(&mut self, array: &Expr, index: &Expr, ir: &mut String) -> String {
-        let array_val = self.generate_expression(array, ir);
+        let element_type = self
+            .array_element_type(&self.infer_expression_type(array))
+            .unwrap_or(Type::I32);
+        let element_llvm_type = self.get_llvm_type(&element_type);
+        let element_ptr = self.generate_element_ptr(array, index, ir);
+
+        let load_id = self.fresh_id();
+        ir.push_str(&format!(
+            "  %{} = load {}, {}* {}\n",
+            load_id, element_llvm_type, element_llvm_type, element_ptr
+        ));
+
+        format!("%{}", load_id)
+    }
+
+    fn generate_element_ptr(&mut self, array: &Expr, index: &Expr, ir: &mut String) -> String {
+        let array_type = self.infer_expression_type(array);
+        // Fixed-size arrays are indexed in place; a reference already points at one
+        let (array_val, array_llvm_type) = match &array_type {
+            Type::Array(..) => (
+                self.generate_address(array, ir),
+                self.get_llvm_type(&array_type),
+            ),
+            Type::Ref(inner, _) if matches!(inner.as_ref(), Type::Array(..)) => (
+                self.generate_expression(array, ir),
+                self.get_llvm_type(inner),
+            ),
+            _ => {
+                let element_llvm_type =
+                    self.get_llvm_type(&self.array_element_type(&array_type).unwrap_or(Type::I32));
+                let array_val = self.generate_expression(array, ir);
+                let index_val = self.generate_expression(index, ir);
+
+                // Anything else is treated as a pointer to its first element
+                let id = self.fresh_id();
+                ir.push_str(&format!(
+                    "  %{} = getelementptr inbounds {}, {}* {}, i32 {}\n",
+                    id, element_llvm_type, element_llvm_type, array_val, index_val
+                ));
+                return format!("%{}", id);
+            }
+        };
         let index_val = self.generate_expression(index, ir);
+        if self.debug_bounds {
+            if let Some(length) = array_length(&array_type) {
+                self.generate_bounds_check(&index_val, length, ir);
+            }
+        }
 
-        // For now, simple implementation - would need more sophisticated handling
         let id = self.fresh_id();
         ir.push_str(&format!(
-            "  %{} = getelementptr inbounds i32, i32* {}, i32 {}\n",
-            id, array_val, index_val
+            "  %{} = getelementptr inbounds {}, {}* {}, i32 0, i32 {}\n",
+            id, array_llvm_type, array_llvm_type, array_val, index_val
         ));
+        format!("%{}", id)
+    }
 
-        let load_id = self.fresh_id();
-        ir.push_str(&format!("  %{} = load i32, i32* %{}\n", load_id, id));
+    // Stores each element into a new stack array and returns its address
+    fn generate_array_literal(&mut self, elements: &[Expr], ir: &mut String) -> String {
+        let element_type = elements
+            .first()
+            .map(|element| self.infer_expression_type(element))
+            .unwrap_or(Type::I32);
+        let element_llvm_type = self.get_llvm_type(&element_type);
+        let array_llvm_type = format!("[{} x {}]", elements.len(), element_llvm_type);
+
+        let array_id = self.fresh_id();
+        ir.push_str(&format!("  %{} = alloca {}\n", array_id, array_llvm_type));
+        for (i, element) in elements.iter().enumerate() {
+            let value = self.generate_expression(element, ir);
+            let slot = self.fresh_id();
+            ir.push_str(&format!(
+                "  %{} = getelementptr inbounds {}, {}* %{}, i32 0, i32 {}\n",
+                slot, array_llvm_type, array_llvm_type, array_id, i
+            ));
+            ir.push_str(&format!(
+                "  store {} {}, {}* %{}\n",
+                element_llvm_type, value, element_llvm_type, slot
+            ));
+        }
 
-        format!("%{}", load_id)
+        format!("%{}", array_id)
     }
 
-    fn get_struct_name_from_type<'a>(&self, zen_type: &'a str) -> Option<&'a str> {
-        if self.structs.contains_key(zen_type) {
-            Some(zen_type)
+    // Fills a new stack array with `count` copies of `value` and returns its address
+    fn generate_array_repeat(&mut self, value: &Expr, count: usize, ir: &mut String) -> String {
+        let element_llvm_type = self.get_llvm_type(&self.infer_expression_type(value));
+        let array_llvm_type = format!("[{} x {}]", count, element_llvm_type);
+        let element = self.generate_expression(value, ir);
+
+        let array_id = self.fresh_id();
+        ir.push_str(&format!("  %{} = alloca {}\n", array_id, array_llvm_type));
+        let index_id = self.fresh_id();
+        ir.push_str(&format!("  %{} = alloca i32\n", index_id));
+        ir.push_str(&format!("  store i32 0, i32* %{}\n", index_id));
+
+        let label = self.fresh_label();
+        ir.push_str(&format!("  br label %fill.{}\n", label));
+        ir.push_str(&format!("fill.{}:\n", label));
+        let current = self.fresh_id();
+        ir.push_str(&format!("  %{} = load i32, i32* %{}\n", current, index_id));
+        let done = self.fresh_id();
+        ir.push_str(&format!(
+            "  %{} = icmp slt i32 %{}, {}\n",
+            done, current, count
+        ));
+        ir.push_str(&format!(
+            "  br i1 %{}, label %fill_body.{}, label %fill_end.{}\n",
+            done, label, label
+        ));
+
+        ir.push_str(&format!("fill_body.{}:\n", label));
+        let slot = self.fresh_id();
+        ir.push_str(&format!(
+            "  %{} = getelementptr inbounds {}, {}* %{}, i32 0, i32 %{}\n",
+            slot, array_llvm_type, array_llvm_type, array_id, current
+        ));
+        ir.push_str(&format!(
+            "  store {} {}, {}* %{}\n",
+            element_llvm_type, element, element_llvm_type, slot
+        ));
+        let next = self.fresh_id();
+        ir.push_str(&format!("  %{} = add i32 %{}, 1\n", next, current));
+        ir.push_str(&format!("  store i32 %{}, i32* %{}\n", next, index_id));
+        ir.push_str(&format!("  br label %fill.{}\n", label));
+        ir.push_str(&format!("fill_end.{}:\n", label));
+
+        format!("%{}", array_id)
+    }
+
+    // An integer operand of a float operation converted to double
+    fn convert_to_double(&mut self, value: &str, value_type: &Type, ir: &mut String) -> String {
+        let Type::Int(kind) = value_type else {
+            return value.to_string();
+        };
+        let op = if kind.is_signed() { "sitofp" } else { "uitofp" };
+        let id = self.fresh_id();
+        ir.push_str(&format!(
+            "  %{} = {} i{} {} to double\n",
+            id,
+            op,
+            kind.bits(),
+            value
+        ));
+        format!("%{}", id)
+    }
+
+    // `base ^ exponent` on integers by repeated multiplication; an exponent of zero or
+    // below gives 1
+    fn generate_integer_power(
+        &mut self,
+        base: &str,
+        exponent: &str,
+        int_type: &str,
+        ir: &mut String,
+    ) -> String {
+        let label = self.fresh_label();
+        let result = self.fresh_id();
+        let counter = self.fresh_id();
+        let done = self.fresh_id();
+        let next_result = self.fresh_id();
+        let next_counter = self.fresh_id();
+
+        ir.push_str(&format!("  br label %pow_entry.{}\n", label));
+        ir.push_str(&format!("pow_entry.{}:\n", label));
+        ir.push_str(&format!("  br label %pow_cond.{}\n", label));
+        ir.push_str(&format!("pow_cond.{}:\n", label));
+        ir.push_str(&format!(
+            "  %{} = phi {} [ 1, %pow_entry.{} ], [ %{}, %pow_body.{} ]\n",
+            result, int_type, label, next_result, label
+        ));
+        ir.push_str(&format!(
+            "  %{} = phi {} [ 0, %pow_entry.{} ], [ %{}, %pow_body.{} ]\n",
+            counter, int_type, label, next_counter, label
+        ));
+        ir.push_str(&format!(
+            "  %{} = icmp slt {} %{}, {}\n",
+            done, int_type, counter, exponent
+        ));
+        ir.push_str(&format!(
+            "  br i1 %{}, label %pow_body.{}, label %pow_end.{}\n",
+            done, label, label
+        ));
+        ir.push_str(&format!("pow_body.{}:\n", label));
+        ir.push_str(&format!(
+            "  %{} = mul {} %{}, {}\n",
+            next_result, int_type, result, base
+        ));
+        ir.push_str(&format!(
+            "  %{} = add {} %{}, 1\n",
+            next_counter, int_type, counter
+        ));
+        ir.push_str(&format!("  br label %pow_cond.{}\n", label));
+        ir.push_str(&format!("pow_end.{}:\n", label));
+        format!("%{}", result)
+    }
+
+    // Test the value against each pattern in turn, then merge the arm results with a phi.
+    // Without a default arm the last arm is reached unconditionally (the patterns are exhaustive).
+    fn generate_match_expression(
+        &mut self,
+        value: &Expr,
+        arms: &[(Expr, Expr)],
+        default: Option<&Expr>,
+        ir: &mut String,
+    ) -> String {
+        let value_type = self.infer_expression_type(value);
+        let value_llvm_type = self.get_llvm_type(&value_type);
+        let result_type = arms
+            .first()
+            .map(|(_, arm_value)| arm_value)
+            .or(default)
+            .map(|arm_value| self.infer_expression_type(arm_value))
+            .unwrap_or(Type::I32);
+        let result_llvm_type = self.get_llvm_type(&result_type);
+        let scrutinee = self.generate_expression(value, ir);
+
+        let label = self.fresh_label();
+        let mut cases: Vec<(Option<&Expr>, &Expr)> = arms
+            .iter()
+            .map(|(pattern, arm_value)| (Some(pattern), arm_value))
+            .collect();
+        match default {
+            Some(default_value) => cases.push((None, default_value)),
+            None => {
+                if let Some(last) = cases.last_mut() {
+                    last.0 = None;
+                }
+            }
+        }
+
+        let mut incoming = Vec::new();
+        for (i, (pattern, arm_value)) in cases.iter().enumerate() {
+            if let Some(pattern) = pattern {
+                let pattern_value = self.generate_expression(pattern, ir);
+                let matched = self.fresh_id();
+                ir.push_str(&format!(
+                    "  %{} = icmp eq {} {}, {}\n",
+                    matched, value_llvm_type, scrutinee, pattern_value
+                ));
+                ir.push_str(&format!(
+                    "  br i1 %{}, label %match_arm.{}.{}, label %match_test.{}.{}\n",
+                    matched,
+                    label,
+                    i,
+                    label,
+                    i + 1
+                ));
+            } else {
+                ir.push_str(&format!("  br label %match_arm.{}.{}\n", label, i));
+            }
+
+            ir.push_str(&format!("match_arm.{}.{}:\n", label, i));
+            let result = self.generate_expression(arm_value, ir);
+            // The arm value may open blocks of its own, so end on a known label for the phi
+            ir.push_str(&format!("  br label %match_arm_end.{}.{}\n", label, i));
+            ir.push_str(&format!("match_arm_end.{}.{}:\n", label, i));
+            ir.push_str(&format!("  br label %match_end.{}\n", label));
+            incoming.push(format!("[ {}, %match_arm_end.{}.{} ]", result, label, i));
+
+            if pattern.is_some() {
+                ir.push_str(&format!("match_test.{}.{}:\n", label, i + 1));
+            }
+        }
+
+        ir.push_str(&format!("match_end.{}:\n", label));
+        let id = self.fresh_id();
+        ir.push_str(&format!(
+            "  %{} = phi {} {}\n",
+            id,
+            result_llvm_type,
+            incoming.join(", ")
+        ));
+        format!("%{}", id)
+    }
+
+    // Evaluates only the taken branch and merges the two results with a phi
+    fn generate_if_expression(
+        &mut self,
+        condition: &Expr,
+        then_expr: &Expr,
+        else_expr: &Expr,
+        ir: &mut String,
+    ) -> String {
+        let result_llvm_type = self.get_llvm_type(&self.infer_expression_type(then_expr));
+        let cond_value = self.generate_expression(condition, ir);
+        let label = self.fresh_label();
+        ir.push_str(&format!(
+            "  br i1 {}, label %if_then.{}, label %if_else.{}\n",
+            cond_value, label, label
+        ));
+
+        let mut incoming = Vec::new();
+        for (branch, value) in [("then", then_expr), ("else", else_expr)] {
+            ir.push_str(&format!("if_{}.{}:\n", branch, label));
+            let result = self.generate_expression(value, ir);
+            // A branch may open blocks of its own, so end on a known label for the phi
+            ir.push_str(&format!("  br label %if_{}_end.{}\n", branch, label));
+            ir.push_str(&format!("if_{}_end.{}:\n", branch, label));
+            ir.push_str(&format!("  br label %if_end.{}\n", label));
+            incoming.push(format!("[ {}, %if_{}_end.{} ]", result, branch, label));
+        }
+
+        ir.push_str(&format!("if_end.{}:\n", label));
+        let id = self.fresh_id();
+        ir.push_str(&format!(
+            "  %{} = phi {} {}\n",
+            id,
+            result_llvm_type,
+            incoming.join(", ")
+        ));
+        format!("%{}", id)
+    }
+
+    // Tests each pattern in turn against the scrutinee, evaluated once; an
+    // unmatched value runs the default arm, or nothing without one
+    fn generate_match_statement(
+        &mut self,
+        value: &Expr,
+        arms: &[(Expr, Vec<Stmt>)],
+        default: Option<&[Stmt]>,
+        ir: &mut String,
+    ) {
+        let value_type = self.infer_expression_type(value);
+        let value_llvm_type = self.get_llvm_type(&value_type);
+        let scrutinee = self.generate_expression(value, ir);
+
+        let label = self.fresh_label();
+        for (i, (pattern, body)) in arms.iter().enumerate() {
+            let pattern_value = self.generate_expression(pattern, ir);
+            let matched = self.fresh_id();
+            ir.push_str(&format!(
+                "  %{} = icmp eq {} {}, {}\n",
+                matched, value_llvm_type, scrutinee, pattern_value
+            ));
+            ir.push_str(&format!(
+                "  br i1 %{}, label %match_arm.{}.{}, label %match_test.{}.{}\n",
+                matched,
+                label,
+                i,
+                label,
+                i + 1
+            ));
+
+            ir.push_str(&format!("match_arm.{}.{}:\n", label, i));
+            if !self.generate_body(body, ir) {
+                ir.push_str(&format!("  br label %match_end.{}\n", label));
+            }
+            ir.push_str(&format!("match_test.{}.{}:\n", label, i + 1));
+        }
+
+        if let Some(default_body) = default {
+            if !self.generate_body(default_body, ir) {
+                ir.push_str(&format!("  br label %match_end.{}\n", label));
+            }
         } else {
-            None
+            ir.push_str(&format!("  br label %match_end.{}\n", label));
+        }
+        ir.push_str(&format!("match_end.{}:\n", label));
+    }
+
+    // Element type of an array, or of the array behind a reference
+    fn array_element_type(&self, array_type: &Type) -> Option<Type> {
+        match array_type {
+            Type::Array(element, _) | Type::Slice(element) => Some(element.as_ref().clone()),
+            Type::Ref(inner, _) => self.array_element_type(inner),
+            _ => None,
+        }
+    }
+
+    fn get_struct_name_from_type<'a>(&self, zen_type: &'a Type) -> Option<&'a str> {
+        // Field access auto-dereferences references to structs
+        let zen_type = match zen_type {
+            Type::Ref(inner, _) => inner,
+            _ => zen_type,
+        };
+        match zen_type {
+            Type::Struct(name) if self.structs.contains_key(name) => Some(name),
+            _ => None,
         }
     }
 
@@ -1515,7 +3082,7 @@ impl CodeGenerator {
         let (_, idx) = match self.string_gen.get_string_literal(value) {
             Ok(result) => result,
             Err(e) => {
-                eprintln!("Error: {}", e);
+                self.error(e);
                 return "null".to_string();
             }
         };
@@ -1530,105 +3097,317 @@ impl CodeGenerator {
         format!("%{}", ptr_id)
     }
 
+    // The whole string is printed by one printf: text becomes the format and each hole
+    // adds a conversion for its value's type plus the value as an argument
     fn generate_interpolated_string(
         &mut self,
         parts: &[crate::ast::expr::StringPart],
+        token: &Token,
+        newline: bool,
         ir: &mut String,
     ) -> String {
-        // Simple approach: print each part separately
+        let mut format = String::new();
+        let mut args = Vec::new();
         for part in parts {
-            match part {
+            let identifier;
+            let value = match part {
                 crate::ast::expr::StringPart::Text(text) => {
-                    if !text.is_empty() {
-                        let text_literal = Expr::StringLiteral {
-                            value: text.clone(),
-                            token: crate::token::Token::new(
-                                crate::token::TokenType::StringLiteral,
-                                format!("\"{}\"", text),
-                                1,
-                                1,
-                            ),
-                        };
-                        let val = self.generate_expression(&text_literal, ir);
-                        let call_id = self.fresh_id();
-                        ir.push_str(&format!("  %{} = call i32 @printf(i8* {})\n", call_id, val));
-                    }
+                    format.push_str(&text.replace('%', "%%"));
+                    continue;
                 }
-                crate::ast::expr::StringPart::Variable(var_name) => {
-                    if let Some((var_type, _, alloc_id)) = self.variables.get(var_name).cloned() {
-                        match var_type.as_str() {
-                            "i32" => {
-                                let load_id = self.fresh_id();
-                                ir.push_str(&format!(
-                                    "  %{} = load i32, i32* %{}\n",
-                                    load_id, alloc_id
-                                ));
-                                let fmt_id = self.fresh_id();
-                                ir.push_str(&format!("  %{} = call i32 (i8*, ...) @printf(i8* getelementptr inbounds ([3 x i8], [3 x i8]* @int_fmt_no_nl, i64 0, i64 0), i32 %{})\n",
-                                    fmt_id, load_id));
-                            }
-                            "str" => {
-                                let load_id = self.fresh_id();
-                                ir.push_str(&format!(
-                                    "  %{} = load i8*, i8** %{}\n",
-                                    load_id, alloc_id
-                                ));
-                                let call_id = self.fresh_id();
-                                ir.push_str(&format!(
-                                    "  %{} = call i32 @printf(i8* %{})\n",
-                                    call_id, load_id
-                                ));
-                            }
-                            _ => {}
-                        }
-                    }
+                crate::ast::expr::StringPart::Variable(name) => {
+                    identifier = Expr::Identifier {
+                        name: name.clone(),
+                        token: token.clone(),
+                    };
+                    &identifier
                 }
-                crate::ast::expr::StringPart::Expression(expr_str) => {
-                    // For now, handle simple function calls like add(result, result)
-                    // This is a simplified implementation - in a full compiler,
-                    // we'd parse and evaluate the expression properly
-                    if expr_str.starts_with("add(") && expr_str.ends_with(')') {
-                        // Extract arguments - very basic parsing
-                        let args_str = &expr_str[4..expr_str.len() - 1];
-                        let args: Vec<&str> = args_str.split(", ").collect();
-
-                        if args.len() == 2 {
-                            // Load both arguments
-                            let mut arg_values = Vec::new();
-                            for arg in args {
-                                if let Some((_, _, alloc_id)) =
-                                    self.variables.get(arg.trim()).cloned()
-                                {
-                                    let load_id = self.fresh_id();
-                                    ir.push_str(&format!(
-                                        "  %{} = load i32, i32* %{}\n",
-                                        load_id, alloc_id
-                                    ));
-                                    arg_values.push(format!("i32 %{}", load_id));
-                                }
-                            }
+                crate::ast::expr::StringPart::Expr(expr) => expr.as_ref(),
+            };
+            let (conversion, arg) = self.generate_interpolated_value(value, ir);
+            format.push_str(conversion);
+            args.push(arg);
+        }
+        if newline {
+            format.push('\n');
+        }
 
-                            if arg_values.len() == 2 {
-                                // Call the function
-                                let call_id = self.fresh_id();
-                                ir.push_str(&format!(
-                                    "  %{} = call i32 @add({})\n",
-                                    call_id,
-                                    arg_values.join(", ")
-                                ));
+        let format_index = match self
+            .interpolation_formats
+            .iter()
+            .position(|existing| *existing == format)
+        {
+            Some(index) => index,
+            None => {
+                self.interpolation_formats.push(format.clone());
+                self.interpolation_formats.len() - 1
+            }
+        };
+        let format_len = format.len() + 1;
+        let call_id = self.fresh_id();
+        ir.push_str(&format!(
+            "  %{} = call i32 (i8*, ...) @printf(i8* getelementptr inbounds ([{} x i8], [{} x i8]* @interp_fmt.{}, i64 0, i64 0){})\n",
+            call_id,
+            format_len,
+            format_len,
+            format_index,
+            args.iter().map(|arg| format!(", {}", arg)).collect::<String>()
+        ));
+        String::new()
+    }
 
-                                // Print the result
-                                let fmt_id = self.fresh_id();
-                                ir.push_str(&format!("  %{} = call i32 (i8*, ...) @printf(i8* getelementptr inbounds ([3 x i8], [3 x i8]* @int_fmt_no_nl, i64 0, i64 0), i32 %{})\n",
-                                    fmt_id, call_id));
-                            }
-                        }
-                    }
-                }
+    // The printf conversion for one interpolated value and the typed argument it takes,
+    // widened as varargs promotion would
+    fn generate_interpolated_value(
+        &mut self,
+        value: &Expr,
+        ir: &mut String,
+    ) -> (&'static str, String) {
+        let value_type = self.infer_expression_type(value);
+        let generated = self.generate_expression(value, ir);
+        match &value_type {
+            Type::Int(kind) if kind.bits() == 64 => {
+                let conversion = if kind.is_signed() { "%lld" } else { "%llu" };
+                (conversion, format!("i64 {}", generated))
+            }
+            Type::Int(kind) => {
+                let (conversion, wide) = if kind.is_signed() {
+                    ("%d", Type::I32)
+                } else {
+                    ("%u", Type::Int(IntKind::U32))
+                };
+                let widened = self.convert_integer(&generated, &value_type, &wide, ir);
+                (conversion, format!("i32 {}", widened))
+            }
+            Type::Char => {
+                let widened = self.convert_integer(&generated, &Type::Char, &Type::I32, ir);
+                ("%c", format!("i32 {}", widened))
+            }
+            Type::Bool => {
+                let true_str = self.generate_string_literal("true", ir);
+                let false_str = self.generate_string_literal("false", ir);
+                let text = self.fresh_id();
+                ir.push_str(&format!(
+                    "  %{} = select i1 {}, i8* {}, i8* {}\n",
+                    text, generated, true_str, false_str
+                ));
+                ("%s", format!("i8* %{}", text))
+            }
+            Type::Float(FloatKind::F32) => {
+                let id = self.fresh_id();
+                ir.push_str(&format!(
+                    "  %{} = fpext float {} to double\n",
+                    id, generated
+                ));
+                ("%f", format!("double %{}", id))
+            }
+            Type::Float(_) => ("%f", format!("double {}", generated)),
+            Type::Str => ("%s", format!("i8* {}", generated)),
+            _ => {
+                self.error(format!(
+                    "Cannot interpolate a value of type '{}'",
+                    value_type
+                ));
+                ("", String::new())
             }
         }
+    }
+}
 
-        // Return empty string since we're printing directly
-        String::new()
+// Width of the LLVM integer that stores a value of this type
+fn integer_bits(zen_type: &Type) -> Option<u32> {
+    match zen_type {
+        Type::Int(kind) => Some(kind.bits()),
+        Type::Char => Some(8),
+        Type::Bool => Some(1),
+        _ => None,
+    }
+}
+
+// The LLVM constant a variable declared without an initializer starts with
+fn zero_value(zen_type: &Type) -> &'static str {
+    match zen_type {
+        Type::Float(_) => "0.0",
+        Type::Str
+        | Type::Ref(..)
+        | Type::Slice(_)
+        | Type::Nullable(_)
+        | Type::Null
+        | Type::Function(..) => "null",
+        Type::Array(..) | Type::Struct(_) => "zeroinitializer",
+        _ => "0",
+    }
+}
+
+// The length of a fixed-size array, or of the array behind a reference
+fn array_length(array_type: &Type) -> Option<usize> {
+    match array_type {
+        Type::Array(_, length) => Some(*length),
+        Type::Ref(inner, _) => array_length(inner),
+        _ => None,
+    }
+}
+
+// Imported items are merged into the program, so `module::item(...)` calls `item`
+fn callee_name(callee: &Expr) -> Option<&String> {
+    match callee {
+        Expr::Identifier { name, .. } => Some(name),
+        Expr::ModuleAccess { item, .. } => Some(item),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::lexer::Lexer;
+    use crate::parser::parser::Parser;
+
+    fn try_generate(code: &str) -> Result<String, ZenError> {
+        let tokens = Lexer::new(code).tokenize().unwrap();
+        let program = Parser::new(tokens).parse_strict().unwrap();
+        CodeGenerator::new().generate(&program)
+    }
+
+    fn generate(code: &str) -> String {
+        try_generate(code).unwrap()
+    }
+
+    #[test]
+    fn test_undefined_variable_is_a_codegen_error() {
+        let err = try_generate("fn main() -> i32 {\n    return missing\n}").unwrap_err();
+
+        match err {
+            ZenError::CodegenError { message, context } => {
+                assert_eq!(message, "Undefined variable 'missing'");
+                assert_eq!(context.as_deref(), Some("in function 'main'"));
+            }
+            other => panic!("Expected a codegen error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_borrow_yields_variable_address() {
+        let ir = generate("fn main() -> i32 {\n    let x = 5\n    let r = &x\n    return 0\n}");
+
+        assert!(ir.contains("  %0 = alloca i32\n"));
+        assert!(ir.contains("  %1 = alloca i32*\n"));
+        assert!(ir.contains("  store i32* %0, i32** %1\n"));
+    }
+
+    #[test]
+    fn test_deref_loads_through_pointer() {
+        let ir = generate(
+            "fn get(p: &i32) -> i32 {\n    return *p\n}\nfn main() -> i32 {\n    return 0\n}",
+        );
+
+        assert!(ir.contains("  %1 = load i32*, i32** %0\n"));
+        assert!(ir.contains("  %2 = load i32, i32* %1\n"));
+    }
+
+    #[test]
+    fn test_uninitialized_declarations_store_zero() {
+        let ir = generate(
+            "fn main() -> i32 {\n    let x: i32;\n    let f: f64;\n    let s: str;\n    let p: ?&i32;\n    return 0\n}",
+        );
+
+        assert!(ir.contains("  store i32 0, i32* %0\n"));
+        assert!(ir.contains("  store double 0.0, double* %1\n"));
+        assert!(ir.contains("  store i8* null, i8** %2\n"));
+        assert!(ir.contains("  store i32* null, i32** %3\n"));
+    }
+
+    #[test]
+    fn test_char_literals_store_their_byte() {
+        let ir = generate(
+            "fn main() -> i32 {\n    let a = 'A'\n    let n = '\\n'\n    let f = '\\xFF'\n    return 0\n}",
+        );
+
+        assert!(ir.contains("  store i8 65, i8* %0\n"));
+        assert!(ir.contains("  store i8 10, i8* %1\n"));
+        assert!(ir.contains("  store i8 -1, i8* %2\n"));
+    }
+
+    #[test]
+    fn test_casts_resize_integers() {
+        let ir = generate(
+            "fn main() -> i32 {\n    let c = 65 as char\n    let n = c as i32\n    let b = 0 as bool\n    let f = b as i32\n    let s = -1 as i8\n    let w = s as i64\n    return 0\n}",
+        );
+
+        assert!(ir.contains("= trunc i32 65 to i8\n"));
+        assert!(ir.contains("= zext i8 %"));
+        assert!(ir.contains("= trunc i32 0 to i1\n"));
+        assert!(ir.contains("= zext i1 %"));
+        assert!(ir.contains("= sext i8 %"));
+    }
+
+    #[test]
+    fn test_code_after_exit_is_unreachable() {
+        let ir = generate(
+            "fn main() -> i32 {\n    println(1)\n    exit(3)\n    println(2)\n    return 5\n}",
+        );
+
+        assert!(ir.contains("  call void @exit(i32 3)\n  unreachable\n}"));
+        assert!(!ir.contains("i32 2)"));
+        assert!(!ir.contains("ret i32"));
+    }
+
+    #[test]
+    fn test_diverging_call_in_branch_skips_branch_to_end() {
+        let ir = generate(
+            "fn main() -> i32 {\n    if 1 > 0 {\n        abort()\n        println(2)\n    }\n    return 0\n}",
+        );
+
+        assert!(ir.contains("  call void @abort()\n  unreachable\nend.1:\n"));
+    }
+
+    #[test]
+    fn test_if_else_returning_on_all_paths_has_no_fallback_return() {
+        let ir = generate(
+            "fn report(ok: bool) -> void {\n    if ok {\n        println(1)\n        return\n    } else {\n        println(0)\n        return\n    }\n}\n\nfn main() -> i32 {\n    report(true)\n    return 0\n}",
+        );
+
+        let report = &ir[ir.find("define void @report").unwrap()..];
+        let report = &report[..report.find("\n}\n").unwrap()];
+        assert_eq!(report.matches("ret void").count(), 2);
+        assert!(report.ends_with("  ret void"));
+        assert!(!report.contains("end."));
+    }
+
+    #[test]
+    fn test_top_level_lets_are_initialized_by_a_constructor() {
+        let ir = generate(
+            "let mut count = 1 + 2\nfn main() -> i32 {\n    count = count + 1\n    return count\n}",
+        );
+        assert!(ir.contains("@count = global i32 0\n"), "{}", ir);
+        assert!(ir.contains("void ()* @zen.init_globals"), "{}", ir);
+        assert!(ir.contains("store i32 %1, i32* @count"), "{}", ir);
+        assert!(ir.contains("load i32, i32* @count"), "{}", ir);
+    }
+
+    #[test]
+    fn test_constants_become_folded_globals() {
+        let ir = generate("const MAX: i32 = 100\nconst AREA: f64 = MAX * 1.5\nconst ON: bool = MAX > 10\nfn main() -> i32 {\n    return MAX\n}");
+        assert!(ir.contains("@MAX = constant i32 100\n"), "{}", ir);
+        assert!(
+            ir.contains("@AREA = constant double 0x4062C00000000000\n"),
+            "{}",
+            ir
+        );
+        assert!(ir.contains("@ON = constant i1 true\n"), "{}", ir);
+        assert!(ir.contains("load i32, i32* @MAX"), "{}", ir);
+    }
+
+    #[test]
+    fn test_generic_function_is_instantiated_per_type() {
+        let ir = generate(
+            "fn id<T>(x: T) -> T {\n    x\n}\n\nfn main() -> i32 {\n    let n = id(1)\n    let s = id(\"a\")\n    let m = id(2)\n    return n + m\n}",
+        );
+
+        assert!(ir.contains("define i32 @id.i32(i32 %x) {"));
+        assert!(ir.contains("define i8* @id.str(i8* %x) {"));
+        assert_eq!(ir.matches("define i32 @id.i32").count(), 1);
+        assert!(ir.contains("call i32 @id.i32(i32 2)"));
+        assert!(!ir.contains("@id("));
     }
 }
@@ -0,0 +1,132 @@
+use crate::ast::expr::Expr;
+use crate::lexer::lexer::parse_integer_literal;
+use crate::token::TokenType;
+use crate::types::{FloatKind, Type};
+
+/// A value computed at compile time, such as the initializer of a `const`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    /// Integers and chars
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+}
+
+impl Constant {
+    /// Evaluate `expr` if it is built from literals, named constants found by `lookup`,
+    /// and operators; `None` for anything else, including division by zero and overflow
+    pub fn evaluate(expr: &Expr, lookup: &dyn Fn(&str) -> Option<Constant>) -> Option<Constant> {
+        match expr {
+            Expr::IntegerLiteral { value, .. } => {
+                parse_integer_literal(value).ok().map(Constant::Int)
+            }
+            Expr::FloatLiteral { value, .. } => Some(Constant::Float(*value)),
+            Expr::BooleanLiteral { value, .. } => Some(Constant::Bool(*value)),
+            Expr::CharLiteral { value, .. } => u8::try_from(*value)
+                .ok()
+                .map(|byte| Constant::Int(byte.into())),
+            Expr::StringLiteral { value, .. } => Some(Constant::Str(value.clone())),
+            Expr::Identifier { name, .. } => lookup(name),
+            Expr::UnaryOp { op, operand } => match (&op.kind, Self::evaluate(operand, lookup)?) {
+                (TokenType::Minus, Constant::Int(value)) => value.checked_neg().map(Constant::Int),
+                (TokenType::Minus, Constant::Float(value)) => Some(Constant::Float(-value)),
+                (TokenType::Not, Constant::Bool(value)) => Some(Constant::Bool(!value)),
+                _ => None,
+            },
+            Expr::BinaryOp { op, left, right } => Self::evaluate_binary(
+                &op.kind,
+                Self::evaluate(left, lookup)?,
+                Self::evaluate(right, lookup)?,
+            ),
+            _ => None,
+        }
+    }
+
+    fn evaluate_binary(op: &TokenType, left: Constant, right: Constant) -> Option<Constant> {
+        use Constant::*;
+        match (left, right) {
+            (Int(a), Int(b)) => match op {
+                TokenType::Plus => a.checked_add(b).map(Int),
+                TokenType::Minus => a.checked_sub(b).map(Int),
+                TokenType::Star => a.checked_mul(b).map(Int),
+                TokenType::Slash => a.checked_div(b).map(Int),
+                TokenType::Percent => a.checked_rem(b).map(Int),
+                TokenType::Caret => u32::try_from(b)
+                    .ok()
+                    .and_then(|b| a.checked_pow(b))
+                    .map(Int),
+                TokenType::Ampersand => Some(Int(a & b)),
+                TokenType::Pipe => Some(Int(a | b)),
+                TokenType::CaretCaret => Some(Int(a ^ b)),
+                TokenType::ShiftLeft => u32::try_from(b)
+                    .ok()
+                    .and_then(|b| a.checked_shl(b))
+                    .map(Int),
+                TokenType::ShiftRight => u32::try_from(b)
+                    .ok()
+                    .and_then(|b| a.checked_shr(b))
+                    .map(Int),
+                _ => compare(op, a.cmp(&b)),
+            },
+            (Float(a), Float(b)) => Self::evaluate_float(op, a, b),
+            (Int(a), Float(b)) => Self::evaluate_float(op, a as f64, b),
+            (Float(a), Int(b)) => Self::evaluate_float(op, a, b as f64),
+            (Bool(a), Bool(b)) => match op {
+                TokenType::And => Some(Bool(a && b)),
+                TokenType::Or => Some(Bool(a || b)),
+                _ => compare(op, a.cmp(&b)),
+            },
+            (Str(a), Str(b)) => compare(op, a.cmp(&b)),
+            _ => None,
+        }
+    }
+
+    fn evaluate_float(op: &TokenType, a: f64, b: f64) -> Option<Constant> {
+        match op {
+            TokenType::Plus => Some(Constant::Float(a + b)),
+            TokenType::Minus => Some(Constant::Float(a - b)),
+            TokenType::Star => Some(Constant::Float(a * b)),
+            TokenType::Slash => Some(Constant::Float(a / b)),
+            TokenType::Caret => Some(Constant::Float(a.powf(b))),
+            _ => compare(op, a.partial_cmp(&b)?),
+        }
+    }
+
+    /// The LLVM constant for this value stored as `zen_type`; strings are left to the
+    /// caller, which knows where their bytes live
+    pub fn to_llvm(&self, zen_type: &Type) -> Option<String> {
+        match (self, zen_type) {
+            (Constant::Int(value), Type::Int(_)) => Some(value.to_string()),
+            // A char is one byte, written like a char literal as a signed i8
+            (Constant::Int(value), Type::Char) => Some((*value as u8 as i8).to_string()),
+            (Constant::Int(value), Type::Float(kind)) => Some(float_constant(*value as f64, *kind)),
+            (Constant::Float(value), Type::Float(kind)) => Some(float_constant(*value, *kind)),
+            (Constant::Bool(value), Type::Bool) => Some(value.to_string()),
+            _ => None,
+        }
+    }
+}
+
+fn compare(op: &TokenType, ordering: std::cmp::Ordering) -> Option<Constant> {
+    let result = match op {
+        TokenType::EqualEqual => ordering.is_eq(),
+        TokenType::NotEqual => ordering.is_ne(),
+        TokenType::LessThan => ordering.is_lt(),
+        TokenType::LessEqual => ordering.is_le(),
+        TokenType::GreaterThan => ordering.is_gt(),
+        TokenType::GreaterEqual => ordering.is_ge(),
+        _ => return None,
+    };
+    Some(Constant::Bool(result))
+}
+
+// LLVM spells float constants exactly as the bits of a double; an f32 value must be
+// one that a float can hold, so it is rounded first
+fn float_constant(value: f64, kind: FloatKind) -> String {
+    let value = match kind {
+        FloatKind::F32 => value as f32 as f64,
+        FloatKind::F64 => value,
+    };
+    format!("0x{:016X}", value.to_bits())
+}
@@ -63,6 +63,17 @@ impl StringGenerator {
                     self.collect_strings(s);
                 }
             }
+            Stmt::Loop { body, .. } => {
+                for s in body {
+                    self.collect_strings(s);
+                }
+            }
+            Stmt::ForIn { iterable, body, .. } => {
+                self.collect_strings_from_expr(iterable);
+                for s in body {
+                    self.collect_strings(s);
+                }
+            }
             Stmt::For {
                 init,
                 condition,
@@ -113,10 +124,13 @@ impl StringGenerator {
                     self.collect_strings(s);
                 }
             }
+            Stmt::Break { .. } | Stmt::Continue { .. } => {
+                // Loop control statements don't contain strings to collect
+            }
             Stmt::Use { .. } => {
                 // Use statements don't contain strings to collect
             }
-            Stmt::Mod { items, .. } => {
+            Stmt::Mod { items, .. } | Stmt::ImplBlock { methods: items, .. } => {
                 for item in items {
                     self.collect_strings(item);
                 }
@@ -144,8 +158,8 @@ impl StringGenerator {
                         crate::ast::expr::StringPart::Variable(_) => {
                             // Variables don't need string collection
                         }
-                        crate::ast::expr::StringPart::Expression(_) => {
-                            // Expressions don't need string collection for now
+                        crate::ast::expr::StringPart::Expr(expr) => {
+                            self.collect_strings_from_expr(expr);
                         }
                     }
                 }
@@ -162,9 +176,61 @@ impl StringGenerator {
                     self.collect_strings_from_expr(arg);
                 }
             }
-            Expr::OwnershipTransfer { expr, .. } => {
+            Expr::ArrayLiteral { elements, .. } => {
+                for element in elements {
+                    self.collect_strings_from_expr(element);
+                }
+            }
+            Expr::StructLiteral { fields, .. } => {
+                for (_, field_value) in fields {
+                    self.collect_strings_from_expr(field_value);
+                }
+            }
+            Expr::ArrayAccess { array, index, .. } => {
+                self.collect_strings_from_expr(array);
+                self.collect_strings_from_expr(index);
+            }
+            Expr::FieldAccess { object: expr, .. }
+            | Expr::Borrow { expr, .. }
+            | Expr::OwnershipTransfer { expr, .. }
+            | Expr::Deref { expr, .. }
+            | Expr::Cast { expr, .. }
+            | Expr::ArrayRepeat { value: expr, .. } => {
                 self.collect_strings_from_expr(expr);
             }
+            Expr::Match {
+                value,
+                arms,
+                default,
+                ..
+            } => {
+                self.collect_strings_from_expr(value);
+                for (pattern, arm_value) in arms {
+                    self.collect_strings_from_expr(pattern);
+                    self.collect_strings_from_expr(arm_value);
+                }
+                if let Some(default_value) = default {
+                    self.collect_strings_from_expr(default_value);
+                }
+            }
+            Expr::IfExpr {
+                condition,
+                then_expr,
+                else_expr,
+                ..
+            } => {
+                self.collect_strings_from_expr(condition);
+                self.collect_strings_from_expr(then_expr);
+                self.collect_strings_from_expr(else_expr);
+            }
+            Expr::Block {
+                statements, tail, ..
+            } => {
+                for s in statements {
+                    self.collect_strings(s);
+                }
+                self.collect_strings_from_expr(tail);
+            }
             Expr::ModuleAccess { .. } => {
                 // Module access doesn't contain strings to collect
             }
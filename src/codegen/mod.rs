@@ -1,4 +1,6 @@
+pub mod constant;
 pub mod ir;
+pub mod monomorphize;
 
 pub use ir::StringGenerator;
 
@@ -0,0 +1,247 @@
+use crate::ast::expr::{Expr, StringPart};
+use crate::ast::stmt::Stmt;
+use crate::types::Type;
+use std::collections::HashMap;
+
+/// Symbol for one instantiation of a generic function, e.g. `id.i32` or `first._i32`
+/// for `&i32`. Characters that LLVM identifiers cannot hold become `_`.
+pub fn instance_name(name: &str, type_args: &[Type]) -> String {
+    let mut symbol = name.to_string();
+    for type_arg in type_args {
+        symbol.push('.');
+        symbol.extend(type_arg.to_string().chars().map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        }));
+    }
+    symbol
+}
+
+/// A copy of a generic function declaration named `name` with its type parameters
+/// replaced throughout the signature and body, ready to be generated as a normal function
+pub fn specialize(decl: &Stmt, name: &str, bindings: &HashMap<String, Type>) -> Stmt {
+    let mut instance = decl.clone();
+    if let Stmt::FunctionDecl {
+        name: instance_name,
+        type_params,
+        ..
+    } = &mut instance
+    {
+        *instance_name = name.to_string();
+        type_params.clear();
+    }
+    substitute_stmt(&mut instance, bindings);
+    instance
+}
+
+fn substitute_body(body: &mut [Stmt], bindings: &HashMap<String, Type>) {
+    for stmt in body {
+        substitute_stmt(stmt, bindings);
+    }
+}
+
+fn substitute_stmt(stmt: &mut Stmt, bindings: &HashMap<String, Type>) {
+    match stmt {
+        Stmt::VariableDecl {
+            type_annotation,
+            initializer,
+            ..
+        } => {
+            if let Some(ty) = type_annotation {
+                *ty = ty.substitute(bindings);
+            }
+            if let Some(init) = initializer {
+                substitute_expr(init, bindings);
+            }
+        }
+        Stmt::ConstDecl {
+            type_annotation,
+            initializer,
+            ..
+        } => {
+            if let Some(ty) = type_annotation {
+                *ty = ty.substitute(bindings);
+            }
+            substitute_expr(initializer, bindings);
+        }
+        Stmt::Assignment { target, value, .. } => {
+            substitute_expr(target, bindings);
+            substitute_expr(value, bindings);
+        }
+        Stmt::FunctionDecl {
+            params,
+            return_type,
+            body,
+            ..
+        } => {
+            for (_, ty) in params.iter_mut() {
+                *ty = ty.substitute(bindings);
+            }
+            *return_type = return_type.substitute(bindings);
+            substitute_body(body, bindings);
+        }
+        Stmt::Return { value, .. } => {
+            if let Some(value) = value {
+                substitute_expr(value, bindings);
+            }
+        }
+        Stmt::If {
+            condition,
+            then_branch,
+            else_if_branches,
+            else_branch,
+            ..
+        } => {
+            substitute_expr(condition, bindings);
+            substitute_body(then_branch, bindings);
+            for branch in else_if_branches {
+                substitute_expr(&mut branch.condition, bindings);
+                substitute_body(&mut branch.body, bindings);
+            }
+            if let Some(else_branch) = else_branch {
+                substitute_body(else_branch, bindings);
+            }
+        }
+        Stmt::While {
+            condition, body, ..
+        } => {
+            substitute_expr(condition, bindings);
+            substitute_body(body, bindings);
+        }
+        Stmt::Loop { body, .. } => substitute_body(body, bindings),
+        Stmt::For {
+            init,
+            condition,
+            increment,
+            body,
+            ..
+        } => {
+            if let Some(init) = init {
+                substitute_stmt(init, bindings);
+            }
+            if let Some(condition) = condition {
+                substitute_expr(condition, bindings);
+            }
+            if let Some(increment) = increment {
+                substitute_expr(increment, bindings);
+            }
+            substitute_body(body, bindings);
+        }
+        Stmt::ForIn { iterable, body, .. } => {
+            substitute_expr(iterable, bindings);
+            substitute_body(body, bindings);
+        }
+        Stmt::Match {
+            value,
+            arms,
+            default,
+            ..
+        } => {
+            substitute_expr(value, bindings);
+            for (pattern, body) in arms {
+                substitute_expr(pattern, bindings);
+                substitute_body(body, bindings);
+            }
+            if let Some(default) = default {
+                substitute_body(default, bindings);
+            }
+        }
+        Stmt::Mod { items, .. } | Stmt::ImplBlock { methods: items, .. } => {
+            substitute_body(items, bindings)
+        }
+        Stmt::ExprStmt { expr } => substitute_expr(expr, bindings),
+        Stmt::Block { statements } => substitute_body(statements, bindings),
+        Stmt::Break { .. } | Stmt::Continue { .. } | Stmt::Use { .. } | Stmt::StructDecl { .. } => {
+        }
+    }
+}
+
+fn substitute_expr(expr: &mut Expr, bindings: &HashMap<String, Type>) {
+    match expr {
+        Expr::Cast {
+            expr, target_type, ..
+        } => {
+            *target_type = target_type.substitute(bindings);
+            substitute_expr(expr, bindings);
+        }
+        Expr::BinaryOp { left, right, .. } => {
+            substitute_expr(left, bindings);
+            substitute_expr(right, bindings);
+        }
+        Expr::UnaryOp { operand: expr, .. }
+        | Expr::OwnershipTransfer { expr, .. }
+        | Expr::Borrow { expr, .. }
+        | Expr::Deref { expr, .. }
+        | Expr::FieldAccess { object: expr, .. }
+        | Expr::ArrayRepeat { value: expr, .. } => substitute_expr(expr, bindings),
+        Expr::Call { callee, args, .. } => {
+            substitute_expr(callee, bindings);
+            for arg in args {
+                substitute_expr(arg, bindings);
+            }
+        }
+        Expr::ArrayAccess { array, index, .. } => {
+            substitute_expr(array, bindings);
+            substitute_expr(index, bindings);
+        }
+        Expr::ArrayLiteral { elements, .. } => {
+            for element in elements {
+                substitute_expr(element, bindings);
+            }
+        }
+        Expr::Match {
+            value,
+            arms,
+            default,
+            ..
+        } => {
+            substitute_expr(value, bindings);
+            for (pattern, arm_value) in arms {
+                substitute_expr(pattern, bindings);
+                substitute_expr(arm_value, bindings);
+            }
+            if let Some(default) = default {
+                substitute_expr(default, bindings);
+            }
+        }
+        Expr::IfExpr {
+            condition,
+            then_expr,
+            else_expr,
+            ..
+        } => {
+            substitute_expr(condition, bindings);
+            substitute_expr(then_expr, bindings);
+            substitute_expr(else_expr, bindings);
+        }
+        Expr::Block {
+            statements, tail, ..
+        } => {
+            substitute_body(statements, bindings);
+            substitute_expr(tail, bindings);
+        }
+        Expr::StructLiteral { fields, .. } => {
+            for (_, value) in fields {
+                substitute_expr(value, bindings);
+            }
+        }
+        Expr::InterpolatedString { parts, .. } => {
+            for part in parts {
+                if let StringPart::Expr(expr) = part {
+                    substitute_expr(expr, bindings);
+                }
+            }
+        }
+        Expr::IntegerLiteral { .. }
+        | Expr::FloatLiteral { .. }
+        | Expr::StringLiteral { .. }
+        | Expr::CharLiteral { .. }
+        | Expr::BooleanLiteral { .. }
+        | Expr::NullLiteral { .. }
+        | Expr::Identifier { .. }
+        | Expr::ModuleAccess { .. } => {}
+    }
+}
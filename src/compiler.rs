@@ -1,14 +1,50 @@
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Instant;
 
+use crate::ast::json::program_to_json;
+use crate::ast::program::Program;
+use crate::ast::stmt::Stmt;
 use crate::codegen::codegen::CodeGenerator;
+use crate::doc::generate_markdown;
+use crate::error::ZenError;
 use crate::lexer::lexer::Lexer;
 use crate::ownership::OwnershipChecker;
 use crate::parser::parser::Parser;
+use crate::token::Token;
 use crate::typechecker::typechecker::TypeChecker;
 
+mod tests;
+mod visibility;
+
 const LLC_CMD: &str = "llc";
 const GCC_CMD: &str = "gcc";
+// Linker driver tried when GCC is not installed
+const CLANG_CMD: &str = "clang";
+
+// Environment variables that override the llc and linker commands
+const LLC_ENV: &str = "ZEN_LLC";
+const CC_ENV: &str = "ZEN_CC";
+
+/// Target triple of the machine the compiler was built for
+pub const HOST_TARGET: &str = env!("ZEN_HOST_TARGET");
+
+pub const DEFAULT_OPT_LEVEL: u8 = 2;
+pub const MAX_OPT_LEVEL: u8 = 3;
+
+// Distinguishes temp files of compiles started in the same nanosecond
+static TEMP_SEQUENCE: AtomicUsize = AtomicUsize::new(0);
+
+// Synthetic file name used in diagnostics when the source comes from stdin
+const STDIN_FILENAME: &str = "<stdin>";
+
+/// Input name that reads the program from stdin
+pub const STDIN_INPUT: &str = "-";
+
+// Default output for stdin, which has no input name to derive one from
+const STDIN_OUTPUT_STEM: &str = "a";
 
 // RAII cleanup guard for temporary files
 struct CleanupGuard {
@@ -29,8 +65,452 @@ impl Drop for CleanupGuard {
     }
 }
 
+// Resolves `use` statements to source files and merges their declarations
+struct ModuleLoader {
+    loaded: HashSet<PathBuf>,
+    stack: Vec<PathBuf>,
+    symbols: HashMap<String, PathBuf>,
+    // Imported declarations without `pub` and the files declaring them. Only those files
+    // may use them, and they are renamed per module so that they never collide.
+    private: HashMap<String, Vec<PathBuf>>,
+    // Each loaded file with the items it refers to, checked once every file is loaded
+    references: Vec<(PathBuf, Vec<visibility::Reference>)>,
+}
+
+impl ModuleLoader {
+    fn new() -> Self {
+        Self {
+            loaded: HashSet::new(),
+            stack: Vec::new(),
+            symbols: HashMap::new(),
+            private: HashMap::new(),
+            references: Vec::new(),
+        }
+    }
+
+    /// Merges the declarations of every file imported (transitively) by
+    /// `program` into it, ahead of the program's own statements.
+    fn load_imports(&mut self, program: &mut Program, input_path: &Path) -> anyhow::Result<()> {
+        let root = canonical_path(input_path);
+        self.register_symbols(&program.statements, &root, true)?;
+        self.loaded.insert(root.clone());
+        self.stack.push(root.clone());
+
+        let imported = self.load_uses(&program.statements, &root)?;
+
+        self.stack.pop();
+        let aliases = self.aliases(&program.statements, &root)?;
+        visibility::rename(&mut program.statements, &aliases);
+        self.record_references(&program.statements, &root);
+        self.check_visibility()?;
+        program.statements.splice(0..0, imported);
+        Ok(())
+    }
+
+    fn record_references(&mut self, statements: &[Stmt], file: &Path) {
+        self.references
+            .push((file.to_path_buf(), visibility::references(statements)));
+    }
+
+    // `use module::item as alias` makes `alias` another name for `item`, which must be an
+    // item `module` exports; the file's uses of `alias` are renamed to `item`
+    fn aliases(
+        &mut self,
+        statements: &[Stmt],
+        file: &Path,
+    ) -> anyhow::Result<HashMap<String, String>> {
+        let mut aliases = HashMap::new();
+        let mut uses = Vec::new();
+        for stmt in statements {
+            let Stmt::Use {
+                path,
+                alias: Some(alias),
+                token,
+            } = stmt
+            else {
+                continue;
+            };
+            let (module, item) = match path.as_slice() {
+                [module, .., item] if item != "*" => (module, item),
+                _ => anyhow::bail!(
+                    "Cannot alias '{}' as '{}' at line {}:{} of '{}'; only an item, as in 'use module::item as {}', can be aliased",
+                    path.join("::"),
+                    alias,
+                    token.line,
+                    token.column,
+                    file.display(),
+                    alias
+                ),
+            };
+            if !self.symbols.contains_key(item) && !self.private.contains_key(item) {
+                anyhow::bail!(
+                    "Module '{}' has no item '{}' to import as '{}' at line {}:{} of '{}'",
+                    module,
+                    item,
+                    alias,
+                    token.line,
+                    token.column,
+                    file.display()
+                );
+            }
+            uses.push(visibility::Reference {
+                name: item.clone(),
+                module: Some(module.clone()),
+                token: token.clone(),
+            });
+            aliases.insert(alias.clone(), item.clone());
+        }
+        self.references.push((file.to_path_buf(), uses));
+        Ok(aliases)
+    }
+
+    // Private items may only be used by the file declaring them, and `module::item` must
+    // name an item of `module`
+    fn check_visibility(&self) -> anyhow::Result<()> {
+        for (file, references) in &self.references {
+            for visibility::Reference {
+                name,
+                module,
+                token,
+            } in references
+            {
+                let private_owners = self.private.get(name);
+                let owner = if private_owners.is_some_and(|owners| owners.contains(file)) {
+                    file
+                } else if let Some(owner) = self.symbols.get(name) {
+                    owner
+                } else if let Some(owner) = private_owners.and_then(|owners| owners.first()) {
+                    anyhow::bail!(
+                        "'{}' is private to module '{}' and cannot be used at line {}:{} of '{}'; declare it 'pub' to export it",
+                        name,
+                        module_name(owner),
+                        token.line,
+                        token.column,
+                        file.display()
+                    );
+                } else {
+                    continue;
+                };
+                let owner_module = module_name(owner);
+                if let Some(module) = module {
+                    if owner != file && *module != owner_module {
+                        anyhow::bail!(
+                            "'{}' is not declared in module '{}' at line {}:{} of '{}'; it comes from '{}'",
+                            name,
+                            module,
+                            token.line,
+                            token.column,
+                            file.display(),
+                            owner.display()
+                        );
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn load_uses(&mut self, statements: &[Stmt], current: &Path) -> anyhow::Result<Vec<Stmt>> {
+        let base_dir = current.parent().unwrap_or_else(|| Path::new("."));
+        let mut imported = Vec::new();
+
+        for stmt in statements {
+            if let Stmt::Use { path, .. } = stmt {
+                if let Some(file) = resolve_use_path(base_dir, path)? {
+                    imported.extend(self.load_file(&file)?);
+                }
+            }
+        }
+
+        Ok(imported)
+    }
+
+    fn load_file(&mut self, file: &Path) -> anyhow::Result<Vec<Stmt>> {
+        let file = canonical_path(file);
+
+        if let Some(pos) = self.stack.iter().position(|p| p == &file) {
+            let cycle: Vec<String> = self.stack[pos..]
+                .iter()
+                .chain(std::iter::once(&file))
+                .map(|p| p.display().to_string())
+                .collect();
+            anyhow::bail!("Import cycle detected: {}", cycle.join(" -> "));
+        }
+
+        if !self.loaded.insert(file.clone()) {
+            return Ok(Vec::new());
+        }
+
+        let source = std::fs::read_to_string(&file).map_err(|e| {
+            anyhow::anyhow!("Failed to read imported file '{}': {}", file.display(), e)
+        })?;
+
+        let tokens = Lexer::new(&source).tokenize().map_err(|errors| {
+            let stage = format!("Lexical analysis of '{}'", file.display());
+            render_errors(&stage, errors, &source)
+        })?;
+        let program = parse_program(&source, tokens)
+            .map_err(|e| anyhow::anyhow!("Parse error in '{}': {}", file.display(), e))?;
+
+        self.stack.push(file.clone());
+        let mut declarations = self.load_uses(&program.statements, &file)?;
+        self.stack.pop();
+
+        let aliases = self.aliases(&program.statements, &file)?;
+
+        // An imported file's own entry point is not part of its interface; the methods
+        // of its structs are
+        let mut own: Vec<Stmt> = program
+            .statements
+            .into_iter()
+            .filter(|stmt| {
+                matches!(stmt, Stmt::ImplBlock { .. })
+                    || declared_name(stmt).is_some_and(|name| name != "main")
+            })
+            .collect();
+        self.register_symbols(&own, &file, false)?;
+        visibility::rename(&mut own, &aliases);
+        self.record_references(&own, &file);
+
+        // References were recorded under the original names, so private items can now
+        // take names no other module uses
+        let module = module_name(&file);
+        let private_names: HashMap<String, String> = own
+            .iter()
+            .filter(|stmt| !is_public(stmt))
+            .filter_map(declared_name)
+            .map(|name| (name.to_string(), format!("{}__{}", module, name)))
+            .collect();
+        visibility::rename(&mut own, &private_names);
+        declarations.extend(own);
+
+        Ok(declarations)
+    }
+
+    // Every declaration of the root file is a global name, but only the `pub` ones of an
+    // imported file are
+    fn register_symbols(
+        &mut self,
+        statements: &[Stmt],
+        file: &Path,
+        is_root: bool,
+    ) -> anyhow::Result<()> {
+        for stmt in statements {
+            let Some(name) = declared_name(stmt) else {
+                continue;
+            };
+            if !is_root && !is_public(stmt) {
+                self.private
+                    .entry(name.to_string())
+                    .or_default()
+                    .push(file.to_path_buf());
+                continue;
+            }
+            if let Some(previous) = self.symbols.get(name) {
+                if previous != file {
+                    anyhow::bail!(
+                        "Duplicate symbol '{}' defined in '{}' and '{}'",
+                        name,
+                        previous.display(),
+                        file.display()
+                    );
+                }
+            }
+            self.symbols.insert(name.to_string(), file.to_path_buf());
+        }
+        Ok(())
+    }
+}
+
+fn module_name(file: &Path) -> String {
+    file.file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn canonical_path(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+// `use "dir/file.zen"` names a file directly; `use foo::bar` refers to `foo.zen`
+fn resolve_use_path(base_dir: &Path, path: &[String]) -> anyhow::Result<Option<PathBuf>> {
+    let Some(first) = path.first() else {
+        return Ok(None);
+    };
+
+    if first.ends_with(".zen") {
+        let file = base_dir.join(first);
+        if !file.exists() {
+            anyhow::bail!("Imported file '{}' does not exist", file.display());
+        }
+        return Ok(Some(file));
+    }
+
+    if matches!(first.as_str(), "crate" | "self" | "super") {
+        return Ok(None);
+    }
+
+    let file = base_dir.join(format!("{}.zen", first));
+    if !file.exists() {
+        anyhow::bail!(
+            "Cannot find module '{}' (looked for '{}')",
+            first,
+            file.display()
+        );
+    }
+    Ok(Some(file))
+}
+
+fn declared_name(stmt: &Stmt) -> Option<&str> {
+    match stmt {
+        Stmt::FunctionDecl { name, .. }
+        | Stmt::StructDecl { name, .. }
+        | Stmt::ConstDecl { name, .. } => Some(name),
+        _ => None,
+    }
+}
+
+fn is_public(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::FunctionDecl { is_public, .. }
+        | Stmt::StructDecl { is_public, .. }
+        | Stmt::ConstDecl { is_public, .. } => *is_public,
+        _ => false,
+    }
+}
+
+// Parse `tokens`, rendering any errors against the lines of `source`
+fn parse_program(source: &str, tokens: Vec<Token>) -> anyhow::Result<Program> {
+    let (program, errors) = Parser::new(tokens).parse();
+    if errors.is_empty() {
+        return Ok(program);
+    }
+    Err(render_errors("Parsing", errors, source))
+}
+
+// Type check `program`, rendering any errors against the lines of `source`
+fn type_check(
+    typechecker: &mut TypeChecker,
+    program: &Program,
+    source: &str,
+) -> anyhow::Result<()> {
+    typechecker
+        .check(program)
+        .map_err(|errors| render_errors("Type checking", errors, source))
+}
+
+// One error per paragraph, each with the source line it points at underlined
+fn render_errors(stage: &str, errors: Vec<ZenError>, source: &str) -> anyhow::Error {
+    let messages: Vec<String> = errors
+        .into_iter()
+        .map(|error| error.with_source(source).format_with_context())
+        .collect();
+    anyhow::anyhow!(
+        "{} failed with {} errors:\n{}",
+        stage,
+        messages.len(),
+        messages.join("\n")
+    )
+}
+
+// Run a compiled program with `args`; only failing to start it is an error
+fn launch_binary(binary: &Path, args: &[String]) -> anyhow::Result<std::process::Output> {
+    std::process::Command::new(binary)
+        .args(args)
+        .output()
+        .map_err(|e| anyhow::anyhow!("Failed to execute program: {}", e))
+}
+
+// The code a shell would report for `status`: 128 plus the signal for a killed program
+fn exit_code(status: std::process::ExitStatus) -> i32 {
+    #[cfg(unix)]
+    if let Some(signal) = std::os::unix::process::ExitStatusExt::signal(&status) {
+        return 128 + signal;
+    }
+    status.code().unwrap_or(1)
+}
+
+// Run a compiled program with `args`, returning its stdout and stderr on success
+fn execute_binary(binary: &Path, args: &[String]) -> anyhow::Result<(String, String)> {
+    let result = launch_binary(binary, args)?;
+
+    if !result.status.success() {
+        anyhow::bail!("Program exited with code {}", exit_code(result.status));
+    }
+
+    Ok((
+        String::from_utf8_lossy(&result.stdout).into_owned(),
+        String::from_utf8_lossy(&result.stderr).into_owned(),
+    ))
+}
+
+// The command given by `flag`, else by the environment variable `var` when set
+fn configured_tool(flag: Option<&str>, var: Option<String>) -> Option<String> {
+    flag.map(str::to_string)
+        .or_else(|| var.filter(|value| !value.is_empty()))
+}
+
+// `command` as it would be typed into a shell, to reproduce a failing step by hand
+fn command_line(command: &std::process::Command) -> String {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|arg| {
+            let arg = arg.to_string_lossy();
+            if arg.is_empty() || arg.contains(char::is_whitespace) {
+                format!("'{}'", arg)
+            } else {
+                arg.into_owned()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Whether `target` is a Windows triple, whose executables end in `.exe`
+fn is_windows_target(target: &str) -> bool {
+    target.contains("-windows")
+}
+
+// Arguments that make a GCC-style driver link `obj_path` into the executable
+// `output_path` for `target`
+fn link_args(target: &str, opt_level: u8, obj_path: &Path, output_path: &Path) -> Vec<OsString> {
+    let mut args: Vec<OsString> = Vec::new();
+    // Windows executables are always relocatable, and the C runtime and its math
+    // functions come with MinGW's or MSVC's default libraries
+    if !is_windows_target(target) {
+        args.push("-no-pie".into());
+    }
+    args.push(format!("-O{}", opt_level).into());
+    args.push(obj_path.into());
+    args.push("-o".into());
+    args.push(output_path.into());
+    if !is_windows_target(target) {
+        args.push("-lc".into());
+        // `^` on floats lowers to llvm.pow, which becomes a call to libm's pow
+        args.push("-lm".into());
+    }
+    args
+}
+
+// GCC driver that links for `target`: the system one for the host, otherwise the
+// cross toolchain named after the triple (MinGW's for Windows)
+fn linker_for(target: &str) -> String {
+    if target == HOST_TARGET {
+        return GCC_CMD.to_string();
+    }
+    match target.split_once('-') {
+        Some((arch, _)) if target.ends_with("windows-gnu") => {
+            format!("{}-w64-mingw32-{}", arch, GCC_CMD)
+        }
+        _ => format!("{}-{}", target, GCC_CMD),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct CompilationStats {
+    pub opt_level: u8,
+    pub target: String,
     pub tokens_count: usize,
     pub statements_count: usize,
     pub lexing_time: std::time::Duration,
@@ -43,9 +523,86 @@ pub struct CompilationStats {
     pub total_time: std::time::Duration,
 }
 
+impl CompilationStats {
+    /// The stats as one JSON object, with every duration in whole microseconds and
+    /// `stages_us` summing the timed stages (`total_us` also covers the work between them)
+    pub fn to_json(&self) -> String {
+        let stages = [
+            ("lexing_us", self.lexing_time),
+            ("parsing_us", self.parsing_time),
+            ("type_checking_us", self.type_checking_time),
+            ("ownership_us", self.ownership_time),
+            ("codegen_us", self.codegen_time),
+            ("llc_us", self.llc_time),
+            ("linking_us", self.linking_time),
+        ];
+        let stages_total: std::time::Duration = stages.iter().map(|(_, time)| *time).sum();
+
+        let mut fields = vec![
+            format!("\"opt_level\":{}", self.opt_level),
+            format!("\"target\":{}", crate::ast::json::string(&self.target)),
+            format!("\"tokens_count\":{}", self.tokens_count),
+            format!("\"statements_count\":{}", self.statements_count),
+        ];
+        for (key, time) in stages {
+            fields.push(format!("\"{}\":{}", key, time.as_micros()));
+        }
+        fields.push(format!("\"stages_us\":{}", stages_total.as_micros()));
+        fields.push(format!("\"total_us\":{}", self.total_time.as_micros()));
+        format!("{{{}}}", fields.join(","))
+    }
+}
+
+/// Where `compile` stops: a linked binary, or one of the intermediate files
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, clap::ValueEnum)]
+pub enum Emit {
+    /// Native executable
+    #[default]
+    Link,
+    /// LLVM IR text (.ll)
+    LlvmIr,
+    /// Assembly from llc (.s)
+    Asm,
+    /// Object file from llc (.o)
+    Obj,
+}
+
+impl Emit {
+    // Extension of the default output path, next to the input file
+    fn extension(self) -> &'static str {
+        match self {
+            Emit::Link => "",
+            Emit::LlvmIr => "ll",
+            Emit::Asm => "s",
+            Emit::Obj => "o",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BenchResult {
+    pub iterations: usize,
+    /// The exit code of each run, in order
+    pub exit_codes: Vec<i32>,
+    pub min: std::time::Duration,
+    pub median: std::time::Duration,
+    pub mean: std::time::Duration,
+}
+
 pub struct Compiler {
     stats: Option<CompilationStats>,
     verbose: bool,
+    warnings_as_errors: bool,
+    debug_bounds: bool,
+    entry: Option<String>,
+    emit: Emit,
+    opt_level: u8,
+    target: Option<String>,
+    llc: Option<String>,
+    cc: Option<String>,
+    keep_temps: bool,
+    kept_temps: Vec<PathBuf>,
+    run_args: Vec<String>,
 }
 
 impl Default for Compiler {
@@ -59,6 +616,17 @@ impl Compiler {
         Compiler {
             stats: None,
             verbose: false,
+            warnings_as_errors: false,
+            debug_bounds: false,
+            entry: None,
+            emit: Emit::Link,
+            opt_level: DEFAULT_OPT_LEVEL,
+            target: None,
+            llc: None,
+            cc: None,
+            keep_temps: false,
+            kept_temps: Vec::new(),
+            run_args: Vec::new(),
         }
     }
 
@@ -67,18 +635,129 @@ impl Compiler {
         self
     }
 
+    pub fn with_warnings_as_errors(mut self, warnings_as_errors: bool) -> Self {
+        self.warnings_as_errors = warnings_as_errors;
+        self
+    }
+
+    /// Check array indices against the array length at runtime
+    pub fn with_debug_bounds(mut self, debug_bounds: bool) -> Self {
+        self.debug_bounds = debug_bounds;
+        self
+    }
+
+    /// Start the program at `entry` instead of `main`
+    pub fn with_entry(mut self, entry: Option<String>) -> Self {
+        self.entry = entry;
+        self
+    }
+
+    /// Stop after writing `emit` instead of linking
+    pub fn with_emit(mut self, emit: Emit) -> Self {
+        self.emit = emit;
+        self
+    }
+
+    /// Optimization level passed to llc and the linker, from 0 to 3
+    pub fn with_opt_level(mut self, opt_level: u8) -> Self {
+        self.opt_level = opt_level;
+        self
+    }
+
+    /// Build for the target `triple` (passed to llc as `-mtriple`) instead of the host
+    pub fn with_target(mut self, triple: Option<String>) -> Self {
+        self.target = triple;
+        self
+    }
+
+    // The triple being built for, which is the host's unless `--target` names another
+    fn target(&self) -> &str {
+        self.target.as_deref().unwrap_or(HOST_TARGET)
+    }
+
+    /// Run `llc` instead of the `llc` on the PATH (`ZEN_LLC` otherwise)
+    pub fn with_llc(mut self, llc: Option<String>) -> Self {
+        self.llc = llc;
+        self
+    }
+
+    /// Link with `cc` instead of gcc or clang (`ZEN_CC` otherwise)
+    pub fn with_cc(mut self, cc: Option<String>) -> Self {
+        self.cc = cc;
+        self
+    }
+
+    // Extension of the default output path: `--emit`'s, or `.exe` for Windows binaries
+    fn output_extension(&self) -> &'static str {
+        if self.emit == Emit::Link && is_windows_target(self.target()) {
+            "exe"
+        } else {
+            self.emit.extension()
+        }
+    }
+
+    /// Leave the intermediate `.ll` and `.o` files in the temp directory
+    pub fn with_keep_temps(mut self, keep_temps: bool) -> Self {
+        self.keep_temps = keep_temps;
+        self
+    }
+
+    /// Command-line arguments `run` passes on to the program
+    pub fn with_run_args(mut self, args: Vec<String>) -> Self {
+        self.run_args = args;
+        self
+    }
+
+    /// Intermediate files retained by the last compile under `--keep-temps`
+    pub fn kept_temps(&self) -> &[PathBuf] {
+        &self.kept_temps
+    }
+
     pub fn get_stats(&self) -> Option<&CompilationStats> {
         self.stats.as_ref()
     }
 
     pub fn compile(input: &str, output: Option<&str>) -> anyhow::Result<()> {
         let mut compiler = Compiler::new().with_verbose(true);
-        compiler.compile_internal(input, output)
+        compiler.compile_file(input, output)
     }
 
-    fn compile_internal(&mut self, input: &str, output: Option<&str>) -> anyhow::Result<()> {
-        let total_start = Instant::now();
+    /// Compile a file using this compiler's settings
+    pub fn compile_file(&mut self, input: &str, output: Option<&str>) -> anyhow::Result<()> {
+        self.compile_internal(input, output)
+    }
+
+    /// Compile source read from standard input, to `a.out` (or `a.ll`, `a.s`, `a.o`
+    /// with `--emit`) unless an output path is given
+    pub fn compile_stdin(&mut self, output: Option<&str>) -> anyhow::Result<()> {
+        let output_path = output
+            .map(PathBuf::from)
+            .unwrap_or_else(|| self.stdin_output_path());
+        self.compile_from_reader(std::io::stdin().lock(), &output_path)
+    }
 
+    fn stdin_output_path(&self) -> PathBuf {
+        match self.emit {
+            Emit::Link if !is_windows_target(self.target()) => {
+                PathBuf::from(format!("{}.out", STDIN_OUTPUT_STEM))
+            }
+            _ => PathBuf::from(STDIN_OUTPUT_STEM).with_extension(self.output_extension()),
+        }
+    }
+
+    fn compile_from_reader(
+        &mut self,
+        mut reader: impl std::io::Read,
+        output_path: &Path,
+    ) -> anyhow::Result<()> {
+        let mut source = String::new();
+        reader
+            .read_to_string(&mut source)
+            .map_err(|e| anyhow::anyhow!("Failed to read from stdin: {}", e))?;
+        self.compile_source(&source, STDIN_FILENAME, output_path)
+    }
+
+    fn compile_internal(&mut self, input: &str, output: Option<&str>) -> anyhow::Result<()> {
         // Validate input file
         let input_path = std::path::Path::new(input);
         if !input_path.exists() {
@@ -88,34 +767,53 @@ impl Compiler {
         let source = std::fs::read_to_string(input)
             .map_err(|e| anyhow::anyhow!("Failed to read input file '{}': {}", input, e))?;
 
+        let output_path = match output {
+            Some(out) => PathBuf::from(out),
+            None => input_path.with_extension(self.output_extension()),
+        };
+
+        self.compile_source(&source, input, &output_path)
+    }
+
+    // Run the whole pipeline on `source`; `input` names it in diagnostics and anchors `use` lookups
+    fn compile_source(
+        &mut self,
+        source: &str,
+        input: &str,
+        output_path: &Path,
+    ) -> anyhow::Result<()> {
+        let total_start = Instant::now();
+        let input_path = Path::new(input);
+        if self.opt_level > MAX_OPT_LEVEL {
+            anyhow::bail!(
+                "Invalid optimization level {}; expected 0 to {}",
+                self.opt_level,
+                MAX_OPT_LEVEL
+            );
+        }
+
         if self.verbose {
             println!("Compiling: {} ({} bytes)", input, source.len());
         }
 
         // Lexical Analysis
         let lexing_start = Instant::now();
-        let mut lexer = Lexer::new(&source);
-        let tokens = match lexer.tokenize() {
-            Ok(tokens) => tokens,
-            Err(errors) => {
-                for error in &errors {
-                    eprintln!("Lexical error: {}", error);
-                }
-                anyhow::bail!("Lexical analysis failed with {} errors", errors.len());
-            }
-        };
+        let mut lexer = Lexer::new(source);
+        let tokens = lexer
+            .tokenize()
+            .map_err(|errors| render_errors("Lexical analysis", errors, source))?;
         let lexing_time = lexing_start.elapsed();
 
+        // The parser takes the tokens over, so count them first
+        let tokens_count = tokens.len();
         if self.verbose {
-            println!("info: {} tokens found", tokens.len());
+            println!("info: {} tokens found", tokens_count);
         }
 
         // Syntax Analysis
         let parsing_start = Instant::now();
-        let mut parser = Parser::new(tokens.clone());
-        let program = parser
-            .parse()
-            .map_err(|e| anyhow::anyhow!("Parse error: {}", e))?;
+        let mut program = parse_program(source, tokens)?;
+        ModuleLoader::new().load_imports(&mut program, input_path)?;
         let parsing_time = parsing_start.elapsed();
 
         if self.verbose {
@@ -125,10 +823,9 @@ impl Compiler {
 
         // Semantic Analysis
         let type_checking_start = Instant::now();
-        let mut typechecker = TypeChecker::new();
-        typechecker
-            .check(&program)
-            .map_err(|e| anyhow::anyhow!("Type error: {}", e))?;
+        let mut typechecker = TypeChecker::new().with_entry(self.entry.clone());
+        type_check(&mut typechecker, &program, source)?;
+        self.check_warnings(&typechecker)?;
         let type_checking_time = type_checking_start.elapsed();
 
         if self.verbose {
@@ -149,26 +846,34 @@ impl Compiler {
 
         // Code Generation
         let codegen_start = Instant::now();
-        let mut codegen = CodeGenerator::new();
-        let llvm_ir = codegen.generate(&program);
+        let mut codegen = CodeGenerator::new()
+            .with_debug_bounds(self.debug_bounds)
+            .with_entry(self.entry.clone());
+        let llvm_ir = codegen
+            .generate(&program)
+            .map_err(|e| anyhow::anyhow!("{}", e.format_with_context()))?;
         let codegen_time = codegen_start.elapsed();
 
-        // Prepare paths
-        let output_path = if let Some(out) = output {
-            std::path::PathBuf::from(out)
-        } else {
-            input_path.with_extension("")
-        };
+        if self.emit == Emit::LlvmIr {
+            std::fs::write(output_path, &llvm_ir)
+                .map_err(|e| anyhow::anyhow!("Failed to write LLVM IR: {}", e))?;
+            if self.verbose {
+                println!("success: Wrote LLVM IR: {}", output_path.display());
+            }
+            return Ok(());
+        }
 
-        // Use more unique temporary file names
+        // Unique per process, time and compile, so concurrent compiles never collide
         let temp_dir = std::env::temp_dir();
         let process_id = std::process::id();
         let timestamp = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap_or_default()
             .as_nanos();
-        let ll_path = temp_dir.join(format!("zen_{}_{}.ll", process_id, timestamp));
-        let obj_path = temp_dir.join(format!("zen_{}_{}.o", process_id, timestamp));
+        let sequence = TEMP_SEQUENCE.fetch_add(1, Ordering::Relaxed);
+        let stem = format!("zen_{}_{}_{}", process_id, timestamp, sequence);
+        let ll_path = temp_dir.join(format!("{}.ll", stem));
+        let obj_path = temp_dir.join(format!("{}.o", stem));
 
         if self.verbose {
             println!("Compiling {}...", input);
@@ -178,51 +883,55 @@ impl Compiler {
         std::fs::write(&ll_path, &llvm_ir)
             .map_err(|e| anyhow::anyhow!("Failed to write LLVM IR: {}", e))?;
 
-        // Ensure cleanup happens even on error
-        let _cleanup = CleanupGuard::new(vec![ll_path.clone(), obj_path.clone()]);
+        // Ensure cleanup happens even on error, unless the files are wanted
+        let temps = vec![ll_path.clone(), obj_path.clone()];
+        let _cleanup = if self.keep_temps {
+            for path in &temps {
+                println!("Keeping: {}", path.display());
+            }
+            self.kept_temps = temps;
+            CleanupGuard::new(Vec::new())
+        } else {
+            CleanupGuard::new(temps)
+        };
 
         // Debug: Also write to a persistent file for inspection
-        if self.verbose {
+        if self.verbose && input != STDIN_FILENAME {
             let debug_path = format!("{}.ll", input.trim_end_matches(".zen"));
             let _ = std::fs::write(&debug_path, &llvm_ir);
             println!("Debug: LLVM IR written to {}", debug_path);
         }
 
         // LLVM Compilation
+        // Assembly and object output go straight to the requested path
+        let (filetype, llc_output) = match self.emit {
+            Emit::Asm => ("asm", output_path),
+            Emit::Obj => ("obj", output_path),
+            Emit::Link | Emit::LlvmIr => ("obj", obj_path.as_path()),
+        };
         let llc_start = Instant::now();
-        let llc_result = std::process::Command::new(LLC_CMD)
-            .arg("-filetype=obj")
-            .arg("-O2") // Add optimization
-            .arg("-o")
-            .arg(&obj_path)
-            .arg(&ll_path)
-            .output()
-            .map_err(|e| anyhow::anyhow!("Failed to execute llc: {}", e))?;
+        self.llc(&ll_path, filetype, llc_output)?;
         let llc_time = llc_start.elapsed();
 
-        if !llc_result.status.success() {
-            let stderr = std::str::from_utf8(&llc_result.stderr).unwrap_or("Invalid UTF-8");
-            anyhow::bail!("llc compilation failed: {}", stderr);
+        if self.emit != Emit::Link {
+            if self.verbose {
+                println!("success: Wrote {}: {}", filetype, output_path.display());
+            }
+            return Ok(());
         }
 
         // Linking
         let linking_start = Instant::now();
-        let linker_result = std::process::Command::new(GCC_CMD)
-            .arg("-no-pie")
-            .arg("-O2") // Add optimization
-            .arg(&obj_path)
-            .arg("-o")
-            .arg(&output_path)
-            .arg("-lc")
-            .output()
-            .map_err(|e| anyhow::anyhow!("Failed to execute linker: {}", e))?;
+        self.link(&obj_path, output_path)?;
         let linking_time = linking_start.elapsed();
 
         let total_time = total_start.elapsed();
 
         // Store statistics
         self.stats = Some(CompilationStats {
-            tokens_count: tokens.len(),
+            opt_level: self.opt_level,
+            target: self.target().to_string(),
+            tokens_count,
             statements_count: program.statements.len(),
             lexing_time,
             parsing_time,
@@ -234,22 +943,99 @@ impl Compiler {
             total_time,
         });
 
-        if linker_result.status.success() {
-            if self.verbose {
-                println!("success: Compiled: {}", output_path.display());
-                self.print_stats();
-            }
-        } else {
-            let stderr = std::str::from_utf8(&linker_result.stderr).unwrap_or("Invalid UTF-8");
-            anyhow::bail!("linking failed: {}", stderr);
+        if self.verbose {
+            println!("success: Compiled: {}", output_path.display());
+            self.print_stats();
+        }
+
+        Ok(())
+    }
+
+    // Compile the IR in `ll_path` with llc into `output`, as assembly or an object file
+    fn llc(&self, ll_path: &Path, filetype: &str, output: &Path) -> anyhow::Result<()> {
+        let llc_cmd = configured_tool(self.llc.as_deref(), std::env::var(LLC_ENV).ok())
+            .unwrap_or_else(|| LLC_CMD.to_string());
+        let mut llc = std::process::Command::new(&llc_cmd);
+        if let Some(triple) = &self.target {
+            llc.arg(format!("-mtriple={}", triple));
         }
+        llc.arg(format!("-filetype={}", filetype))
+            .arg(format!("-O{}", self.opt_level))
+            .arg("-o")
+            .arg(output)
+            .arg(ll_path);
 
+        let result = self
+            .run_tool(&mut llc)
+            .map_err(|e| anyhow::anyhow!("Failed to execute llc '{}': {}", llc_cmd, e))?;
+        if !result.status.success() {
+            let stderr = std::str::from_utf8(&result.stderr).unwrap_or("Invalid UTF-8");
+            anyhow::bail!(
+                "llc compilation failed: {}\ncommand: {}",
+                stderr.trim_end(),
+                command_line(&llc)
+            );
+        }
         Ok(())
     }
 
+    // Link `obj_path` into `output_path` with the configured linker, or else the first of
+    // GCC and clang that is installed
+    fn link(&self, obj_path: &Path, output_path: &Path) -> anyhow::Result<()> {
+        let configured = configured_tool(self.cc.as_deref(), std::env::var(CC_ENV).ok());
+        let candidates = match &configured {
+            Some(cc) => vec![cc.clone()],
+            None => vec![linker_for(self.target()), CLANG_CMD.to_string()],
+        };
+
+        for linker in &candidates {
+            let mut command = std::process::Command::new(linker);
+            if linker == CLANG_CMD && self.target() != HOST_TARGET {
+                command.arg(format!("--target={}", self.target()));
+            }
+            command.args(link_args(
+                self.target(),
+                self.opt_level,
+                obj_path,
+                output_path,
+            ));
+            match self.run_tool(&mut command) {
+                Ok(output) if output.status.success() => return Ok(()),
+                Ok(output) => {
+                    let stderr = std::str::from_utf8(&output.stderr).unwrap_or("Invalid UTF-8");
+                    anyhow::bail!(
+                        "linking failed: {}\ncommand: {}",
+                        stderr.trim_end(),
+                        command_line(&command)
+                    );
+                }
+                Err(e) if configured.is_none() && e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => anyhow::bail!("Failed to execute linker '{}': {}", linker, e),
+            }
+        }
+        anyhow::bail!(
+            "No linker found (tried {}); install gcc or clang, or set {} to a C compiler",
+            candidates.join(", "),
+            CC_ENV
+        )
+    }
+
+    // Run an external tool, echoing its command line under --verbose
+    fn run_tool(
+        &self,
+        command: &mut std::process::Command,
+    ) -> std::io::Result<std::process::Output> {
+        if self.verbose {
+            println!("$ {}", command_line(command));
+        }
+        command.output()
+    }
+
     fn print_stats(&self) {
         if let Some(stats) = &self.stats {
             println!("\nCompilation Statistics:");
+            println!("  Optimization: -O{}", stats.opt_level);
+            println!("  Target: {}", stats.target);
             println!("  Tokens: {}", stats.tokens_count);
             println!("  Statements: {}", stats.statements_count);
             println!("  Lexing: {:?}", stats.lexing_time);
@@ -262,53 +1048,212 @@ impl Compiler {
         }
     }
 
-    pub fn run(input: &str) -> anyhow::Result<()> {
+    /// Compile and run a file, returning the program's exit code
+    pub fn run(input: &str) -> anyhow::Result<i32> {
         let mut compiler = Compiler::new().with_verbose(false);
-        compiler.run_internal(input)
+        compiler.run_file(input)
     }
 
-    fn run_internal(&mut self, input: &str) -> anyhow::Result<()> {
-        let input_path = PathBuf::from(input);
-        let output_path = input_path.with_extension("");
+    /// Compile and run a file using this compiler's settings, returning the program's
+    /// exit code. Errors are reserved for failing to build or start it.
+    pub fn run_file(&mut self, input: &str) -> anyhow::Result<i32> {
+        self.run_internal(input)
+    }
 
-        // Compile first
-        self.compile_internal(input, None)?;
+    fn run_internal(&mut self, input: &str) -> anyhow::Result<i32> {
+        let binary = self.build_for_run(input)?;
+        // A program read from stdin has nowhere to keep its binary
+        let _cleanup = CleanupGuard::new(if input == STDIN_INPUT {
+            vec![binary.clone()]
+        } else {
+            Vec::new()
+        });
 
-        let output_path_abs = std::env::current_dir()?.join(&output_path);
-        let output_path_str = output_path_abs.to_string_lossy();
+        if self.target() != HOST_TARGET {
+            println!(
+                "note: not running {}: it was built for {}, but this host is {}",
+                binary.display(),
+                self.target(),
+                HOST_TARGET
+            );
+            return Ok(0);
+        }
 
         if self.verbose {
-            println!("Running: {}", output_path_str);
+            println!("Running: {}", binary.display());
         }
 
         // Execute with timeout and resource monitoring
         let execution_start = std::time::Instant::now();
-        let result = std::process::Command::new(&*output_path_str)
-            .output()
-            .map_err(|e| anyhow::anyhow!("Failed to execute program: {}", e))?;
+        let result = launch_binary(&binary, &self.run_args)?;
         let execution_time = execution_start.elapsed();
 
-        if !result.status.success() {
-            let exit_code = result.status.code().unwrap_or(-1);
-            anyhow::bail!("Program exited with code {}", exit_code);
+        // Output program results
+        print!("{}", String::from_utf8_lossy(&result.stdout));
+        eprint!("{}", String::from_utf8_lossy(&result.stderr));
+        // The caller may exit with the program's code, which skips flushing
+        let _ = std::io::Write::flush(&mut std::io::stdout());
+
+        if self.verbose {
+            println!("\nExecution completed in {:?}", execution_time);
         }
 
-        // Output program results
-        let stdout = std::str::from_utf8(&result.stdout).unwrap_or("Invalid UTF-8");
-        let stderr = std::str::from_utf8(&result.stderr).unwrap_or("Invalid UTF-8");
+        Ok(exit_code(result.status))
+    }
+
+    /// Compile and run a Zen file, returning its stdout instead of printing it
+    pub fn run_capture(input: &str) -> anyhow::Result<String> {
+        let mut compiler = Compiler::new().with_verbose(false);
+        let binary = compiler.build_for_run(input)?;
+        let (stdout, _) = execute_binary(&binary, &[])?;
+        Ok(stdout)
+    }
 
-        print!("{}", stdout);
-        if !stderr.is_empty() {
-            eprint!("{}", stderr);
+    // Compile next to the input and return the absolute path of the binary
+    fn build_for_run(&mut self, input: &str) -> anyhow::Result<PathBuf> {
+        if input == STDIN_INPUT {
+            return self.build_from_reader(std::io::stdin().lock());
         }
+        // Only host binaries are run, so the host decides whether they need `.exe`
+        let output_path = PathBuf::from(input).with_extension(std::env::consts::EXE_EXTENSION);
+        self.compile_internal(input, output_path.to_str())?;
+        Ok(std::env::current_dir()?.join(output_path))
+    }
 
-        if self.verbose {
-            println!("\nExecution completed in {:?}", execution_time);
+    // Compile source from `reader` into a binary in the temp directory
+    fn build_from_reader(&mut self, reader: impl std::io::Read) -> anyhow::Result<PathBuf> {
+        let output_path = std::env::temp_dir()
+            .join(format!("zen_stdin_{}", std::process::id()))
+            .with_extension(std::env::consts::EXE_EXTENSION);
+        self.compile_from_reader(reader, &output_path)?;
+        Ok(output_path)
+    }
+
+    pub fn bench(&mut self, input: &str, iterations: usize) -> anyhow::Result<()> {
+        let result = self.bench_internal(input, iterations)?;
+
+        println!("Benchmark: {} ({} iterations)", input, result.iterations);
+        println!("  {:<8} {:>12}", "metric", "time");
+        println!("  {:<8} {:>12}", "min", format!("{:?}", result.min));
+        println!("  {:<8} {:>12}", "median", format!("{:?}", result.median));
+        println!("  {:<8} {:>12}", "mean", format!("{:?}", result.mean));
+        if let Some(code) = result.exit_codes.iter().find(|code| **code != 0) {
+            println!("note: the program exited with code {}", code);
         }
 
         Ok(())
     }
 
+    fn bench_internal(&mut self, input: &str, iterations: usize) -> anyhow::Result<BenchResult> {
+        if iterations == 0 {
+            anyhow::bail!("Benchmark needs at least one iteration");
+        }
+
+        // Compile once, then time only the executions. A nonzero exit code is the
+        // program's result, not a failure, so it is recorded rather than reported.
+        let binary = self.build_for_run(input)?;
+        let mut samples = Vec::with_capacity(iterations);
+        let mut exit_codes = Vec::with_capacity(iterations);
+        for _ in 0..iterations {
+            let start = Instant::now();
+            let status = std::process::Command::new(&binary)
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .status()
+                .map_err(|e| anyhow::anyhow!("Failed to execute program: {}", e))?;
+            samples.push(start.elapsed());
+            exit_codes.push(exit_code(status));
+        }
+        samples.sort();
+
+        let total: std::time::Duration = samples.iter().sum();
+        Ok(BenchResult {
+            iterations,
+            exit_codes,
+            min: samples[0],
+            median: samples[samples.len() / 2],
+            mean: total / iterations as u32,
+        })
+    }
+
+    /// Write markdown docs for the public items of a file, next to it unless `output` is given
+    pub fn doc(input: &str, output: Option<&str>) -> anyhow::Result<PathBuf> {
+        let input_path = Path::new(input);
+        let program = Self::parse_source_file(input)?;
+
+        let title = input_path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| input.to_string());
+        let output_path = match output {
+            Some(out) => PathBuf::from(out),
+            None => input_path.with_extension("md"),
+        };
+        std::fs::write(&output_path, generate_markdown(&program, &title)).map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to write documentation '{}': {}",
+                output_path.display(),
+                e
+            )
+        })?;
+        Ok(output_path)
+    }
+
+    /// Type and ownership check a file and its imports without generating code, failing
+    /// on warnings just as compiling it would
+    pub fn check(&self, input: &str) -> anyhow::Result<()> {
+        let source = Self::read_source_file(input)?;
+        let mut program = Self::parse_source(&source)?;
+        ModuleLoader::new().load_imports(&mut program, Path::new(input))?;
+        let mut typechecker = TypeChecker::new().with_entry(self.entry.clone());
+        type_check(&mut typechecker, &program, &source)?;
+        self.check_warnings(&typechecker)?;
+        OwnershipChecker::new()
+            .check(&program)
+            .map_err(|e| anyhow::anyhow!("Ownership error: {}", e))
+    }
+
+    fn check_warnings(&self, typechecker: &TypeChecker) -> anyhow::Result<()> {
+        if self.warnings_as_errors && !typechecker.warnings().is_empty() {
+            anyhow::bail!(
+                "{} warning(s) treated as errors (--warnings-as-errors)",
+                typechecker.warnings().len()
+            );
+        }
+        Ok(())
+    }
+
+    /// Print the parsed program, as a debug tree or as JSON
+    pub fn ast(input: &str, json: bool) -> anyhow::Result<()> {
+        let program = Self::parse_source_file(input)?;
+        if json {
+            println!("{}", program_to_json(&program));
+        } else {
+            println!("{:#?}", program.statements);
+        }
+        Ok(())
+    }
+
+    fn parse_source_file(input: &str) -> anyhow::Result<Program> {
+        Self::parse_source(&Self::read_source_file(input)?)
+    }
+
+    fn read_source_file(input: &str) -> anyhow::Result<String> {
+        if !Path::new(input).exists() {
+            anyhow::bail!("Input file '{}' does not exist", input);
+        }
+
+        std::fs::read_to_string(input)
+            .map_err(|e| anyhow::anyhow!("Failed to read input file '{}': {}", input, e))
+    }
+
+    fn parse_source(source: &str) -> anyhow::Result<Program> {
+        let tokens = Lexer::new(source)
+            .tokenize()
+            .map_err(|errors| render_errors("Lexical analysis", errors, source))?;
+        parse_program(source, tokens)
+    }
+
     pub fn tokenize(input: &str) -> anyhow::Result<()> {
         let compiler = Compiler::new().with_verbose(true);
         compiler.tokenize_internal(input)
@@ -333,7 +1278,7 @@ impl Compiler {
             Ok(toks) => toks,
             Err(errs) => {
                 for err in &errs {
-                    eprintln!("Lexical error: {}", err);
+                    eprintln!("{}", err.format_with_context());
                 }
                 eprintln!("Tokenization failed");
                 return Ok(());
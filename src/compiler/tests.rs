@@ -0,0 +1,1415 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests {
+    use crate::compiler::{
+        configured_tool, execute_binary, link_args, Compiler, Emit, HOST_TARGET,
+    };
+    use std::path::{Path, PathBuf};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("zen_test_{}_{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_file(dir: &std::path::Path, name: &str, code: &str) -> String {
+        let path = dir.join(name);
+        std::fs::write(&path, code).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn compile_and_run(dir: &std::path::Path, input: &str) -> String {
+        let output = dir.join("program");
+        let mut compiler = Compiler::new();
+        compiler
+            .compile_internal(input, Some(output.to_str().unwrap()))
+            .expect("compilation should succeed");
+        let result = std::process::Command::new(&output)
+            .output()
+            .expect("program should run");
+        String::from_utf8_lossy(&result.stdout).into_owned()
+    }
+
+    #[test]
+    fn test_use_imports_function_from_other_file() {
+        let dir = temp_dir("use_module");
+        write_file(
+            &dir,
+            "math.zen",
+            r#"
+            pub fn add(a: i32, b: i32) -> i32 {
+                return a + b + zero()
+            }
+
+            fn zero() -> i32 {
+                return 0
+            }
+
+            fn main() -> i32 {
+                return 1
+            }
+        "#,
+        );
+        let main = write_file(
+            &dir,
+            "main.zen",
+            r#"
+            use math;
+
+            fn main() -> i32 {
+                let sum = add(2, 3)
+                println(sum)
+                println(math::add(4, 5))
+                return 0
+            }
+        "#,
+        );
+
+        assert_eq!(compile_and_run(&dir, &main), "5\n9\n");
+    }
+
+    #[test]
+    fn test_private_items_stay_in_their_module() {
+        let dir = temp_dir("use_private");
+        write_file(
+            &dir,
+            "math.zen",
+            "pub fn add(a: i32, b: i32) -> i32 { return a + b }\nfn secret() -> i32 { return 42 }\nconst LIMIT: i32 = 10",
+        );
+        let compile = |body: &str| {
+            let main = write_file(
+                &dir,
+                "main.zen",
+                &format!(
+                    "use math;\nfn main() -> i32 {{\n    {}\n    return 0\n}}",
+                    body
+                ),
+            );
+            Compiler::new().compile_internal(&main, dir.join("program").to_str())
+        };
+
+        let err = compile("println(secret())").unwrap_err().to_string();
+        assert!(
+            err.starts_with("'secret' is private to module 'math' and cannot be used at line 3:13"),
+            "{}",
+            err
+        );
+        assert!(err.ends_with("declare it 'pub' to export it"), "{}", err);
+        let err = compile("println(math::LIMIT)").unwrap_err().to_string();
+        assert!(
+            err.starts_with("'LIMIT' is private to module 'math'"),
+            "{}",
+            err
+        );
+        let err = compile("println(other::add(1, 2))")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.starts_with("'add' is not declared in module 'other'"),
+            "{}",
+            err
+        );
+        // A local of the same name is not the private constant
+        assert!(compile("let LIMIT = 3\n    println(LIMIT)").is_ok());
+    }
+
+    #[test]
+    fn test_private_items_do_not_collide_with_the_importer() {
+        let dir = temp_dir("use_private_names");
+        write_file(
+            &dir,
+            "mathy.zen",
+            "struct Pair {\n    a: i32,\n    b: i32,\n}\nimpl Pair {\n    fn sum(self) -> i32 {\n        return self.a + self.b\n    }\n}\nconst BASE: i32 = 100\nfn helper(x: i32) -> i32 {\n    let p = Pair { a: x, b: BASE }\n    return p.sum()\n}\npub fn add(a: i32, b: i32) -> i32 {\n    return helper(a) + b\n}",
+        );
+        let main = write_file(
+            &dir,
+            "main.zen",
+            "use mathy;\nstruct Pair {\n    x: i32,\n}\nconst BASE: i32 = 7\nfn helper(x: i32) -> i32 {\n    return x * 2\n}\nfn main() -> i32 {\n    println(helper(3))\n    println(mathy::add(1, 2))\n    let p = Pair { x: BASE }\n    println(p.x)\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &main), "6\n103\n7\n");
+    }
+
+    #[test]
+    fn test_use_as_binds_an_alias() {
+        let dir = temp_dir("use_alias");
+        write_file(
+            &dir,
+            "mathx.zen",
+            "pub fn add(a: i32, b: i32) -> i32 { return a + b }\npub const PI: f64 = 3.5\nfn secret() -> i32 { return 42 }",
+        );
+        let compile = |uses: &str, body: &str| {
+            let main = write_file(
+                &dir,
+                "main.zen",
+                &format!(
+                    "{}\nfn main() -> i32 {{\n    {}\n    return 0\n}}",
+                    uses, body
+                ),
+            );
+            Compiler::new().compile_internal(&main, dir.join("program").to_str())
+        };
+
+        compile(
+            "use mathx::add as sum;\nuse mathx::PI as MATH_PI;",
+            "println(sum(1, 2))\n    println(\"{MATH_PI}\")",
+        )
+        .unwrap();
+        let output = std::process::Command::new(dir.join("program"))
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "3\n3.500000\n");
+
+        let err = compile("use mathx::secret as s;", "println(s())")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.starts_with("'secret' is private to module 'mathx'"),
+            "{}",
+            err
+        );
+        let err = compile("use mathx::mul as m;", "println(m(1, 2))")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.starts_with("Module 'mathx' has no item 'mul' to import as 'm' at line 1:1"),
+            "{}",
+            err
+        );
+        let err = compile("use mathx as m;", "println(1)")
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("Cannot alias 'mathx' as 'm'"), "{}", err);
+    }
+
+    #[test]
+    fn test_use_file_path_import() {
+        let dir = temp_dir("use_file");
+        std::fs::create_dir_all(dir.join("lib")).unwrap();
+        write_file(
+            &dir,
+            "lib/helpers.zen",
+            "pub fn twice(x: i32) -> i32 { return x * 2 }",
+        );
+        let main = write_file(
+            &dir,
+            "main.zen",
+            r#"
+            use "lib/helpers.zen";
+
+            fn main() -> i32 {
+                println(twice(21))
+                return 0
+            }
+        "#,
+        );
+
+        assert_eq!(compile_and_run(&dir, &main), "42\n");
+    }
+
+    #[test]
+    fn test_import_cycle_is_rejected() {
+        let dir = temp_dir("use_cycle");
+        write_file(&dir, "a.zen", "use b;\nfn from_a() -> i32 { return 1 }");
+        write_file(&dir, "b.zen", "use a;\nfn from_b() -> i32 { return 2 }");
+        let main = write_file(&dir, "main.zen", "use a;\nfn main() -> i32 { return 0 }");
+
+        let err = Compiler::new()
+            .compile_internal(&main, Some(dir.join("program").to_str().unwrap()))
+            .unwrap_err();
+        assert!(err.to_string().contains("Import cycle detected"));
+    }
+
+    #[test]
+    fn test_duplicate_symbol_across_files_is_rejected() {
+        let dir = temp_dir("use_duplicate");
+        write_file(
+            &dir,
+            "math.zen",
+            "pub fn add(a: i32, b: i32) -> i32 { return a + b }",
+        );
+        let main = write_file(
+            &dir,
+            "main.zen",
+            r#"
+            use math;
+            fn add(a: i32, b: i32) -> i32 { return a - b }
+            fn main() -> i32 { return 0 }
+        "#,
+        );
+
+        let err = Compiler::new()
+            .compile_internal(&main, Some(dir.join("program").to_str().unwrap()))
+            .unwrap_err();
+        assert!(err.to_string().contains("Duplicate symbol 'add'"));
+    }
+
+    #[test]
+    fn test_continue_in_for_loop_runs_increment() {
+        let dir = temp_dir("for_continue");
+        let main = write_file(
+            &dir,
+            "main.zen",
+            r#"
+            fn main() -> i32 {
+                let mut sum = 0
+                for (let mut i = 0; i < 10; i = i + 1) {
+                    if i % 2 == 0 {
+                        continue
+                    }
+                    sum = sum + i
+                }
+                println(sum)
+                return 0
+            }
+        "#,
+        );
+
+        assert_eq!(compile_and_run(&dir, &main), "25\n");
+    }
+
+    #[test]
+    fn test_for_init_reuses_existing_variable() {
+        let dir = temp_dir("for_init_reuse");
+        let main = write_file(
+            &dir,
+            "main.zen",
+            r#"
+            fn main() -> i32 {
+                let mut i = 10
+                for (i = 0; i < 3; i = i + 1) {
+                    println(i)
+                }
+                println(i)
+                for (j = 5; j < 7; j = j + 1) {
+                    println(j)
+                }
+                return 0
+            }
+        "#,
+        );
+
+        assert_eq!(compile_and_run(&dir, &main), "0\n1\n2\n3\n5\n6\n");
+    }
+
+    #[test]
+    fn test_run_capture_returns_stdout() {
+        let dir = temp_dir("run_capture");
+        let main = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    println(7)\n    return 0\n}",
+        );
+
+        assert_eq!(Compiler::run_capture(&main).unwrap(), "7\n");
+    }
+
+    #[test]
+    fn test_bench_runs_requested_iterations() {
+        let dir = temp_dir("bench");
+        let main = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    println(1)\n    return 3\n}",
+        );
+
+        // Each run is a real execution, and a nonzero exit code does not stop the bench
+        let result = Compiler::new().bench_internal(&main, 3).unwrap();
+        assert_eq!(result.iterations, 3);
+        assert_eq!(result.exit_codes, vec![3, 3, 3]);
+        assert!(result.min <= result.median);
+        assert!(result.min <= result.mean);
+        assert!(Compiler::new().bench_internal(&main, 0).is_err());
+
+        // The configured tools build the benchmarked binary
+        let err = Compiler::new()
+            .with_llc(Some("false".to_string()))
+            .bench_internal(&main, 1)
+            .unwrap_err();
+        assert!(err.to_string().contains("llc"), "{}", err);
+        let unused = write_file(
+            &dir,
+            "unused.zen",
+            "fn main() -> i32 {\n    let x = 1\n    return 0\n}",
+        );
+        assert!(Compiler::new()
+            .with_warnings_as_errors(true)
+            .bench_internal(&unused, 1)
+            .is_err());
+    }
+
+    #[test]
+    fn test_compile_from_stdin_reader() {
+        let dir = temp_dir("stdin");
+        let output = dir.join("program");
+        let source = "fn main() -> i32 {\n    println(3)\n    return 0\n}";
+
+        Compiler::new()
+            .compile_from_reader(source.as_bytes(), &output)
+            .expect("compilation should succeed");
+        assert!(output.exists());
+
+        let result = std::process::Command::new(&output).output().unwrap();
+        assert_eq!(String::from_utf8_lossy(&result.stdout), "3\n");
+    }
+
+    #[test]
+    fn test_run_builds_stdin_program_in_temp_dir() {
+        let source = "fn main() -> i32 {\n    println(4)\n    return 0\n}";
+        let binary = Compiler::new()
+            .build_from_reader(source.as_bytes())
+            .expect("compilation should succeed");
+        assert!(binary.starts_with(std::env::temp_dir()));
+
+        let result = std::process::Command::new(&binary).output().unwrap();
+        let _ = std::fs::remove_file(&binary);
+        assert_eq!(String::from_utf8_lossy(&result.stdout), "4\n");
+
+        assert_eq!(Compiler::new().stdin_output_path(), PathBuf::from("a.out"));
+        assert_eq!(
+            Compiler::new().with_emit(Emit::Asm).stdin_output_path(),
+            PathBuf::from("a.s")
+        );
+    }
+
+    #[test]
+    fn test_struct_fields_read_through_reference() {
+        let dir = temp_dir("borrow");
+        let main = write_file(
+            &dir,
+            "main.zen",
+            r#"
+            struct Point { x: i32, y: i32 }
+
+            fn sum(p: &Point) -> i32 {
+                return p.x + p.y
+            }
+
+            fn main() -> i32 {
+                let pt = Point { x: 3, y: 4 }
+                println(sum(&pt))
+                return 0
+            }
+        "#,
+        );
+
+        assert_eq!(compile_and_run(&dir, &main), "7\n");
+    }
+
+    #[test]
+    fn test_deref_reads_and_writes_through_references() {
+        let dir = temp_dir("deref");
+        let main = write_file(
+            &dir,
+            "main.zen",
+            r#"
+            fn set(p: &mut i32, v: i32) -> void {
+                *p = v
+            }
+
+            fn show(p: &i32) -> void {
+                println(*p + 1)
+            }
+
+            fn bump(p: &mut i32) -> void {
+                let old = *p
+                *p = old + 1
+            }
+
+            fn main() -> i32 {
+                let mut x = 1
+                set(&mut x, 41)
+                println(x)
+                let y = 9
+                show(&y)
+                let mut z = 41
+                bump(&mut z)
+                println(z)
+                return 0
+            }
+        "#,
+        );
+
+        assert_eq!(compile_and_run(&dir, &main), "41\n10\n42\n");
+    }
+
+    #[test]
+    fn test_null_dereference_traps_at_runtime() {
+        let dir = temp_dir("null_deref");
+        let read = r#"
+            fn read(p: ?&i32) -> i32 {
+                return *p
+            }
+        "#;
+        let ok = write_file(
+            &dir,
+            "ok.zen",
+            &format!(
+                "{}\nfn main() -> i32 {{\n    let x = 4\n    println(read(&x))\n    return 0\n}}",
+                read
+            ),
+        );
+        assert_eq!(compile_and_run(&dir, &ok), "4\n");
+
+        let null = write_file(
+            &dir,
+            "null.zen",
+            &format!(
+                "{}\nfn main() -> i32 {{\n    println(1)\n    println(read(null))\n    return 0\n}}",
+                read
+            ),
+        );
+        let output = dir.join("null_program");
+        Compiler::new()
+            .compile_internal(&null, Some(output.to_str().unwrap()))
+            .expect("compilation should succeed");
+        // Output is piped, so earlier lines only appear if the trap flushes them
+        let result = std::process::Command::new(&output).output().unwrap();
+        assert!(!result.status.success());
+        assert_eq!(String::from_utf8_lossy(&result.stdout), "1\n");
+        assert_eq!(
+            String::from_utf8_lossy(&result.stderr),
+            "null pointer dereference\n"
+        );
+    }
+
+    #[test]
+    fn test_todo_flushes_output_and_reports_on_stderr() {
+        let dir = temp_dir("todo");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    println(1)\n    todo()\n}",
+        );
+        let output = dir.join("program");
+        Compiler::new()
+            .compile_internal(&input, Some(output.to_str().unwrap()))
+            .expect("compilation should succeed");
+        let result = std::process::Command::new(&output).output().unwrap();
+        assert!(!result.status.success());
+        assert_eq!(String::from_utf8_lossy(&result.stdout), "1\n");
+        assert_eq!(
+            String::from_utf8_lossy(&result.stderr),
+            "not yet implemented\n"
+        );
+    }
+
+    #[test]
+    fn test_nullable_references_compare_and_reassign_to_null() {
+        let dir = temp_dir("null_compare");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn is_set(p: ?&i32) -> bool {\n    return p != null\n}\nfn none() -> ?&i32 {\n    return null\n}\nfn main() -> i32 {\n    let x = 5\n    let mut p: ?&i32 = null\n    if p == null {\n        println(\"empty\")\n    }\n    p = &x\n    if null != p {\n        println(*p)\n    }\n    p = null\n    println(is_set(p))\n    println(none() == null)\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &input), "empty\n5\nfalse\ntrue\n");
+    }
+
+    #[test]
+    fn test_casts_between_integers_chars_and_bools() {
+        let dir = temp_dir("casts");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    let c = 65 as char\n    println(c as i32)\n    println('B' as i32)\n    let flag = true\n    println(flag as i32)\n    let z = 0 as bool\n    println(z)\n    println(300 as char as i32)\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &input), "65\n66\n1\nfalse\n44\n");
+    }
+
+    #[test]
+    fn test_doubled_braces_print_literally() {
+        let dir = temp_dir("brace_escape");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    let x = 5\n    println(\"{{x}}\")\n    println(\"{x}\")\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &input), "{x}\n5\n");
+    }
+
+    #[test]
+    fn test_declaration_chain_declares_each_variable() {
+        let dir = temp_dir("let_chain");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    let a = 1, b = 2, c = a + b\n    println(a)\n    println(b)\n    println(c)\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &input), "1\n2\n3\n");
+    }
+
+    #[test]
+    fn test_array_repeat_fills_every_element() {
+        let dir = temp_dir("array_repeat");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    let a = [1; 5]\n    let first = a[0]\n    let last = a[4]\n    println(first + last)\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &input), "2\n");
+    }
+
+    #[test]
+    fn test_match_expression_selects_arm_value() {
+        let dir = temp_dir("match_expression");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn describe(n: i32) -> i32 {\n    let s = match n { 1 => \"one\", _ => \"many\" }\n    println(s)\n    return 0\n}\nfn main() -> i32 {\n    describe(1)\n    describe(7)\n    let b = 2 > 1\n    let n = match b { true => 10, false => 20 }\n    println(n)\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &input), "one\nmany\n10\n");
+    }
+
+    #[test]
+    fn test_match_expression_prints_directly() {
+        let dir = temp_dir("match_println");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    let x = 5\n    println(match x { 5 => 7, _ => 8 })\n    println(match x { 1 => \"one\", _ => \"other\" })\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &input), "7\nother\n");
+    }
+
+    #[test]
+    fn test_for_in_sums_array_elements() {
+        let dir = temp_dir("for_in");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    let mut sum = 0\n    for x in [1, 2, 3] {\n        sum = sum + x\n    }\n    println(sum)\n    let a = [4; 2]\n    for x in a {\n        println(x)\n    }\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &input), "6\n4\n4\n");
+    }
+
+    #[test]
+    fn test_declaration_without_initializer_is_zero() {
+        let dir = temp_dir("zero_init");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    let x: i32;\n    println(x)\n    let mut n: i32\n    n = n + 7\n    println(n)\n    let b: bool;\n    println(b as i32)\n    let a: [i32; 3];\n    let last = a[2]\n    println(last)\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &input), "0\n7\n0\n0\n");
+    }
+
+    #[test]
+    fn test_struct_addition_calls_add_function() {
+        let dir = temp_dir("struct_add");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "struct Vec2 {\n    x: i32,\n    y: i32\n}\n\nfn Vec2_add(a: Vec2, b: Vec2) -> Vec2 {\n    return Vec2 { x: a.x + b.x, y: a.y + b.y }\n}\n\nfn main() -> i32 {\n    let a = Vec2 { x: 1, y: 2 }\n    let b = Vec2 { x: 10, y: 20 }\n    let c = a + b\n    let x: i32 = c.x\n    let y: i32 = c.y\n    println(x)\n    println(y)\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &input), "11\n22\n");
+    }
+
+    #[test]
+    fn test_derived_struct_equality_compares_fields() {
+        let dir = temp_dir("derive_eq");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "#[derive(Eq)]\nstruct Point {\n    x: i32,\n    y: i32\n}\n\nfn main() -> i32 {\n    let a = Point { x: 1, y: 2 }\n    let b = Point { x: 1, y: 2 }\n    let c = Point { x: 1, y: 3 }\n    if a == b {\n        println(\"a == b\")\n    }\n    if a != c {\n        println(\"a != c\")\n    }\n    if a == c {\n        println(\"a == c\")\n    }\n    if a != b {\n        println(\"a != b\")\n    }\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &input), "a == b\na != c\n");
+    }
+
+    #[test]
+    fn test_entry_flag_starts_program_at_named_function() {
+        let dir = temp_dir("entry");
+        let input = write_file(
+            &dir,
+            "tool.zen",
+            "fn helper() -> i32 {\n    return 7\n}\n\nfn start() -> i32 {\n    let n = helper()\n    println(n)\n    return 0\n}",
+        );
+        let output = dir.join("program");
+
+        Compiler::new()
+            .with_entry(Some("start".to_string()))
+            .compile_internal(&input, output.to_str())
+            .expect("compilation should succeed");
+        let result = std::process::Command::new(&output).output().unwrap();
+        assert!(result.status.success());
+        assert_eq!(String::from_utf8_lossy(&result.stdout), "7\n");
+
+        let err = Compiler::new()
+            .with_entry(Some("helper2".to_string()))
+            .compile_internal(&input, output.to_str())
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Entry point 'helper2' is not defined"));
+    }
+
+    #[test]
+    fn test_prefixed_integer_literals_compare_to_decimal() {
+        let dir = temp_dir("int_radix");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    if 0xFF == 255 {\n        println(1)\n    }\n    if 0b1010 == 10 {\n        println(2)\n    }\n    println(0xFF_FF)\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &input), "1\n2\n65535\n");
+    }
+
+    #[test]
+    fn test_assert_eq_aborts_with_both_values_on_mismatch() {
+        let dir = temp_dir("assert_eq");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    assert_eq(2 + 2, 4)\n    assert_eq(\"zen\", \"zen\")\n    println(1)\n    assert_eq(2 + 2, 5)\n    println(2)\n    return 0\n}",
+        );
+        let output = dir.join("program");
+
+        Compiler::new()
+            .compile_internal(&input, output.to_str())
+            .expect("compilation should succeed");
+        let result = std::process::Command::new(&output).output().unwrap();
+        assert!(!result.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&result.stdout),
+            "1\nassertion failed at line 5: left != right\n  left: 4\n right: 5\n"
+        );
+    }
+
+    #[test]
+    fn test_compound_assignment_in_loops() {
+        let dir = temp_dir("compound_assign");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    let mut count = 0\n    let mut i = 0\n    while i < 10 {\n        count += 2\n        i += 1\n    }\n    println(count)\n    let mut product = 1\n    for (j = 1; j <= 5; j += 1) {\n        product *= j\n    }\n    product -= 20\n    product /= 4\n    product %= 7\n    println(product)\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &input), "20\n4\n");
+    }
+
+    #[test]
+    fn test_break_stops_counting_loop() {
+        let dir = temp_dir("break_loop");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    let mut i = 0\n    while i < 100 {\n        if i == 5 {\n            break\n        }\n        i += 1\n    }\n    println(i)\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &input), "5\n");
+    }
+
+    #[test]
+    fn test_loop_runs_until_break() {
+        let dir = temp_dir("loop_break");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    let mut i = 0\n    loop { i += 1 if i == 3 { break } }\n    println(i)\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &input), "3\n");
+    }
+
+    #[test]
+    fn test_array_element_assignment() {
+        let dir = temp_dir("array_assign");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    let mut arr = [10, 20, 30]\n    println(arr[2])\n    arr[1] = 5\n    arr[2] += arr[1]\n    println(arr[2])\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &input), "30\n35\n");
+    }
+
+    #[test]
+    fn test_println_reads_struct_fields() {
+        let dir = temp_dir("struct_fields");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "struct Label {\n    text: str,\n}\n\nstruct Point {\n    x: i32,\n    y: i32,\n    label: Label,\n}\n\nfn main() -> i32 {\n    let p = Point { x: 1, y: 2, label: Label { text: \"origin\" } }\n    println(p.x)\n    println(p.y)\n    println(p.label.text)\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &input), "1\n2\norigin\n");
+    }
+
+    #[test]
+    fn test_match_statement_runs_matching_arm() {
+        let dir = temp_dir("match_stmt");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    let x = 1\n    match x { 1 => println(10), _ => println(20) }\n    match x + 1 { 1 => println(10), _ => println(20) }\n    match x { 5 => println(50) }\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &input), "10\n20\n");
+    }
+
+    #[test]
+    fn test_emit_stops_before_linking() {
+        let dir = temp_dir("emit");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    println(1)\n    return 0\n}",
+        );
+
+        Compiler::new()
+            .with_emit(Emit::LlvmIr)
+            .compile_internal(&input, None)
+            .expect("emitting IR should succeed");
+        let ir = std::fs::read_to_string(dir.join("main.ll")).unwrap();
+        assert!(ir.contains("define i32 @main"));
+
+        let object = dir.join("out.o");
+        Compiler::new()
+            .with_emit(Emit::Obj)
+            .compile_internal(&input, object.to_str())
+            .expect("emitting an object should succeed");
+        assert!(object.exists());
+        assert!(!dir.join("main").exists());
+    }
+
+    #[test]
+    fn test_check_reports_type_errors_without_compiling() {
+        let dir = temp_dir("check");
+        let valid = write_file(
+            &dir,
+            "valid.zen",
+            "fn main() -> i32 {\n    let x: i32 = 1\n    return x\n}",
+        );
+        assert!(Compiler::new().check(&valid).is_ok());
+        assert!(!dir.join("valid").exists());
+
+        let invalid = write_file(
+            &dir,
+            "invalid.zen",
+            "fn main() -> i32 {\n    if 1 {\n        return 1\n    }\n    return 0\n}",
+        );
+        let err = Compiler::new().check(&invalid).unwrap_err();
+        assert!(err.to_string().contains("If condition must be boolean"));
+    }
+
+    #[test]
+    fn test_check_uses_the_entry_point() {
+        let dir = temp_dir("check_entry");
+        let input = write_file(
+            &dir,
+            "tool.zen",
+            "fn start(n: i32) -> i32 {\n    return n\n}",
+        );
+        assert!(Compiler::new().check(&input).is_ok());
+        let err = Compiler::new()
+            .with_entry(Some("start".to_string()))
+            .check(&input)
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Entry point 'start' must take no parameters"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_keep_temps_leaves_intermediate_files() {
+        let dir = temp_dir("keep_temps");
+        let input = write_file(&dir, "main.zen", "fn main() -> i32 {\n    return 0\n}");
+        let output = dir.join("program");
+
+        let mut compiler = Compiler::new().with_keep_temps(true);
+        compiler
+            .compile_internal(&input, output.to_str())
+            .expect("compilation should succeed");
+        let kept = compiler.kept_temps().to_vec();
+        assert_eq!(kept.len(), 2);
+        assert!(kept[0].extension().is_some_and(|ext| ext == "ll"));
+        assert!(kept.iter().all(|path| path.exists()));
+        for path in &kept {
+            let _ = std::fs::remove_file(path);
+        }
+
+        let mut compiler = Compiler::new();
+        compiler
+            .compile_internal(&input, output.to_str())
+            .expect("compilation should succeed");
+        assert!(compiler.kept_temps().is_empty());
+    }
+
+    #[test]
+    fn test_opt_level_is_recorded_and_validated() {
+        let dir = temp_dir("opt_level");
+        let input = write_file(&dir, "main.zen", "fn main() -> i32 {\n    return 0\n}");
+        let output = dir.join("program");
+
+        let mut compiler = Compiler::new().with_opt_level(0);
+        compiler
+            .compile_internal(&input, output.to_str())
+            .expect("compilation should succeed");
+        assert_eq!(compiler.get_stats().unwrap().opt_level, 0);
+        // `fn main ( ) -> i32 { return 0 }` and the EOF token
+        assert_eq!(compiler.get_stats().unwrap().tokens_count, 11);
+
+        let err = Compiler::new()
+            .with_opt_level(5)
+            .compile_internal(&input, output.to_str())
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Invalid optimization level 5; expected 0 to 3"));
+    }
+
+    #[test]
+    fn test_stats_serialize_every_timing_as_microseconds() {
+        let dir = temp_dir("stats_json");
+        let input = write_file(&dir, "main.zen", "fn main() -> i32 {\n    return 0\n}");
+
+        let mut compiler = Compiler::new();
+        compiler
+            .compile_internal(&input, dir.join("program").to_str())
+            .expect("compilation should succeed");
+        let json: serde_json::Value =
+            serde_json::from_str(&compiler.get_stats().unwrap().to_json()).unwrap();
+
+        assert_eq!(json["tokens_count"], 11);
+        assert_eq!(json["statements_count"], 1);
+        assert_eq!(json["target"], HOST_TARGET);
+        let stages = [
+            "lexing_us",
+            "parsing_us",
+            "type_checking_us",
+            "ownership_us",
+            "codegen_us",
+            "llc_us",
+            "linking_us",
+        ];
+        let mut sum = 0;
+        for key in stages.iter().chain(&["stages_us", "total_us"]) {
+            assert!(json[key].is_u64(), "{} is not a number in {}", key, json);
+        }
+        for key in stages {
+            sum += json[key].as_u64().unwrap();
+        }
+        // Each stage rounds down on its own, so the derived total can only be larger
+        let stages_us = json["stages_us"].as_u64().unwrap();
+        assert!(stages_us >= sum && stages_us < sum + stages.len() as u64);
+        assert!(json["total_us"].as_u64().unwrap() >= sum);
+    }
+
+    #[test]
+    fn test_target_triple_is_passed_to_llc() {
+        let dir = temp_dir("target");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    println(7)\n    return 0\n}",
+        );
+        let output = dir.join("program");
+
+        let mut compiler = Compiler::new().with_target(Some(HOST_TARGET.to_string()));
+        compiler
+            .compile_internal(&input, output.to_str())
+            .expect("compilation for the host triple should succeed");
+        assert_eq!(compiler.get_stats().unwrap().target, HOST_TARGET);
+        let run = std::process::Command::new(&output).output().unwrap();
+        assert_eq!(String::from_utf8_lossy(&run.stdout), "7\n");
+
+        let err = Compiler::new()
+            .with_target(Some("bogus-none-nothing".to_string()))
+            .compile_internal(&input, output.to_str())
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("llc compilation failed: "), "{}", err);
+        assert!(err.contains("'bogus-none-nothing'"), "{}", err);
+        assert!(!err.ends_with('\n'));
+    }
+
+    #[test]
+    fn test_link_arguments_and_executable_names_follow_the_target() {
+        let args = |target: &str| -> Vec<String> {
+            link_args(target, 2, Path::new("main.o"), Path::new("main"))
+                .into_iter()
+                .map(|arg| arg.into_string().unwrap())
+                .collect()
+        };
+        assert_eq!(
+            args("x86_64-unknown-linux-gnu"),
+            ["-no-pie", "-O2", "main.o", "-o", "main", "-lc", "-lm"]
+        );
+        assert_eq!(
+            args("x86_64-pc-windows-gnu"),
+            ["-O2", "main.o", "-o", "main"]
+        );
+
+        let windows = Compiler::new().with_target(Some("x86_64-pc-windows-gnu".to_string()));
+        assert_eq!(windows.output_extension(), "exe");
+        assert_eq!(windows.stdin_output_path(), PathBuf::from("a.exe"));
+        let windows_ir = windows.with_emit(Emit::LlvmIr);
+        assert_eq!(windows_ir.output_extension(), "ll");
+        let linux = Compiler::new().with_target(Some("x86_64-unknown-linux-gnu".to_string()));
+        assert_eq!(linux.output_extension(), "");
+        assert_eq!(linux.stdin_output_path(), PathBuf::from("a.out"));
+    }
+
+    #[test]
+    fn test_llc_failure_reports_the_command_line() {
+        let dir = temp_dir("broken_ir");
+        let ll_path = dir.join("broken.ll");
+        let obj_path = dir.join("broken.o");
+        std::fs::write(&ll_path, "define i32 @main() {\nentry:\n  ret i64 0\n}\n").unwrap();
+
+        let err = Compiler::new()
+            .with_opt_level(1)
+            .llc(&ll_path, "obj", &obj_path)
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("llc compilation failed: "), "{}", err);
+        assert!(
+            err.ends_with(&format!(
+                "\ncommand: llc -filetype=obj -O1 -o {} {}",
+                obj_path.display(),
+                ll_path.display()
+            )),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_missing_configured_linker_is_reported() {
+        // `--cc` wins over `ZEN_CC`, which is passed in rather than set here so that
+        // concurrently running tests keep linking with the default
+        let env = |value: &str| Some(value.to_string());
+        assert_eq!(configured_tool(None, env("clang-17")), env("clang-17"));
+        assert_eq!(configured_tool(Some("cc"), env("clang-17")), env("cc"));
+        assert_eq!(configured_tool(None, env("")), None);
+        assert_eq!(configured_tool(None, None), None);
+
+        let dir = temp_dir("missing_cc");
+        let input = write_file(&dir, "main.zen", "fn main() -> i32 {\n    return 0\n}");
+        let err = Compiler::new()
+            .with_cc(Some("zen-no-such-cc".to_string()))
+            .compile_internal(&input, dir.join("program").to_str())
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("Failed to execute linker 'zen-no-such-cc': "),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_println_prints_chars_as_characters() {
+        let dir = temp_dir("print_char");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    let c = 'Y'\n    println('Z')\n    println(c)\n    println(c as i32)\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &input), "Z\nY\n89\n");
+    }
+
+    #[test]
+    fn test_println_prints_bools_as_words() {
+        let dir = temp_dir("print_bool");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    let done = 2 > 3\n    println(true)\n    println(done)\n    println(1 == 1)\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &input), "true\nfalse\ntrue\n");
+    }
+
+    #[test]
+    fn test_integer_arithmetic_keeps_operand_width() {
+        let dir = temp_dir("int_width");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    let a: i64 = 100000\n    let b: i64 = 100000\n    println(a * b)\n    let x: u8 = 200\n    let y: u8 = 100\n    println(x > y)\n    println(x / 3)\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &input), "10000000000\ntrue\n66\n");
+    }
+
+    #[test]
+    fn test_unsigned_division_ignores_sign_bit() {
+        let dir = temp_dir("unsigned_div");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    let big: u32 = 65536 * 65535\n    let two: u32 = 2\n    println(big / two)\n    println(big % 7)\n    println(big > two)\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &input), "2147450880\n2\ntrue\n");
+    }
+
+    #[test]
+    fn test_constant_products_keep_the_width_they_are_stored_as() {
+        let dir = temp_dir("wide_constants");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    let w: i64 = 100000 * 100000\n    let big: u32 = 65536 * 65535\n    println(w)\n    println(big)\n    return 0\n}",
+        );
+        let output = dir.join("program");
+        // Neither constant overflows the type it is stored as, so there is no warning
+        Compiler::new()
+            .with_warnings_as_errors(true)
+            .compile_internal(&input, output.to_str())
+            .expect("compilation should succeed");
+        let result = std::process::Command::new(&output).output().unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&result.stdout),
+            "10000000000\n4294901760\n"
+        );
+    }
+
+    #[test]
+    fn test_power_operator_raises_ints_and_floats() {
+        let dir = temp_dir("power");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    println(2 ^ 10 == 1024)\n    println(2 ^ 3 ^ 2)\n    let base: i64 = 10\n    println(base ^ 12)\n    let x = 1.5\n    println(x ^ 2)\n    return 0\n}",
+        );
+
+        assert_eq!(
+            compile_and_run(&dir, &input),
+            "true\n512\n1000000000000\n2.250000\n"
+        );
+    }
+
+    #[test]
+    fn test_bitwise_operators_and_shifts() {
+        let dir = temp_dir("bitwise");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    println(6 & 3 == 2)\n    println(1 << 4 == 16)\n    println(5 ^^ 3)\n    let big: u32 = 65536 * 65535\n    println(big >> 28)\n    let neg = 0 - 64\n    println(neg >> 2)\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &input), "true\ntrue\n6\n15\n-16\n");
+    }
+
+    #[test]
+    fn test_for_increment_can_be_any_statement_expression() {
+        let dir = temp_dir("for_increment");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn bump(counter: &mut i32) -> void {\n    *counter = *counter + 1\n    println(*counter)\n}\n\nfn main() -> i32 {\n    let mut total = 0\n    for (mut i = 0; i < 3; bump(&mut i)) {\n        total += 10\n    }\n    println(total)\n    let mut a = [0, 0, 0]\n    for (mut k = 0; k < 3; a[k - 1] = k * 2) {\n        k = k + 1\n    }\n    println(a[2])\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &input), "1\n2\n3\n30\n6\n");
+    }
+
+    #[test]
+    fn test_interpolation_prints_values_and_expressions() {
+        let dir = temp_dir("interpolation");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    let x = 2\n    let y = 3\n    let name = \"Zen\"\n    println(\"{x} plus {y} is {x + y}, {name}! 100%\")\n    println(\"done: {x < y}\")\n    return 0\n}",
+        );
+
+        assert_eq!(
+            compile_and_run(&dir, &input),
+            "2 plus 3 is 5, Zen! 100%\ndone: true\n"
+        );
+    }
+
+    #[test]
+    fn test_interpolated_expressions_keep_operator_precedence() {
+        let dir = temp_dir("interpolation_precedence");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    let a = 1\n    let b = 4\n    println(\"sum={a + b * 2}\")\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &input), "sum=9\n");
+    }
+
+    #[test]
+    fn test_constants_are_folded_into_globals() {
+        let dir = temp_dir("constants");
+        let main = write_file(
+            &dir,
+            "main.zen",
+            "fn scaled(x: i32) -> i32 {\n    return x * MAX\n}\n\nconst MAX: i32 = 100\nconst HALF: i32 = MAX / 2\nconst GREETING: str = \"hi\"\n\nfn main() -> i32 {\n    const STEP: i32 = 3\n    println(scaled(2))\n    println(HALF + STEP)\n    println(\"{GREETING} {MAX}\")\n    return 0\n}\n",
+        );
+
+        assert_eq!(compile_and_run(&dir, &main), "200\n53\nhi 100\n");
+    }
+
+    #[test]
+    fn test_global_counter_is_shared_between_calls() {
+        let dir = temp_dir("globals");
+        let main = write_file(
+            &dir,
+            "main.zen",
+            "let mut counter = 0\nlet start: i32 = 40 + 2\nlet name = \"zen\"\n\nfn bump() -> void {\n    counter += 1\n}\n\nfn main() -> i32 {\n    bump()\n    bump()\n    println(counter)\n    println(start + counter)\n    println(name)\n    return 0\n}\n",
+        );
+
+        assert_eq!(compile_and_run(&dir, &main), "2\n44\nzen\n");
+    }
+
+    #[test]
+    fn test_functions_are_called_through_pointers() {
+        let dir = temp_dir("function_pointers");
+        let main = write_file(
+            &dir,
+            "main.zen",
+            "fn add(a: i32, b: i32) -> i32 {\n    return a + b\n}\n\nfn mul(a: i32, b: i32) -> i32 {\n    return a * b\n}\n\nfn apply(op: fn(i32, i32) -> i32, x: i32, y: i32) -> i32 {\n    return op(x, y)\n}\n\nfn main() -> i32 {\n    let f = add\n    println(f(1, 2))\n    let mut g: fn(i32, i32) -> i32 = add\n    g = mul\n    println(g(3, 4))\n    println(apply(add, 5, 6))\n    return 0\n}\n",
+        );
+
+        assert_eq!(compile_and_run(&dir, &main), "3\n12\n11\n");
+    }
+
+    #[test]
+    fn test_methods_read_fields_through_self() {
+        let dir = temp_dir("methods");
+        let main = write_file(
+            &dir,
+            "main.zen",
+            "struct Rect {\n    width: i32,\n    height: i32,\n}\n\nimpl Rect {\n    fn area(self) -> i32 {\n        return self.width * self.height\n    }\n\n    fn grown(self, by: i32) -> i32 {\n        return (self.width + by) * (self.height + by)\n    }\n}\n\nfn main() -> i32 {\n    let r = Rect { width: 3, height: 4 }\n    println(r.area())\n    println(r.grown(1))\n    let borrowed = &r\n    println(borrowed.area())\n    return 0\n}\n",
+        );
+
+        assert_eq!(compile_and_run(&dir, &main), "12\n20\n12\n");
+    }
+
+    #[test]
+    fn test_field_assignment_stores_into_the_struct() {
+        let dir = temp_dir("field_assignment");
+        let main = write_file(
+            &dir,
+            "main.zen",
+            "struct Point {\n    x: i32,\n    y: i32,\n}\n\nstruct Line {\n    start: Point,\n    end: Point,\n}\n\nfn main() -> i32 {\n    let mut p = Point { x: 1, y: 2 }\n    p.x = 10\n    p.y += 5\n    println(p.x)\n    println(p.y)\n    let mut line = Line { start: p, end: Point { x: 0, y: 0 } }\n    line.end.y = 42\n    println(line.end.y)\n    let r = &mut p\n    r.x = 99\n    println(p.x)\n    return 0\n}\n",
+        );
+
+        assert_eq!(compile_and_run(&dir, &main), "10\n7\n42\n99\n");
+    }
+
+    #[test]
+    fn test_array_element_store_is_read_back() {
+        let dir = temp_dir("element_store");
+        let main = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    let mut a = [1, 2, 3]\n    a[1] = 20\n    let i = 2\n    a[i] += 5\n    println(a[0])\n    println(a[1])\n    println(a[2])\n    return 0\n}\n",
+        );
+
+        assert_eq!(compile_and_run(&dir, &main), "1\n20\n8\n");
+    }
+
+    #[test]
+    fn test_if_expression_selects_the_larger_value() {
+        let dir = temp_dir("if_expression");
+        let main = write_file(
+            &dir,
+            "main.zen",
+            "fn max(a: i32, b: i32) -> i32 {\n    let m = if a > b { a } else { b }\n    return m\n}\n\nfn main() -> i32 {\n    println(max(3, 9))\n    println(max(12, 4))\n    let label = if max(1, 2) == 2 { \"two\" } else { \"other\" }\n    println(label)\n    println(if false { 1 } else if true { 2 } else { 3 })\n    return 0\n}\n",
+        );
+
+        assert_eq!(compile_and_run(&dir, &main), "9\n12\ntwo\n2\n");
+    }
+
+    #[test]
+    fn test_block_expression_binds_its_tail_value() {
+        let dir = temp_dir("block_expression");
+        let main = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    let a = 10\n    let x = { let a = 2; a * 3 }\n    println(x)\n    println(a)\n    let half = { let h: f64 = 2.5; h * 2.0 }\n    println(half)\n    println(if x > 5 { let d = x - 5; d * 10 } else { 0 })\n    return 0\n}\n",
+        );
+
+        assert_eq!(compile_and_run(&dir, &main), "6\n10\n5.000000\n10\n");
+    }
+
+    #[test]
+    fn test_shadowing_ends_with_the_inner_block() {
+        let dir = temp_dir("shadowing");
+        let main = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    let x: i32 = 1\n    if x > 0 {\n        let x: str = \"inner\"\n        println(x)\n    }\n    println(x + 1)\n    let x = 2.5\n    println(x)\n    return 0\n}\n",
+        );
+
+        assert_eq!(compile_and_run(&dir, &main), "inner\n2\n2.500000\n");
+    }
+
+    #[test]
+    fn test_statements_after_a_terminator_are_not_generated() {
+        let dir = temp_dir("dead_code");
+        let main = write_file(
+            &dir,
+            "main.zen",
+            "fn f(x: i32) -> i32 {\n    let mut i = 0\n    while i < 10 {\n        i = i + 1\n        if i == x {\n            break\n            println(99)\n        }\n        if i == 2 {\n            continue\n            println(98)\n        }\n    }\n    if x > 100 {\n        return 1\n        println(97)\n    }\n    return i\n    println(96)\n}\nfn main() -> i32 {\n    println(f(5))\n    return 0\n    println(95)\n}\n",
+        );
+
+        // llc rejects instructions emitted after a block's terminator, so this only
+        // builds when the dead statements are dropped
+        assert_eq!(compile_and_run(&dir, &main), "5\n");
+    }
+
+    #[test]
+    fn test_tail_branches_and_endless_loops_return_values() {
+        let dir = temp_dir("tail_branches");
+        let main = write_file(
+            &dir,
+            "main.zen",
+            "fn k(x: i32) -> i32 {\n    let mut i = x\n    loop {\n        i += 1\n        if i > 10 {\n            return i\n        }\n    }\n}\nfn h(x: i32) -> i32 {\n    if x > 0 {\n        1\n    } else {\n        2\n    }\n}\nfn m(x: i32) -> i32 {\n    match x {\n        1 => 10,\n        _ => 20,\n    }\n}\nfn main() -> i32 {\n    println(k(3))\n    println(h(1))\n    println(h(-1))\n    println(m(1))\n    println(m(5))\n    return 0\n}\n",
+        );
+
+        assert_eq!(compile_and_run(&dir, &main), "11\n1\n2\n10\n20\n");
+    }
+
+    #[test]
+    fn test_run_returns_the_program_exit_code() {
+        let dir = temp_dir("run_exit_code");
+        let failing = write_file(
+            &dir,
+            "failing.zen",
+            "fn main() -> i32 {\n    println(\"partial output\")\n    return 3\n}\n",
+        );
+        let void_main = write_file(
+            &dir,
+            "void_main.zen",
+            "fn main() -> void {\n    println(1)\n}\n",
+        );
+
+        assert_eq!(Compiler::new().run_file(&failing).unwrap(), 3);
+        assert_eq!(Compiler::new().run_file(&void_main).unwrap(), 0);
+        // Not being able to start the program is still an error
+        let err = execute_binary(&dir.join("missing"), &[]).unwrap_err();
+        assert!(err.to_string().starts_with("Failed to execute program: "));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_run_arguments_reach_the_program() {
+        // Zen programs cannot read their arguments yet, so a shell stands in for one
+        // that echoes its first
+        let dir = temp_dir("run_args");
+        let echo = write_file(&dir, "echo_first", "#!/bin/sh\necho \"$1\"\n");
+        let mut permissions = std::fs::metadata(&echo).unwrap().permissions();
+        std::os::unix::fs::PermissionsExt::set_mode(&mut permissions, 0o755);
+        std::fs::set_permissions(&echo, permissions).unwrap();
+
+        let args = ["forwarded value".to_string(), "second".to_string()];
+        let (stdout, _) = execute_binary(Path::new(&echo), &args).unwrap();
+        assert_eq!(stdout, "forwarded value\n");
+    }
+
+    #[test]
+    fn test_debug_bounds_aborts_on_out_of_range_index() {
+        let dir = temp_dir("debug_bounds");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    let a = [1, 2, 3]\n    let mut i = 0\n    while i < 4 {\n        let v = a[i]\n        println(v)\n        i = i + 1\n    }\n    return 0\n}",
+        );
+        let output = dir.join("program");
+
+        Compiler::new()
+            .with_debug_bounds(true)
+            .compile_internal(&input, output.to_str())
+            .expect("compilation should succeed");
+        let checked = std::process::Command::new(&output).output().unwrap();
+        assert!(!checked.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&checked.stdout),
+            "1\n2\n3\nindex 3 out of bounds for array of length 3\n"
+        );
+
+        // Without the flag the access is unchecked, and reading past the end is undefined
+        Compiler::new()
+            .compile_internal(&input, output.to_str())
+            .expect("compilation should succeed");
+        let unchecked = std::process::Command::new(&output).output().unwrap();
+        assert!(!String::from_utf8_lossy(&unchecked.stdout).contains("out of bounds"));
+    }
+
+    #[test]
+    fn test_generic_function_runs_for_each_instantiation() {
+        let dir = temp_dir("generics");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn id<T>(x: T) -> T {\n    x\n}\n\nfn main() -> i32 {\n    let n = id(41) + 1\n    let s = id(\"hello\")\n    println(n)\n    println(s)\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &input), "42\nhello\n");
+    }
+
+    #[test]
+    fn test_println_of_a_call_uses_its_return_type() {
+        let dir = temp_dir("println_call");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn id<T>(x: T) -> T {\n    x\n}\n\nfn half() -> f64 {\n    return 0.5\n}\n\nfn quarter() -> f32 {\n    return 0.25\n}\n\nfn main() -> i32 {\n    println(id(\"hi\"))\n    println(id(3))\n    println(half())\n    println(quarter())\n    return 0\n}",
+        );
+
+        assert_eq!(compile_and_run(&dir, &input), "hi\n3\n0.500000\n0.250000\n");
+    }
+
+    #[test]
+    fn test_println_uses_format_for_integer_width() {
+        let dir = temp_dir("integer_formats");
+        let input = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    let max = -1 as u32\n    let big = max as i64\n    println(big)\n    println(max)\n    println(-5 as i64)\n    let small: u8 = 200 as u8\n    println(small)\n    return 0\n}",
+        );
+
+        assert_eq!(
+            compile_and_run(&dir, &input),
+            "4294967295\n4294967295\n-5\n200\n"
+        );
+    }
+
+    #[test]
+    fn test_warnings_as_errors_fails_on_warning() {
+        let dir = temp_dir("werror");
+        let main = write_file(
+            &dir,
+            "main.zen",
+            "fn main() -> i32 {\n    exit(0)\n    return 1\n}",
+        );
+        let output = dir.join("program");
+
+        assert!(Compiler::new().compile_file(&main, output.to_str()).is_ok());
+        let err = Compiler::new()
+            .with_warnings_as_errors(true)
+            .compile_file(&main, output.to_str())
+            .unwrap_err();
+        assert!(err.to_string().contains("1 warning(s) treated as errors"));
+
+        // `check` fails on the same warnings as the build
+        assert!(Compiler::new().check(&main).is_ok());
+        let err = Compiler::new()
+            .with_warnings_as_errors(true)
+            .check(&main)
+            .unwrap_err();
+        assert!(err.to_string().contains("1 warning(s) treated as errors"));
+    }
+}
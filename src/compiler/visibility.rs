@@ -0,0 +1,562 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::ast::expr::{Expr, StringPart};
+use crate::ast::stmt::Stmt;
+use crate::token::Token;
+use crate::types::Type;
+
+/// A use of a top-level item by name, which visibility rules apply to
+pub struct Reference {
+    pub name: String,
+    /// The module named in `module::item`, if the use was qualified
+    pub module: Option<String>,
+    pub token: Token,
+}
+
+impl Reference {
+    fn new(name: &str, module: Option<&str>, token: &Token) -> Self {
+        Reference {
+            name: name.to_string(),
+            module: module.map(str::to_string),
+            token: token.clone(),
+        }
+    }
+}
+
+/// Every call, struct literal, qualified access and bare name in `statements` that can
+/// refer to a top-level item. A bare name is left out once a local of that name has been
+/// bound in the same function, since it may be the local.
+pub fn references(statements: &[Stmt]) -> Vec<Reference> {
+    let mut collector = Collector::default();
+    collector.body(statements);
+    collector.found
+}
+
+#[derive(Default)]
+struct Collector<'a> {
+    found: Vec<Reference>,
+    locals: HashSet<&'a str>,
+}
+
+impl<'a> Collector<'a> {
+    fn body(&mut self, body: &'a [Stmt]) {
+        for stmt in body {
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &'a Stmt) {
+        match stmt {
+            Stmt::VariableDecl {
+                name, initializer, ..
+            } => {
+                if let Some(init) = initializer {
+                    self.expr(init);
+                }
+                self.locals.insert(name);
+            }
+            Stmt::ConstDecl { initializer, .. } => self.expr(initializer),
+            Stmt::Assignment { target, value, .. } => {
+                self.expr(target);
+                self.expr(value);
+            }
+            Stmt::FunctionDecl { params, body, .. } => {
+                let outer = std::mem::take(&mut self.locals);
+                self.locals
+                    .extend(params.iter().map(|(name, _)| name.as_str()));
+                self.body(body);
+                self.locals = outer;
+            }
+            Stmt::Return { value, .. } => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_if_branches,
+                else_branch,
+                ..
+            } => {
+                self.expr(condition);
+                self.body(then_branch);
+                for branch in else_if_branches {
+                    self.expr(&branch.condition);
+                    self.body(&branch.body);
+                }
+                if let Some(else_branch) = else_branch {
+                    self.body(else_branch);
+                }
+            }
+            Stmt::While {
+                condition, body, ..
+            } => {
+                self.expr(condition);
+                self.body(body);
+            }
+            Stmt::Loop { body, .. } => self.body(body),
+            Stmt::For {
+                init,
+                condition,
+                increment,
+                body,
+                ..
+            } => {
+                if let Some(init) = init {
+                    self.stmt(init);
+                }
+                if let Some(condition) = condition {
+                    self.expr(condition);
+                }
+                if let Some(increment) = increment {
+                    self.expr(increment);
+                }
+                self.body(body);
+            }
+            Stmt::ForIn {
+                variable,
+                iterable,
+                body,
+                ..
+            } => {
+                self.expr(iterable);
+                self.locals.insert(variable);
+                self.body(body);
+            }
+            Stmt::Match {
+                value,
+                arms,
+                default,
+                ..
+            } => {
+                self.expr(value);
+                for (pattern, body) in arms {
+                    self.expr(pattern);
+                    self.body(body);
+                }
+                if let Some(default) = default {
+                    self.body(default);
+                }
+            }
+            Stmt::Mod { items, .. } | Stmt::ImplBlock { methods: items, .. } => self.body(items),
+            Stmt::ExprStmt { expr } => self.expr(expr),
+            Stmt::Block { statements } => self.body(statements),
+            Stmt::Break { .. }
+            | Stmt::Continue { .. }
+            | Stmt::Use { .. }
+            | Stmt::StructDecl { .. } => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &'a Expr) {
+        match expr {
+            Expr::Identifier { name, token } => {
+                if !self.locals.contains(name.as_str()) {
+                    self.found.push(Reference::new(name, None, token));
+                }
+            }
+            Expr::ModuleAccess {
+                module,
+                item,
+                token,
+            } => self.found.push(Reference::new(item, Some(module), token)),
+            Expr::Call { callee, args, .. } => {
+                // A call names a function even where a local of the same name is in scope
+                match callee.as_ref() {
+                    Expr::Identifier { name, token } => {
+                        self.found.push(Reference::new(name, None, token))
+                    }
+                    callee => self.expr(callee),
+                }
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            Expr::StructLiteral {
+                struct_name,
+                fields,
+                token,
+            } => {
+                self.found.push(Reference::new(struct_name, None, token));
+                for (_, value) in fields {
+                    self.expr(value);
+                }
+            }
+            Expr::BinaryOp { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::UnaryOp { operand: expr, .. }
+            | Expr::OwnershipTransfer { expr, .. }
+            | Expr::Borrow { expr, .. }
+            | Expr::Deref { expr, .. }
+            | Expr::Cast { expr, .. }
+            | Expr::FieldAccess { object: expr, .. }
+            | Expr::ArrayRepeat { value: expr, .. } => self.expr(expr),
+            Expr::ArrayAccess { array, index, .. } => {
+                self.expr(array);
+                self.expr(index);
+            }
+            Expr::ArrayLiteral { elements, .. } => {
+                for element in elements {
+                    self.expr(element);
+                }
+            }
+            Expr::Match {
+                value,
+                arms,
+                default,
+                ..
+            } => {
+                self.expr(value);
+                for (pattern, arm_value) in arms {
+                    self.expr(pattern);
+                    self.expr(arm_value);
+                }
+                if let Some(default) = default {
+                    self.expr(default);
+                }
+            }
+            Expr::IfExpr {
+                condition,
+                then_expr,
+                else_expr,
+                ..
+            } => {
+                self.expr(condition);
+                self.expr(then_expr);
+                self.expr(else_expr);
+            }
+            Expr::Block {
+                statements, tail, ..
+            } => {
+                self.body(statements);
+                self.expr(tail);
+            }
+            Expr::InterpolatedString { parts, token } => {
+                for part in parts {
+                    match part {
+                        StringPart::Variable(name) if !self.locals.contains(name.as_str()) => {
+                            self.found.push(Reference::new(name, None, token))
+                        }
+                        StringPart::Expr(expr) => self.expr(expr),
+                        StringPart::Variable(_) | StringPart::Text(_) => {}
+                    }
+                }
+            }
+            Expr::IntegerLiteral { .. }
+            | Expr::FloatLiteral { .. }
+            | Expr::StringLiteral { .. }
+            | Expr::CharLiteral { .. }
+            | Expr::BooleanLiteral { .. }
+            | Expr::NullLiteral { .. } => {}
+        }
+    }
+}
+
+/// Renames the top-level items in `renames` throughout `statements`: their declarations
+/// (and the methods of renamed structs), the types naming them, and every reference
+/// [`references`] would report, so a bare name bound to a local is left alone
+pub fn rename(statements: &mut [Stmt], renames: &HashMap<String, String>) {
+    if !renames.is_empty() {
+        Renamer {
+            renames,
+            locals: HashSet::new(),
+        }
+        .body(statements);
+    }
+}
+
+struct Renamer<'r> {
+    renames: &'r HashMap<String, String>,
+    locals: HashSet<String>,
+}
+
+impl Renamer<'_> {
+    fn name(&self, name: &mut String) {
+        if let Some(renamed) = self.renames.get(name) {
+            *name = renamed.clone();
+        }
+    }
+
+    // A bare name, which a local of the same name shadows
+    fn value_name(&self, name: &mut String) {
+        if !self.locals.contains(name) {
+            self.name(name);
+        }
+    }
+
+    fn ty(&self, ty: &mut Type) {
+        match ty {
+            Type::Struct(name) => self.name(name),
+            Type::Array(inner, _)
+            | Type::Slice(inner)
+            | Type::Ref(inner, _)
+            | Type::Nullable(inner) => self.ty(inner),
+            Type::Function(params, return_type) => {
+                for param in params {
+                    self.ty(param);
+                }
+                self.ty(return_type);
+            }
+            _ => {}
+        }
+    }
+
+    fn body(&mut self, body: &mut [Stmt]) {
+        for stmt in body {
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &mut Stmt) {
+        match stmt {
+            Stmt::VariableDecl {
+                name,
+                type_annotation,
+                initializer,
+                ..
+            } => {
+                if let Some(ty) = type_annotation {
+                    self.ty(ty);
+                }
+                if let Some(init) = initializer {
+                    self.expr(init);
+                }
+                self.locals.insert(name.clone());
+            }
+            Stmt::ConstDecl {
+                name,
+                type_annotation,
+                initializer,
+                ..
+            } => {
+                self.name(name);
+                if let Some(ty) = type_annotation {
+                    self.ty(ty);
+                }
+                self.expr(initializer);
+            }
+            Stmt::Assignment { target, value, .. } => {
+                self.expr(target);
+                self.expr(value);
+            }
+            Stmt::FunctionDecl {
+                name,
+                params,
+                return_type,
+                body,
+                ..
+            } => {
+                self.name(name);
+                for (_, ty) in params.iter_mut() {
+                    self.ty(ty);
+                }
+                self.ty(return_type);
+                let outer = std::mem::take(&mut self.locals);
+                self.locals
+                    .extend(params.iter().map(|(name, _)| name.clone()));
+                self.body(body);
+                self.locals = outer;
+            }
+            Stmt::Return { value, .. } => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_if_branches,
+                else_branch,
+                ..
+            } => {
+                self.expr(condition);
+                self.body(then_branch);
+                for branch in else_if_branches {
+                    self.expr(&mut branch.condition);
+                    self.body(&mut branch.body);
+                }
+                if let Some(else_branch) = else_branch {
+                    self.body(else_branch);
+                }
+            }
+            Stmt::While {
+                condition, body, ..
+            } => {
+                self.expr(condition);
+                self.body(body);
+            }
+            Stmt::Loop { body, .. } => self.body(body),
+            Stmt::For {
+                init,
+                condition,
+                increment,
+                body,
+                ..
+            } => {
+                if let Some(init) = init {
+                    self.stmt(init);
+                }
+                if let Some(condition) = condition {
+                    self.expr(condition);
+                }
+                if let Some(increment) = increment {
+                    self.expr(increment);
+                }
+                self.body(body);
+            }
+            Stmt::ForIn {
+                variable,
+                iterable,
+                body,
+                ..
+            } => {
+                self.expr(iterable);
+                self.locals.insert(variable.clone());
+                self.body(body);
+            }
+            Stmt::Match {
+                value,
+                arms,
+                default,
+                ..
+            } => {
+                self.expr(value);
+                for (pattern, body) in arms {
+                    self.expr(pattern);
+                    self.body(body);
+                }
+                if let Some(default) = default {
+                    self.body(default);
+                }
+            }
+            Stmt::StructDecl { name, fields, .. } => {
+                self.name(name);
+                for (_, ty) in fields {
+                    self.ty(ty);
+                }
+            }
+            // Methods compile to `Name_method`, which follows the struct's new name
+            Stmt::ImplBlock {
+                struct_name,
+                methods,
+                ..
+            } => {
+                if let Some(renamed) = self.renames.get(struct_name.as_str()) {
+                    for method in methods.iter_mut() {
+                        if let Stmt::FunctionDecl { name, .. } = method {
+                            if let Some(method_name) = name.strip_prefix(struct_name.as_str()) {
+                                *name = format!("{}{}", renamed, method_name);
+                            }
+                        }
+                    }
+                    *struct_name = renamed.clone();
+                }
+                self.body(methods);
+            }
+            Stmt::Mod { items, .. } => self.body(items),
+            Stmt::ExprStmt { expr } => self.expr(expr),
+            Stmt::Block { statements } => self.body(statements),
+            Stmt::Break { .. } | Stmt::Continue { .. } | Stmt::Use { .. } => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Identifier { name, .. } => self.value_name(name),
+            Expr::ModuleAccess { item, .. } => self.name(item),
+            Expr::Call { callee, args, .. } => {
+                match callee.as_mut() {
+                    Expr::Identifier { name, .. } => self.name(name),
+                    callee => self.expr(callee),
+                }
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            Expr::StructLiteral {
+                struct_name,
+                fields,
+                ..
+            } => {
+                self.name(struct_name);
+                for (_, value) in fields {
+                    self.expr(value);
+                }
+            }
+            Expr::Cast {
+                expr, target_type, ..
+            } => {
+                self.expr(expr);
+                self.ty(target_type);
+            }
+            Expr::BinaryOp { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::UnaryOp { operand: expr, .. }
+            | Expr::OwnershipTransfer { expr, .. }
+            | Expr::Borrow { expr, .. }
+            | Expr::Deref { expr, .. }
+            | Expr::FieldAccess { object: expr, .. }
+            | Expr::ArrayRepeat { value: expr, .. } => self.expr(expr),
+            Expr::ArrayAccess { array, index, .. } => {
+                self.expr(array);
+                self.expr(index);
+            }
+            Expr::ArrayLiteral { elements, .. } => {
+                for element in elements {
+                    self.expr(element);
+                }
+            }
+            Expr::Match {
+                value,
+                arms,
+                default,
+                ..
+            } => {
+                self.expr(value);
+                for (pattern, arm_value) in arms {
+                    self.expr(pattern);
+                    self.expr(arm_value);
+                }
+                if let Some(default) = default {
+                    self.expr(default);
+                }
+            }
+            Expr::IfExpr {
+                condition,
+                then_expr,
+                else_expr,
+                ..
+            } => {
+                self.expr(condition);
+                self.expr(then_expr);
+                self.expr(else_expr);
+            }
+            Expr::Block {
+                statements, tail, ..
+            } => {
+                self.body(statements);
+                self.expr(tail);
+            }
+            Expr::InterpolatedString { parts, .. } => {
+                for part in parts {
+                    match part {
+                        StringPart::Variable(name) => self.value_name(name),
+                        StringPart::Expr(expr) => self.expr(expr),
+                        StringPart::Text(_) => {}
+                    }
+                }
+            }
+            Expr::IntegerLiteral { .. }
+            | Expr::FloatLiteral { .. }
+            | Expr::StringLiteral { .. }
+            | Expr::CharLiteral { .. }
+            | Expr::BooleanLiteral { .. }
+            | Expr::NullLiteral { .. } => {}
+        }
+    }
+}
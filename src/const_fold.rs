@@ -0,0 +1,331 @@
+use crate::ast::expr::{Expr, StringPart};
+use crate::ast::program::Program;
+use crate::ast::stmt::Stmt;
+use crate::codegen::constant::Constant;
+use crate::token::{Token, TokenType};
+
+/// A copy of `program` in which every operator applied only to literals has been
+/// replaced by the literal it evaluates to
+pub fn fold_program(program: &Program) -> Program {
+    let mut folded = program.clone();
+    fold_body(&mut folded.statements);
+    folded
+}
+
+/// Fold the literal-only `BinaryOp`/`UnaryOp` subtrees of `expr` in place, innermost
+/// first. Integer results keep their exact value, so the type they are stored as decides
+/// whether they wrap; the typechecker warns when they do.
+pub fn fold_expr(expr: &mut Expr) {
+    match expr {
+        Expr::BinaryOp { left, right, .. } => {
+            fold_expr(left);
+            fold_expr(right);
+        }
+        Expr::UnaryOp { operand: expr, .. }
+        | Expr::OwnershipTransfer { expr, .. }
+        | Expr::Borrow { expr, .. }
+        | Expr::Deref { expr, .. }
+        | Expr::Cast { expr, .. }
+        | Expr::FieldAccess { object: expr, .. }
+        | Expr::ArrayRepeat { value: expr, .. } => fold_expr(expr),
+        Expr::Call { callee, args, .. } => {
+            fold_expr(callee);
+            for arg in args {
+                fold_expr(arg);
+            }
+        }
+        Expr::ArrayAccess { array, index, .. } => {
+            fold_expr(array);
+            fold_expr(index);
+        }
+        Expr::ArrayLiteral { elements, .. } => {
+            for element in elements {
+                fold_expr(element);
+            }
+        }
+        Expr::Match {
+            value,
+            arms,
+            default,
+            ..
+        } => {
+            fold_expr(value);
+            for (pattern, arm_value) in arms {
+                fold_expr(pattern);
+                fold_expr(arm_value);
+            }
+            if let Some(default) = default {
+                fold_expr(default);
+            }
+        }
+        Expr::IfExpr {
+            condition,
+            then_expr,
+            else_expr,
+            ..
+        } => {
+            fold_expr(condition);
+            fold_expr(then_expr);
+            fold_expr(else_expr);
+        }
+        Expr::Block {
+            statements, tail, ..
+        } => {
+            fold_body(statements);
+            fold_expr(tail);
+        }
+        Expr::StructLiteral { fields, .. } => {
+            for (_, value) in fields {
+                fold_expr(value);
+            }
+        }
+        Expr::InterpolatedString { parts, .. } => {
+            for part in parts {
+                if let StringPart::Expr(expr) = part {
+                    fold_expr(expr);
+                }
+            }
+        }
+        Expr::IntegerLiteral { .. }
+        | Expr::FloatLiteral { .. }
+        | Expr::StringLiteral { .. }
+        | Expr::CharLiteral { .. }
+        | Expr::BooleanLiteral { .. }
+        | Expr::NullLiteral { .. }
+        | Expr::Identifier { .. }
+        | Expr::ModuleAccess { .. } => {}
+    }
+
+    if let Some(literal) = folded_literal(expr) {
+        *expr = literal;
+    }
+}
+
+/// The value `expr` folds to when it is integer arithmetic on literals, such as
+/// `100000 * 100000`; `None` for a bare literal or anything that does not fold
+pub fn folded_integer(expr: &Expr) -> Option<i64> {
+    if !matches!(expr, Expr::BinaryOp { .. } | Expr::UnaryOp { .. }) {
+        return None;
+    }
+    let mut folded = expr.clone();
+    fold_expr(&mut folded);
+    match folded {
+        Expr::IntegerLiteral { value, .. } => value.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Whether `expr` is an integer built from literals that evaluates to zero, such as
+/// the divisor in `x / (2 - 2)`
+pub fn is_constant_zero(expr: &Expr) -> bool {
+    Constant::evaluate(expr, &|_| None) == Some(Constant::Int(0))
+}
+
+fn fold_body(body: &mut [Stmt]) {
+    for stmt in body {
+        fold_stmt(stmt);
+    }
+}
+
+fn fold_stmt(stmt: &mut Stmt) {
+    match stmt {
+        Stmt::VariableDecl { initializer, .. } => {
+            if let Some(init) = initializer {
+                fold_expr(init);
+            }
+        }
+        Stmt::ConstDecl { initializer, .. } => fold_expr(initializer),
+        Stmt::Assignment { target, value, .. } => {
+            fold_expr(target);
+            fold_expr(value);
+        }
+        Stmt::FunctionDecl { body, .. } => fold_body(body),
+        Stmt::Return { value, .. } => {
+            if let Some(value) = value {
+                fold_expr(value);
+            }
+        }
+        Stmt::If {
+            condition,
+            then_branch,
+            else_if_branches,
+            else_branch,
+            ..
+        } => {
+            fold_expr(condition);
+            fold_body(then_branch);
+            for branch in else_if_branches {
+                fold_expr(&mut branch.condition);
+                fold_body(&mut branch.body);
+            }
+            if let Some(else_branch) = else_branch {
+                fold_body(else_branch);
+            }
+        }
+        Stmt::While {
+            condition, body, ..
+        } => {
+            fold_expr(condition);
+            fold_body(body);
+        }
+        Stmt::Loop { body, .. } => fold_body(body),
+        Stmt::For {
+            init,
+            condition,
+            increment,
+            body,
+            ..
+        } => {
+            if let Some(init) = init {
+                fold_stmt(init);
+            }
+            if let Some(condition) = condition {
+                fold_expr(condition);
+            }
+            if let Some(increment) = increment {
+                fold_expr(increment);
+            }
+            fold_body(body);
+        }
+        Stmt::ForIn { iterable, body, .. } => {
+            fold_expr(iterable);
+            fold_body(body);
+        }
+        Stmt::Match {
+            value,
+            arms,
+            default,
+            ..
+        } => {
+            fold_expr(value);
+            for (pattern, body) in arms {
+                fold_expr(pattern);
+                fold_body(body);
+            }
+            if let Some(default) = default {
+                fold_body(default);
+            }
+        }
+        Stmt::Mod { items, .. } | Stmt::ImplBlock { methods: items, .. } => fold_body(items),
+        Stmt::ExprStmt { expr } => fold_expr(expr),
+        Stmt::Block { statements } => fold_body(statements),
+        Stmt::Break { .. } | Stmt::Continue { .. } | Stmt::Use { .. } | Stmt::StructDecl { .. } => {
+        }
+    }
+}
+
+// The literal an operator node evaluates to, when its operands already are literals.
+// Chars are left alone, since arithmetic on one would turn it into an integer.
+fn folded_literal(expr: &Expr) -> Option<Expr> {
+    let op = match expr {
+        Expr::BinaryOp { op, left, right } if is_literal(left) && is_literal(right) => op,
+        Expr::UnaryOp { op, operand } if is_literal(operand) => op,
+        _ => return None,
+    };
+    let literal = |kind: TokenType, lexeme: String| Token::new(kind, lexeme, op.line, op.column);
+
+    match Constant::evaluate(expr, &|_| None)? {
+        Constant::Int(value) => Some(Expr::IntegerLiteral {
+            value: value.to_string(),
+            token: literal(TokenType::IntegerLiteral, value.to_string()),
+        }),
+        Constant::Float(value) if value.is_finite() => Some(Expr::FloatLiteral {
+            value,
+            token: literal(TokenType::FloatLiteral, format!("{:?}", value)),
+        }),
+        Constant::Bool(value) => Some(Expr::BooleanLiteral {
+            value,
+            token: literal(
+                if value {
+                    TokenType::True
+                } else {
+                    TokenType::False
+                },
+                value.to_string(),
+            ),
+        }),
+        Constant::Float(_) | Constant::Str(_) => None,
+    }
+}
+
+fn is_literal(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::IntegerLiteral { .. }
+            | Expr::FloatLiteral { .. }
+            | Expr::BooleanLiteral { .. }
+            | Expr::StringLiteral { .. }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::lexer::Lexer;
+    use crate::parser::parser::Parser;
+
+    // The initializer of `let x = <code>`
+    fn initializer(code: &str) -> Expr {
+        let tokens = Lexer::new(&format!("let x = {}", code)).tokenize().unwrap();
+        let program = Parser::new(tokens).parse_strict().unwrap();
+        match &program.statements[0] {
+            Stmt::VariableDecl {
+                initializer: Some(init),
+                ..
+            } => init.clone(),
+            other => panic!("Expected a variable declaration, got {:?}", other),
+        }
+    }
+
+    // The folded initializer of `let x = <code>`
+    fn folded(code: &str) -> Expr {
+        let mut init = initializer(code);
+        fold_expr(&mut init);
+        init
+    }
+
+    #[test]
+    fn test_literal_arithmetic_folds_to_one_literal() {
+        assert!(matches!(folded("2 + 3 * 4"), Expr::IntegerLiteral { value, .. } if value == "14"));
+        assert!(
+            matches!(folded("-(7 - 10) / 2"), Expr::IntegerLiteral { value, .. } if value == "1")
+        );
+        assert!(matches!(folded("1.5 * 2.0"), Expr::FloatLiteral { value, .. } if value == 3.0));
+        assert!(matches!(
+            folded("true && false"),
+            Expr::BooleanLiteral { value: false, .. }
+        ));
+        assert!(matches!(
+            folded("!(1 > 2)"),
+            Expr::BooleanLiteral { value: true, .. }
+        ));
+    }
+
+    #[test]
+    fn test_integer_results_keep_their_exact_value() {
+        assert!(
+            matches!(folded("100000 * 100000"), Expr::IntegerLiteral { value, .. } if value == "10000000000")
+        );
+        assert_eq!(
+            folded_integer(&initializer("2147483647 + 1")),
+            Some(2147483648)
+        );
+        assert_eq!(folded_integer(&initializer("7")), None);
+    }
+
+    #[test]
+    fn test_only_literal_subtrees_are_folded() {
+        match folded("y * (2 + 3)") {
+            Expr::BinaryOp { left, right, .. } => {
+                assert!(matches!(left.as_ref(), Expr::Identifier { .. }));
+                assert!(
+                    matches!(right.as_ref(), Expr::IntegerLiteral { value, .. } if value == "5")
+                );
+            }
+            other => panic!("Expected a multiplication, got {:?}", other),
+        }
+        // Dividing by zero and char arithmetic are not folded
+        assert!(matches!(folded("1 / 0"), Expr::BinaryOp { .. }));
+        assert!(matches!(folded("'a' + 1"), Expr::BinaryOp { .. }));
+    }
+}
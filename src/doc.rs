@@ -0,0 +1,110 @@
+use crate::ast::program::Program;
+use crate::ast::stmt::Stmt;
+
+/// Render the public functions and structs of a program, with their doc comments, as markdown
+pub fn generate_markdown(program: &Program, title: &str) -> String {
+    let mut functions = Vec::new();
+    let mut structs = Vec::new();
+
+    for stmt in &program.statements {
+        match stmt {
+            Stmt::FunctionDecl {
+                name,
+                type_params,
+                params,
+                return_type,
+                is_public: true,
+                doc,
+                ..
+            } => {
+                let params: Vec<String> = params
+                    .iter()
+                    .map(|(param_name, param_type)| format!("{}: {}", param_name, param_type))
+                    .collect();
+                let type_params = if type_params.is_empty() {
+                    String::new()
+                } else {
+                    format!("<{}>", type_params.join(", "))
+                };
+                let signature = format!(
+                    "pub fn {}{}({}) -> {}",
+                    name,
+                    type_params,
+                    params.join(", "),
+                    return_type
+                );
+                functions.push((name, signature, doc));
+            }
+            Stmt::StructDecl {
+                name,
+                fields,
+                derives,
+                is_public: true,
+                doc,
+                ..
+            } => {
+                let mut signature = String::new();
+                if !derives.is_empty() {
+                    signature.push_str(&format!("#[derive({})]\n", derives.join(", ")));
+                }
+                signature.push_str(&format!("pub struct {} {{\n", name));
+                for (field_name, field_type) in fields {
+                    signature.push_str(&format!("    {}: {},\n", field_name, field_type));
+                }
+                signature.push('}');
+                structs.push((name, signature, doc));
+            }
+            _ => {}
+        }
+    }
+
+    let mut markdown = format!("# {}\n", title);
+    for (heading, items) in [("Structs", &structs), ("Functions", &functions)] {
+        if items.is_empty() {
+            continue;
+        }
+        markdown.push_str(&format!("\n## {}\n", heading));
+        for (name, signature, doc) in items {
+            markdown.push_str(&format!("\n### `{}`\n\n```zen\n{}\n```\n", name, signature));
+            if let Some(doc) = doc {
+                markdown.push_str(&format!("\n{}\n", doc));
+            }
+        }
+    }
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::lexer::Lexer;
+    use crate::parser::parser::Parser;
+
+    fn document(code: &str) -> String {
+        let tokens = Lexer::new(code).tokenize().unwrap();
+        let program = Parser::new(tokens).parse_strict().unwrap();
+        generate_markdown(&program, "math")
+    }
+
+    #[test]
+    fn test_public_function_signature_and_doc() {
+        let markdown = document(
+            "/// Adds two numbers.\n/// Overflow wraps.\npub fn add(a: i32, b: i32) -> i32 {\n    return a + b\n}\n\n/// Not exported\nfn helper() -> void {}",
+        );
+
+        assert!(markdown.starts_with("# math\n"));
+        assert!(markdown.contains("```zen\npub fn add(a: i32, b: i32) -> i32\n```"));
+        assert!(markdown.contains("\nAdds two numbers.\nOverflow wraps.\n"));
+        assert!(!markdown.contains("helper"));
+        assert!(!markdown.contains("Not exported"));
+    }
+
+    #[test]
+    fn test_public_struct_lists_fields() {
+        let markdown = document("/// A point on the plane\npub struct Point { x: i32, y: &i32 }");
+
+        assert!(markdown.contains("## Structs"));
+        assert!(markdown.contains("pub struct Point {\n    x: i32,\n    y: &i32,\n}"));
+        assert!(markdown.contains("A point on the plane"));
+    }
+}
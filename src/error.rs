@@ -0,0 +1,210 @@
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub enum ZenError {
+    LexError {
+        message: String,
+        line: usize,
+        column: usize,
+        source_line: Option<String>,
+    },
+    ParseError {
+        message: String,
+        line: usize,
+        column: usize,
+        source_line: Option<String>,
+        expected: Option<String>,
+        found: Option<String>,
+    },
+    TypeError {
+        message: String,
+        line: usize,
+        column: usize,
+        source_line: Option<String>,
+        expected_type: Option<String>,
+        found_type: Option<String>,
+    },
+    CodegenError {
+        message: String,
+        context: Option<String>,
+    },
+    IoError {
+        message: String,
+        path: Option<String>,
+    },
+}
+
+impl ZenError {
+    pub fn with_source_line(mut self, source_line: String) -> Self {
+        match &mut self {
+            ZenError::LexError {
+                source_line: ref mut sl,
+                ..
+            } => *sl = Some(source_line),
+            ZenError::ParseError {
+                source_line: ref mut sl,
+                ..
+            } => *sl = Some(source_line),
+            ZenError::TypeError {
+                source_line: ref mut sl,
+                ..
+            } => *sl = Some(source_line),
+            _ => {}
+        }
+        self
+    }
+
+    /// The error's message, without its position or source context
+    pub fn message(&self) -> &str {
+        match self {
+            ZenError::LexError { message, .. }
+            | ZenError::ParseError { message, .. }
+            | ZenError::TypeError { message, .. }
+            | ZenError::CodegenError { message, .. }
+            | ZenError::IoError { message, .. } => message,
+        }
+    }
+
+    /// The 1-based source line the error points at, if it has one
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            ZenError::LexError { line, .. }
+            | ZenError::ParseError { line, .. }
+            | ZenError::TypeError { line, .. } => Some(*line),
+            ZenError::CodegenError { .. } | ZenError::IoError { .. } => None,
+        }
+    }
+
+    /// Attach the line of `source` the error points at, for `format_with_context`
+    pub fn with_source(self, source: &str) -> Self {
+        let text = self
+            .line()
+            .and_then(|line| source.lines().nth(line.checked_sub(1)?));
+        match text {
+            Some(text) => {
+                let text = text.to_string();
+                self.with_source_line(text)
+            }
+            None => self,
+        }
+    }
+
+    pub fn format_with_context(&self) -> String {
+        match self {
+            ZenError::LexError {
+                message,
+                line,
+                column,
+                source_line,
+            } => {
+                let mut result = format!("Lexical error at {}:{}: {}", line, column, message);
+                if let Some(src) = source_line {
+                    result.push_str(&format!(
+                        "\n  {}\n  {}^",
+                        src,
+                        " ".repeat(column.saturating_sub(1))
+                    ));
+                }
+                result
+            }
+            ZenError::ParseError {
+                message,
+                line,
+                column,
+                source_line,
+                expected,
+                found,
+            } => {
+                let mut result = format!("Parse error at {}:{}: {}", line, column, message);
+                if let (Some(exp), Some(fnd)) = (expected, found) {
+                    result.push_str(&format!("\n  Expected: {}\n  Found: {}", exp, fnd));
+                }
+                if let Some(src) = source_line {
+                    result.push_str(&format!(
+                        "\n  {}\n  {}^",
+                        src,
+                        " ".repeat(column.saturating_sub(1))
+                    ));
+                }
+                result
+            }
+            ZenError::TypeError {
+                message,
+                line,
+                column,
+                source_line,
+                expected_type,
+                found_type,
+            } => {
+                // Line 0 marks an error with no source position
+                let mut result = if *line == 0 {
+                    format!("Type error: {}", message)
+                } else {
+                    format!("Type error at {}:{}: {}", line, column, message)
+                };
+                if let (Some(exp), Some(fnd)) = (expected_type, found_type) {
+                    result.push_str(&format!(
+                        "\n  Expected type: {}\n  Found type: {}",
+                        exp, fnd
+                    ));
+                }
+                if let Some(src) = source_line {
+                    result.push_str(&format!(
+                        "\n  {}\n  {}^",
+                        src,
+                        " ".repeat(column.saturating_sub(1))
+                    ));
+                }
+                result
+            }
+            ZenError::CodegenError { message, context } => {
+                let mut result = format!("Code generation error: {}", message);
+                if let Some(ctx) = context {
+                    result.push_str(&format!("\n  Context: {}", ctx));
+                }
+                result
+            }
+            ZenError::IoError { message, path } => {
+                let mut result = format!("I/O error: {}", message);
+                if let Some(p) = path {
+                    result.push_str(&format!("\n  Path: {}", p));
+                }
+                result
+            }
+        }
+    }
+}
+
+impl fmt::Display for ZenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZenError::LexError {
+                message,
+                line,
+                column,
+                ..
+            } => write!(f, "Lexical error at {}:{}: {}", line, column, message),
+            ZenError::ParseError {
+                message,
+                line,
+                column,
+                ..
+            } => write!(f, "Parse error at {}:{}: {}", line, column, message),
+            ZenError::TypeError { message, line, .. } if *line == 0 => {
+                write!(f, "Type error: {}", message)
+            }
+            ZenError::TypeError {
+                message,
+                line,
+                column,
+                ..
+            } => write!(f, "Type error at {}:{}: {}", line, column, message),
+            ZenError::CodegenError { message, .. } => {
+                write!(f, "Code generation error: {}", message)
+            }
+            ZenError::IoError { message, .. } => write!(f, "I/O error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ZenError {}
@@ -1,9 +1,13 @@
 use std::collections::HashMap;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::{OnceLock, RwLock};
 
-/// String interning for better memory usage and faster comparisons
+/// String interning for better memory usage and faster comparisons. Interned strings
+/// live for the rest of the process, so lookups hand out `&'static str`s.
 pub struct StringInterner {
-    strings: Vec<String>,
-    indices: HashMap<String, usize>,
+    strings: Vec<&'static str>,
+    indices: HashMap<&'static str, usize>,
 }
 
 impl StringInterner {
@@ -19,14 +23,24 @@ impl StringInterner {
             index
         } else {
             let index = self.strings.len();
-            self.strings.push(s.to_string());
-            self.indices.insert(s.to_string(), index);
+            let stored: &'static str = Box::leak(s.into());
+            self.strings.push(stored);
+            self.indices.insert(stored, index);
             index
         }
     }
 
-    pub fn get(&self, index: usize) -> Option<&str> {
-        self.strings.get(index).map(|s| s.as_str())
+    pub fn get(&self, index: usize) -> Option<&'static str> {
+        self.strings.get(index).copied()
+    }
+
+    /// Number of distinct strings interned so far
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
     }
 }
 
@@ -36,26 +50,85 @@ impl Default for StringInterner {
     }
 }
 
-/// Optimized token representation using interned strings
-#[derive(Debug, Clone, PartialEq)]
-pub struct InternedToken {
-    pub kind: crate::token::TokenType,
-    pub lexeme_index: usize,
-    pub line: usize,
-    pub column: usize,
+// Every symbol indexes this one interner, so two symbols are equal exactly when their
+// indices are
+fn interner() -> &'static RwLock<StringInterner> {
+    static INTERNER: OnceLock<RwLock<StringInterner>> = OnceLock::new();
+    INTERNER.get_or_init(|| RwLock::new(StringInterner::new()))
 }
 
-impl InternedToken {
-    pub fn new(kind: crate::token::TokenType, lexeme_index: usize, line: usize, column: usize) -> Self {
-        Self {
-            kind,
-            lexeme_index,
-            line,
-            column,
+/// An interned string: a `Copy` index into the process-wide [`StringInterner`]. It
+/// derefs to `str`, so lexemes compare and format like the `String`s they replace.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Symbol(usize);
+
+impl Symbol {
+    pub fn intern(s: &str) -> Self {
+        if let Some(&index) = interner().read().unwrap().indices.get(s) {
+            return Symbol(index);
         }
+        Symbol(interner().write().unwrap().intern(s))
+    }
+
+    pub fn index(self) -> usize {
+        self.0
+    }
+
+    pub fn as_str(self) -> &'static str {
+        interner()
+            .read()
+            .unwrap()
+            .get(self.0)
+            .expect("symbols are only created by interning")
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(s: &str) -> Self {
+        Symbol::intern(s)
     }
+}
+
+impl From<String> for Symbol {
+    fn from(s: String) -> Self {
+        Symbol::intern(&s)
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for Symbol {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
 
-    pub fn lexeme<'a>(&self, interner: &'a StringInterner) -> Option<&'a str> {
-        interner.get(self.lexeme_index)
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
     }
 }
@@ -1,22 +1,26 @@
 use std::iter::Peekable;
 use std::str::Chars;
 
+use crate::error::ZenError;
 use crate::token::{Token, TokenType};
+use crate::types::FloatKind;
 
 pub struct Lexer<'a> {
+    source: &'a str,
     input: Peekable<Chars<'a>>,
     line: usize,
     column: usize,
     // Enhanced state tracking
     start_line: usize,
     start_column: usize,
-    errors: Vec<String>,
+    errors: Vec<ZenError>,
     current_lexeme: String,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(input: &'a str) -> Self {
         Lexer {
+            source: input,
             input: input.chars().peekable(),
             line: 1,
             column: 1,
@@ -27,7 +31,7 @@ impl<'a> Lexer<'a> {
         }
     }
 
-    pub fn tokenize(&mut self) -> Result<Vec<Token>, Vec<String>> {
+    pub fn tokenize(&mut self) -> Result<Vec<Token>, Vec<ZenError>> {
         let mut tokens = Vec::with_capacity(1024); // Pre-allocate for better performance
 
         while self.peek().is_some() {
@@ -50,7 +54,12 @@ impl<'a> Lexer<'a> {
         if self.errors.is_empty() {
             Ok(tokens)
         } else {
-            Err(self.errors.clone())
+            let source = self.source;
+            Err(self
+                .errors
+                .drain(..)
+                .map(|error| error.with_source(source))
+                .collect())
         }
     }
 
@@ -58,20 +67,22 @@ impl<'a> Lexer<'a> {
         match &token.kind {
             TokenType::IntegerLiteral => {
                 // Enhanced integer validation
-                match token.lexeme.parse::<i64>() {
+                match parse_integer_literal(&token.lexeme) {
                     Ok(val) if val > i32::MAX as i64 || val < i32::MIN as i64 => {
-                        self.report_warning(format!(
-                            "Integer literal '{}' may overflow i32 at {}:{}",
-                            token.lexeme, token.line, token.column
-                        ));
+                        self.report_warning(
+                            format!("Integer literal '{}' may overflow i32", token.lexeme),
+                            token.line,
+                            token.column,
+                        );
                         true
                     }
                     Ok(_) => true,
                     Err(_) => {
-                        self.report_error(format!(
-                            "Invalid integer literal '{}' at {}:{}",
-                            token.lexeme, token.line, token.column
-                        ));
+                        self.report_error(
+                            format!("Invalid integer literal '{}'", token.lexeme),
+                            token.line,
+                            token.column,
+                        );
                         false
                     }
                 }
@@ -79,20 +90,22 @@ impl<'a> Lexer<'a> {
 
             TokenType::FloatLiteral => {
                 // Enhanced float validation
-                match token.lexeme.parse::<f64>() {
+                match split_float_suffix(&token.lexeme).0.parse::<f64>() {
                     Ok(val) if !val.is_finite() => {
-                        self.report_error(format!(
-                            "Invalid float literal '{}' (non-finite) at {}:{}",
-                            token.lexeme, token.line, token.column
-                        ));
+                        self.report_error(
+                            format!("Invalid float literal '{}' (non-finite)", token.lexeme),
+                            token.line,
+                            token.column,
+                        );
                         false
                     }
                     Ok(_) => true,
                     Err(_) => {
-                        self.report_error(format!(
-                            "Invalid float literal '{}' at {}:{}",
-                            token.lexeme, token.line, token.column
-                        ));
+                        self.report_error(
+                            format!("Invalid float literal '{}'", token.lexeme),
+                            token.line,
+                            token.column,
+                        );
                         false
                     }
                 }
@@ -101,29 +114,29 @@ impl<'a> Lexer<'a> {
             TokenType::Identifier => {
                 // Enhanced identifier validation
                 if token.lexeme.is_empty() {
-                    self.report_error(format!(
-                        "Empty identifier at {}:{}",
-                        token.line, token.column
-                    ));
+                    self.report_error("Empty identifier".to_string(), token.line, token.column);
                     return false;
                 }
 
                 if token.lexeme.len() > 255 {
-                    self.report_warning(format!(
-                        "Identifier '{}' is very long ({} chars) at {}:{}",
-                        token.lexeme,
-                        token.lexeme.len(),
+                    self.report_warning(
+                        format!(
+                            "Identifier '{}' is very long ({} chars)",
+                            token.lexeme,
+                            token.lexeme.len()
+                        ),
                         token.line,
-                        token.column
-                    ));
+                        token.column,
+                    );
                 }
 
                 // Check for reserved words that might be missed
                 if self.is_reserved_word(&token.lexeme) {
-                    self.report_warning(format!(
-                        "Identifier '{}' conflicts with reserved word at {}:{}",
-                        token.lexeme, token.line, token.column
-                    ));
+                    self.report_warning(
+                        format!("Identifier '{}' conflicts with reserved word", token.lexeme),
+                        token.line,
+                        token.column,
+                    );
                 }
 
                 true
@@ -132,12 +145,20 @@ impl<'a> Lexer<'a> {
             TokenType::StringLiteral => {
                 // Enhanced string validation
                 if token.lexeme.len() > 1000 {
-                    self.report_warning(format!(
-                        "String literal is very long ({} chars) at {}:{}",
-                        token.lexeme.len(),
+                    self.report_warning(
+                        format!("String literal is very long ({} chars)", token.lexeme.len()),
                         token.line,
-                        token.column
-                    ));
+                        token.column,
+                    );
+                }
+                // Escapes are decoded by the parser, but a bad one is reported here
+                let body = token
+                    .lexeme
+                    .get(1..token.lexeme.len() - 1)
+                    .unwrap_or_default();
+                if let Err(e) = decode_escapes(body) {
+                    self.report_error(e.to_string(), token.line, token.column);
+                    return false;
                 }
                 true
             }
@@ -146,12 +167,17 @@ impl<'a> Lexer<'a> {
         }
     }
 
-    fn report_error(&mut self, message: String) {
-        self.errors.push(format!("ERROR: {}", message));
+    fn report_error(&mut self, message: String, line: usize, column: usize) {
+        self.errors.push(ZenError::LexError {
+            message,
+            line,
+            column,
+            source_line: None,
+        });
     }
 
-    fn report_warning(&mut self, message: String) {
-        self.errors.push(format!("WARNING: {}", message));
+    fn report_warning(&mut self, message: String, line: usize, column: usize) {
+        self.report_error(format!("WARNING: {}", message), line, column);
     }
 
     fn is_reserved_word(&self, word: &str) -> bool {
@@ -179,55 +205,68 @@ impl<'a> Lexer<'a> {
 
         match ch {
             // Skip whitespace and newlines
-            ' ' | '\t' | '\r' => self.next_token(),
-            '\n' => {
-                self.line += 1;
-                self.column = 1;
-                self.next_token()
-            }
+            ' ' | '\t' | '\r' | '\n' => self.next_token(),
 
             // Comments
             '/' => {
-                if self.peek() == Some('/') {
+                if self.peek() == Some('/')
+                    && self.peek_nth(1) == Some('/')
+                    && self.peek_nth(2) != Some('/')
+                {
+                    // Doc comment - `///` but not a `////` separator line
+                    let line = self.line;
+                    let column = self.column - 1;
+                    self.advance();
+                    self.advance();
+                    let mut text = String::new();
+                    while let Some(c) = self.peek().filter(|&c| c != '\n') {
+                        text.push(c);
+                        self.advance();
+                    }
+                    let text = text.strip_prefix(' ').unwrap_or(&text).trim_end();
+                    Some(Token::new(
+                        TokenType::DocComment,
+                        text.to_string(),
+                        line,
+                        column,
+                    ))
+                } else if self.peek() == Some('/') {
                     // Single line comment - skip to end of line
                     while self.peek().is_some() && self.peek() != Some('\n') {
                         self.advance();
                     }
                     self.next_token()
                 } else if self.peek() == Some('*') {
-                    // Multi-line comment
+                    // Multi-line comment; `/* ... */` pairs inside it nest
                     self.advance(); // consume *
                     let start_line = self.line;
-                    let start_col = self.column;
-                    while self.peek().is_some() {
-                        if self.advance() == Some('*') && self.peek() == Some('/') {
+                    let start_col = self.column - 2;
+                    let mut depth = 1;
+                    while let Some(c) = self.advance() {
+                        if c == '/' && self.peek() == Some('*') {
+                            self.advance(); // consume *
+                            depth += 1;
+                        } else if c == '*' && self.peek() == Some('/') {
                             self.advance(); // consume /
-                            return self.next_token();
+                            depth -= 1;
+                            if depth == 0 {
+                                return self.next_token();
+                            }
                         }
                     }
-                    Some(Token::new(
-                        TokenType::Unknown,
+                    self.report_error(
                         "Unterminated multi-line comment".to_string(),
                         start_line,
                         start_col,
-                    ))
+                    );
+                    None
                 } else {
-                    Some(Token::new(
-                        TokenType::Slash,
-                        "/".to_string(),
-                        self.line,
-                        self.column - 1,
-                    ))
+                    self.operator_or_compound(ch, TokenType::Slash, TokenType::SlashEqual)
                 }
             }
 
             // Operators
-            '+' => Some(Token::new(
-                TokenType::Plus,
-                "+".to_string(),
-                self.line,
-                self.column - 1,
-            )),
+            '+' => self.operator_or_compound(ch, TokenType::Plus, TokenType::PlusEqual),
             '-' => {
                 if self.peek() == Some('>') {
                     self.advance();
@@ -237,33 +276,30 @@ impl<'a> Lexer<'a> {
                         self.line,
                         self.column - 2,
                     ))
+                } else {
+                    self.operator_or_compound(ch, TokenType::Minus, TokenType::MinusEqual)
+                }
+            }
+            '*' => self.operator_or_compound(ch, TokenType::Star, TokenType::StarEqual),
+            '%' => self.operator_or_compound(ch, TokenType::Percent, TokenType::PercentEqual),
+            '^' => {
+                if self.peek() == Some('^') {
+                    self.advance();
+                    Some(Token::new(
+                        TokenType::CaretCaret,
+                        "^^".to_string(),
+                        self.line,
+                        self.column - 2,
+                    ))
                 } else {
                     Some(Token::new(
-                        TokenType::Minus,
-                        "-".to_string(),
+                        TokenType::Caret,
+                        "^".to_string(),
                         self.line,
                         self.column - 1,
                     ))
                 }
             }
-            '*' => Some(Token::new(
-                TokenType::Star,
-                "*".to_string(),
-                self.line,
-                self.column - 1,
-            )),
-            '%' => Some(Token::new(
-                TokenType::Percent,
-                "%".to_string(),
-                self.line,
-                self.column - 1,
-            )),
-            '^' => Some(Token::new(
-                TokenType::Caret,
-                "^".to_string(),
-                self.line,
-                self.column - 1,
-            )),
 
             '<' => {
                 if self.peek() == Some('=') {
@@ -282,6 +318,14 @@ impl<'a> Lexer<'a> {
                         self.line,
                         self.column - 2,
                     ))
+                } else if self.peek() == Some('<') {
+                    self.advance();
+                    Some(Token::new(
+                        TokenType::ShiftLeft,
+                        "<<".to_string(),
+                        self.line,
+                        self.column - 2,
+                    ))
                 } else {
                     Some(Token::new(
                         TokenType::LessThan,
@@ -301,6 +345,14 @@ impl<'a> Lexer<'a> {
                         self.line,
                         self.column - 2,
                     ))
+                } else if self.peek() == Some('>') {
+                    self.advance();
+                    Some(Token::new(
+                        TokenType::ShiftRight,
+                        ">>".to_string(),
+                        self.line,
+                        self.column - 2,
+                    ))
                 } else {
                     Some(Token::new(
                         TokenType::GreaterThan,
@@ -323,7 +375,7 @@ impl<'a> Lexer<'a> {
                 } else if self.peek() == Some('>') {
                     self.advance();
                     Some(Token::new(
-                        TokenType::ArrowRight,
+                        TokenType::FatArrow,
                         "=>".to_string(),
                         self.line,
                         self.column - 2,
@@ -365,7 +417,7 @@ impl<'a> Lexer<'a> {
                         self.column - 2,
                     ))
                 } else {
-                    // Check for &mut
+                    // Check for &mut; `& mut` with a space is joined by the parser
                     let start_pos = self.column - 1;
                     if self.match_keyword("mut") {
                         Some(Token::new(
@@ -414,6 +466,12 @@ impl<'a> Lexer<'a> {
                 self.line,
                 self.column - 1,
             )),
+            '#' => Some(Token::new(
+                TokenType::Hash,
+                "#".to_string(),
+                self.line,
+                self.column - 1,
+            )),
             ':' => {
                 if self.peek() == Some(':') {
                     self.advance();
@@ -444,6 +502,8 @@ impl<'a> Lexer<'a> {
                 self.line,
                 self.column - 1,
             )),
+            // A dot directly followed by a digit starts a float such as `.5`
+            '.' if self.peek().is_some_and(|c| c.is_ascii_digit()) => self.number_literal(ch),
             '.' => {
                 if self.peek() == Some('.') {
                     self.advance();
@@ -523,8 +583,14 @@ impl<'a> Lexer<'a> {
 
     fn advance(&mut self) -> Option<char> {
         let ch = self.input.next();
-        if ch.is_some() {
-            self.column += 1;
+        match ch {
+            // Newlines are tracked here so strings and block comments keep line numbers in sync
+            Some('\n') => {
+                self.line += 1;
+                self.column = 1;
+            }
+            Some(_) => self.column += 1,
+            None => {}
         }
         ch
     }
@@ -533,17 +599,54 @@ impl<'a> Lexer<'a> {
         self.input.peek().copied()
     }
 
+    // Looks `n` characters past the next one without consuming anything
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.input.clone().nth(n)
+    }
+
+    // An arithmetic operator, or its compound assignment when followed by `=`
+    fn operator_or_compound(
+        &mut self,
+        ch: char,
+        operator: TokenType,
+        compound: TokenType,
+    ) -> Option<Token> {
+        if self.peek() == Some('=') {
+            self.advance();
+            return Some(Token::new(
+                compound,
+                format!("{}=", ch),
+                self.line,
+                self.column - 2,
+            ));
+        }
+        Some(Token::new(
+            operator,
+            ch.to_string(),
+            self.line,
+            self.column - 1,
+        ))
+    }
+
+    // Consume `keyword` only as a whole word, so `&mutable` is not `&mut` + `able`
     fn match_keyword(&mut self, keyword: &str) -> bool {
         let current_pos = self.input.clone();
+        let current_column = self.column;
 
         for expected in keyword.chars() {
             if self.peek() != Some(expected) {
                 self.input = current_pos;
+                self.column = current_column;
                 return false;
             }
             self.advance();
         }
 
+        if self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            self.input = current_pos;
+            self.column = current_column;
+            return false;
+        }
         true
     }
 
@@ -576,12 +679,12 @@ impl<'a> Lexer<'a> {
             }
         }
 
-        Some(Token::new(
-            TokenType::Unknown,
-            format!("Unterminated string: {}", lexeme),
+        self.report_error(
+            "Unterminated string literal".to_string(),
             start_line,
             start_col,
-        ))
+        );
+        None
     }
 
     fn char_literal(&mut self) -> Option<Token> {
@@ -590,10 +693,13 @@ impl<'a> Lexer<'a> {
         let mut lexeme = String::new();
         lexeme.push('\'');
 
-        if let Some(ch) = self.advance() {
+        // Take everything up to the closing quote; escapes are decoded by the parser
+        while let Some(ch) = self.peek().filter(|&c| c != '\'' && c != '\n') {
+            self.advance();
             lexeme.push(ch);
-            if ch == '\\' && self.peek().is_some() {
-                if let Some(escaped) = self.advance() {
+            if ch == '\\' {
+                if let Some(escaped) = self.peek().filter(|&c| c != '\n') {
+                    self.advance();
                     lexeme.push(escaped);
                 }
             }
@@ -608,12 +714,12 @@ impl<'a> Lexer<'a> {
                 start_col,
             ))
         } else {
-            Some(Token::new(
-                TokenType::Unknown,
-                format!("Unterminated char: {}", lexeme),
+            self.report_error(
+                "Unterminated char literal".to_string(),
                 start_line,
                 start_col,
-            ))
+            );
+            None
         }
     }
 
@@ -623,13 +729,33 @@ impl<'a> Lexer<'a> {
         let mut lexeme = String::new();
         lexeme.push(first);
 
-        let mut is_float = false;
+        // `0x`, `0o` and `0b` literals take every following letter and digit, so that a
+        // digit outside the radix is reported instead of starting a new token
+        if first == '0' && matches!(self.peek(), Some('x' | 'o' | 'b')) {
+            lexeme.push(self.advance().unwrap_or_default());
+            while let Some(ch) = self
+                .peek()
+                .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+            {
+                self.advance();
+                lexeme.push(ch);
+            }
+            return Some(Token::new(
+                TokenType::IntegerLiteral,
+                lexeme,
+                start_line,
+                start_col,
+            ));
+        }
+
+        let mut is_float = first == '.';
 
         while let Some(ch) = self.peek() {
             if ch.is_ascii_digit() {
                 self.advance();
                 lexeme.push(ch);
-            } else if ch == '.' && !is_float {
+            } else if ch == '.' && !is_float && self.peek_nth(1) != Some('.') {
+                // `1..5` is a range, not the float `1.` followed by `.5`
                 self.advance();
                 lexeme.push(ch);
                 is_float = true;
@@ -641,6 +767,25 @@ impl<'a> Lexer<'a> {
             }
         }
 
+        // Exponent: `e`/`E`, an optional sign, then at least one digit
+        if matches!(self.peek(), Some('e' | 'E')) {
+            let digits_at = if matches!(self.peek_nth(1), Some('+' | '-')) {
+                2
+            } else {
+                1
+            };
+            if self.peek_nth(digits_at).is_some_and(|c| c.is_ascii_digit()) {
+                for _ in 0..digits_at {
+                    lexeme.push(self.advance().unwrap_or_default());
+                }
+                while let Some(ch) = self.peek().filter(|c| c.is_ascii_digit() || *c == '_') {
+                    self.advance();
+                    lexeme.push(ch);
+                }
+                is_float = true;
+            }
+        }
+
         // Check for float type suffix
         if self.peek() == Some('f') {
             self.advance();
@@ -719,9 +864,14 @@ impl<'a> Lexer<'a> {
             "if" => TokenType::If,
             "else" => TokenType::Else,
             "for" => TokenType::For,
+            "in" => TokenType::In,
             "while" => TokenType::While,
+            "loop" => TokenType::Loop,
+            "break" => TokenType::Break,
+            "continue" => TokenType::Continue,
             "match" => TokenType::Match,
             "struct" => TokenType::Struct,
+            "impl" => TokenType::Impl,
             "const" => TokenType::Const,
             "mod" => TokenType::Mod,
             "use" => TokenType::Use,
@@ -754,6 +904,92 @@ impl<'a> Lexer<'a> {
     }
 }
 
+/// The value of an integer literal: decimal, or hexadecimal, octal or binary after a
+/// `0x`, `0o` or `0b` prefix, with `_` separators allowed between digits
+pub fn parse_integer_literal(lexeme: &str) -> Result<i64, std::num::ParseIntError> {
+    let (digits, radix) = match lexeme.get(..2) {
+        Some("0x") => (&lexeme[2..], 16),
+        Some("0o") => (&lexeme[2..], 8),
+        Some("0b") => (&lexeme[2..], 2),
+        _ => (lexeme, 10),
+    };
+    i64::from_str_radix(&digits.replace('_', ""), radix)
+}
+
+// The code point of `\u{1F600}`, with `chars` just past the `u`
+fn decode_unicode_escape(chars: &mut std::str::Chars) -> Result<char, String> {
+    if chars.next() != Some('{') {
+        return Err(
+            "Invalid escape '\\u': expected '{' with 1 to 6 hex digits and '}'".to_string(),
+        );
+    }
+    let mut digits = String::new();
+    loop {
+        match chars.next() {
+            Some('}') => break,
+            Some(c) => digits.push(c),
+            None => return Err(format!("Invalid escape '\\u{{{}': missing '}}'", digits)),
+        }
+    }
+    let escape = format!("\\u{{{}}}", digits);
+    if digits.is_empty() || digits.len() > 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!(
+            "Invalid escape '{}': expected 1 to 6 hex digits",
+            escape
+        ));
+    }
+    u32::from_str_radix(&digits, 16)
+        .ok()
+        .and_then(char::from_u32)
+        .ok_or_else(|| format!("Invalid escape '{}': not a Unicode scalar value", escape))
+}
+
+/// Splits a float literal such as `0.5f32` into its digits and optional type suffix
+pub fn split_float_suffix(lexeme: &str) -> (&str, Option<FloatKind>) {
+    if let Some(digits) = lexeme.strip_suffix("f32") {
+        (digits, Some(FloatKind::F32))
+    } else if let Some(digits) = lexeme.strip_suffix("f64") {
+        (digits, Some(FloatKind::F64))
+    } else {
+        (lexeme, None)
+    }
+}
+
+/// Replaces the backslash escapes `\n`, `\t`, `\r`, `\\`, `\'`, `\"`, `\0`, `\xNN` and `\u{N...}`
+pub fn decode_escapes(text: &str) -> Result<String, String> {
+    let mut decoded = String::new();
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            decoded.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => decoded.push('\n'),
+            Some('t') => decoded.push('\t'),
+            Some('r') => decoded.push('\r'),
+            Some('0') => decoded.push('\0'),
+            Some(c @ ('\\' | '\'' | '"')) => decoded.push(c),
+            Some('x') => {
+                let digits: String = chars.by_ref().take(2).collect();
+                match u8::from_str_radix(&digits, 16) {
+                    Ok(byte) if digits.len() == 2 => decoded.push(char::from(byte)),
+                    _ => {
+                        return Err(format!(
+                            "Invalid escape '\\x{}': expected two hex digits",
+                            digits
+                        ))
+                    }
+                }
+            }
+            Some('u') => decoded.push(decode_unicode_escape(&mut chars)?),
+            Some(c) => return Err(format!("Unknown escape sequence '\\{}'", c)),
+            None => return Err("Incomplete escape sequence at end of literal".to_string()),
+        }
+    }
+    Ok(decoded)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -817,6 +1053,78 @@ mod tests {
         assert_eq!(tokens[3].lexeme, "'c'");
     }
 
+    #[test]
+    fn test_leading_dot_and_exponent_floats() {
+        let tokens = Lexer::new(".5 .5e3 1e-3 2.5E+2").tokenize().unwrap();
+
+        let floats: Vec<_> = tokens[..4]
+            .iter()
+            .map(|t| (t.kind.clone(), t.lexeme.as_str()))
+            .collect();
+        assert_eq!(
+            floats,
+            vec![
+                (TokenType::FloatLiteral, ".5"),
+                (TokenType::FloatLiteral, ".5e3"),
+                (TokenType::FloatLiteral, "1e-3"),
+                (TokenType::FloatLiteral, "2.5E+2"),
+            ]
+        );
+        assert_eq!(tokens[0].lexeme.parse::<f64>(), Ok(0.5));
+        assert_eq!(tokens[2].lexeme.parse::<f64>(), Ok(0.001));
+    }
+
+    #[test]
+    fn test_scientific_floats_parse_with_suffixes() {
+        let tokens = Lexer::new("1e10 2.5E+3 6.022e-23 1e3f32")
+            .tokenize()
+            .unwrap();
+
+        let values: Vec<_> = tokens[..4]
+            .iter()
+            .map(|t| {
+                assert_eq!(t.kind, TokenType::FloatLiteral);
+                let (digits, suffix) = split_float_suffix(&t.lexeme);
+                (digits.parse::<f64>().unwrap(), suffix)
+            })
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                (1e10, None),
+                (2500.0, None),
+                (6.022e-23, None),
+                (1000.0, Some(FloatKind::F32)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dots_after_names_and_numbers_stay_operators() {
+        let kinds: Vec<_> = Lexer::new("a.b 0..5 e.e1")
+            .tokenize()
+            .unwrap()
+            .into_iter()
+            .map(|t| t.kind)
+            .collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                TokenType::Identifier,
+                TokenType::Dot,
+                TokenType::Identifier,
+                TokenType::IntegerLiteral,
+                TokenType::DotDot,
+                TokenType::IntegerLiteral,
+                TokenType::Identifier,
+                TokenType::Dot,
+                TokenType::Identifier,
+                TokenType::EOF,
+            ]
+        );
+    }
+
     #[test]
     fn test_identifiers() {
         let code = "my_variable function_name _private";
@@ -832,6 +1140,25 @@ mod tests {
         assert_eq!(tokens[2].lexeme, "_private");
     }
 
+    #[test]
+    fn test_repeated_lexemes_share_interned_storage() {
+        let code: String = (0..2000)
+            .map(|i| format!("let value_{} = counter + value_{}\n", i % 50, (i + 1) % 50))
+            .collect();
+        let tokens = Lexer::new(&code).tokenize().unwrap();
+        assert_eq!(tokens.len(), 2000 * 6 + 1);
+
+        let mut storage = std::collections::HashMap::new();
+        for token in &tokens {
+            let text = token.lexeme.as_str();
+            let first = *storage.entry(text).or_insert(text.as_ptr());
+            assert_eq!(first, text.as_ptr(), "'{}' is stored more than once", text);
+        }
+        // `let`, `=`, `+`, `counter`, 50 `value_N`s and the final EOF
+        assert_eq!(storage.len(), 55);
+        assert_eq!(tokens[3].lexeme, tokens[6 * 2000 - 6 + 3].lexeme);
+    }
+
     #[test]
     fn test_comments() {
         let code = "let x = 10 // this is a comment\nlet y = 20";
@@ -846,6 +1173,177 @@ mod tests {
         assert_eq!(tokens[4].kind, TokenType::Let);
     }
 
+    #[test]
+    fn test_nested_block_comments() {
+        let code = "let x = /* outer /* inner */ still comment */ 10";
+        let tokens = Lexer::new(code).tokenize().unwrap();
+        let kinds: Vec<_> = tokens.iter().map(|t| t.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            [
+                TokenType::Let,
+                TokenType::Identifier,
+                TokenType::Equal,
+                TokenType::IntegerLiteral,
+                TokenType::EOF
+            ]
+        );
+
+        let errors = Lexer::new("let x = 1\n/* a /* b */\nlet y = 2")
+            .tokenize()
+            .unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            errors[0],
+            ZenError::LexError {
+                line: 2,
+                column: 1,
+                ..
+            }
+        ));
+        assert!(errors[0]
+            .message()
+            .starts_with("Unterminated multi-line comment"));
+    }
+
+    #[test]
+    fn test_prefixed_integer_literals() {
+        let tokens = Lexer::new("0xFF 0b1010 0o17 0xFF_FF").tokenize().unwrap();
+        let lexemes: Vec<&str> = tokens[..4].iter().map(|t| t.lexeme.as_str()).collect();
+        assert_eq!(lexemes, ["0xFF", "0b1010", "0o17", "0xFF_FF"]);
+        assert!(tokens[..4]
+            .iter()
+            .all(|t| t.kind == TokenType::IntegerLiteral));
+
+        assert_eq!(parse_integer_literal("0xFF"), Ok(255));
+        assert_eq!(parse_integer_literal("0b1010"), Ok(10));
+        assert_eq!(parse_integer_literal("0o17"), Ok(15));
+        assert_eq!(parse_integer_literal("0xFF_FF"), Ok(65535));
+    }
+
+    #[test]
+    fn test_prefixed_integer_literal_without_digits_is_rejected() {
+        let errors = Lexer::new("let a = 0x\nlet b = 0b12")
+            .tokenize()
+            .unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0]
+            .to_string()
+            .contains("Lexical error at 1:9: Invalid integer literal '0x'"));
+        assert!(errors[1]
+            .to_string()
+            .contains("Lexical error at 2:9: Invalid integer literal '0b12'"));
+    }
+
+    #[test]
+    fn test_decode_unicode_escapes() {
+        assert_eq!(decode_escapes(r"\u{41}").unwrap(), "A");
+        assert_eq!(decode_escapes(r"\u{1F600}!").unwrap(), "\u{1F600}!");
+        assert!(decode_escapes(r"\u{}")
+            .unwrap_err()
+            .contains("'\\u{}': expected 1 to 6 hex digits"));
+        assert!(decode_escapes(r"\u{110000}")
+            .unwrap_err()
+            .contains("'\\u{110000}': not a Unicode scalar value"));
+        assert!(decode_escapes(r"\u41")
+            .unwrap_err()
+            .contains("expected '{' with 1 to 6 hex digits and '}'"));
+        assert!(decode_escapes(r"\u{41")
+            .unwrap_err()
+            .contains("missing '}'"));
+    }
+
+    #[test]
+    fn test_invalid_string_escape_is_a_lex_error() {
+        let errors = Lexer::new("let s = \"ok\"\nlet t = \"bad \\q\"")
+            .tokenize()
+            .unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].format_with_context(),
+            "Lexical error at 2:9: Unknown escape sequence '\\q'\n  let t = \"bad \\q\"\n          ^"
+        );
+    }
+
+    #[test]
+    fn test_unterminated_literals_and_comments_are_lex_errors() {
+        let start = |code: &str| {
+            let errors = Lexer::new(code).tokenize().unwrap_err();
+            assert_eq!(errors.len(), 1, "{:?}", errors);
+            match &errors[0] {
+                ZenError::LexError {
+                    message,
+                    line,
+                    column,
+                    source_line,
+                } => (message.clone(), *line, *column, source_line.clone()),
+                other => panic!("Expected a lex error, got {:?}", other),
+            }
+        };
+
+        let (message, line, column, source_line) = start("let a = 1\nlet s = \"open\n");
+        assert!(message.starts_with("Unterminated string literal"));
+        assert_eq!((line, column), (2, 9));
+        assert_eq!(source_line.as_deref(), Some("let s = \"open"));
+
+        let (message, line, column, _) = start("let a = 1 /* never\nclosed");
+        assert!(message.starts_with("Unterminated multi-line comment"));
+        assert_eq!((line, column), (1, 11));
+
+        let (message, line, column, _) = start("let c = 'x");
+        assert!(message.starts_with("Unterminated char literal"));
+        assert_eq!((line, column), (1, 9));
+    }
+
+    #[test]
+    fn test_thin_and_fat_arrows_are_distinct() {
+        let tokens = Lexer::new("-> =>").tokenize().unwrap();
+        assert_eq!(tokens[0].kind, TokenType::ArrowRight);
+        assert_eq!(tokens[1].kind, TokenType::FatArrow);
+        assert_eq!(tokens[1].lexeme, "=>");
+    }
+
+    #[test]
+    fn test_shift_and_xor_tokens() {
+        let tokens = Lexer::new("<< >> ^^ ^ <= >= <-").tokenize().unwrap();
+        let kinds: Vec<_> = tokens.iter().map(|token| token.kind.clone()).collect();
+        assert_eq!(
+            kinds[..7],
+            [
+                TokenType::ShiftLeft,
+                TokenType::ShiftRight,
+                TokenType::CaretCaret,
+                TokenType::Caret,
+                TokenType::LessEqual,
+                TokenType::GreaterEqual,
+                TokenType::ArrowLeft,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_escapes() {
+        assert_eq!(decode_escapes(r"a\tb\n").unwrap(), "a\tb\n");
+        assert_eq!(decode_escapes(r"\x41\\\'").unwrap(), "A\\'");
+        assert_eq!(decode_escapes(r"\xFF").unwrap(), "\u{FF}");
+        assert!(decode_escapes(r"\xG1")
+            .unwrap_err()
+            .contains("expected two hex digits"));
+        assert!(decode_escapes(r"\q")
+            .unwrap_err()
+            .contains("Unknown escape sequence"));
+    }
+
+    #[test]
+    fn test_doc_comments() {
+        let code = "/// Adds two numbers\n////\nfn add";
+        let tokens = Lexer::new(code).tokenize().unwrap();
+
+        assert_eq!(tokens[0].kind, TokenType::DocComment);
+        assert_eq!(tokens[0].lexeme, "Adds two numbers");
+        assert_eq!(tokens[1].kind, TokenType::Fn);
+    }
+
     #[test]
     fn test_types() {
         let code = "i32 f64 bool str char void";
@@ -860,4 +1358,46 @@ mod tests {
         assert_eq!(tokens[4].kind, TokenType::Char);
         assert_eq!(tokens[5].kind, TokenType::Void);
     }
+
+    fn positions(code: &str) -> Vec<(usize, usize)> {
+        let mut l = Lexer::new(code);
+        l.tokenize()
+            .unwrap()
+            .iter()
+            .map(|t| (t.line, t.column))
+            .collect()
+    }
+
+    #[test]
+    fn test_columns_after_newlines() {
+        let code = "let x = 1\nx = x + 2\n    return x";
+        assert_eq!(
+            positions(code),
+            vec![
+                (1, 1),
+                (1, 5),
+                (1, 7),
+                (1, 9),
+                (2, 1),
+                (2, 3),
+                (2, 5),
+                (2, 7),
+                (2, 9),
+                (3, 5),
+                (3, 12),
+                (3, 13),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_columns_after_multiline_tokens() {
+        let code = "/* a\ncomment */ a\n\"s\nt\" b\n&mu c";
+        let positions = positions(code);
+        assert_eq!(positions[0], (2, 12));
+        assert_eq!(positions[1], (3, 1));
+        assert_eq!(positions[2], (4, 4));
+        // `&` followed by a partial `mut` must not leave the column advanced
+        assert_eq!(&positions[3..6], &[(5, 1), (5, 2), (5, 5)]);
+    }
 }
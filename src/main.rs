@@ -1,14 +1,19 @@
-use crate::cli::Cli;
+use crate::cli::{version_info, Cli};
 
 pub mod ast;
 pub mod cli;
 pub mod codegen;
 pub mod compiler;
+pub mod const_fold;
+pub mod doc;
+pub mod error;
+pub mod intern;
 pub mod lexer;
 pub mod ownership;
 pub mod parser;
 pub mod token;
 pub mod typechecker;
+pub mod types;
 
 fn main() {
     let args: Vec<String> = std::env::args().collect();
@@ -22,6 +27,14 @@ fn main() {
         return;
     }
 
+    if args
+        .get(1)
+        .is_some_and(|arg| arg == "--version" || arg == "-V")
+    {
+        println!("{}", version_info());
+        return;
+    }
+
     let cli = match Cli::from_args(args) {
         Ok(c) => c,
         Err(e) => {
@@ -34,5 +47,6 @@ fn main() {
 
     if let Err(e) = cli.run() {
         eprintln!("error: {}", e);
+        std::process::exit(1);
     }
 }
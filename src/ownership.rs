@@ -1,4 +1,4 @@
-use crate::ast::expr::Expr;
+use crate::ast::expr::{Expr, StringPart};
 use crate::ast::stmt::Stmt;
 use std::collections::HashMap;
 
@@ -26,6 +26,10 @@ pub struct OwnershipInfo {
     pub borrows: Vec<BorrowInfo>,
     pub scope_level: usize,
     pub is_mutable: bool,
+    /// Copy values stay usable after `<-`
+    pub is_copy: bool,
+    /// Declared with `const`, so it can never be assigned
+    pub is_const: bool,
 }
 
 pub struct OwnershipChecker {
@@ -46,7 +50,7 @@ impl OwnershipChecker {
     }
 
     pub fn check(&mut self, program: &crate::ast::program::Program) -> Result<(), String> {
-        for stmt in &program.statements {
+        for stmt in program.items() {
             self.check_statement(stmt)?;
         }
 
@@ -70,14 +74,19 @@ impl OwnershipChecker {
         match stmt {
             Stmt::VariableDecl {
                 name,
+                type_annotation,
                 initializer,
                 is_mutable,
                 token: _,
-                ..
             } => {
                 if let Some(init) = initializer {
                     self.check_expression(init)?;
                 }
+                let is_copy = match (type_annotation, initializer) {
+                    (Some(annotation), _) => annotation.is_copy(),
+                    (None, Some(init)) => self.is_copy_value(init),
+                    (None, None) => false,
+                };
 
                 self.variables.insert(
                     name.clone(),
@@ -88,6 +97,28 @@ impl OwnershipChecker {
                         borrows: Vec::new(),
                         scope_level: self.scope_level,
                         is_mutable: *is_mutable,
+                        is_copy,
+                        is_const: false,
+                    },
+                );
+            }
+
+            // Constants are scalars or string literals, so using one never moves it
+            Stmt::ConstDecl {
+                name, initializer, ..
+            } => {
+                self.check_expression(initializer)?;
+                self.variables.insert(
+                    name.clone(),
+                    OwnershipInfo {
+                        owner: name.clone(),
+                        is_moved: false,
+                        move_location: None,
+                        borrows: Vec::new(),
+                        scope_level: self.scope_level,
+                        is_mutable: false,
+                        is_copy: true,
+                        is_const: true,
                     },
                 );
             }
@@ -101,6 +132,13 @@ impl OwnershipChecker {
 
                 if let Expr::Identifier { name, .. } = target {
                     if let Some(info) = self.variables.get(name) {
+                        if info.is_const {
+                            self.errors.push(format!(
+                                "Cannot assign to constant '{}' at {}:{}",
+                                name, token.line, token.column
+                            ));
+                        }
+
                         if info.is_moved {
                             self.errors.push(format!(
                                 "Cannot assign to moved variable '{}' at {}:{}",
@@ -170,6 +208,41 @@ impl OwnershipChecker {
                 self.exit_scope();
             }
 
+            Stmt::Loop { body, .. } => {
+                self.enter_scope();
+                for stmt in body {
+                    self.check_statement(stmt)?;
+                }
+                self.exit_scope();
+            }
+
+            Stmt::ForIn {
+                variable,
+                iterable,
+                body,
+                ..
+            } => {
+                self.check_expression(iterable)?;
+                self.enter_scope();
+                self.variables.insert(
+                    variable.clone(),
+                    OwnershipInfo {
+                        owner: variable.clone(),
+                        is_moved: false,
+                        move_location: None,
+                        borrows: Vec::new(),
+                        scope_level: self.scope_level,
+                        is_mutable: false,
+                        is_copy: false,
+                        is_const: false,
+                    },
+                );
+                for stmt in body {
+                    self.check_statement(stmt)?;
+                }
+                self.exit_scope();
+            }
+
             Stmt::Block { statements } => {
                 self.enter_scope();
                 for stmt in statements {
@@ -202,7 +275,9 @@ impl OwnershipChecker {
             Expr::OwnershipTransfer { expr, token } => {
                 if let Expr::Identifier { name, .. } = expr.as_ref() {
                     if let Some(info) = self.variables.get_mut(name) {
-                        if info.is_moved {
+                        if info.is_copy {
+                            // Copying leaves the original usable
+                        } else if info.is_moved {
                             self.errors.push(format!(
                                 "Cannot move already moved variable '{}' at {}:{}",
                                 name, token.line, token.column
@@ -236,11 +311,69 @@ impl OwnershipChecker {
                 self.check_expression(expr)?;
             }
 
+            Expr::Deref { expr, .. }
+            | Expr::Cast { expr, .. }
+            | Expr::ArrayRepeat { value: expr, .. } => {
+                self.check_expression(expr)?;
+            }
+
             Expr::BinaryOp { left, right, .. } => {
                 self.check_expression(left)?;
                 self.check_expression(right)?;
             }
 
+            Expr::ArrayLiteral { elements, .. } => {
+                for element in elements {
+                    self.check_expression(element)?;
+                }
+            }
+
+            Expr::InterpolatedString { parts, .. } => {
+                for part in parts {
+                    if let StringPart::Expr(expr) = part {
+                        self.check_expression(expr)?;
+                    }
+                }
+            }
+
+            Expr::Match {
+                value,
+                arms,
+                default,
+                ..
+            } => {
+                self.check_expression(value)?;
+                for (pattern, arm_value) in arms {
+                    self.check_expression(pattern)?;
+                    self.check_expression(arm_value)?;
+                }
+                if let Some(default_value) = default {
+                    self.check_expression(default_value)?;
+                }
+            }
+
+            Expr::IfExpr {
+                condition,
+                then_expr,
+                else_expr,
+                ..
+            } => {
+                self.check_expression(condition)?;
+                self.check_expression(then_expr)?;
+                self.check_expression(else_expr)?;
+            }
+
+            Expr::Block {
+                statements, tail, ..
+            } => {
+                self.enter_scope();
+                for stmt in statements {
+                    self.check_statement(stmt)?;
+                }
+                self.check_expression(tail)?;
+                self.exit_scope();
+            }
+
             Expr::Call { callee, args, .. } => {
                 self.check_expression(callee)?;
                 for arg in args {
@@ -267,6 +400,26 @@ impl OwnershipChecker {
         Ok(())
     }
 
+    // Whether an initializer produces a Copy scalar; anything unknown keeps move semantics
+    fn is_copy_value(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::IntegerLiteral { .. }
+            | Expr::FloatLiteral { .. }
+            | Expr::BooleanLiteral { .. }
+            | Expr::CharLiteral { .. } => true,
+            Expr::Identifier { name, .. } => {
+                self.variables.get(name).is_some_and(|info| info.is_copy)
+            }
+            Expr::Cast { target_type, .. } => target_type.is_copy(),
+            Expr::OwnershipTransfer { expr, .. } => self.is_copy_value(expr),
+            Expr::UnaryOp { operand, .. } => self.is_copy_value(operand),
+            Expr::BinaryOp { left, right, .. } => {
+                self.is_copy_value(left) && self.is_copy_value(right)
+            }
+            _ => false,
+        }
+    }
+
     fn add_borrow(
         &mut self,
         var_name: &str,
@@ -1,32 +1,68 @@
 use crate::ast::expr::Expr;
 use crate::ast::program::Program;
 use crate::ast::stmt::Stmt;
+use crate::const_fold::fold_expr;
+use crate::error::ZenError;
+use crate::intern::Symbol;
+use crate::lexer::lexer::parse_integer_literal;
 use crate::token::{Token, TokenType};
+use crate::types::Type;
+use std::collections::HashMap;
+
+// Errors after which `parse` gives up on the rest of the file
+const MAX_ERRORS: usize = 10;
 
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    // `///` comments, keyed by the index of the token they document
+    docs: HashMap<usize, String>,
+    // Type parameters of the function being parsed, which type names may refer to
+    type_params: Vec<String>,
+    // The struct of the `impl` block being parsed, which `self` points to
+    impl_type: Option<String>,
     // Enhanced error tracking
-    errors: Vec<String>,
+    errors: Vec<ZenError>,
     panic_mode: bool,
     had_error: bool,
 }
 
 impl Parser {
-    pub fn new(tokens: Vec<Token>) -> Self {
+    pub fn new(mut tokens: Vec<Token>) -> Self {
+        let mut docs = HashMap::new();
+        let mut pending: Vec<String> = Vec::new();
+        let mut kept = 0;
+        // Doc comments are dropped in place rather than copying the rest into a new vector
+        tokens.retain(|token| {
+            if token.kind == TokenType::DocComment {
+                pending.push(token.lexeme.to_string());
+                return false;
+            }
+            if !pending.is_empty() {
+                docs.insert(kept, pending.join("\n"));
+                pending.clear();
+            }
+            kept += 1;
+            true
+        });
+
         Parser {
             tokens,
             current: 0,
+            docs,
+            type_params: Vec::new(),
+            impl_type: None,
             errors: Vec::new(),
             panic_mode: false,
             had_error: false,
         }
     }
 
-    pub fn parse(&mut self) -> Result<Program, String> {
+    /// Parse as much as possible: statements that fail are skipped and reported, so tools
+    /// still get the rest of the file. Parsing stops after `MAX_ERRORS` errors.
+    pub fn parse(&mut self) -> (Program, Vec<ZenError>) {
         let mut program = Program::new();
         let mut error_count = 0;
-        const MAX_ERRORS: usize = 10;
 
         while !self.is_at_end() && error_count < MAX_ERRORS {
             if self.panic_mode {
@@ -34,8 +70,11 @@ impl Parser {
             }
 
             match self.declaration() {
-                Ok(Some(stmt)) => program.add_statement(stmt),
-                Ok(None) => continue,
+                Ok(stmts) => {
+                    for stmt in stmts {
+                        program.add_statement(stmt);
+                    }
+                }
                 Err(e) => {
                     self.report_error(e);
                     error_count += 1;
@@ -46,43 +85,99 @@ impl Parser {
             }
         }
 
-        if self.had_error {
-            let error_summary = if error_count >= MAX_ERRORS {
-                format!(
-                    "Parsing failed with {} errors (stopped after {} errors):\n{}",
-                    self.errors.len(),
-                    MAX_ERRORS,
-                    self.errors.join("\n")
-                )
-            } else {
-                format!(
-                    "Parsing failed with {} errors:\n{}",
-                    self.errors.len(),
-                    self.errors.join("\n")
-                )
-            };
-            Err(error_summary)
-        } else {
-            Ok(program)
+        (program, std::mem::take(&mut self.errors))
+    }
+
+    /// Parse a single expression that must use up every token, such as the contents of
+    /// an interpolation hole
+    pub fn parse_expression(&mut self) -> Result<Expr, ZenError> {
+        let expr = self.expression()?;
+        if !self.is_at_end() {
+            let token = self.peek();
+            return Err(Self::error_at(
+                token,
+                format!("Unexpected '{}' after expression", token.lexeme),
+            ));
+        }
+        Ok(expr)
+    }
+
+    /// Parse for compilation, where any error fails the whole file
+    pub fn parse_strict(&mut self) -> Result<Program, String> {
+        let (program, errors) = self.parse();
+        if errors.is_empty() {
+            return Ok(program);
+        }
+
+        let mut messages = Vec::new();
+        for error in &errors {
+            if let ZenError::ParseError {
+                message,
+                line,
+                column,
+                found,
+                ..
+            } = error
+            {
+                eprintln!("Parse error: {}", message);
+                messages.push(format!(
+                    "Error at line {}, column {}: {} (token: {})",
+                    line,
+                    column,
+                    message,
+                    found.as_deref().unwrap_or_default()
+                ));
+            }
         }
+        let stopped = if errors.len() >= MAX_ERRORS {
+            format!(" (stopped after {} errors)", MAX_ERRORS)
+        } else {
+            String::new()
+        };
+        Err(format!(
+            "Parsing failed with {} errors{}:\n{}",
+            errors.len(),
+            stopped,
+            messages.join("\n")
+        ))
     }
 
-    fn report_error(&mut self, message: String) {
+    fn report_error(&mut self, error: ZenError) {
         if self.panic_mode {
             return;
         }
 
         self.panic_mode = true;
         self.had_error = true;
+        self.errors.push(error);
+    }
 
-        let current_token = self.peek();
-        let error_msg = format!(
-            "Error at line {}, column {}: {} (token: {:?})",
-            current_token.line, current_token.column, message, current_token.kind
-        );
+    // A parse error at the current token
+    fn error(&self, message: impl Into<String>) -> ZenError {
+        Self::error_at(self.peek(), message)
+    }
+
+    // A parse error pointing at `token`
+    fn error_at(token: &Token, message: impl Into<String>) -> ZenError {
+        ZenError::ParseError {
+            message: message.into(),
+            line: token.line,
+            column: token.column,
+            source_line: None,
+            expected: None,
+            found: Some(format!("{:?}", token.kind)),
+        }
+    }
 
-        self.errors.push(error_msg);
-        eprintln!("Parse error: {}", message);
+    // An error at `column` of `token`'s line, such as inside a string literal
+    fn error_at_column(token: &Token, column: usize, message: impl Into<String>) -> ZenError {
+        Self::error_at(
+            &Token {
+                column,
+                ..token.clone()
+            },
+            message,
+        )
     }
 
     fn synchronize(&mut self) {
@@ -98,6 +193,7 @@ impl Parser {
                 | TokenType::Let
                 | TokenType::If
                 | TokenType::While
+                | TokenType::Loop
                 | TokenType::For
                 | TokenType::Return => return,
                 _ => {}
@@ -107,7 +203,11 @@ impl Parser {
         }
     }
 
-    fn declaration(&mut self) -> Result<Option<Stmt>, String> {
+    // Usually a single statement; a `let` chain declares one variable per group
+    fn declaration(&mut self) -> Result<Vec<Stmt>, ZenError> {
+        let doc = self.docs.get(&self.current).cloned();
+        let derives = self.attributes()?;
+
         // Check for pub keyword
         let is_public = if self.check(TokenType::Pub) {
             self.advance(); // consume 'pub'
@@ -117,36 +217,69 @@ impl Parser {
         };
 
         if self.check(TokenType::Fn) {
-            return Ok(Some(self.function_declaration_with_visibility(is_public)?));
+            return Ok(vec![
+                self.function_declaration_with_visibility(is_public, doc)?
+            ]);
         }
         if self.check(TokenType::Struct) {
-            return Ok(Some(self.struct_declaration_with_visibility(is_public)?));
+            let decl = self.struct_declaration_with_visibility(is_public, doc, derives)?;
+            return Ok(match derived_eq(&decl) {
+                Some(eq) => vec![decl, eq],
+                None => vec![decl],
+            });
+        }
+        if !derives.is_empty() {
+            return Err(self.error("Attributes are only supported on struct declarations"));
+        }
+        if self.check(TokenType::Impl) {
+            if is_public {
+                return Err(self.error("An impl block cannot be public; mark its methods 'pub'"));
+            }
+            let block = self.impl_block();
+            self.impl_type = None;
+            return Ok(vec![block?]);
         }
         if self.check(TokenType::Const) {
-            return Ok(Some(self.const_declaration_with_visibility(is_public)?));
+            let declaration = self.const_declaration_with_visibility(is_public)?;
+            self.match_token(TokenType::Semicolon);
+            return Ok(vec![declaration]);
         }
         if self.check(TokenType::Let) || self.check(TokenType::Mut) {
             if is_public {
-                return Err("Variables cannot be public".to_string());
+                return Err(self.error("Variables cannot be public"));
             }
-            return Ok(Some(self.variable_declaration()?));
+            let declarations = self.variable_declaration()?;
+            // As with `continue`, a trailing semicolon is optional
+            self.match_token(TokenType::Semicolon);
+            return Ok(declarations);
         }
 
         if is_public {
-            return Err("Expected function, struct, or const after 'pub'".to_string());
+            return Err(self.error("Expected function, struct, or const after 'pub'"));
         }
 
-        self.statement().map(Some)
+        self.statement().map(|stmt| vec![stmt])
     }
 
     #[allow(dead_code)]
-    fn function_declaration(&mut self) -> Result<Stmt, String> {
-        self.function_declaration_with_visibility(false)
+    fn function_declaration(&mut self) -> Result<Stmt, ZenError> {
+        self.function_declaration_with_visibility(false, None)
     }
 
-    fn function_declaration_with_visibility(&mut self, is_public: bool) -> Result<Stmt, String> {
+    fn function_declaration_with_visibility(
+        &mut self,
+        is_public: bool,
+        doc: Option<String>,
+    ) -> Result<Stmt, ZenError> {
         self.consume(TokenType::Fn, "Expected 'fn' keyword")?;
         let name = self.consume_identifier()?;
+        let type_params = if self.match_token(TokenType::LessThan) {
+            self.type_parameters()?
+        } else {
+            Vec::new()
+        };
+        // Type names in the signature and body may refer to the type parameters
+        self.type_params = type_params.clone();
 
         self.consume(TokenType::LeftParen, "Expected '(' after function name")?;
         let params = self.parameters()?;
@@ -156,23 +289,91 @@ impl Parser {
         let return_type = self.type_annotation()?;
 
         let body = self.block()?;
+        self.type_params.clear();
 
         Ok(Stmt::FunctionDecl {
             name,
+            type_params,
             params,
             return_type,
             body,
             is_public,
+            doc,
             token: self.previous().clone(),
         })
     }
 
+    // The names between `<` and `>` after a function name; the `<` is already consumed
+    fn type_parameters(&mut self) -> Result<Vec<String>, ZenError> {
+        let mut names: Vec<String> = Vec::new();
+        loop {
+            let token = self.peek().clone();
+            let name = self.consume_identifier()?;
+            if names.contains(&name) {
+                return Err(Self::error_at(
+                    &token,
+                    format!("Duplicate type parameter '{}'", name),
+                ));
+            }
+            names.push(name);
+            if !self.match_token(TokenType::Comma) {
+                break;
+            }
+        }
+        self.consume(TokenType::GreaterThan, "Expected '>' after type parameters")?;
+        Ok(names)
+    }
+
+    // Zero or more `#[derive(Trait, ...)]` attributes, returning the derived traits
+    fn attributes(&mut self) -> Result<Vec<String>, ZenError> {
+        let mut derives: Vec<String> = Vec::new();
+        while self.match_token(TokenType::Hash) {
+            self.consume(TokenType::LeftBracket, "Expected '[' after '#'")?;
+            let attribute = self.peek().clone();
+            if self.consume_identifier()? != "derive" {
+                return Err(Self::error_at(
+                    &attribute,
+                    format!("Unknown attribute '{}'", attribute.lexeme),
+                ));
+            }
+            self.consume(TokenType::LeftParen, "Expected '(' after 'derive'")?;
+            loop {
+                let token = self.peek().clone();
+                let name = self.consume_identifier()?;
+                if !DERIVABLE.contains(&name.as_str()) {
+                    return Err(Self::error_at(
+                        &token,
+                        format!(
+                            "Cannot derive '{}'; derivable traits: {}",
+                            name,
+                            DERIVABLE.join(", ")
+                        ),
+                    ));
+                }
+                if !derives.contains(&name) {
+                    derives.push(name);
+                }
+                if !self.match_token(TokenType::Comma) {
+                    break;
+                }
+            }
+            self.consume(TokenType::RightParen, "Expected ')' after derived traits")?;
+            self.consume(TokenType::RightBracket, "Expected ']' to close attribute")?;
+        }
+        Ok(derives)
+    }
+
     #[allow(dead_code)]
-    fn struct_declaration(&mut self) -> Result<Stmt, String> {
-        self.struct_declaration_with_visibility(false)
+    fn struct_declaration(&mut self) -> Result<Stmt, ZenError> {
+        self.struct_declaration_with_visibility(false, None, Vec::new())
     }
 
-    fn struct_declaration_with_visibility(&mut self, is_public: bool) -> Result<Stmt, String> {
+    fn struct_declaration_with_visibility(
+        &mut self,
+        is_public: bool,
+        doc: Option<String>,
+        derives: Vec<String>,
+    ) -> Result<Stmt, ZenError> {
         self.consume(TokenType::Struct, "Expected 'struct' keyword")?;
         let name = self.consume_identifier()?;
 
@@ -188,7 +389,7 @@ impl Parser {
             fields.push((field_name, field_type));
 
             if !self.match_token(TokenType::Comma) && !self.check(TokenType::RightBrace) {
-                return Err("Expected ',' or '}' after field".to_string());
+                return Err(self.error("Expected ',' or '}' after field"));
             }
         }
 
@@ -197,12 +398,69 @@ impl Parser {
         Ok(Stmt::StructDecl {
             name,
             fields,
+            derives,
             is_public,
+            doc,
             token: self.previous().clone(),
         })
     }
 
-    fn const_declaration_with_visibility(&mut self, is_public: bool) -> Result<Stmt, String> {
+    // `impl Name { ... }` holds methods whose first parameter is `self`
+    fn impl_block(&mut self) -> Result<Stmt, ZenError> {
+        let token = self.advance().clone();
+        let struct_name = self.consume_identifier()?;
+        self.consume(TokenType::LeftBrace, "Expected '{' after impl type name")?;
+        self.impl_type = Some(struct_name.clone());
+
+        let mut methods = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            let doc = self.docs.get(&self.current).cloned();
+            let is_public = self.match_token(TokenType::Pub);
+            let fn_token = self.peek().clone();
+            let method = self.function_declaration_with_visibility(is_public, doc)?;
+            let Stmt::FunctionDecl {
+                name,
+                type_params,
+                params,
+                return_type,
+                body,
+                is_public,
+                doc,
+                token,
+            } = method
+            else {
+                unreachable!("function_declaration_with_visibility returns a FunctionDecl");
+            };
+            if params.first().map(|(param, _)| param.as_str()) != Some("self") {
+                return Err(Self::error_at(
+                    &fn_token,
+                    format!(
+                        "Method '{}' of '{}' must take 'self' as its first parameter",
+                        name, struct_name
+                    ),
+                ));
+            }
+            methods.push(Stmt::FunctionDecl {
+                name: format!("{}_{}", struct_name, name),
+                type_params,
+                params,
+                return_type,
+                body,
+                is_public,
+                doc,
+                token,
+            });
+        }
+
+        self.consume(TokenType::RightBrace, "Expected '}' after impl block")?;
+        Ok(Stmt::ImplBlock {
+            struct_name,
+            methods,
+            token,
+        })
+    }
+
+    fn const_declaration_with_visibility(&mut self, is_public: bool) -> Result<Stmt, ZenError> {
         self.consume(TokenType::Const, "Expected 'const' keyword")?;
         let name = self.consume_identifier()?;
 
@@ -225,9 +483,29 @@ impl Parser {
         })
     }
 
-    fn variable_declaration(&mut self) -> Result<Stmt, String> {
+    // `let a = 1, mut b: i64 = 2` declares each comma-separated group separately
+    fn variable_declaration(&mut self) -> Result<Vec<Stmt>, ZenError> {
         self.consume(TokenType::Let, "Expected 'let' keyword")?;
+        let mut declarations = vec![self.variable_binding()?];
+
+        // Only continue when the next group starts a binding, so a trailing
+        // comma (e.g. between match arms) still ends the declaration
+        while self.check(TokenType::Comma)
+            && (self.check_ahead(1, TokenType::Mut)
+                || (self.check_ahead(1, TokenType::Identifier)
+                    && (self.check_ahead(2, TokenType::Equal)
+                        || self.check_ahead(2, TokenType::Colon))))
+        {
+            self.advance(); // consume ','
+            declarations.push(self.variable_binding()?);
+        }
+
+        Ok(declarations)
+    }
+
+    fn variable_binding(&mut self) -> Result<Stmt, ZenError> {
         let is_mutable = self.match_token(TokenType::Mut);
+        let name_token = self.peek().clone();
         let name = self.consume_identifier()?;
 
         let type_annotation = if self.match_token(TokenType::Colon) {
@@ -247,53 +525,136 @@ impl Parser {
             type_annotation,
             initializer,
             is_mutable,
-            token: self.previous().clone(),
+            token: name_token,
         })
     }
 
-    fn parameters(&mut self) -> Result<Vec<(String, String)>, String> {
-        let mut params = Vec::new();
+    fn parameters(&mut self) -> Result<Vec<(String, Type)>, ZenError> {
+        let mut params: Vec<(String, Type)> = Vec::new();
+        // Where each parameter name was written, for duplicate diagnostics
+        let mut positions: Vec<(usize, usize)> = Vec::new();
 
         if !self.check(TokenType::RightParen) {
-            params.push(self.param()?);
+            loop {
+                let name_token = self.peek().clone();
+                let param = self.param()?;
+                if let Some(i) = params.iter().position(|(name, _)| *name == param.0) {
+                    return Err(Self::error_at(
+                        &name_token,
+                        format!(
+                            "Duplicate parameter '{}' (first declared at line {}:{})",
+                            param.0, positions[i].0, positions[i].1
+                        ),
+                    ));
+                }
+                params.push(param);
+                positions.push((name_token.line, name_token.column));
 
-            while self.match_token(TokenType::Comma) {
-                params.push(self.param()?);
+                if !self.match_token(TokenType::Comma) {
+                    break;
+                }
             }
         }
 
         Ok(params)
     }
 
-    fn param(&mut self) -> Result<(String, String), String> {
+    fn param(&mut self) -> Result<(String, Type), ZenError> {
+        // A method's receiver is a pointer to the struct of its `impl` block
+        if self.check(TokenType::Self_) {
+            let token = self.advance().clone();
+            let Some(struct_name) = self.impl_type.clone() else {
+                return Err(Self::error_at(&token, "'self' is only allowed in methods"));
+            };
+            return Ok((
+                "self".to_string(),
+                Type::Ref(Box::new(Type::Struct(struct_name)), false),
+            ));
+        }
         let name = self.consume_identifier()?;
         self.consume(TokenType::Colon, "Expected ':' after parameter name")?;
         let type_annotation = self.type_annotation()?;
         Ok((name, type_annotation))
     }
 
-    fn type_annotation(&mut self) -> Result<String, String> {
+    fn type_annotation(&mut self) -> Result<Type, ZenError> {
+        // Nullable pointer types: ?T
+        if self.match_token(TokenType::Question) {
+            return Ok(Type::Nullable(Box::new(self.type_annotation()?)));
+        }
+
+        // Reference types: &T or &mut T, where T may itself be compound
+        if self.match_token(TokenType::Ampersand) {
+            let is_mutable = self.match_token(TokenType::Mut);
+            return Ok(Type::Ref(Box::new(self.type_annotation()?), is_mutable));
+        }
+        if self.match_token(TokenType::AmpersandMut) {
+            return Ok(Type::Ref(Box::new(self.type_annotation()?), true));
+        }
+
+        // Function pointer types: fn(A, B) -> R
+        if self.match_token(TokenType::Fn) {
+            self.consume(
+                TokenType::LeftParen,
+                "Expected '(' after 'fn' in a function type",
+            )?;
+            let mut params = Vec::new();
+            if !self.check(TokenType::RightParen) {
+                loop {
+                    params.push(self.type_annotation()?);
+                    if !self.match_token(TokenType::Comma) {
+                        break;
+                    }
+                }
+            }
+            self.consume(
+                TokenType::RightParen,
+                "Expected ')' after function type parameters",
+            )?;
+            self.consume(TokenType::ArrowRight, "Expected '->' in function type")?;
+            let return_type = self.type_annotation()?;
+            return Ok(Type::Function(params, Box::new(return_type)));
+        }
+
         // Check for array type: [ElementType; Size] or [ElementType]
         if self.match_token(TokenType::LeftBracket) {
-            // Parse element type - can be any valid type
-            let element_type = self.parse_type_name()?;
-            let mut array_spec = format!("[{}", element_type);
+            // Parse element type - can be any valid type, including references and arrays
+            let element_type = Box::new(self.type_annotation()?);
 
-            if self.match_token(TokenType::Semicolon) {
-                let size_token = self.consume_identifier()?; // Get the integer literal as string
-                array_spec.push_str(&format!("; {}", size_token));
-            }
+            let array_type = if self.match_token(TokenType::Semicolon) {
+                Type::Array(element_type, self.array_length("Array size")?)
+            } else {
+                Type::Slice(element_type)
+            };
 
             self.consume(TokenType::RightBracket, "Expected ']' after array type")?;
-            return Ok(array_spec);
+            return Ok(array_type);
         }
 
         // Regular type identifier
         self.parse_type_name()
     }
 
-    fn parse_type_name(&mut self) -> Result<String, String> {
-        let token = self.advance();
+    // The size in `[T; N]` or count in `[value; N]`: an expression of integer literals
+    // that folds to a single non-negative integer
+    fn array_length(&mut self, what: &str) -> Result<usize, ZenError> {
+        let token = self.peek().clone();
+        let mut length = self.expression()?;
+        fold_expr(&mut length);
+        let Expr::IntegerLiteral { value, .. } = length else {
+            return Err(Self::error_at(
+                &token,
+                format!("{} must be a constant integer", what),
+            ));
+        };
+        parse_integer_literal(&value)
+            .ok()
+            .and_then(|length| usize::try_from(length).ok())
+            .ok_or_else(|| Self::error_at(&token, format!("Invalid array size '{}'", value)))
+    }
+
+    fn parse_type_name(&mut self) -> Result<Type, ZenError> {
+        let token = self.advance().clone();
 
         // Handle built-in types
         match token.kind {
@@ -310,13 +671,18 @@ impl Parser {
             | TokenType::Bool
             | TokenType::Str
             | TokenType::Char
-            | TokenType::Void => Ok(token.lexeme.clone()),
-            TokenType::Identifier => Ok(token.lexeme.clone()),
-            _ => Err(format!("Expected type name, found {:?}", token.kind)),
+            | TokenType::Void => Ok(Type::from_name(&token.lexeme)),
+            TokenType::Identifier
+                if self.type_params.iter().any(|param| token.lexeme == *param) =>
+            {
+                Ok(Type::Param(token.lexeme.to_string()))
+            }
+            TokenType::Identifier => Ok(Type::from_name(&token.lexeme)),
+            _ => Err(self.error(format!("Expected type name, found {:?}", token.kind))),
         }
     }
 
-    fn statement(&mut self) -> Result<Stmt, String> {
+    fn statement(&mut self) -> Result<Stmt, ZenError> {
         if self.check(TokenType::Return) {
             return self.return_statement();
         }
@@ -331,9 +697,22 @@ impl Parser {
         if self.check(TokenType::While) {
             return self.while_statement();
         }
+        if self.check(TokenType::Loop) {
+            return self.loop_statement();
+        }
         if self.check(TokenType::For) {
             return self.for_statement();
         }
+        if self.check(TokenType::Break) {
+            let token = self.advance().clone();
+            self.match_token(TokenType::Semicolon);
+            return Ok(Stmt::Break { token });
+        }
+        if self.check(TokenType::Continue) {
+            let token = self.advance().clone();
+            self.match_token(TokenType::Semicolon);
+            return Ok(Stmt::Continue { token });
+        }
         if self.check(TokenType::Match) {
             return self.match_statement();
         }
@@ -342,18 +721,27 @@ impl Parser {
         }
 
         if self.check(TokenType::Let) {
-            return self.variable_declaration();
+            let mut declarations = self.variable_declaration()?;
+            if declarations.len() > 1 {
+                return Err(self.error("A chain of declarations is not allowed here"));
+            }
+            self.match_token(TokenType::Semicolon);
+            return Ok(declarations.remove(0));
         }
 
         self.expression_statement()
     }
 
-    fn expression_statement(&mut self) -> Result<Stmt, String> {
+    fn expression_statement(&mut self) -> Result<Stmt, ZenError> {
         let expr = self.expression()?;
 
         if let Expr::BinaryOp { op, left, right } = &expr {
-            if op.kind == TokenType::Equal {
-                if let Expr::Identifier { .. } = left.as_ref() {
+            if op.kind.is_assignment() {
+                if let Expr::Identifier { .. }
+                | Expr::Deref { .. }
+                | Expr::ArrayAccess { .. }
+                | Expr::FieldAccess { .. } = left.as_ref()
+                {
                     return Ok(Stmt::Assignment {
                         target: *left.clone(),
                         value: *right.clone(),
@@ -367,7 +755,7 @@ impl Parser {
         Ok(Stmt::ExprStmt { expr })
     }
 
-    fn return_statement(&mut self) -> Result<Stmt, String> {
+    fn return_statement(&mut self) -> Result<Stmt, ZenError> {
         self.consume(TokenType::Return, "Expected 'return' keyword")?;
         let value = if !self.check(TokenType::Semicolon) && !self.check(TokenType::RightBrace) {
             Some(self.expression()?)
@@ -381,7 +769,7 @@ impl Parser {
         })
     }
 
-    fn if_statement(&mut self) -> Result<Stmt, String> {
+    fn if_statement(&mut self) -> Result<Stmt, ZenError> {
         let if_token = self.peek().clone();
         self.consume(TokenType::If, "Expected 'if' keyword")?;
         let condition = self.expression()?;
@@ -420,7 +808,7 @@ impl Parser {
         })
     }
 
-    fn while_statement(&mut self) -> Result<Stmt, String> {
+    fn while_statement(&mut self) -> Result<Stmt, ZenError> {
         self.consume(TokenType::While, "Expected 'while' keyword")?;
         let condition = self.expression()?;
         let body = self.block()?;
@@ -432,16 +820,31 @@ impl Parser {
         })
     }
 
-    fn for_statement(&mut self) -> Result<Stmt, String> {
+    fn loop_statement(&mut self) -> Result<Stmt, ZenError> {
+        self.consume(TokenType::Loop, "Expected 'loop' keyword")?;
+        let token = self.previous().clone();
+        let body = self.block()?;
+
+        Ok(Stmt::Loop { body, token })
+    }
+
+    fn for_statement(&mut self) -> Result<Stmt, ZenError> {
         self.consume(TokenType::For, "Expected 'for' keyword")?;
+        let for_token = self.previous().clone();
+        if self.check(TokenType::Identifier) {
+            return self.for_in_statement(for_token);
+        }
         self.consume(TokenType::LeftParen, "Expected '(' after 'for'")?;
 
         let init = if self.match_token(TokenType::Semicolon) {
             None
         } else if self.check(TokenType::Let) {
-            Some(Box::new(self.variable_declaration()?))
-        } else {
-            let is_mutable = self.match_token(TokenType::Mut);
+            let mut declarations = self.variable_declaration()?;
+            if declarations.len() > 1 {
+                return Err(self.error("Expected a single variable in for loop initializer"));
+            }
+            Some(Box::new(declarations.remove(0)))
+        } else if self.match_token(TokenType::Mut) {
             let name = self.consume_identifier()?;
             self.consume(TokenType::Equal, "Expected '=' in for init")?;
             let value = self.expression()?;
@@ -449,9 +852,24 @@ impl Parser {
                 name,
                 type_annotation: None,
                 initializer: Some(value),
-                is_mutable,
+                is_mutable: true,
                 token: self.previous().clone(),
             }))
+        } else {
+            // Whether this declares the loop variable depends on what is in scope,
+            // which later passes decide with `Stmt::resolve_for_init`
+            let token = self.peek().clone();
+            let name = self.consume_identifier()?;
+            self.consume(TokenType::Equal, "Expected '=' in for init")?;
+            let value = self.expression()?;
+            Some(Box::new(Stmt::Assignment {
+                target: Expr::Identifier {
+                    name,
+                    token: token.clone(),
+                },
+                value,
+                token,
+            }))
         };
 
         self.consume(TokenType::Semicolon, "Expected ';' after for init")?;
@@ -482,8 +900,22 @@ impl Parser {
         })
     }
 
-    fn match_statement(&mut self) -> Result<Stmt, String> {
-        let match_token = self.advance();
+    fn for_in_statement(&mut self, token: Token) -> Result<Stmt, ZenError> {
+        let variable = self.consume_identifier()?;
+        self.consume(TokenType::In, "Expected 'in' after for loop variable")?;
+        let iterable = self.expression()?;
+        let body = self.block()?;
+
+        Ok(Stmt::ForIn {
+            variable,
+            iterable,
+            body,
+            token,
+        })
+    }
+
+    fn match_statement(&mut self) -> Result<Stmt, ZenError> {
+        let match_token = self.advance().clone();
         let value = self.expression()?;
 
         self.consume(TokenType::LeftBrace, "Expected '{' after match value")?;
@@ -491,39 +923,40 @@ impl Parser {
         let mut arms = Vec::new();
         let mut default = None;
 
-        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
-            let pattern = self.expression()?;
-            self.consume(TokenType::ArrowRight, "Expected '=>' after match pattern")?;
+        // First token of the previous arm, if its body had neither braces nor a trailing comma
+        let mut unbraced_arm: Option<Token> = None;
 
-            if let Expr::Identifier { name, .. } = &pattern {
-                if name == "_" {
-                    let stmt = self.statement()?;
-                    let body = if let Stmt::Block { statements } = stmt {
-                        statements
-                    } else {
-                        vec![stmt]
-                    };
-                    default = Some(body);
-                } else {
-                    let stmt = self.statement()?;
-                    let body = if let Stmt::Block { statements } = stmt {
-                        statements
-                    } else {
-                        vec![stmt]
-                    };
-                    arms.push((pattern, body));
-                }
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            let arm_token = self.peek().clone();
+            // Anything but `pattern =>` here means the previous arm ran on past one statement
+            let pattern = self
+                .expression()
+                .and_then(|pattern| {
+                    self.consume(TokenType::FatArrow, "Expected '=>' after match pattern")?;
+                    Ok(pattern)
+                })
+                .map_err(|e| match &unbraced_arm {
+                    Some(arm) => Self::error_at(arm, format!(
+                        "Match arm has more than one statement; wrap its body in braces: '{} => {{ ... }}'",
+                        arm.lexeme
+                    )),
+                    None => e,
+                })?;
+
+            let braced = self.check(TokenType::LeftBrace);
+            let stmt = self.statement()?;
+            let body = if let Stmt::Block { statements } = stmt {
+                statements
             } else {
-                let stmt = self.statement()?;
-                let body = if let Stmt::Block { statements } = stmt {
-                    statements
-                } else {
-                    vec![stmt]
-                };
-                arms.push((pattern, body));
+                vec![stmt]
+            };
+            match &pattern {
+                Expr::Identifier { name, .. } if name == "_" => default = Some(body),
+                _ => arms.push((pattern, body)),
             }
 
-            self.match_token(TokenType::Comma);
+            let separated = self.match_token(TokenType::Comma);
+            unbraced_arm = (!braced && !separated).then_some(arm_token);
         }
 
         self.consume(TokenType::RightBrace, "Expected '}' to close match")?;
@@ -536,44 +969,170 @@ impl Parser {
         })
     }
 
-    fn block(&mut self) -> Result<Vec<Stmt>, String> {
-        self.consume(TokenType::LeftBrace, "Expected '{'")?;
+    // A match in expression position, where each arm is a single value
+    // `if c { a } else { b }`, where `else if` nests another if-expression
+    fn if_expression(&mut self) -> Result<Expr, ZenError> {
+        let if_token = self.advance().clone();
+        let condition = self.expression()?;
+        let then_expr = self.branch_value()?;
+        self.consume(
+            TokenType::Else,
+            "Expected 'else' after if-expression branch",
+        )?;
+        let else_expr = if self.check(TokenType::If) {
+            self.if_expression()?
+        } else {
+            self.branch_value()?
+        };
+
+        Ok(Expr::IfExpr {
+            condition: Box::new(condition),
+            then_expr: Box::new(then_expr),
+            else_expr: Box::new(else_expr),
+            token: if_token,
+        })
+    }
+
+    // A branch is a block expression; one holding nothing but its value is just that value
+    fn branch_value(&mut self) -> Result<Expr, ZenError> {
+        if !self.check(TokenType::LeftBrace) {
+            return Err(self.error("Expected '{' before if-expression branch"));
+        }
+        match self.block_expression()? {
+            Expr::Block {
+                statements, tail, ..
+            } if statements.is_empty() => Ok(*tail),
+            block => Ok(block),
+        }
+    }
+
+    // `{ statements; tail }` in expression position, whose last statement is its value
+    fn block_expression(&mut self) -> Result<Expr, ZenError> {
+        let brace = self.advance().clone();
         let mut statements = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            statements.extend(self.declaration()?);
+        }
+        self.consume(TokenType::RightBrace, "Expected '}' after block")?;
+
+        match statements.pop() {
+            Some(Stmt::ExprStmt { expr }) => Ok(Expr::Block {
+                statements,
+                tail: Box::new(expr),
+                token: brace,
+            }),
+            _ => Err(Self::error_at(
+                &brace,
+                "Block used as a value must end with an expression".to_string(),
+            )),
+        }
+    }
+
+    fn match_expression(&mut self) -> Result<Expr, ZenError> {
+        let match_token = self.advance().clone();
+        let value = self.expression()?;
+
+        self.consume(TokenType::LeftBrace, "Expected '{' after match value")?;
+
+        let mut arms = Vec::new();
+        let mut default = None;
 
         while !self.check(TokenType::RightBrace) && !self.is_at_end() {
-            if let Some(stmt) = self.declaration()? {
-                statements.push(stmt);
+            let pattern = self.expression()?;
+            self.consume(TokenType::FatArrow, "Expected '=>' after match pattern")?;
+            let arm_value = self.expression()?;
+
+            match &pattern {
+                Expr::Identifier { name, .. } if name == "_" => {
+                    default = Some(Box::new(arm_value));
+                }
+                _ => arms.push((pattern, arm_value)),
+            }
+
+            if !self.match_token(TokenType::Comma) && !self.check(TokenType::RightBrace) {
+                return Err(self.error(format!(
+                    "Expected ',' or '}}' after match arm, found {:?}",
+                    self.peek().kind
+                )));
             }
         }
 
+        self.consume(TokenType::RightBrace, "Expected '}' to close match")?;
+
+        Ok(Expr::Match {
+            value: Box::new(value),
+            arms,
+            default,
+            token: match_token,
+        })
+    }
+
+    fn block(&mut self) -> Result<Vec<Stmt>, ZenError> {
+        self.consume(TokenType::LeftBrace, "Expected '{'")?;
+        let mut statements = Vec::new();
+
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            statements.extend(self.declaration()?);
+        }
+
         self.consume(TokenType::RightBrace, "Expected '}'")?;
         Ok(statements)
     }
 
-    fn expression(&mut self) -> Result<Expr, String> {
+    // Binary operator precedence, loosest to tightest:
+    //   = += -= *= /= %=  assignment (right-associative)
+    //   ||                logical_or
+    //   &&                logical_and
+    //   == !=             equality
+    //   < <= > >=         comparison
+    //   |                 bitwise_or
+    //   ^^                bitwise_xor
+    //   &                 bitwise_and
+    //   << >>             shift
+    //   + -               term
+    //   * / %             factor
+    //   ^                 power (right-associative)
+    //   as                cast
+    //   ! - <- & &mut     unary (prefix)
+    fn expression(&mut self) -> Result<Expr, ZenError> {
         self.assignment()
     }
 
-    fn assignment(&mut self) -> Result<Expr, String> {
+    fn assignment(&mut self) -> Result<Expr, ZenError> {
         let expr = self.logical_or()?;
 
-        if self.match_token(TokenType::Equal) {
-            let equal_token = self.previous().clone();
-            let value = self.assignment()?;
-            if let Expr::Identifier { .. } = expr {
+        if self.peek().kind.is_assignment() {
+            let equal_token = self.advance().clone();
+            let mut value = self.assignment()?;
+            // `x += e` assigns `x + e`; the assignment keeps the `+=` token
+            if let Some(operator) = equal_token.kind.compound_operator() {
+                let mut op = equal_token.clone();
+                op.kind = operator;
+                op.lexeme = Symbol::intern(&equal_token.lexeme[..equal_token.lexeme.len() - 1]);
+                value = Expr::BinaryOp {
+                    left: Box::new(expr.clone()),
+                    op,
+                    right: Box::new(value),
+                };
+            }
+            if let Expr::Identifier { .. }
+            | Expr::Deref { .. }
+            | Expr::ArrayAccess { .. }
+            | Expr::FieldAccess { .. } = expr
+            {
                 return Ok(Expr::BinaryOp {
                     left: Box::new(expr),
                     op: equal_token,
                     right: Box::new(value),
                 });
             }
-            return Err("Invalid assignment target".to_string());
+            return Err(self.error("Invalid assignment target"));
         }
 
         Ok(expr)
     }
 
-    fn logical_or(&mut self) -> Result<Expr, String> {
+    fn logical_or(&mut self) -> Result<Expr, ZenError> {
         let mut expr = self.logical_and()?;
 
         while self.match_token(TokenType::Or) {
@@ -589,7 +1148,7 @@ impl Parser {
         Ok(expr)
     }
 
-    fn logical_and(&mut self) -> Result<Expr, String> {
+    fn logical_and(&mut self) -> Result<Expr, ZenError> {
         let mut expr = self.equality()?;
 
         while self.match_token(TokenType::And) {
@@ -605,7 +1164,7 @@ impl Parser {
         Ok(expr)
     }
 
-    fn equality(&mut self) -> Result<Expr, String> {
+    fn equality(&mut self) -> Result<Expr, ZenError> {
         let mut expr = self.comparison()?;
 
         while self.match_token(TokenType::EqualEqual) || self.match_token(TokenType::NotEqual) {
@@ -621,8 +1180,8 @@ impl Parser {
         Ok(expr)
     }
 
-    fn comparison(&mut self) -> Result<Expr, String> {
-        let mut expr = self.term()?;
+    fn comparison(&mut self) -> Result<Expr, ZenError> {
+        let mut expr = self.bitwise_or()?;
 
         while self.match_token(TokenType::GreaterThan)
             || self.match_token(TokenType::GreaterEqual)
@@ -630,7 +1189,7 @@ impl Parser {
             || self.match_token(TokenType::LessEqual)
         {
             let op = self.previous().clone();
-            let right = self.term()?;
+            let right = self.bitwise_or()?;
             expr = Expr::BinaryOp {
                 left: Box::new(expr),
                 op,
@@ -641,12 +1200,12 @@ impl Parser {
         Ok(expr)
     }
 
-    fn term(&mut self) -> Result<Expr, String> {
-        let mut expr = self.factor()?;
+    fn bitwise_or(&mut self) -> Result<Expr, ZenError> {
+        let mut expr = self.bitwise_xor()?;
 
-        while self.match_token(TokenType::Plus) || self.match_token(TokenType::Minus) {
+        while self.match_token(TokenType::Pipe) {
             let op = self.previous().clone();
-            let right = self.factor()?;
+            let right = self.bitwise_xor()?;
             expr = Expr::BinaryOp {
                 left: Box::new(expr),
                 op,
@@ -657,15 +1216,12 @@ impl Parser {
         Ok(expr)
     }
 
-    fn factor(&mut self) -> Result<Expr, String> {
-        let mut expr = self.unary()?;
+    fn bitwise_xor(&mut self) -> Result<Expr, ZenError> {
+        let mut expr = self.bitwise_and()?;
 
-        while self.match_token(TokenType::Star)
-            || self.match_token(TokenType::Slash)
-            || self.match_token(TokenType::Percent)
-        {
+        while self.match_token(TokenType::CaretCaret) {
             let op = self.previous().clone();
-            let right = self.unary()?;
+            let right = self.bitwise_and()?;
             expr = Expr::BinaryOp {
                 left: Box::new(expr),
                 op,
@@ -676,57 +1232,173 @@ impl Parser {
         Ok(expr)
     }
 
-    fn unary(&mut self) -> Result<Expr, String> {
-        if self.match_token(TokenType::Not)
-            || self.match_token(TokenType::Minus)
-            || self.match_token(TokenType::ArrowLeft)
-            || self.match_token(TokenType::Ampersand)
-            || self.match_token(TokenType::AmpersandMut)
-        {
-            let op = self.previous().clone();
-            let right = self.unary()?;
+    // Binary `&` is only reached after an operand; in prefix position `&` borrows
+    fn bitwise_and(&mut self) -> Result<Expr, ZenError> {
+        let mut expr = self.shift()?;
 
-            match op.kind {
-                TokenType::ArrowLeft => Ok(Expr::OwnershipTransfer {
-                    expr: Box::new(right),
-                    token: op,
-                }),
-                TokenType::Ampersand => Ok(Expr::Borrow {
-                    expr: Box::new(right),
-                    is_mutable: false,
-                    token: op,
-                }),
-                TokenType::AmpersandMut => Ok(Expr::Borrow {
-                    expr: Box::new(right),
-                    is_mutable: true,
-                    token: op,
-                }),
-                _ => Ok(Expr::UnaryOp {
-                    op,
-                    operand: Box::new(right),
-                }),
-            }
-        } else {
-            self.call()
+        while self.match_token(TokenType::Ampersand) {
+            let op = self.previous().clone();
+            let right = self.shift()?;
+            expr = Expr::BinaryOp {
+                left: Box::new(expr),
+                op,
+                right: Box::new(right),
+            };
         }
+
+        Ok(expr)
     }
 
-    fn call(&mut self) -> Result<Expr, String> {
-        let mut expr = self.primary()?;
+    fn shift(&mut self) -> Result<Expr, ZenError> {
+        let mut expr = self.term()?;
 
-        loop {
-            if self.match_token(TokenType::LeftParen) {
-                // Function call: expr(args)
-                let mut args = Vec::new();
+        while self.match_token(TokenType::ShiftLeft) || self.match_token(TokenType::ShiftRight) {
+            let op = self.previous().clone();
+            let right = self.term()?;
+            expr = Expr::BinaryOp {
+                left: Box::new(expr),
+                op,
+                right: Box::new(right),
+            };
+        }
 
-                if !self.check(TokenType::RightParen) {
-                    args.push(self.expression()?);
-                    while self.match_token(TokenType::Comma) {
-                        args.push(self.expression()?);
-                    }
-                }
+        Ok(expr)
+    }
 
-                self.consume(TokenType::RightParen, "Expected ')' after arguments")?;
+    fn term(&mut self) -> Result<Expr, ZenError> {
+        let mut expr = self.factor()?;
+
+        while self.match_token(TokenType::Plus) || self.match_token(TokenType::Minus) {
+            let op = self.previous().clone();
+            let right = self.factor()?;
+            expr = Expr::BinaryOp {
+                left: Box::new(expr),
+                op,
+                right: Box::new(right),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    // An operator starting a new line begins the next statement instead, so that
+    // `let old = *p` followed by `*p = old + 1` is two statements
+    fn factor(&mut self) -> Result<Expr, ZenError> {
+        let mut expr = self.power()?;
+
+        while self.peek().line == self.previous().line
+            && (self.match_token(TokenType::Star)
+                || self.match_token(TokenType::Slash)
+                || self.match_token(TokenType::Percent))
+        {
+            let op = self.previous().clone();
+            let right = self.power()?;
+            expr = Expr::BinaryOp {
+                left: Box::new(expr),
+                op,
+                right: Box::new(right),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    // `^` is exponentiation and right-associative: `2 ^ 3 ^ 2` is `2 ^ (3 ^ 2)`
+    fn power(&mut self) -> Result<Expr, ZenError> {
+        let expr = self.cast()?;
+
+        if self.match_token(TokenType::Caret) {
+            let op = self.previous().clone();
+            let right = self.power()?;
+            return Ok(Expr::BinaryOp {
+                left: Box::new(expr),
+                op,
+                right: Box::new(right),
+            });
+        }
+
+        Ok(expr)
+    }
+
+    // `as` binds tighter than binary operators but looser than prefix operators
+    fn cast(&mut self) -> Result<Expr, ZenError> {
+        let mut expr = self.unary()?;
+
+        while self.match_token(TokenType::As) {
+            let token = self.previous().clone();
+            let target_type = self.type_annotation()?;
+            expr = Expr::Cast {
+                expr: Box::new(expr),
+                target_type,
+                token,
+            };
+        }
+
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ZenError> {
+        if self.match_token(TokenType::Not)
+            || self.match_token(TokenType::Minus)
+            || self.match_token(TokenType::ArrowLeft)
+            || self.match_token(TokenType::Ampersand)
+            || self.match_token(TokenType::AmpersandMut)
+            || self.match_token(TokenType::Star)
+        {
+            let mut op = self.previous().clone();
+            // `& mut x` lexes as two tokens
+            if op.kind == TokenType::Ampersand && self.match_token(TokenType::Mut) {
+                op.kind = TokenType::AmpersandMut;
+                op.lexeme = Symbol::intern("&mut");
+            }
+            let right = self.unary()?;
+
+            match op.kind {
+                TokenType::ArrowLeft => Ok(Expr::OwnershipTransfer {
+                    expr: Box::new(right),
+                    token: op,
+                }),
+                TokenType::Ampersand => Ok(Expr::Borrow {
+                    expr: Box::new(right),
+                    is_mutable: false,
+                    token: op,
+                }),
+                TokenType::AmpersandMut => Ok(Expr::Borrow {
+                    expr: Box::new(right),
+                    is_mutable: true,
+                    token: op,
+                }),
+                // In prefix position `*` dereferences; binary `*` is handled in `factor`
+                TokenType::Star => Ok(Expr::Deref {
+                    expr: Box::new(right),
+                    token: op,
+                }),
+                _ => Ok(Expr::UnaryOp {
+                    op,
+                    operand: Box::new(right),
+                }),
+            }
+        } else {
+            self.call()
+        }
+    }
+
+    fn call(&mut self) -> Result<Expr, ZenError> {
+        let mut expr = self.primary()?;
+
+        loop {
+            if self.match_token(TokenType::LeftParen) {
+                // Function call: expr(args)
+                let mut args = Vec::new();
+
+                if !self.check(TokenType::RightParen) {
+                    args.push(self.expression()?);
+                    while self.match_token(TokenType::Comma) {
+                        args.push(self.expression()?);
+                    }
+                }
+
+                self.consume(TokenType::RightParen, "Expected ')' after arguments")?;
                 expr = Expr::Call {
                     callee: Box::new(expr),
                     args,
@@ -757,7 +1429,7 @@ impl Parser {
         Ok(expr)
     }
 
-    fn primary(&mut self) -> Result<Expr, String> {
+    fn primary(&mut self) -> Result<Expr, ZenError> {
         if self.match_token(TokenType::True) {
             return Ok(Expr::BooleanLiteral {
                 value: true,
@@ -773,8 +1445,7 @@ impl Parser {
         }
 
         if self.match_token(TokenType::Null) {
-            return Ok(Expr::Identifier {
-                name: "null".to_string(),
+            return Ok(Expr::NullLiteral {
                 token: self.previous().clone(),
             });
         }
@@ -783,11 +1454,11 @@ impl Parser {
             return Ok(number);
         }
 
-        if let Some(string_lit) = self.match_string() {
+        if let Some(string_lit) = self.match_string()? {
             return Ok(string_lit);
         }
 
-        if let Some(char_lit) = self.match_char() {
+        if let Some(char_lit) = self.match_char()? {
             return Ok(char_lit);
         }
 
@@ -797,9 +1468,73 @@ impl Parser {
             return Ok(expr);
         }
 
+        if self.check(TokenType::Match) {
+            return self.match_expression();
+        }
+
+        // In statement position `if` is a statement; anywhere else it yields a value
+        if self.check(TokenType::If) {
+            return self.if_expression();
+        }
+
+        // Likewise a braced block is a statement on its own and a value anywhere else
+        if self.check(TokenType::LeftBrace) {
+            return self.block_expression();
+        }
+
+        // Array literal `[a, b, c]` or repeat expression `[value; count]`
+        if self.match_token(TokenType::LeftBracket) {
+            let token = self.previous().clone();
+            if self.match_token(TokenType::RightBracket) {
+                return Ok(Expr::ArrayLiteral {
+                    elements: Vec::new(),
+                    token,
+                });
+            }
+            let value = self.expression()?;
+            if !self.check(TokenType::Semicolon) {
+                let mut elements = vec![value];
+                while self.match_token(TokenType::Comma) {
+                    // Allow a trailing comma
+                    if self.check(TokenType::RightBracket) {
+                        break;
+                    }
+                    elements.push(self.expression()?);
+                }
+                self.consume(
+                    TokenType::RightBracket,
+                    "Expected ',' or ']' after array element",
+                )?;
+                return Ok(Expr::ArrayLiteral { elements, token });
+            }
+            self.consume(
+                TokenType::Semicolon,
+                "Expected ';' after array element value",
+            )?;
+            // Like the size in `[T; N]`, the count must be known at compile time
+            let count = self.array_length("Array repeat count")?;
+            self.consume(
+                TokenType::RightBracket,
+                "Expected ']' after array repeat count",
+            )?;
+            return Ok(Expr::ArrayRepeat {
+                value: Box::new(value),
+                count,
+                token,
+            });
+        }
+
+        if self.check(TokenType::Self_) {
+            let token = self.advance().clone();
+            return Ok(Expr::Identifier {
+                name: "self".to_string(),
+                token,
+            });
+        }
+
         if self.check(TokenType::Identifier) {
-            let token = self.advance();
-            let name = token.lexeme.clone();
+            let token = self.advance().clone();
+            let name = token.lexeme.to_string();
 
             // Check for module access: module::item
             if self.match_token(TokenType::DoubleColon) {
@@ -830,7 +1565,7 @@ impl Parser {
             return Ok(Expr::Identifier { name, token });
         }
 
-        Err(format!("Unexpected token: {:?}", self.peek()))
+        Err(self.error(format!("Unexpected token: {:?}", self.peek())))
     }
 
     fn is_struct_literal_context(&mut self) -> bool {
@@ -857,7 +1592,7 @@ impl Parser {
         false
     }
 
-    fn struct_literal_fields(&mut self) -> Result<Vec<(String, Expr)>, String> {
+    fn struct_literal_fields(&mut self) -> Result<Vec<(String, Expr)>, ZenError> {
         let mut fields = Vec::new();
 
         while !self.check(TokenType::RightBrace) && !self.is_at_end() {
@@ -868,7 +1603,7 @@ impl Parser {
             fields.push((field_name, field_value));
 
             if !self.match_token(TokenType::Comma) && !self.check(TokenType::RightBrace) {
-                return Err("Expected ',' or '}' after field".to_string());
+                return Err(self.error("Expected ',' or '}' after field"));
             }
         }
 
@@ -877,16 +1612,18 @@ impl Parser {
 
     fn match_number(&mut self) -> Option<Expr> {
         if self.check(TokenType::IntegerLiteral) {
-            let token = self.advance();
-            return Some(Expr::IntegerLiteral {
-                value: token.lexeme.clone(),
-                token,
-            });
+            let token = self.advance().clone();
+            // Later passes see the decimal value; the token keeps the source spelling
+            let value = crate::lexer::lexer::parse_integer_literal(&token.lexeme)
+                .map(|value| value.to_string())
+                .unwrap_or_else(|_| token.lexeme.to_string());
+            return Some(Expr::IntegerLiteral { value, token });
         }
 
         if self.check(TokenType::FloatLiteral) {
-            let token = self.advance();
-            if let Ok(value) = token.lexeme.parse::<f64>() {
+            let token = self.advance().clone();
+            let (digits, _) = crate::lexer::lexer::split_float_suffix(&token.lexeme);
+            if let Ok(value) = digits.parse::<f64>() {
                 return Some(Expr::FloatLiteral { value, token });
             }
         }
@@ -894,47 +1631,101 @@ impl Parser {
         None
     }
 
-    fn match_string(&mut self) -> Option<Expr> {
+    fn match_string(&mut self) -> Result<Option<Expr>, ZenError> {
         if self.check(TokenType::StringLiteral) {
-            let token = self.advance();
+            let token = self.advance().clone();
             if token.lexeme.len() < 2 {
-                return None; // Invalid string literal
+                return Ok(None); // Invalid string literal
             }
             let value = token.lexeme[1..token.lexeme.len() - 1].to_string();
+            let decode = |text: &str| {
+                crate::lexer::lexer::decode_escapes(text)
+                    .map_err(|e| Self::error_at(&token, e.to_string()))
+            };
 
-            // Check if string contains interpolation
-            if value.contains('{') && value.contains('}') {
-                let parts = self.parse_interpolated_string(&value);
-                return Some(Expr::InterpolatedString { parts, token });
+            // Check if string contains interpolation; escapes are decoded in the text
+            // between holes, so `\x7B` stays a literal brace
+            if value.contains('{') || value.contains('}') {
+                let mut parts = self.parse_interpolated_string(&value, &token)?;
+                for part in parts.iter_mut() {
+                    if let crate::ast::expr::StringPart::Text(text) = part {
+                        *text = decode(text)?;
+                    }
+                }
+                // Only escaped braces: no holes to fill in
+                if let [crate::ast::expr::StringPart::Text(text)] = parts.as_mut_slice() {
+                    let value = std::mem::take(text);
+                    return Ok(Some(Expr::StringLiteral { value, token }));
+                }
+                return Ok(Some(Expr::InterpolatedString { parts, token }));
             }
 
-            return Some(Expr::StringLiteral { value, token });
+            let value = decode(&value)?;
+            return Ok(Some(Expr::StringLiteral { value, token }));
         }
-        None
+        Ok(None)
     }
 
-    fn match_char(&mut self) -> Option<Expr> {
-        if self.check(TokenType::CharLiteral) {
-            let token = self.advance();
-            if token.lexeme.len() != 3
-                || !token.lexeme.starts_with('\'')
-                || !token.lexeme.ends_with('\'')
-            {
-                return None; // Invalid char literal format
-            }
-            let value = token.lexeme.chars().nth(1).unwrap_or('\0');
-            return Some(Expr::CharLiteral { value, token });
+    fn match_char(&mut self) -> Result<Option<Expr>, ZenError> {
+        if !self.check(TokenType::CharLiteral) {
+            return Ok(None);
+        }
+        let token = self.advance().clone();
+        let body = token
+            .lexeme
+            .strip_prefix('\'')
+            .and_then(|rest| rest.strip_suffix('\''))
+            .unwrap_or_default();
+        let decoded = crate::lexer::lexer::decode_escapes(body)
+            .map_err(|e| Self::error_at(&token, e.to_string()))?;
+
+        let mut chars = decoded.chars();
+        match (chars.next(), chars.next()) {
+            (Some(value), None) => Ok(Some(Expr::CharLiteral { value, token })),
+            _ => Err(Self::error_at(
+                &token,
+                format!(
+                    "Char literal {} must contain exactly one character",
+                    token.lexeme
+                ),
+            )),
         }
-        None
     }
 
-    fn parse_interpolated_string(&self, value: &str) -> Vec<crate::ast::expr::StringPart> {
+    fn parse_interpolated_string(
+        &self,
+        value: &str,
+        token: &Token,
+    ) -> Result<Vec<crate::ast::expr::StringPart>, ZenError> {
         let mut parts = Vec::new();
         let mut current = String::new();
-        let mut chars = value.chars().peekable();
-
-        while let Some(ch) = chars.next() {
-            if ch == '{' {
+        // Columns are reported relative to the opening quote of the literal
+        let mut chars = value
+            .chars()
+            .enumerate()
+            .map(|(i, c)| (token.column + 1 + i, c))
+            .peekable();
+
+        while let Some((column, ch)) = chars.next() {
+            // Escapes are decoded later; the braces of `\u{...}` are not a hole
+            if ch == '\\' {
+                current.push(ch);
+                if let Some((_, escaped)) = chars.next() {
+                    current.push(escaped);
+                    if escaped == 'u' && chars.peek().map(|&(_, c)| c) == Some('{') {
+                        for (_, c) in chars.by_ref() {
+                            current.push(c);
+                            if c == '}' {
+                                break;
+                            }
+                        }
+                    }
+                }
+            } else if (ch == '{' || ch == '}') && chars.peek().map(|&(_, c)| c) == Some(ch) {
+                // `{{` and `}}` stand for literal braces, as in Rust's `format!`
+                chars.next();
+                current.push(ch);
+            } else if ch == '{' {
                 // Save any text before the variable
                 if !current.is_empty() {
                     parts.push(crate::ast::expr::StringPart::Text(current.clone()));
@@ -943,22 +1734,42 @@ impl Parser {
 
                 // Extract variable name or expression
                 let mut expr_content = String::new();
-                while let Some(&next_ch) = chars.peek() {
-                    if next_ch == '}' {
-                        chars.next(); // consume '}'
-                        break;
+                loop {
+                    match chars.next() {
+                        Some((_, '}')) => break,
+                        Some((_, next_ch)) => expr_content.push(next_ch),
+                        None => {
+                            return Err(Self::error_at_column(
+                                token,
+                                column,
+                                "Unclosed '{' in interpolated string",
+                            ))
+                        }
                     }
-                    expr_content.push(chars.next().unwrap());
                 }
 
+                let leading = expr_content.len() - expr_content.trim_start().len();
+                let expr_content = expr_content.trim();
                 if !expr_content.is_empty() {
-                    // Check if it's a function call (contains parentheses)
-                    if expr_content.contains('(') && expr_content.contains(')') {
-                        parts.push(crate::ast::expr::StringPart::Expression(expr_content));
+                    // A bare name is a variable; anything else is parsed as an expression
+                    let is_name = expr_content
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || c == '_');
+                    if is_name {
+                        parts.push(crate::ast::expr::StringPart::Variable(
+                            expr_content.to_string(),
+                        ));
                     } else {
-                        parts.push(crate::ast::expr::StringPart::Variable(expr_content));
+                        let expr = Self::parse_hole(expr_content, token, column + leading)?;
+                        parts.push(crate::ast::expr::StringPart::Expr(Box::new(expr)));
                     }
                 }
+            } else if ch == '}' {
+                return Err(Self::error_at_column(
+                    token,
+                    column,
+                    "Unmatched '}' in interpolated string; use '}}' for a literal brace",
+                ));
             } else {
                 current.push(ch);
             }
@@ -969,56 +1780,58 @@ impl Parser {
             parts.push(crate::ast::expr::StringPart::Text(current));
         }
 
-        parts
+        Ok(parts)
     }
 
-    fn consume_identifier(&mut self) -> Result<String, String> {
+    // Lex and parse the contents of an interpolation hole; `column` is where the hole's
+    // `{` sits, so tokens report their position within the enclosing string literal
+    fn parse_hole(source: &str, token: &Token, column: usize) -> Result<Expr, ZenError> {
+        let mut tokens = crate::lexer::lexer::Lexer::new(source)
+            .tokenize()
+            .map_err(|errors| {
+                Self::error_at_column(
+                    token,
+                    column,
+                    format!(
+                        "Invalid interpolation '{{{}}}': {}",
+                        source,
+                        errors
+                            .iter()
+                            .map(ZenError::message)
+                            .collect::<Vec<_>>()
+                            .join("; ")
+                    ),
+                )
+            })?;
+        for hole_token in tokens.iter_mut() {
+            hole_token.line = token.line;
+            hole_token.column += column;
+        }
+        Parser::new(tokens).parse_expression()
+    }
+
+    fn consume_identifier(&mut self) -> Result<String, ZenError> {
         if self.check(TokenType::Identifier) {
-            return Ok(self.advance().lexeme);
+            return Ok(self.advance().lexeme.to_string());
         }
-        Err(format!("Expected identifier, got {:?}", self.peek()))
+        Err(self.error(format!("Expected identifier, got {:?}", self.peek())))
     }
 
-    fn consume(&mut self, token_type: TokenType, message: &str) -> Result<(), String> {
+    fn consume(&mut self, token_type: TokenType, message: &str) -> Result<(), ZenError> {
         if self.check(token_type.clone()) {
             self.advance();
             return Ok(());
         }
 
         let current = self.peek();
-        let detailed_error = format!(
-            "{} at line {}, column {}. Expected {:?}, but found {:?} '{}'",
-            message, current.line, current.column, token_type, current.kind, current.lexeme
-        );
-
-        // Enhanced error context
-        let context = self.get_error_context();
-        let full_error = if !context.is_empty() {
-            format!("{}\nContext: {}", detailed_error, context)
-        } else {
-            detailed_error
-        };
-
-        Err(full_error)
-    }
-
-    fn get_error_context(&self) -> String {
-        // Provide context around the error location
-        let mut context = String::new();
-        let start = self.current.saturating_sub(2);
-        let end = std::cmp::min(self.current + 3, self.tokens.len());
-
-        for i in start..end {
-            if i < self.tokens.len() {
-                let marker = if i == self.current { " >>> " } else { "     " };
-                context.push_str(&format!(
-                    "{}Token {}: {:?} '{}'\n",
-                    marker, i, self.tokens[i].kind, self.tokens[i].lexeme
-                ));
-            }
-        }
-
-        context
+        Err(ZenError::ParseError {
+            message: format!("{}, found '{}'", message, current.lexeme),
+            line: current.line,
+            column: current.column,
+            source_line: None,
+            expected: Some(format!("{:?}", token_type)),
+            found: Some(format!("{:?}", current.kind)),
+        })
     }
 
     fn match_token(&mut self, token_type: TokenType) -> bool {
@@ -1048,25 +1861,37 @@ impl Parser {
         self.peek().kind == TokenType::EOF
     }
 
-    fn advance(&mut self) -> Token {
+    fn advance(&mut self) -> &Token {
         if !self.is_at_end() {
             self.current += 1;
         }
         self.previous()
     }
 
-    fn peek(&self) -> Token {
-        self.tokens[self.current].clone()
+    fn peek(&self) -> &Token {
+        &self.tokens[self.current]
     }
 
-    fn previous(&self) -> Token {
-        self.tokens[self.current - 1].clone()
+    fn previous(&self) -> &Token {
+        &self.tokens[self.current - 1]
     }
 
-    fn use_statement(&mut self) -> Result<Stmt, String> {
-        let token = self.advance(); // consume 'use'
+    fn use_statement(&mut self) -> Result<Stmt, ZenError> {
+        let token = self.advance().clone(); // consume 'use'
         let mut path = Vec::new();
 
+        // File import: use "path/to/file.zen";
+        if self.check(TokenType::StringLiteral) {
+            let literal = self.advance().lexeme;
+            path.push(literal[1..literal.len() - 1].to_string());
+            self.consume(TokenType::Semicolon, "Expected ';' after use statement")?;
+            return Ok(Stmt::Use {
+                path,
+                alias: None,
+                token,
+            });
+        }
+
         // Parse path like: crate::module::item or module::*
         loop {
             if self.check(TokenType::Identifier)
@@ -1074,16 +1899,16 @@ impl Parser {
                 || self.check(TokenType::Super)
                 || self.check(TokenType::Self_)
             {
-                path.push(self.advance().lexeme);
+                path.push(self.advance().lexeme.to_string());
             } else if self.check(TokenType::Star) {
                 // Handle wildcard import: use module::*;
-                path.push(self.advance().lexeme);
+                path.push(self.advance().lexeme.to_string());
                 break;
             } else {
-                return Err(format!(
+                return Err(self.error(format!(
                     "Expected identifier or '*' in use path, got {:?}",
                     self.peek()
-                ));
+                )));
             }
 
             if !self.match_token(TokenType::DoubleColon) {
@@ -1102,6 +1927,74 @@ impl Parser {
     }
 }
 
+// Traits that `#[derive(...)]` can implement
+const DERIVABLE: &[&str] = &["Eq"];
+
+// `#[derive(Eq)]` expands to `fn Name_eq(a: Name, b: Name) -> bool` comparing every field,
+// which `==` and `!=` on the struct then call
+fn derived_eq(decl: &Stmt) -> Option<Stmt> {
+    let Stmt::StructDecl {
+        name,
+        fields,
+        derives,
+        is_public,
+        token,
+        ..
+    } = decl
+    else {
+        return None;
+    };
+    if !derives.iter().any(|derive| derive == "Eq") {
+        return None;
+    }
+
+    let synthetic = |kind: TokenType, lexeme: &str| {
+        Token::new(kind, lexeme.to_string(), token.line, token.column)
+    };
+    let field = |param: &str, field: &str| Expr::FieldAccess {
+        object: Box::new(Expr::Identifier {
+            name: param.to_string(),
+            token: synthetic(TokenType::Identifier, param),
+        }),
+        field: field.to_string(),
+        token: synthetic(TokenType::Dot, "."),
+    };
+    let all_equal = fields
+        .iter()
+        .map(|(field_name, _)| Expr::BinaryOp {
+            left: Box::new(field("a", field_name)),
+            op: synthetic(TokenType::EqualEqual, "=="),
+            right: Box::new(field("b", field_name)),
+        })
+        .reduce(|left, right| Expr::BinaryOp {
+            left: Box::new(left),
+            op: synthetic(TokenType::And, "&&"),
+            right: Box::new(right),
+        })
+        .unwrap_or(Expr::BooleanLiteral {
+            value: true,
+            token: synthetic(TokenType::True, "true"),
+        });
+
+    let struct_type = Type::Struct(name.clone());
+    Some(Stmt::FunctionDecl {
+        name: format!("{}_eq", name),
+        type_params: Vec::new(),
+        params: vec![
+            ("a".to_string(), struct_type.clone()),
+            ("b".to_string(), struct_type),
+        ],
+        return_type: Type::Bool,
+        body: vec![Stmt::Return {
+            value: Some(all_equal),
+            token: synthetic(TokenType::Return, "return"),
+        }],
+        is_public: *is_public,
+        doc: None,
+        token: token.clone(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1112,7 +2005,7 @@ mod tests {
         let mut lexer = crate::lexer::lexer::Lexer::new(code);
         let mut parser = Parser::new(lexer.tokenize().unwrap());
 
-        let result = parser.parse();
+        let result = parser.parse_strict();
         assert!(result.is_ok(), "Parsing should succeed");
 
         let program = result.expect("Failed to parse program");
@@ -1125,7 +2018,7 @@ mod tests {
         let mut lexer = crate::lexer::lexer::Lexer::new(code);
         let mut parser = Parser::new(lexer.tokenize().unwrap());
 
-        let result = parser.parse();
+        let result = parser.parse_strict();
         assert!(
             result.is_ok(),
             "Parsing function with params should succeed"
@@ -1138,20 +2031,51 @@ mod tests {
         let mut lexer = crate::lexer::lexer::Lexer::new(code);
         let mut parser = Parser::new(lexer.tokenize().unwrap());
 
-        let result = parser.parse();
+        let result = parser.parse_strict();
         assert!(
             result.is_ok(),
             "Parsing variable declaration should succeed"
         );
     }
 
+    #[test]
+    fn test_declaration_chain() {
+        let code = "let a = 1, mut b: i64 = 2, c = a";
+        let mut lexer = crate::lexer::lexer::Lexer::new(code);
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let program = parser.parse_strict().unwrap();
+        let declared: Vec<_> = program
+            .statements
+            .iter()
+            .map(|stmt| match stmt {
+                Stmt::VariableDecl {
+                    name,
+                    type_annotation,
+                    is_mutable,
+                    initializer: Some(_),
+                    ..
+                } => (name.as_str(), type_annotation.clone(), *is_mutable),
+                other => panic!("Expected variable declaration, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            declared,
+            vec![
+                ("a", None, false),
+                ("b", Some(Type::Int(crate::types::IntKind::I64)), true),
+                ("c", None, false),
+            ]
+        );
+    }
+
     #[test]
     fn test_mutable_variable() {
         let code = "let mut counter = 0";
         let mut lexer = crate::lexer::lexer::Lexer::new(code);
         let mut parser = Parser::new(lexer.tokenize().unwrap());
 
-        let result = parser.parse();
+        let result = parser.parse_strict();
         assert!(result.is_ok(), "Parsing mutable variable should succeed");
     }
 
@@ -1161,7 +2085,7 @@ mod tests {
         let mut lexer = crate::lexer::lexer::Lexer::new(code);
         let mut parser = Parser::new(lexer.tokenize().unwrap());
 
-        let result = parser.parse();
+        let result = parser.parse_strict();
         assert!(result.is_ok(), "Parsing variable with type should succeed");
     }
 
@@ -1171,7 +2095,7 @@ mod tests {
         let mut lexer = crate::lexer::lexer::Lexer::new(code);
         let mut parser = Parser::new(lexer.tokenize().unwrap());
 
-        let result = parser.parse();
+        let result = parser.parse_strict();
         assert!(result.is_ok(), "Parsing if statement should succeed");
     }
 
@@ -1181,7 +2105,7 @@ mod tests {
         let mut lexer = crate::lexer::lexer::Lexer::new(code);
         let mut parser = Parser::new(lexer.tokenize().unwrap());
 
-        let result = parser.parse();
+        let result = parser.parse_strict();
         assert!(result.is_ok(), "Parsing if-else statement should succeed");
     }
 
@@ -1191,37 +2115,384 @@ mod tests {
         let mut lexer = crate::lexer::lexer::Lexer::new(code);
         let mut parser = Parser::new(lexer.tokenize().unwrap());
 
-        let result = parser.parse();
+        let result = parser.parse_strict();
         assert!(result.is_ok(), "Parsing while loop should succeed");
     }
 
+    #[test]
+    fn test_loop_statement() {
+        let code = "loop { i += 1 if i == 3 { break } }";
+        let mut lexer = crate::lexer::lexer::Lexer::new(code);
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let program = parser.parse_strict().unwrap();
+        match &program.statements[0] {
+            Stmt::Loop { body, token } => {
+                assert_eq!(token.lexeme, "loop");
+                assert_eq!(body.len(), 2);
+                assert!(matches!(body[1], Stmt::If { .. }));
+            }
+            other => panic!("Expected a loop statement, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_c_style_for_loop() {
         let code = "for (i = 0; i < 10; i = i + 1) { println(i) }";
         let mut lexer = crate::lexer::lexer::Lexer::new(code);
         let mut parser = Parser::new(lexer.tokenize().unwrap());
 
-        let result = parser.parse();
+        let result = parser.parse_strict();
         assert!(result.is_ok(), "Parsing C-style for loop should succeed");
     }
 
+    #[test]
+    fn test_for_init_without_let() {
+        let init_of = |code: &str| {
+            let mut lexer = crate::lexer::lexer::Lexer::new(code);
+            let program = Parser::new(lexer.tokenize().unwrap())
+                .parse_strict()
+                .unwrap();
+            match program.statements.into_iter().next() {
+                Some(Stmt::For {
+                    init: Some(init), ..
+                }) => *init,
+                other => panic!("Expected for loop, got {:?}", other),
+            }
+        };
+
+        // A bare name may refer to an existing variable, so it stays an assignment
+        assert!(matches!(
+            init_of("for (i = 0; i < 3; i = i + 1) {}"),
+            Stmt::Assignment { target: Expr::Identifier { name, .. }, .. } if name == "i"
+        ));
+        assert!(matches!(
+            init_of("for (mut i = 0; i < 3; i = i + 1) {}"),
+            Stmt::VariableDecl { name, is_mutable: true, .. } if name == "i"
+        ));
+    }
+
+    #[test]
+    fn test_for_in_over_array_literal() {
+        let code = "for x in [1, 2, 3,] { println(x) }";
+        let mut lexer = crate::lexer::lexer::Lexer::new(code);
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let program = parser.parse_strict().unwrap();
+        match &program.statements[0] {
+            Stmt::ForIn {
+                variable,
+                iterable: Expr::ArrayLiteral { elements, .. },
+                body,
+                ..
+            } => {
+                assert_eq!(variable, "x");
+                assert_eq!(elements.len(), 3);
+                assert_eq!(body.len(), 1);
+            }
+            other => panic!("Expected for-in loop, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_duplicate_parameter_is_rejected() {
+        let code = "fn f(a: i32, a: i32) -> i32 {\n    return a\n}";
+        let mut lexer = crate::lexer::lexer::Lexer::new(code);
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let err = parser.parse_strict().unwrap_err();
+        assert!(
+            err.contains(
+                "Error at line 1, column 14: Duplicate parameter 'a' (first declared at line 1:6)"
+            ),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_generic_function_declaration() {
+        let code = "fn id<T>(x: &T) -> T {\n    x\n}";
+        let mut lexer = crate::lexer::lexer::Lexer::new(code);
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let program = parser.parse_strict().unwrap();
+        match &program.statements[0] {
+            Stmt::FunctionDecl {
+                type_params,
+                params,
+                return_type,
+                ..
+            } => {
+                let t = Type::Param("T".to_string());
+                assert_eq!(type_params, &["T".to_string()]);
+                assert_eq!(params[0].1, Type::Ref(Box::new(t.clone()), false));
+                assert_eq!(*return_type, t);
+            }
+            other => panic!("Expected function declaration, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_match_statement() {
         let code = "match value { 1 => println(\"One\"), _ => println(\"Other\") }";
         let mut lexer = crate::lexer::lexer::Lexer::new(code);
         let mut parser = Parser::new(lexer.tokenize().unwrap());
 
-        let result = parser.parse();
+        let result = parser.parse_strict();
         assert!(result.is_ok(), "Parsing match statement should succeed");
     }
 
+    #[test]
+    fn test_match_arm_with_braced_statements() {
+        let code = "match n {\n    1 => {\n        println(1)\n        println(2)\n    }\n    _ => println(0)\n}";
+        let mut lexer = crate::lexer::lexer::Lexer::new(code);
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let program = parser.parse_strict().unwrap();
+        match &program.statements[0] {
+            Stmt::Match { arms, default, .. } => {
+                assert_eq!(arms[0].1.len(), 2);
+                assert_eq!(default.as_ref().map(Vec::len), Some(1));
+            }
+            other => panic!("Expected match statement, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_match_arm_with_unbraced_statements_is_rejected() {
+        let code = "match n {\n    1 => println(1)\n    println(2)\n    _ => println(0)\n}";
+        let mut lexer = crate::lexer::lexer::Lexer::new(code);
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let err = parser.parse_strict().unwrap_err();
+        assert!(err.contains(
+            "Error at line 2, column 5: Match arm has more than one statement; wrap its body in braces: '1 => { ... }'"
+        ));
+    }
+
+    #[test]
+    fn test_derive_eq_generates_equality_function() {
+        let code = "#[derive(Eq)]\nstruct Point { x: i32, y: i32 }";
+        let mut lexer = crate::lexer::lexer::Lexer::new(code);
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let program = parser.parse_strict().unwrap();
+        assert_eq!(program.statements.len(), 2);
+        match &program.statements[1] {
+            Stmt::FunctionDecl {
+                name,
+                params,
+                return_type,
+                ..
+            } => {
+                assert_eq!(name, "Point_eq");
+                assert_eq!(params.len(), 2);
+                assert_eq!(params[0].1, Type::Struct("Point".to_string()));
+                assert_eq!(*return_type, Type::Bool);
+            }
+            other => panic!("Expected Point_eq, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_impl_methods_take_a_pointer_to_self() {
+        let code = "struct Point { x: i32 }\nimpl Point {\n    fn get(self, scale: i32) -> i32 {\n        return self.x * scale\n    }\n}";
+        let mut lexer = crate::lexer::lexer::Lexer::new(code);
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let program = parser.parse_strict().unwrap();
+        let Stmt::ImplBlock {
+            struct_name,
+            methods,
+            ..
+        } = &program.statements[1]
+        else {
+            panic!("Expected an impl block, got {:?}", program.statements[1]);
+        };
+        assert_eq!(struct_name, "Point");
+        match &methods[0] {
+            Stmt::FunctionDecl { name, params, .. } => {
+                assert_eq!(name, "Point_get");
+                assert_eq!(params[0].0, "self");
+                assert_eq!(params[0].1.to_string(), "&Point");
+                assert_eq!(params[1].1, Type::I32);
+            }
+            other => panic!("Expected Point_get, got {:?}", other),
+        }
+
+        let code = "struct Point { x: i32 }\nimpl Point {\n    fn get(p: Point) -> i32 {\n        return 0\n    }\n}";
+        let mut lexer = crate::lexer::lexer::Lexer::new(code);
+        let err = Parser::new(lexer.tokenize().unwrap())
+            .parse_strict()
+            .unwrap_err();
+        assert!(
+            err.contains("Method 'get' of 'Point' must take 'self' as its first parameter"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_derive_of_unknown_trait_is_rejected() {
+        let code = "#[derive(Ord)]\nstruct Point { x: i32 }";
+        let mut lexer = crate::lexer::lexer::Lexer::new(code);
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let err = parser.parse_strict().unwrap_err();
+        assert!(
+            err.contains("Error at line 1, column 10: Cannot derive 'Ord'; derivable traits: Eq")
+        );
+    }
+
+    #[test]
+    fn test_parse_keeps_functions_around_a_bad_one() {
+        let code = "fn first() -> i32 {\n    return 1\n}\n\nfn broken( -> i32 {\n    return 2\n}\n\nfn last() -> i32 {\n    return 3\n}";
+        let mut lexer = crate::lexer::lexer::Lexer::new(code);
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let (program, errors) = parser.parse();
+        let names: Vec<&str> = program
+            .statements
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::FunctionDecl { name, .. } => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(names, ["first", "last"]);
+        assert!(matches!(errors[0], ZenError::ParseError { line: 5, .. }));
+    }
+
+    #[test]
+    fn test_string_literal_escapes_are_decoded() {
+        let code = r#"let s = "a\nb\t\\\"\0\x41""#;
+        let mut lexer = crate::lexer::lexer::Lexer::new(code);
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let program = parser.parse_strict().unwrap();
+        match &program.statements[0] {
+            Stmt::VariableDecl {
+                initializer: Some(Expr::StringLiteral { value, .. }),
+                ..
+            } => {
+                assert_eq!(value.len(), 8);
+                assert_eq!(value.as_bytes(), b"a\nb\t\\\"\0A");
+            }
+            other => panic!("Expected a string literal, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_unicode_escapes_in_strings_and_chars() {
+        let code = "let s = \"\\u{41}\"\nlet c = '\\u{7A}'";
+        let mut lexer = crate::lexer::lexer::Lexer::new(code);
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let program = parser.parse_strict().unwrap();
+        let initializers: Vec<&Expr> = program
+            .statements
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::VariableDecl {
+                    initializer: Some(init),
+                    ..
+                } => Some(init),
+                _ => None,
+            })
+            .collect();
+        assert!(matches!(initializers[0], Expr::StringLiteral { value, .. } if value == "A"));
+        assert!(matches!(
+            initializers[1],
+            Expr::CharLiteral { value: 'z', .. }
+        ));
+    }
+
+    #[test]
+    fn test_use_with_alias() {
+        let code = "use math::add as plus;";
+        let mut lexer = crate::lexer::lexer::Lexer::new(code);
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let program = parser.parse_strict().unwrap();
+        match &program.statements[0] {
+            Stmt::Use { path, alias, .. } => {
+                assert_eq!(path, &["math", "add"]);
+                assert_eq!(alias.as_deref(), Some("plus"));
+            }
+            other => panic!("Expected a use statement, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_mutable_borrow_with_and_without_space() {
+        let code = "let a = &mut x\nlet b = & mut x\nlet c = &mutable";
+        let mut lexer = crate::lexer::lexer::Lexer::new(code);
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let program = parser.parse_strict().unwrap();
+        let borrows: Vec<(bool, &Expr)> = program
+            .statements
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::VariableDecl {
+                    initializer:
+                        Some(Expr::Borrow {
+                            expr, is_mutable, ..
+                        }),
+                    ..
+                } => Some((*is_mutable, expr.as_ref())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(borrows.len(), 3);
+        assert!(matches!(borrows[0], (true, Expr::Identifier { name, .. }) if name == "x"));
+        assert!(matches!(borrows[1], (true, Expr::Identifier { name, .. }) if name == "x"));
+        assert!(matches!(borrows[2], (false, Expr::Identifier { name, .. }) if name == "mutable"));
+    }
+
+    #[test]
+    fn test_compound_assignment_desugars_to_binary_op() {
+        let code = "total -= step * 2";
+        let mut lexer = crate::lexer::lexer::Lexer::new(code);
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let program = parser.parse_strict().unwrap();
+        match &program.statements[0] {
+            Stmt::Assignment {
+                target: Expr::Identifier { name, .. },
+                value: Expr::BinaryOp { left, op, right },
+                token,
+            } => {
+                assert_eq!(name, "total");
+                assert_eq!(token.kind, TokenType::MinusEqual);
+                assert_eq!(op.kind, TokenType::Minus);
+                assert!(matches!(left.as_ref(), Expr::Identifier { name, .. } if name == "total"));
+                assert!(
+                    matches!(right.as_ref(), Expr::BinaryOp { op, .. } if op.kind == TokenType::Star)
+                );
+            }
+            other => panic!("Expected a compound assignment, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_fat_arrow_is_not_a_return_arrow() {
+        let code = "fn f() => i32 {}";
+        let mut lexer = crate::lexer::lexer::Lexer::new(code);
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let err = parser.parse_strict().unwrap_err();
+        assert!(err.contains("Expected '->' after parameters"));
+    }
+
     #[test]
     fn test_function_call() {
         let code = "println(\"Hello\")";
         let mut lexer = crate::lexer::lexer::Lexer::new(code);
         let mut parser = Parser::new(lexer.tokenize().unwrap());
 
-        let result = parser.parse();
+        let result = parser.parse_strict();
         assert!(result.is_ok(), "Parsing function call should succeed");
     }
 
@@ -1231,7 +2502,7 @@ mod tests {
         let mut lexer = crate::lexer::lexer::Lexer::new(code);
         let mut parser = Parser::new(lexer.tokenize().unwrap());
 
-        let result = parser.parse();
+        let result = parser.parse_strict();
         assert!(result.is_ok(), "Parsing assignment should succeed");
     }
 
@@ -1241,7 +2512,7 @@ mod tests {
         let mut lexer = crate::lexer::lexer::Lexer::new(code);
         let mut parser = Parser::new(lexer.tokenize().unwrap());
 
-        let result = parser.parse();
+        let result = parser.parse_strict();
         assert!(result.is_ok(), "Parsing binary expressions should succeed");
     }
 
@@ -1251,13 +2522,53 @@ mod tests {
         let mut lexer = crate::lexer::lexer::Lexer::new(code);
         let mut parser = Parser::new(lexer.tokenize().unwrap());
 
-        let result = parser.parse();
+        let result = parser.parse_strict();
         assert!(
             result.is_ok(),
             "Parsing comparison expressions should succeed"
         );
     }
 
+    #[test]
+    fn test_operator_precedence_grouping() {
+        fn render(expr: &Expr) -> String {
+            match expr {
+                Expr::BinaryOp { left, op, right } => {
+                    format!("({} {} {})", render(left), op.lexeme, render(right))
+                }
+                Expr::UnaryOp { op, operand } => format!("({}{})", op.lexeme, render(operand)),
+                Expr::Cast {
+                    expr, target_type, ..
+                } => format!("({} as {})", render(expr), target_type),
+                Expr::Identifier { name, .. } => name.clone(),
+                Expr::IntegerLiteral { value, .. } => value.clone(),
+                other => panic!("Unexpected expression {:?}", other),
+            }
+        }
+
+        let cases = [
+            ("a || b && c == d | e", "(a || (b && (c == (d | e))))"),
+            ("a == b < c | d + e * f", "(a == (b < (c | (d + (e * f)))))"),
+            ("!a && b || c", "(((!a) && b) || c)"),
+            ("a - b - c", "((a - b) - c)"),
+            ("a | b | c", "((a | b) | c)"),
+            ("a + b as i64 * c", "(a + ((b as i64) * c))"),
+            ("-a as u8 as char", "(((-a) as u8) as char)"),
+        ];
+
+        for (code, expected) in cases {
+            let mut lexer = crate::lexer::lexer::Lexer::new(code);
+            let mut parser = Parser::new(lexer.tokenize().unwrap());
+            let program = parser.parse_strict().expect("Failed to parse expression");
+
+            if let Stmt::ExprStmt { expr } = &program.statements[0] {
+                assert_eq!(render(expr), expected, "Wrong grouping for `{}`", code);
+            } else {
+                panic!("Expected expression statement for `{}`", code);
+            }
+        }
+    }
+
     #[test]
     fn test_complex_program() {
         let code = r#"
@@ -1274,7 +2585,7 @@ fn main() -> i32 {
         let mut lexer = crate::lexer::lexer::Lexer::new(code);
         let mut parser = Parser::new(lexer.tokenize().unwrap());
 
-        let result = parser.parse();
+        let result = parser.parse_strict();
         assert!(result.is_ok(), "Parsing complex program should work");
 
         let program = result.expect("Failed to parse complex program");
@@ -1288,7 +2599,7 @@ fn main() -> i32 {
         let mut lexer = crate::lexer::lexer::Lexer::new(code);
         let mut parser = Parser::new(lexer.tokenize().unwrap());
 
-        let result = parser.parse();
+        let result = parser.parse_strict();
         assert!(result.is_ok());
 
         let program = result.expect("Failed to parse multiple declarations");
@@ -1313,7 +2624,7 @@ fn main() -> i32 {
         let mut lexer = crate::lexer::lexer::Lexer::new(code);
         let mut parser = Parser::new(lexer.tokenize().unwrap());
 
-        let result = parser.parse();
+        let result = parser.parse_strict();
         assert!(result.is_ok(), "Parsing single else if should succeed");
 
         let program = result.expect("Failed to parse else if");
@@ -1359,7 +2670,7 @@ fn main() -> i32 {
         let mut lexer = crate::lexer::lexer::Lexer::new(code);
         let mut parser = Parser::new(lexer.tokenize().unwrap());
 
-        let result = parser.parse();
+        let result = parser.parse_strict();
         assert!(result.is_ok(), "Parsing multiple else if should succeed");
 
         let program = result.expect("Failed to parse multiple else if");
@@ -1404,7 +2715,7 @@ fn main() -> i32 {
         let mut lexer = crate::lexer::lexer::Lexer::new(code);
         let mut parser = Parser::new(lexer.tokenize().unwrap());
 
-        let result = parser.parse();
+        let result = parser.parse_strict();
         assert!(
             result.is_ok(),
             "Parsing else if without final else should succeed"
@@ -1459,10 +2770,343 @@ fn main() -> i32 {
         let mut lexer = crate::lexer::lexer::Lexer::new(code);
         let mut parser = Parser::new(lexer.tokenize().unwrap());
 
-        let result = parser.parse();
+        let result = parser.parse_strict();
         assert!(result.is_ok(), "Parsing nested else if should succeed");
 
         let program = result.expect("Failed to parse nested else if");
         assert_eq!(program.statements.len(), 1);
     }
+
+    #[test]
+    fn test_prefix_star_is_deref() {
+        let code = "*p = a * *q";
+        let mut lexer = crate::lexer::lexer::Lexer::new(code);
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let program = parser.parse_strict().unwrap();
+        match &program.statements[0] {
+            Stmt::Assignment { target, value, .. } => {
+                assert!(matches!(target, Expr::Deref { .. }));
+                match value {
+                    Expr::BinaryOp { op, right, .. } => {
+                        assert_eq!(op.kind, TokenType::Star);
+                        assert!(matches!(right.as_ref(), Expr::Deref { .. }));
+                    }
+                    other => panic!("Expected multiplication, got {:?}", other),
+                }
+            }
+            other => panic!("Expected assignment through pointer, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_deref_assignment_after_a_let_starts_a_new_statement() {
+        let code = "let old = *p\n*p = old + 1";
+        let mut lexer = crate::lexer::lexer::Lexer::new(code);
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let program = parser.parse_strict().unwrap();
+        assert_eq!(program.statements.len(), 2);
+        match &program.statements[0] {
+            Stmt::VariableDecl {
+                initializer: Some(init),
+                ..
+            } => assert!(matches!(init, Expr::Deref { .. })),
+            other => panic!("Expected variable declaration, got {:?}", other),
+        }
+        match &program.statements[1] {
+            Stmt::Assignment { target, .. } => assert!(matches!(target, Expr::Deref { .. })),
+            other => panic!("Expected assignment through pointer, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_nullable_type_with_null_initializer() {
+        let code = "let p: ?str = null";
+        let mut lexer = crate::lexer::lexer::Lexer::new(code);
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let program = parser.parse_strict().unwrap();
+        match &program.statements[0] {
+            Stmt::VariableDecl {
+                type_annotation,
+                initializer,
+                ..
+            } => {
+                assert_eq!(type_annotation, &Some(Type::Nullable(Box::new(Type::Str))));
+                assert!(matches!(initializer, Some(Expr::NullLiteral { .. })));
+            }
+            other => panic!("Expected variable declaration, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_doubled_braces_escape_interpolation() {
+        use crate::ast::expr::StringPart;
+
+        fn parse_string(code: &str) -> Expr {
+            let mut lexer = crate::lexer::lexer::Lexer::new(code);
+            let mut parser = Parser::new(lexer.tokenize().unwrap());
+            match parser.parse_strict().unwrap().statements.remove(0) {
+                Stmt::ExprStmt { expr } => expr,
+                other => panic!("Expected expression statement, got {:?}", other),
+            }
+        }
+
+        match parse_string("\"{{x}}\"") {
+            Expr::StringLiteral { value, .. } => assert_eq!(value, "{x}"),
+            other => panic!("Expected string literal, got {:?}", other),
+        }
+        match parse_string("\"a {{b}} {x}\"") {
+            Expr::InterpolatedString { parts, .. } => assert!(matches!(
+                parts.as_slice(),
+                [StringPart::Text(text), StringPart::Variable(name)]
+                    if text == "a {b} " && name == "x"
+            )),
+            other => panic!("Expected interpolated string, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_unclosed_interpolation_brace_is_error() {
+        let mut lexer = crate::lexer::lexer::Lexer::new("println(\"a{b\")");
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let err = parser.parse_strict().unwrap_err();
+        assert!(err.contains("Error at line 1, column 11: Unclosed '{' in interpolated string"));
+    }
+
+    #[test]
+    fn test_stray_closing_interpolation_brace_is_error() {
+        let mut lexer = crate::lexer::lexer::Lexer::new("println(\"a}b\")");
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let err = parser.parse_strict().unwrap_err();
+        assert!(err.contains("Error at line 1, column 11: Unmatched '}' in interpolated string"));
+
+        let mut lexer = crate::lexer::lexer::Lexer::new("println(\"a}}b\")");
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+        assert!(parser.parse_strict().is_ok());
+    }
+
+    #[test]
+    fn test_missing_right_paren_reports_structured_error() {
+        let code = "fn main() -> i32 {\n    let x = (1 + 2\n    return 0\n}";
+        let mut lexer = crate::lexer::lexer::Lexer::new(code);
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let (_, errors) = parser.parse();
+        match &errors[0] {
+            ZenError::ParseError {
+                line,
+                column,
+                expected,
+                found,
+                ..
+            } => {
+                assert_eq!((*line, *column), (3, 5));
+                assert_eq!(expected.as_deref(), Some("RightParen"));
+                assert_eq!(found.as_deref(), Some("Return"));
+            }
+            other => panic!("Expected a parse error, got {:?}", other),
+        }
+
+        let rendered = errors[0].clone().with_source(code).format_with_context();
+        assert!(rendered.contains("    return 0\n      ^"), "{}", rendered);
+    }
+
+    #[test]
+    fn test_array_repeat_expression() {
+        let mut lexer = crate::lexer::lexer::Lexer::new("let a = [1; 5]");
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let program = parser.parse_strict().unwrap();
+        match &program.statements[0] {
+            Stmt::VariableDecl {
+                initializer: Some(Expr::ArrayRepeat { value, count, .. }),
+                ..
+            } => {
+                assert!(
+                    matches!(value.as_ref(), Expr::IntegerLiteral { value, .. } if value == "1")
+                );
+                assert_eq!(*count, 5);
+            }
+            other => panic!("Expected array repeat initializer, got {:?}", other),
+        }
+
+        let mut lexer = crate::lexer::lexer::Lexer::new("let a: [i32; 2 * 3] = [0; 4 + 2]");
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+        match &parser.parse_strict().unwrap().statements[0] {
+            Stmt::VariableDecl {
+                type_annotation: Some(Type::Array(_, 6)),
+                initializer: Some(Expr::ArrayRepeat { count: 6, .. }),
+                ..
+            } => {}
+            other => panic!("Expected folded array lengths, got {:?}", other),
+        }
+
+        let mut lexer = crate::lexer::lexer::Lexer::new("let a = [1; n]");
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+        let err = parser.parse_strict().unwrap_err();
+        assert!(err.contains("Array repeat count must be a constant integer"));
+    }
+
+    #[test]
+    fn test_match_expression() {
+        let code = "let s = match n { 1 => \"one\", 2 => \"two\", _ => \"many\" }";
+        let mut lexer = crate::lexer::lexer::Lexer::new(code);
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let program = parser.parse_strict().unwrap();
+        match &program.statements[0] {
+            Stmt::VariableDecl {
+                initializer: Some(Expr::Match { arms, default, .. }),
+                ..
+            } => {
+                assert_eq!(arms.len(), 2);
+                assert!(matches!(
+                    default.as_deref(),
+                    Some(Expr::StringLiteral { value, .. }) if value == "many"
+                ));
+            }
+            other => panic!("Expected match initializer, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_if_in_expression_position_is_an_if_expression() {
+        let code =
+            "let m = if a > b { a } else if a == b { 0 } else { b }\nif a > b {\n    println(a)\n}";
+        let mut lexer = crate::lexer::lexer::Lexer::new(code);
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let program = parser.parse_strict().unwrap();
+        match &program.statements[0] {
+            Stmt::VariableDecl {
+                initializer: Some(Expr::IfExpr { else_expr, .. }),
+                ..
+            } => assert!(matches!(else_expr.as_ref(), Expr::IfExpr { .. })),
+            other => panic!("Expected an if-expression initializer, got {:?}", other),
+        }
+        assert!(matches!(program.statements[1], Stmt::If { .. }));
+    }
+
+    #[test]
+    fn test_block_in_expression_position_yields_its_last_expression() {
+        let code = "let x = { let a = 2; a * 3 }\nlet y = {\n    println(1)\n}";
+        let mut lexer = crate::lexer::lexer::Lexer::new(code);
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let program = parser.parse_strict().unwrap();
+        match &program.statements[0] {
+            Stmt::VariableDecl {
+                initializer:
+                    Some(Expr::Block {
+                        statements, tail, ..
+                    }),
+                ..
+            } => {
+                assert!(matches!(statements[..], [Stmt::VariableDecl { .. }]));
+                assert!(matches!(tail.as_ref(), Expr::BinaryOp { .. }));
+            }
+            other => panic!("Expected a block initializer, got {:?}", other),
+        }
+        match &program.statements[1] {
+            Stmt::VariableDecl {
+                initializer: Some(Expr::Block { statements, .. }),
+                ..
+            } => assert!(statements.is_empty()),
+            other => panic!("Expected a block initializer, got {:?}", other),
+        }
+
+        let mut lexer = crate::lexer::lexer::Lexer::new("let z = { let a = 2 }");
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+        assert!(parser.parse_strict().is_err());
+    }
+
+    #[test]
+    fn test_power_is_right_associative_and_binds_tighter_than_factor() {
+        let mut lexer = crate::lexer::lexer::Lexer::new("let x = 3 * 2 ^ 3 ^ 2");
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+        let program = parser.parse_strict().unwrap();
+        let Stmt::VariableDecl {
+            initializer: Some(Expr::BinaryOp { op, right, .. }),
+            ..
+        } = &program.statements[0]
+        else {
+            panic!(
+                "Expected binary initializer, got {:?}",
+                program.statements[0]
+            );
+        };
+        assert_eq!(op.kind, TokenType::Star);
+        let Expr::BinaryOp { op, right, .. } = right.as_ref() else {
+            panic!("Expected power on the right of '*', got {:?}", right);
+        };
+        assert_eq!(op.kind, TokenType::Caret);
+        assert!(matches!(
+            right.as_ref(),
+            Expr::BinaryOp { op, .. } if op.kind == TokenType::Caret
+        ));
+    }
+
+    #[test]
+    fn test_nested_compound_types() {
+        let cases = [
+            ("[i32; 3]", "[i32; 3]"),
+            ("[&i32; 3]", "[&i32; 3]"),
+            ("&[i32; 3]", "&[i32; 3]"),
+            ("&mut [&mut i32; 2]", "&mut [&mut i32; 2]"),
+            ("[[i32; 2]; 4]", "[[i32; 2]; 4]"),
+            ("?&[str]", "?&[str]"),
+        ];
+
+        for (written, expected) in cases {
+            let code = format!("fn f(x: {}) -> void {{}}", written);
+            let mut lexer = crate::lexer::lexer::Lexer::new(&code);
+            let mut parser = Parser::new(lexer.tokenize().unwrap());
+
+            let program = parser.parse_strict().unwrap();
+            match &program.statements[0] {
+                Stmt::FunctionDecl { params, .. } => {
+                    assert_eq!(params[0].1.to_string(), expected)
+                }
+                other => panic!("Expected function declaration, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_large_input_keeps_every_stored_token() {
+        let code: String = (0..500)
+            .map(|i| {
+                format!(
+                    "fn step{}(x: i32) -> i32 {{\n    let y = x * {} + 1\n    if y > 10 {{\n        return y\n    }}\n    return x\n}}\n",
+                    i, i
+                )
+            })
+            .collect();
+        let mut lexer = crate::lexer::lexer::Lexer::new(&code);
+        let program = Parser::new(lexer.tokenize().unwrap())
+            .parse_strict()
+            .unwrap();
+
+        assert_eq!(program.statements.len(), 500);
+        match &program.statements[499] {
+            Stmt::FunctionDecl { name, body, .. } => {
+                assert_eq!(name, "step499");
+                assert!(matches!(
+                    &body[0],
+                    Stmt::VariableDecl { initializer: Some(Expr::BinaryOp { op, .. }), .. }
+                        if op.lexeme == "+" && (op.line, op.column) == (3495, 21)
+                ));
+                assert!(matches!(
+                    &body[2],
+                    Stmt::Return { token, .. } if token.line == 3499
+                ));
+            }
+            other => panic!("Expected function declaration, got {:?}", other),
+        }
+    }
 }
@@ -1,3 +1,4 @@
+use crate::intern::Symbol;
 use std::fmt;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -10,9 +11,14 @@ pub enum TokenType {
     If,
     Else,
     For,
+    In,
     While,
+    Loop,
+    Break,
+    Continue,
     Match,
     Struct,
+    Impl,
     Const,
     Mod,
     Use,
@@ -48,6 +54,9 @@ pub enum TokenType {
     Slash,
     Percent,
     Caret,
+    CaretCaret,
+    ShiftLeft,
+    ShiftRight,
 
     LessThan,
     GreaterThan,
@@ -56,12 +65,18 @@ pub enum TokenType {
     EqualEqual,
     NotEqual,
     Equal,
+    PlusEqual,
+    MinusEqual,
+    StarEqual,
+    SlashEqual,
+    PercentEqual,
     Not,
     And,
     Or,
 
     ArrowLeft,
     ArrowRight,
+    FatArrow,
     Dot,
     DoubleDot,
     DotDot,
@@ -72,6 +87,7 @@ pub enum TokenType {
     Bang,
     Tilde,
     Question,
+    Hash,
     Colon,
     DoubleColon,
     Semicolon,
@@ -93,30 +109,65 @@ pub enum TokenType {
     Identifier,
 
     // Special
+    DocComment,
     EOF,
     Unknown,
 }
 
+impl TokenType {
+    /// The arithmetic operator a compound assignment applies: `+` for `+=`
+    pub fn compound_operator(&self) -> Option<TokenType> {
+        match self {
+            TokenType::PlusEqual => Some(TokenType::Plus),
+            TokenType::MinusEqual => Some(TokenType::Minus),
+            TokenType::StarEqual => Some(TokenType::Star),
+            TokenType::SlashEqual => Some(TokenType::Slash),
+            TokenType::PercentEqual => Some(TokenType::Percent),
+            _ => None,
+        }
+    }
+
+    /// `=` or a compound assignment such as `+=`
+    pub fn is_assignment(&self) -> bool {
+        *self == TokenType::Equal || self.compound_operator().is_some()
+    }
+
+    /// Method suffix an arithmetic operator on structs resolves to: `a + b` on a `Vec2`
+    /// calls `Vec2_add(a, b)`
+    pub fn operator_method(&self) -> Option<&'static str> {
+        match self {
+            TokenType::Plus => Some("add"),
+            TokenType::Minus => Some("sub"),
+            TokenType::Star => Some("mul"),
+            TokenType::Slash => Some("div"),
+            TokenType::Percent => Some("rem"),
+            TokenType::EqualEqual | TokenType::NotEqual => Some("eq"),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Token {
     pub kind: TokenType,
-    pub lexeme: String,
+    /// Interned, so cloning a token never copies its text
+    pub lexeme: Symbol,
     pub line: usize,
     pub column: usize,
 }
 
 impl Token {
-    pub fn new(kind: TokenType, lexeme: String, line: usize, column: usize) -> Self {
+    pub fn new(kind: TokenType, lexeme: impl Into<Symbol>, line: usize, column: usize) -> Self {
         Token {
             kind,
-            lexeme,
+            lexeme: lexeme.into(),
             line,
             column,
         }
     }
 
     pub fn eof(line: usize, column: usize) -> Self {
-        Token::new(TokenType::EOF, String::new(), line, column)
+        Token::new(TokenType::EOF, "", line, column)
     }
 }
 
@@ -1,21 +1,37 @@
-use crate::ast::expr::Expr;
-use crate::ast::stmt::Stmt;
-use std::collections::HashMap;
+use crate::ast::expr::{Expr, StringPart};
+use crate::ast::stmt::{Stmt, DIVERGING_BUILTINS};
+use crate::const_fold::{folded_integer, is_constant_zero};
+use crate::error::ZenError;
+use crate::lexer::lexer::{parse_integer_literal, split_float_suffix};
+use crate::token::{Token, TokenType};
+use crate::types::{FloatKind, Type};
+use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 struct TypeInfo {
-    name: String,
+    name: Type,
     is_mutable: bool,
     scope_level: usize,
     is_initialized: bool,
+    // Declared with `const`, so it can never be assigned
+    is_const: bool,
+    // Index into `locals` for a `let` inside a function, whose reads are tracked
+    local: Option<usize>,
+}
+
+// A `let` binding inside a function, reported at the end if it is never read
+struct Local {
+    name: String,
+    token: Token,
+    is_used: bool,
 }
 
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 struct FunctionInfo {
-    params: Vec<(String, String)>,
-    return_type: String,
+    params: Vec<(String, Type)>,
+    return_type: Type,
     is_defined: bool,
     call_count: usize,
 }
@@ -23,9 +39,18 @@ struct FunctionInfo {
 pub struct TypeChecker {
     variables: HashMap<String, TypeInfo>,
     functions: HashMap<String, FunctionInfo>,
-    errors: Vec<String>,
+    structs: HashMap<String, Vec<(String, Type)>>,
+    errors: Vec<ZenError>,
     warnings: Vec<String>,
+    locals: Vec<Local>,
     scope_level: usize,
+    // One frame per open scope, listing each name declared in it with the binding it
+    // shadowed, so leaving the scope brings the outer bindings back
+    scopes: Vec<Vec<(String, Option<TypeInfo>)>>,
+    current_function: Option<(String, Type)>, // (name, return_type)
+    // Loops enclosing the statement being checked, for `break` and `continue`
+    loop_depth: usize,
+    entry: Option<String>,
 }
 
 impl Default for TypeChecker {
@@ -39,46 +64,109 @@ impl TypeChecker {
         let mut checker = TypeChecker {
             variables: HashMap::new(),
             functions: HashMap::new(),
+            structs: HashMap::new(),
             errors: Vec::new(),
             warnings: Vec::new(),
+            locals: Vec::new(),
             scope_level: 0,
+            scopes: Vec::new(),
+            current_function: None,
+            loop_depth: 0,
+            entry: None,
         };
 
         // Initialize built-in functions
         checker.functions.insert(
             "println".to_string(),
             FunctionInfo {
-                params: vec![("value".to_string(), "any".to_string())],
-                return_type: "void".to_string(),
+                params: vec![("value".to_string(), Type::Any)],
+                return_type: Type::Void,
                 is_defined: true,
                 call_count: 0,
             },
         );
 
+        // Diverging builtins never return, so their `void` result is never observed
+        for (name, params) in [
+            ("exit", vec![("code".to_string(), Type::I32)]),
+            ("abort", Vec::new()),
+            ("todo", Vec::new()),
+        ] {
+            checker.functions.insert(
+                name.to_string(),
+                FunctionInfo {
+                    params,
+                    return_type: Type::Void,
+                    is_defined: true,
+                    call_count: 0,
+                },
+            );
+        }
+
         checker
     }
 
-    pub fn check(&mut self, program: &crate::ast::program::Program) -> Result<(), String> {
-        // First pass: collect all function signatures
-        for stmt in &program.statements {
+    /// Require `entry` to be a function the generated C `main` can call
+    pub fn with_entry(mut self, entry: Option<String>) -> Self {
+        self.entry = entry;
+        self
+    }
+
+    pub fn check(&mut self, program: &crate::ast::program::Program) -> Result<(), Vec<ZenError>> {
+        // First pass: collect struct layouts, then all function signatures
+        let mut struct_order = Vec::new();
+        for stmt in program.items() {
+            if let Stmt::StructDecl {
+                name,
+                fields,
+                token,
+                ..
+            } = stmt
+            {
+                self.structs.insert(name.clone(), fields.clone());
+                struct_order.push((name.clone(), token));
+            }
+        }
+        self.check_struct_cycles(&struct_order);
+
+        for stmt in program.items() {
             if let Stmt::FunctionDecl {
                 name,
                 params,
                 return_type,
+                token,
                 ..
             } = stmt
             {
-                self.register_function(name, params, return_type)?;
+                self.register_function(name, params, return_type, token)
+                    .map_err(|e| vec![e])?;
             }
         }
+        if let Err(e) = self.check_entry() {
+            self.errors.push(e);
+        }
 
-        // Second pass: type check all statements
-        for stmt in &program.statements {
+        // Second pass: type check all statements, constants first so that functions
+        // declared before a constant can still refer to it
+        let (constants, others): (Vec<&Stmt>, Vec<&Stmt>) = program
+            .items()
+            .partition(|stmt| matches!(stmt, Stmt::ConstDecl { .. }));
+        for stmt in constants.into_iter().chain(others) {
+            let locals_before = self.locals.len();
             if let Err(e) = self.check_statement(stmt) {
                 self.errors.push(e);
+                // An error can leave scopes open; close them before the next item
+                while !self.scopes.is_empty() {
+                    self.exit_scope();
+                }
+                // Reads after the error were never checked, so its locals are not
+                // reported as unused
+                self.locals.truncate(locals_before);
             }
         }
 
+        self.warn_unused_locals();
+
         // Report results
         if !self.warnings.is_empty() {
             for warning in &self.warnings {
@@ -87,41 +175,94 @@ impl TypeChecker {
         }
 
         if !self.errors.is_empty() {
-            let error_summary = format!(
-                "Type checking failed with {} errors:\n{}",
-                self.errors.len(),
-                self.errors.join("\n")
-            );
-            return Err(error_summary);
+            return Err(std::mem::take(&mut self.errors));
         }
 
         Ok(())
     }
 
+    // The entry point takes no arguments and returns the exit status (or nothing, for 0)
+    fn check_entry(&self) -> Result<(), ZenError> {
+        let Some(entry) = &self.entry else {
+            return Ok(());
+        };
+        let Some(function) = self.functions.get(entry) else {
+            return Err(unlocated_error(format!(
+                "Entry point '{}' is not defined",
+                entry
+            )));
+        };
+        if !function.params.is_empty() || !matches!(function.return_type, Type::I32 | Type::Void) {
+            return Err(unlocated_error(format!(
+                "Entry point '{}' must take no parameters and return i32 or void",
+                entry
+            )));
+        }
+        if entry != "main" && self.functions.contains_key("main") {
+            return Err(unlocated_error(format!(
+                "Entry point '{}' conflicts with the function 'main'; rename it or drop --entry",
+                entry
+            )));
+        }
+        Ok(())
+    }
+
+    fn mark_read(&mut self, name: &str) {
+        if let Some(local) = self.variables.get(name).and_then(|info| info.local) {
+            self.locals[local].is_used = true;
+        }
+    }
+
+    // A leading underscore marks a binding as deliberately unused
+    fn warn_unused_locals(&mut self) {
+        for local in &self.locals {
+            if !local.is_used && !local.name.starts_with('_') {
+                self.warnings.push(format!(
+                    "Unused variable '{}' at line {}:{}; prefix it with '_' if this is intended",
+                    local.name, local.token.line, local.token.column
+                ));
+            }
+        }
+    }
+
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
     fn register_function(
         &mut self,
         name: &str,
-        params: &[(String, String)],
-        return_type: &str,
-    ) -> Result<(), String> {
+        params: &[(String, Type)],
+        return_type: &Type,
+        token: &Token,
+    ) -> Result<(), ZenError> {
         if self.functions.contains_key(name) && name != "println" {
-            return Err(format!("Function '{}' is already defined", name));
+            return Err(type_error(
+                token,
+                format!("Function '{}' is already defined", name),
+            ));
         }
 
         // Validate parameter types
         for (_param_name, param_type) in params {
             if !self.is_valid_type(param_type) {
-                return Err(format!(
-                    "Invalid parameter type '{}' in function '{}'",
-                    param_type, name
+                return Err(type_error(
+                    token,
+                    format!(
+                        "Invalid parameter type '{}' in function '{}'",
+                        param_type, name
+                    ),
                 ));
             }
         }
 
         if !self.is_valid_type(return_type) {
-            return Err(format!(
-                "Invalid return type '{}' in function '{}'",
-                return_type, name
+            return Err(type_error(
+                token,
+                format!(
+                    "Invalid return type '{}' in function '{}'",
+                    return_type, name
+                ),
             ));
         }
 
@@ -129,7 +270,7 @@ impl TypeChecker {
             name.to_string(),
             FunctionInfo {
                 params: params.to_vec(),
-                return_type: return_type.to_string(),
+                return_type: return_type.clone(),
                 is_defined: true,
                 call_count: 0,
             },
@@ -138,7 +279,250 @@ impl TypeChecker {
         Ok(())
     }
 
-    fn check_statement(&mut self, stmt: &Stmt) -> Result<(), String> {
+    // Arithmetic on a struct calls its operator function, e.g. `Vec2_add` for `+`;
+    // `==` and `!=` call `Vec2_eq`, which `#[derive(Eq)]` generates
+    fn check_operator_method(
+        &self,
+        struct_name: &str,
+        op: &Token,
+        left_type: &Type,
+        right_type: &Type,
+    ) -> Result<Type, ZenError> {
+        let method = format!(
+            "{}_{}",
+            struct_name,
+            op.kind.operator_method().unwrap_or_default()
+        );
+        let Some(function) = self.functions.get(&method) else {
+            let derive_hint = if op.kind.operator_method() == Some("eq") {
+                "add #[derive(Eq)] or "
+            } else {
+                ""
+            };
+            return Err(type_error(
+                op,
+                format!(
+                    "Binary '{}' is not defined for struct '{}'; {}define a function '{}'",
+                    op.lexeme, struct_name, derive_hint, method
+                ),
+            ));
+        };
+
+        let param_types: Vec<&Type> = function.params.iter().map(|(_, t)| t).collect();
+        if param_types != [left_type, right_type] {
+            return Err(type_error(
+                op,
+                format!(
+                    "Operator function '{}' must take ({}, {}) for binary '{}'",
+                    method, left_type, right_type, op.lexeme
+                ),
+            ));
+        }
+        Ok(function.return_type.clone())
+    }
+
+    // A struct that contains itself by value (directly or through other structs) has no finite size
+    fn check_struct_cycles(&mut self, struct_order: &[(String, &Token)]) {
+        let mut finished = HashSet::new();
+        for (name, token) in struct_order {
+            let mut path = Vec::new();
+            if let Some(cycle) = self.find_struct_cycle(name, &mut path, &mut finished) {
+                self.errors.push(type_error(token, format!(
+                    "Recursive struct '{}' has infinite size ({}); use a reference or pointer field to break the cycle",
+                    cycle[0],
+                    cycle.join(" -> ")
+                )));
+            }
+        }
+    }
+
+    fn find_struct_cycle(
+        &self,
+        name: &str,
+        path: &mut Vec<String>,
+        finished: &mut HashSet<String>,
+    ) -> Option<Vec<String>> {
+        if let Some(start) = path.iter().position(|n| n == name) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(name.to_string());
+            return Some(cycle);
+        }
+        if finished.contains(name) {
+            return None;
+        }
+        let fields = self.structs.get(name)?;
+
+        path.push(name.to_string());
+        for (_field_name, field_type) in fields {
+            if let Some(inner) = contained_struct(field_type) {
+                if let Some(cycle) = self.find_struct_cycle(inner, path, finished) {
+                    // Mark every struct on the cycle so it is reported only once
+                    finished.extend(cycle.iter().cloned());
+                    return Some(cycle);
+                }
+            }
+        }
+        path.pop();
+        finished.insert(name.to_string());
+        None
+    }
+
+    fn check_body(&mut self, body: &[Stmt]) -> Result<(), ZenError> {
+        self.check_statements(body, false).map(|_| ())
+    }
+
+    // When `yields_value`, the body's trailing value (see `Stmt::tail_value`) is its result
+    // rather than a discarded value, and its type is returned
+    fn check_statements(
+        &mut self,
+        body: &[Stmt],
+        yields_value: bool,
+    ) -> Result<Option<Type>, ZenError> {
+        let mut unreachable = false;
+        let mut tail_type = None;
+        for (i, stmt) in body.iter().enumerate() {
+            match stmt
+                .tail_value()
+                .filter(|_| yields_value && i + 1 == body.len())
+            {
+                Some(value) => tail_type = Some(self.infer_expression_type(&value)?),
+                None => {
+                    self.check_statement(stmt)?;
+                    self.warn_discarded_result(stmt);
+                }
+            }
+            // Dead code is still checked, but only reported once per body
+            if !unreachable && i + 1 < body.len() {
+                if let Some((keyword, token)) = exit_point(stmt) {
+                    unreachable = true;
+                    self.warnings.push(format!(
+                        "Unreachable code after '{}' at line {}:{}",
+                        keyword, token.line, token.column
+                    ));
+                }
+            }
+            if let Some((name, token)) = stmt.diverging_call() {
+                if i + 1 < body.len() {
+                    self.warnings.push(format!(
+                        "Unreachable code after call to '{}' at line {}:{}",
+                        name, token.line, token.column
+                    ));
+                }
+                break;
+            }
+        }
+        Ok(tail_type)
+    }
+
+    // A call statement throwing away what a non-void function returns
+    fn warn_discarded_result(&mut self, stmt: &Stmt) {
+        let Stmt::ExprStmt {
+            expr: Expr::Call { callee, .. },
+        } = stmt
+        else {
+            return;
+        };
+        let Expr::Identifier { name, token } = callee.as_ref() else {
+            return;
+        };
+        let return_type = match self.variables.get(name) {
+            Some(info) => match &info.name {
+                Type::Function(_, return_type) => return_type.as_ref(),
+                _ => return,
+            },
+            None => match self.functions.get(name) {
+                Some(function) => &function.return_type,
+                None => return,
+            },
+        };
+        if *return_type != Type::Void {
+            self.warnings.push(format!(
+                "Unused result of type '{}' from call to '{}' at line {}:{}",
+                return_type, name, token.line, token.column
+            ));
+        }
+    }
+
+    fn enter_scope(&mut self) {
+        self.scope_level += 1;
+        self.scopes.push(Vec::new());
+    }
+
+    fn exit_scope(&mut self) {
+        for (name, shadowed) in self.scopes.pop().unwrap_or_default().into_iter().rev() {
+            match shadowed {
+                Some(info) => self.variables.insert(name, info),
+                None => self.variables.remove(&name),
+            };
+        }
+        self.scope_level -= 1;
+    }
+
+    // Bind `name` in the innermost scope, shadowing any outer binding until it closes
+    fn declare(&mut self, name: String, info: TypeInfo) {
+        let shadowed = self.variables.insert(name.clone(), info);
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push((name, shadowed));
+        }
+    }
+
+    fn check_loop_body(&mut self, body: &[Stmt]) -> Result<(), ZenError> {
+        self.loop_depth += 1;
+        let result = self.check_body(body);
+        self.loop_depth -= 1;
+        result
+    }
+
+    // A non-void function must return on every path; the body's trailing value, of type
+    // `tail_type`, is the returned value when control reaches the end of the body
+    fn check_falls_through(
+        &mut self,
+        name: &str,
+        return_type: &Type,
+        body: &[Stmt],
+        tail_type: Option<Type>,
+        token: &Token,
+    ) -> Result<(), ZenError> {
+        if *return_type == Type::Void || Stmt::body_always_returns(body) {
+            return Ok(());
+        }
+        if let Some(value_type) = tail_type.filter(|t| *t != Type::Void) {
+            return check_return_type(name, return_type, &value_type, token);
+        }
+        Err(type_error(
+            token,
+            format!(
+                "Function '{}' must return a value of type '{}' on every path, but can reach the end of its body",
+                name, return_type
+            ),
+        ))
+    }
+
+    fn check_returned_value(
+        &mut self,
+        name: &str,
+        return_type: &Type,
+        value: &Expr,
+        token: &Token,
+    ) -> Result<(), ZenError> {
+        if matches!(value, Expr::NullLiteral { .. }) {
+            if matches!(return_type, Type::Nullable(_)) {
+                return Ok(());
+            }
+            return Err(type_error(
+                token,
+                format!(
+                    "Function '{}' returns non-nullable type '{}' but null is returned",
+                    name, return_type
+                ),
+            ));
+        }
+        let value_type = self.infer_expression_type(value)?;
+        self.check_integer_constant(return_type, value);
+        check_return_type(name, return_type, &value_type, token)
+    }
+
+    fn check_statement(&mut self, stmt: &Stmt) -> Result<(), ZenError> {
         match stmt {
             Stmt::VariableDecl {
                 name,
@@ -149,28 +533,70 @@ impl TypeChecker {
             } => {
                 let var_type = if let Some(t) = type_annotation {
                     if !self.is_valid_type(t) {
-                        return Err(format!(
-                            "Invalid type '{}' at line {}:{}",
-                            t, token.line, token.column
+                        return Err(type_error(token, format!("Invalid type '{}'", t)));
+                    }
+                    if let Some(init) = initializer {
+                        self.check_f32_literal(t, init);
+                        self.check_integer_constant(t, init);
+                    }
+                    if matches!(initializer, Some(Expr::NullLiteral { .. }))
+                        && !matches!(t, Type::Nullable(_))
+                    {
+                        return Err(type_error(
+                            token,
+                            format!("Cannot initialize non-nullable type '{}' with null", t),
                         ));
                     }
+                    if let Some(init) = initializer
+                        .as_ref()
+                        .filter(|init| !matches!(init, Expr::NullLiteral { .. }))
+                    {
+                        let init_type = self.infer_expression_type(init)?;
+                        if !initializer_fits(t, &init_type) {
+                            return Err(type_mismatch(
+                                token,
+                                format!(
+                                    "Cannot initialize '{}' with a value of type '{}'",
+                                    t, init_type
+                                ),
+                                t,
+                                &init_type,
+                            ));
+                        }
+                    }
                     t.clone()
                 } else if let Some(init) = initializer {
-                    self.infer_expression_type(init)?
+                    let init_type = self.infer_expression_type(init)?;
+                    self.check_integer_constant(&init_type, init);
+                    init_type
                 } else {
-                    return Err(format!(
-                        "Variable '{}' must have type annotation or initializer",
-                        name
+                    return Err(type_error(
+                        token,
+                        format!(
+                            "Variable '{}' must have type annotation or initializer",
+                            name
+                        ),
                     ));
                 };
 
-                self.variables.insert(
+                // Top-level lets are globals, which other modules may read
+                let local = self.current_function.is_some().then(|| {
+                    self.locals.push(Local {
+                        name: name.clone(),
+                        token: token.clone(),
+                        is_used: false,
+                    });
+                    self.locals.len() - 1
+                });
+                self.declare(
                     name.clone(),
                     TypeInfo {
                         name: var_type,
                         is_mutable: *is_mutable,
                         scope_level: self.scope_level,
                         is_initialized: initializer.is_some(),
+                        is_const: false,
+                        local,
                     },
                 );
             }
@@ -180,83 +606,376 @@ impl TypeChecker {
                 then_branch,
                 else_if_branches,
                 else_branch,
-                ..
+                token,
             } => {
                 // Check main condition
                 let condition_type = self.infer_expression_type(condition)?;
-                if condition_type != "bool" {
-                    return Err(format!(
-                        "If condition must be boolean, got '{}'",
-                        condition_type
+                if condition_type != Type::Bool {
+                    return Err(type_mismatch(
+                        token,
+                        format!("If condition must be boolean, got '{}'", condition_type),
+                        &Type::Bool,
+                        &condition_type,
                     ));
                 }
 
                 // Check then branch
-                self.scope_level += 1;
-                for stmt in then_branch {
-                    self.check_statement(stmt)?;
-                }
-                self.scope_level -= 1;
+                self.enter_scope();
+                self.check_body(then_branch)?;
+                self.exit_scope();
 
                 // Check all else if branches
                 for else_if_branch in else_if_branches {
                     let else_if_condition_type =
                         self.infer_expression_type(&else_if_branch.condition)?;
-                    if else_if_condition_type != "bool" {
-                        return Err(format!(
-                            "Else if condition must be boolean, got '{}'",
-                            else_if_condition_type
+                    if else_if_condition_type != Type::Bool {
+                        return Err(type_mismatch(
+                            &else_if_branch.token,
+                            format!(
+                                "Else if condition must be boolean, got '{}'",
+                                else_if_condition_type
+                            ),
+                            &Type::Bool,
+                            &else_if_condition_type,
                         ));
                     }
 
-                    self.scope_level += 1;
-                    for stmt in &else_if_branch.body {
-                        self.check_statement(stmt)?;
-                    }
-                    self.scope_level -= 1;
+                    self.enter_scope();
+                    self.check_body(&else_if_branch.body)?;
+                    self.exit_scope();
                 }
 
                 // Check else branch if present
                 if let Some(else_stmts) = else_branch {
-                    self.scope_level += 1;
-                    for stmt in else_stmts {
-                        self.check_statement(stmt)?;
+                    self.enter_scope();
+                    self.check_body(else_stmts)?;
+                    self.exit_scope();
+                }
+            }
+
+            Stmt::Match {
+                value,
+                arms,
+                default,
+                token,
+            } => {
+                let value_type = self.infer_expression_type(value)?;
+                check_match_value(&value_type, token)?;
+                for (pattern, body) in arms {
+                    let pattern_type = self.infer_expression_type(pattern)?;
+                    if pattern_type != value_type {
+                        return Err(type_error(
+                            token,
+                            format!(
+                                "Match pattern of type '{}' does not match value of type '{}'",
+                                pattern_type, value_type
+                            ),
+                        ));
                     }
-                    self.scope_level -= 1;
+                    self.enter_scope();
+                    self.check_body(body)?;
+                    self.exit_scope();
+                }
+                if let Some(default_body) = default {
+                    self.enter_scope();
+                    self.check_body(default_body)?;
+                    self.exit_scope();
                 }
             }
 
             Stmt::FunctionDecl {
-                name: _,
+                name,
+                type_params,
                 params,
+                return_type,
                 body,
+                token,
                 ..
             } => {
+                // Type arguments are inferred from the call's arguments
+                if let Some(unused) = type_params
+                    .iter()
+                    .find(|param| !params.iter().any(|(_, t)| t.uses_param(param)))
+                {
+                    return Err(type_error(token, format!(
+                        "Type parameter '{}' of function '{}' is not used by any parameter, so it cannot be inferred",
+                        unused, name
+                    )));
+                }
+
                 // Enter function scope
-                self.scope_level += 1;
+                self.enter_scope();
+                self.current_function = Some((name.clone(), return_type.clone()));
 
                 // Add parameters to scope
                 for (param_name, param_type) in params {
-                    self.variables.insert(
+                    self.declare(
                         param_name.clone(),
                         TypeInfo {
                             name: param_type.clone(),
                             is_mutable: false,
                             scope_level: self.scope_level,
                             is_initialized: true,
+                            is_const: false,
+                            local: None,
                         },
                     );
                 }
 
                 // Check function body
-                for stmt in body {
-                    self.check_statement(stmt)?;
-                }
+                let tail_type = self.check_statements(body, *return_type != Type::Void)?;
+                self.check_falls_through(name, return_type, body, tail_type, token)?;
 
                 // Exit function scope
-                self.variables
-                    .retain(|_, info| info.scope_level < self.scope_level);
-                self.scope_level -= 1;
+                self.exit_scope();
+                self.current_function = None;
+            }
+
+            Stmt::Return { value, token } => {
+                if let Some((name, return_type)) = self.current_function.clone() {
+                    match value {
+                        Some(_) if return_type == Type::Void => {
+                            let message =
+                                format!("Function '{}' returns void but a value is returned", name);
+                            return Err(type_error(token, message));
+                        }
+                        None if return_type != Type::Void => {
+                            return Err(type_error(
+                                token,
+                                format!(
+                                    "Function '{}' must return a value of type '{}'",
+                                    name, return_type
+                                ),
+                            ));
+                        }
+                        Some(value) => {
+                            self.check_returned_value(&name, &return_type, value, token)?
+                        }
+                        None => {}
+                    }
+                }
+            }
+
+            Stmt::ConstDecl {
+                name,
+                type_annotation,
+                initializer,
+                token,
+                ..
+            } => {
+                let Some(const_type) = type_annotation else {
+                    return Err(type_error(
+                        token,
+                        format!("Constant '{}' must have a type annotation", name),
+                    ));
+                };
+                if !(const_type.is_copy() || *const_type == Type::Str) {
+                    return Err(type_error(
+                        token,
+                        format!(
+                            "Constant '{}' must have a scalar or str type, found '{}'",
+                            name, const_type
+                        ),
+                    ));
+                }
+                if !self.is_constant_expression(initializer) {
+                    let message = format!(
+                        "Initializer of constant '{}' must be a constant expression",
+                        name
+                    );
+                    return Err(type_error(token, message));
+                }
+                let init_type = self.infer_expression_type(initializer)?;
+                if !initializer_fits(const_type, &init_type) {
+                    return Err(type_mismatch(
+                        token,
+                        format!(
+                            "Cannot initialize '{}' with a value of type '{}'",
+                            const_type, init_type
+                        ),
+                        const_type,
+                        &init_type,
+                    ));
+                }
+                self.check_f32_literal(const_type, initializer);
+                self.check_integer_constant(const_type, initializer);
+
+                self.declare(
+                    name.clone(),
+                    TypeInfo {
+                        name: const_type.clone(),
+                        is_mutable: false,
+                        scope_level: self.scope_level,
+                        is_initialized: true,
+                        is_const: true,
+                        local: None,
+                    },
+                );
+            }
+
+            Stmt::Assignment {
+                target: Expr::Identifier { name, .. },
+                value,
+                token,
+            } => {
+                if self.variables.get(name).is_some_and(|info| info.is_const) {
+                    return Err(type_error(
+                        token,
+                        format!("Cannot assign to constant '{}'", name),
+                    ));
+                }
+                if token.kind.compound_operator().is_some() {
+                    self.check_compound_assignment(name, token)?;
+                }
+                let target_type = self.variables.get(name).map(|info| info.name.clone());
+                if matches!(value, Expr::NullLiteral { .. }) {
+                    if let Some(target_type) = target_type
+                        .as_ref()
+                        .filter(|t| !matches!(t, Type::Nullable(_)))
+                    {
+                        return Err(type_error(
+                            token,
+                            format!(
+                                "Cannot assign null to '{}' of non-nullable type '{}'",
+                                name, target_type
+                            ),
+                        ));
+                    }
+                } else {
+                    self.infer_expression_type(value)?;
+                }
+                if let Some(target_type) = target_type {
+                    self.check_f32_literal(&target_type, value);
+                    self.check_integer_constant(&target_type, value);
+                }
+            }
+
+            Stmt::Assignment {
+                target: Expr::Deref { expr, .. },
+                value,
+                token,
+            } => {
+                self.infer_expression_type(value)?;
+                let pointer_type = self.infer_expression_type(expr)?;
+                if !matches!(pointer_type, Type::Ref(_, true)) {
+                    let message = format!(
+                        "Cannot assign through '{}'; a '&mut' reference is required",
+                        pointer_type
+                    );
+                    return Err(type_error(token, message));
+                }
+            }
+
+            Stmt::Assignment {
+                target: target @ (Expr::ArrayAccess { .. } | Expr::FieldAccess { .. }),
+                value,
+                token,
+            } => {
+                let element_type = self.infer_expression_type(target)?;
+                let value_type = self.infer_expression_type(value)?;
+                if element_type != Type::Unknown && !same_value_kind(&element_type, &value_type) {
+                    let place = match target {
+                        Expr::FieldAccess { field, .. } => format!("field '{}'", field),
+                        _ => "an element".to_string(),
+                    };
+                    return Err(type_mismatch(
+                        token,
+                        format!(
+                            "Cannot assign '{}' to {} of type '{}'",
+                            value_type, place, element_type
+                        ),
+                        &element_type,
+                        &value_type,
+                    ));
+                }
+            }
+
+            Stmt::ForIn {
+                variable,
+                iterable,
+                body,
+                token,
+            } => {
+                let iterable_type = self.infer_expression_type(iterable)?;
+                let element_type = match iterable_type.pointee().unwrap_or(&iterable_type) {
+                    Type::Array(element, _) => element.as_ref().clone(),
+                    _ => {
+                        return Err(type_error(
+                            token,
+                            format!("Cannot iterate over '{}'; expected an array", iterable_type),
+                        ));
+                    }
+                };
+
+                self.enter_scope();
+                self.declare(
+                    variable.clone(),
+                    TypeInfo {
+                        name: element_type,
+                        is_mutable: false,
+                        scope_level: self.scope_level,
+                        is_initialized: true,
+                        is_const: false,
+                        local: None,
+                    },
+                );
+                self.check_loop_body(body)?;
+                self.exit_scope();
+            }
+
+            Stmt::While {
+                condition, body, ..
+            } => {
+                self.infer_expression_type(condition)?;
+                self.enter_scope();
+                self.check_loop_body(body)?;
+                self.exit_scope();
+            }
+
+            Stmt::Loop { body, .. } => {
+                self.enter_scope();
+                self.check_loop_body(body)?;
+                self.exit_scope();
+            }
+
+            Stmt::Break { token } | Stmt::Continue { token } if self.loop_depth == 0 => {
+                return Err(type_error(
+                    token,
+                    format!("'{}' outside of a loop", token.lexeme),
+                ));
+            }
+
+            Stmt::For {
+                init,
+                condition,
+                increment,
+                body,
+                ..
+            } => {
+                self.enter_scope();
+                if let Some(init_stmt) = init {
+                    let init_stmt =
+                        init_stmt.resolve_for_init(|name| self.variables.contains_key(name));
+                    self.check_statement(&init_stmt)?;
+                }
+                if let Some(condition) = condition {
+                    self.infer_expression_type(condition)?;
+                }
+                if let Some(increment) = increment {
+                    self.infer_expression_type(increment)?;
+                }
+                self.check_loop_body(body)?;
+                self.exit_scope();
+            }
+
+            Stmt::ExprStmt { expr } => {
+                self.infer_expression_type(expr)?;
+            }
+
+            Stmt::Block { statements } => {
+                self.enter_scope();
+                self.check_body(statements)?;
+                self.exit_scope();
             }
 
             _ => {
@@ -266,80 +985,1844 @@ impl TypeChecker {
         Ok(())
     }
 
-    fn infer_expression_type(&mut self, expr: &Expr) -> Result<String, String> {
+    // Literals, other constants, and operators applied to them; codegen folds these into
+    // the constant's global
+    fn is_constant_expression(&self, expr: &Expr) -> bool {
         match expr {
-            Expr::IntegerLiteral { .. } => Ok("i32".to_string()),
-            Expr::FloatLiteral { .. } => Ok("f64".to_string()),
-            Expr::BooleanLiteral { .. } => Ok("bool".to_string()),
-            Expr::CharLiteral { .. } => Ok("char".to_string()),
-            Expr::StringLiteral { .. } => Ok("str".to_string()),
+            Expr::IntegerLiteral { .. }
+            | Expr::FloatLiteral { .. }
+            | Expr::BooleanLiteral { .. }
+            | Expr::CharLiteral { .. }
+            | Expr::StringLiteral { .. } => true,
             Expr::Identifier { name, .. } => {
-                if let Some(var_info) = self.variables.get(name) {
-                    Ok(var_info.name.clone())
-                } else {
-                    Err(format!("Undefined variable '{}'", name))
-                }
+                self.variables.get(name).is_some_and(|info| info.is_const)
             }
+            Expr::UnaryOp { operand, .. } => self.is_constant_expression(operand),
             Expr::BinaryOp { op, left, right } => {
-                let left_type = self.infer_expression_type(left)?;
-                let right_type = self.infer_expression_type(right)?;
+                !op.kind.is_assignment()
+                    && self.is_constant_expression(left)
+                    && self.is_constant_expression(right)
+            }
+            _ => false,
+        }
+    }
 
-                match op.kind {
-                    // Comparison operators return bool
-                    crate::token::TokenType::EqualEqual
-                    | crate::token::TokenType::NotEqual
-                    | crate::token::TokenType::LessThan
-                    | crate::token::TokenType::LessEqual
-                    | crate::token::TokenType::GreaterThan
-                    | crate::token::TokenType::GreaterEqual => Ok("bool".to_string()),
+    // `object.method(args)` calls `Struct_method` with a pointer to `object` as `self`
+    fn check_method_call(
+        &mut self,
+        object: &Expr,
+        method: &str,
+        token: &Token,
+        args: &[Expr],
+    ) -> Result<Type, ZenError> {
+        let object_type = self.infer_expression_type(object)?;
+        let Type::Struct(struct_name) = object_type.pointee().unwrap_or(&object_type) else {
+            let message = format!(
+                "Cannot call method '{}' on non-struct type '{}'",
+                method, object_type
+            );
+            return Err(type_error(token, message));
+        };
+        let Some(function) = self
+            .functions
+            .get(&format!("{}_{}", struct_name, method))
+            .filter(
+                |function| matches!(function.params.first(), Some((name, _)) if name == "self"),
+            )
+        else {
+            let message = format!("No method '{}' on struct '{}'", method, struct_name);
+            return Err(type_error(token, message));
+        };
+        let params: Vec<Type> = function.params[1..]
+            .iter()
+            .map(|(_, t)| t.clone())
+            .collect();
+        let return_type = function.return_type.clone();
+        self.check_arguments(method, token, &params, args)?;
+        Ok(return_type)
+    }
 
-                    // Logical operators return bool
-                    crate::token::TokenType::And | crate::token::TokenType::Or => {
-                        Ok("bool".to_string())
-                    }
+    // A call through a function pointer or a method takes exactly the arguments listed
+    fn check_arguments(
+        &mut self,
+        name: &str,
+        token: &Token,
+        params: &[Type],
+        args: &[Expr],
+    ) -> Result<(), ZenError> {
+        if params.len() != args.len() {
+            return Err(type_error(
+                token,
+                format!(
+                    "'{}' expects {} arguments, got {}",
+                    name,
+                    params.len(),
+                    args.len()
+                ),
+            ));
+        }
+        for (param_type, arg) in params.iter().zip(args) {
+            let arg_type = self.infer_expression_type(arg)?;
+            if !initializer_fits(param_type, &arg_type) {
+                let message = format!(
+                    "Argument of '{}' expects '{}', got '{}'",
+                    name, param_type, arg_type
+                );
+                return Err(type_mismatch(token, message, param_type, &arg_type));
+            }
+            self.check_integer_constant(param_type, arg);
+        }
+        Ok(())
+    }
 
-                    // Arithmetic operators return the operand type (simplified)
-                    crate::token::TokenType::Plus
-                    | crate::token::TokenType::Minus
-                    | crate::token::TokenType::Star
-                    | crate::token::TokenType::Slash
-                    | crate::token::TokenType::Percent => {
-                        if left_type == right_type {
-                            Ok(left_type)
+    // `x += e` reads and writes `x`, so it must be a mutable number
+    fn check_compound_assignment(&self, name: &str, token: &Token) -> Result<(), ZenError> {
+        let Some(info) = self.variables.get(name) else {
+            return Err(type_error(token, format!("Undefined variable '{}'", name)));
+        };
+        if !info.is_mutable {
+            return Err(type_error(
+                token,
+                format!(
+                    "Cannot apply '{}' to immutable variable '{}'; declare it with 'let mut'",
+                    token.lexeme, name
+                ),
+            ));
+        }
+        if !info.name.is_integer() && !info.name.is_float() {
+            return Err(type_error(
+                token,
+                format!(
+                    "Cannot apply '{}' to '{}' of type '{}'; a numeric variable is required",
+                    token.lexeme, name, info.name
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    // `assert_eq(a, b)` compares two values that print with one format: integers (the right
+    // side is converted to the left's type), or two bools, chars, floats or strings
+    fn check_assert_eq(&mut self, args: &[Expr], token: &Token) -> Result<Type, ZenError> {
+        let [left, right] = args else {
+            return Err(type_error(
+                token,
+                format!("assert_eq expects 2 arguments, got {}", args.len()),
+            ));
+        };
+        let left_type = self.infer_expression_type(left)?;
+        let right_type = self.infer_expression_type(right)?;
+        let comparable = match (&left_type, &right_type) {
+            (Type::Int(_), Type::Int(_)) => true,
+            (Type::Bool | Type::Char | Type::Float(_) | Type::Str, _) => left_type == right_type,
+            _ => false,
+        };
+        if !comparable {
+            return Err(type_error(
+                token,
+                format!(
+                    "assert_eq cannot compare '{}' with '{}'",
+                    left_type, right_type
+                ),
+            ));
+        }
+        Ok(Type::Void)
+    }
+
+    // An unsuffixed float literal stored as f32 is rounded; warn when that changes its value
+    fn check_f32_literal(&mut self, target_type: &Type, value: &Expr) {
+        if *target_type != Type::Float(FloatKind::F32) {
+            return;
+        }
+        let literal = match value {
+            Expr::UnaryOp { op, operand } if op.kind == TokenType::Minus => operand,
+            _ => value,
+        };
+        if let Expr::FloatLiteral { value, token } = literal {
+            let rounded = *value as f32;
+            if split_float_suffix(&token.lexeme).1.is_none() && rounded as f64 != *value {
+                self.warnings.push(format!(
+                    "Float literal '{}' is not exactly representable as f32 and will be rounded to {} at line {}:{}",
+                    token.lexeme, rounded as f64, token.line, token.column
+                ));
+            }
+        }
+    }
+
+    // Integer arithmetic on literals is folded to its exact value, which wraps when it
+    // is stored as a type too narrow for it; warn when that happens
+    fn check_integer_constant(&mut self, target_type: &Type, value: &Expr) {
+        let (Type::Int(kind), Expr::BinaryOp { op, .. } | Expr::UnaryOp { op, .. }) =
+            (target_type, value)
+        else {
+            return;
+        };
+        if let Some(result) = folded_integer(value).filter(|result| !kind.contains(*result)) {
+            self.warnings.push(format!(
+                "Constant expression overflows {} (the exact result is {}) at line {}:{}; it wraps at run time",
+                kind.name(), result, op.line, op.column
+            ));
+        }
+    }
+
+    fn infer_expression_type(&mut self, expr: &Expr) -> Result<Type, ZenError> {
+        match expr {
+            Expr::IntegerLiteral { .. } => Ok(Type::I32),
+            Expr::FloatLiteral { token, .. } => {
+                let (_, suffix) = split_float_suffix(&token.lexeme);
+                Ok(Type::Float(suffix.unwrap_or(FloatKind::F64)))
+            }
+            Expr::BooleanLiteral { .. } => Ok(Type::Bool),
+            Expr::CharLiteral { value, token } => {
+                if u32::from(*value) > 255 {
+                    return Err(type_error(
+                        token,
+                        format!(
+                            "Char literal '{}' (U+{:04X}) does not fit in a byte",
+                            value,
+                            u32::from(*value)
+                        ),
+                    ));
+                }
+                Ok(Type::Char)
+            }
+            Expr::StringLiteral { .. } => Ok(Type::Str),
+            Expr::InterpolatedString { parts, token } => {
+                for part in parts {
+                    let part_type = match part {
+                        StringPart::Text(_) => continue,
+                        StringPart::Variable(name) => {
+                            self.infer_expression_type(&Expr::Identifier {
+                                name: name.clone(),
+                                token: token.clone(),
+                            })?
+                        }
+                        StringPart::Expr(expr) => self.infer_expression_type(expr)?,
+                    };
+                    let printable = part_type.is_integer()
+                        || part_type.is_float()
+                        || matches!(
+                            part_type,
+                            Type::Bool | Type::Char | Type::Str | Type::Unknown
+                        );
+                    if !printable {
+                        return Err(type_error(
+                            token,
+                            format!("Cannot interpolate a value of type '{}'", part_type),
+                        ));
+                    }
+                }
+                Ok(Type::Str)
+            }
+            Expr::Identifier { name, token } => {
+                if let Some(var_info) = self.variables.get(name) {
+                    let var_type = var_info.name.clone();
+                    self.mark_read(name);
+                    Ok(var_type)
+                } else if let Some(function) = self.functions.get(name) {
+                    // A bare function name is a pointer to it
+                    let function_type = Type::Function(
+                        function.params.iter().map(|(_, t)| t.clone()).collect(),
+                        Box::new(function.return_type.clone()),
+                    );
+                    // Builtins are expanded inline and generic functions only exist as
+                    // instances, so neither has an address
+                    if function_type.is_generic()
+                        || name == "println"
+                        || DIVERGING_BUILTINS.contains(&name.as_str())
+                    {
+                        return Err(type_error(
+                            token,
+                            format!("Function '{}' cannot be used as a value", name),
+                        ));
+                    }
+                    Ok(function_type)
+                } else {
+                    Err(type_error(token, format!("Undefined variable '{}'", name)))
+                }
+            }
+            Expr::BinaryOp { op, left, right } => {
+                // `null` compares against any nullable reference, taking its type
+                if matches!(op.kind, TokenType::EqualEqual | TokenType::NotEqual) {
+                    let other = match (left.as_ref(), right.as_ref()) {
+                        (Expr::NullLiteral { .. }, other) | (other, Expr::NullLiteral { .. }) => {
+                            Some(other)
+                        }
+                        _ => None,
+                    };
+                    if let Some(other) = other {
+                        let other_type = self.infer_expression_type(other)?;
+                        if !matches!(other_type, Type::Nullable(_)) {
+                            return Err(type_error(
+                                op,
+                                format!(
+                                    "Cannot compare non-nullable type '{}' with null",
+                                    other_type
+                                ),
+                            ));
+                        }
+                        return Ok(Type::Bool);
+                    }
+                }
+
+                let left_type = self.infer_expression_type(left)?;
+                let right_type = self.infer_expression_type(right)?;
+
+                match op.kind {
+                    // Struct equality calls the struct's `_eq` function
+                    TokenType::EqualEqual | TokenType::NotEqual
+                        if matches!(left_type, Type::Struct(_)) =>
+                    {
+                        let Type::Struct(struct_name) = &left_type else {
+                            unreachable!()
+                        };
+                        self.check_operator_method(struct_name, op, &left_type, &right_type)?;
+                        Ok(Type::Bool)
+                    }
+
+                    // Comparison operators return bool
+                    TokenType::EqualEqual
+                    | TokenType::NotEqual
+                    | TokenType::LessThan
+                    | TokenType::LessEqual
+                    | TokenType::GreaterThan
+                    | TokenType::GreaterEqual => Ok(Type::Bool),
+
+                    // Logical operators return bool
+                    TokenType::And | TokenType::Or => Ok(Type::Bool),
+
+                    // Arithmetic operators return the operand type (simplified)
+                    TokenType::Plus
+                    | TokenType::Minus
+                    | TokenType::Star
+                    | TokenType::Slash
+                    | TokenType::Percent => {
+                        if let Some(param) = [&left_type, &right_type]
+                            .into_iter()
+                            .find(|ty| matches!(ty, Type::Param(_)))
+                        {
+                            let message = format!(
+                                "Binary '{}' is not defined for type parameter '{}'",
+                                op.lexeme, param
+                            );
+                            return Err(type_error(op, message));
+                        }
+                        if let Type::Struct(struct_name) = &left_type {
+                            return self.check_operator_method(
+                                struct_name,
+                                op,
+                                &left_type,
+                                &right_type,
+                            );
+                        }
+                        if matches!(op.kind, TokenType::Slash | TokenType::Percent)
+                            && right_type.is_integer()
+                            && is_constant_zero(right)
+                        {
+                            let operation = if op.kind == TokenType::Slash {
+                                "Division"
+                            } else {
+                                "Remainder"
+                            };
+                            return Err(type_error(
+                                op,
+                                format!("{} by a constant zero", operation),
+                            ));
+                        }
+                        if left_type == right_type {
+                            Ok(left_type)
+                        } else {
+                            Ok(Type::I32) // Default to i32
+                        }
+                    }
+
+                    // Exponentiation is numeric only; a float on either side makes it f64
+                    TokenType::Caret => {
+                        let numeric = |ty: &Type| ty.is_integer() || ty.is_float();
+                        if !numeric(&left_type) || !numeric(&right_type) {
+                            return Err(type_error(
+                                op,
+                                format!(
+                                    "Binary '^' requires numeric operands, found '{}' and '{}'",
+                                    left_type, right_type
+                                ),
+                            ));
+                        }
+                        if left_type.is_float() || right_type.is_float() {
+                            Ok(Type::F64)
+                        } else {
+                            Ok(left_type)
+                        }
+                    }
+
+                    // Bitwise operators require integer operands
+                    TokenType::Pipe
+                    | TokenType::Ampersand
+                    | TokenType::CaretCaret
+                    | TokenType::ShiftLeft
+                    | TokenType::ShiftRight => {
+                        if !left_type.is_integer() || !right_type.is_integer() {
+                            return Err(type_error(
+                                op,
+                                format!(
+                                    "Binary '{}' requires integer operands, found '{}' and '{}'",
+                                    op.lexeme, left_type, right_type
+                                ),
+                            ));
+                        }
+                        if left_type == right_type {
+                            Ok(left_type)
                         } else {
-                            Ok("i32".to_string()) // Default to i32
+                            Ok(Type::I32)
                         }
                     }
 
-                    _ => Ok("unknown".to_string()),
+                    _ => Ok(Type::Unknown),
+                }
+            }
+            Expr::UnaryOp { op, operand } => {
+                self.infer_expression_type(operand)?;
+                match op.kind {
+                    TokenType::Bang => Ok(Type::Bool),
+                    _ => Ok(Type::I32),
+                }
+            }
+            Expr::Call { callee, args, .. } => {
+                if let Expr::Identifier { name, token } = callee.as_ref() {
+                    if name == "assert_eq" {
+                        return self.check_assert_eq(args, token);
+                    }
+                    if let Some(Type::Function(params, return_type)) =
+                        self.variables.get(name).map(|info| info.name.clone())
+                    {
+                        self.mark_read(name);
+                        self.check_arguments(name, token, &params, args)?;
+                        return Ok(*return_type);
+                    }
+                }
+                if let Expr::FieldAccess {
+                    object,
+                    field,
+                    token,
+                } = callee.as_ref()
+                {
+                    return self.check_method_call(object, field, token, args);
+                }
+                if let Expr::ModuleAccess {
+                    module,
+                    item,
+                    token,
+                } = callee.as_ref()
+                {
+                    return match self.functions.get(item) {
+                        Some(function) if !function.return_type.is_generic() => {
+                            Ok(function.return_type.clone())
+                        }
+                        Some(_) => Ok(Type::Unknown),
+                        None => Err(type_error(
+                            token,
+                            format!("Undefined function '{}::{}'", module, item),
+                        )),
+                    };
+                }
+                let generic_signature = match callee.as_ref() {
+                    Expr::Identifier { name, token } => self
+                        .functions
+                        .get(name)
+                        .filter(|function| function.params.iter().any(|(_, t)| t.is_generic()))
+                        .map(|function| {
+                            let signature = (function.params.clone(), function.return_type.clone());
+                            (name, token, signature)
+                        }),
+                    _ => None,
+                };
+                let Some((name, token, (params, return_type))) = generic_signature else {
+                    // Arguments must still be well-typed; a bare `null` takes the
+                    // parameter's type, which must be nullable
+                    let params = match callee.as_ref() {
+                        Expr::Identifier { name, .. } => self
+                            .functions
+                            .get(name)
+                            .map(|function| function.params.clone()),
+                        _ => None,
+                    };
+                    for (i, arg) in args.iter().enumerate() {
+                        let Expr::NullLiteral { token } = arg else {
+                            self.infer_expression_type(arg)?;
+                            continue;
+                        };
+                        if let Some((param, param_type)) = params
+                            .as_ref()
+                            .and_then(|params| params.get(i))
+                            .filter(|(_, t)| !matches!(t, Type::Nullable(_)))
+                        {
+                            return Err(type_error(
+                                token,
+                                format!(
+                                    "Cannot pass null as parameter '{}' of non-nullable type '{}'",
+                                    param, param_type
+                                ),
+                            ));
+                        }
+                    }
+                    // Builtins are still assumed to return i32
+                    let declared = match callee.as_ref() {
+                        Expr::Identifier { name, .. } => self
+                            .functions
+                            .get(name)
+                            .map(|function| function.return_type.clone()),
+                        _ => None,
+                    };
+                    return Ok(declared.unwrap_or(Type::I32));
+                };
+
+                // A generic function returns whatever its type parameters are bound to
+                let mut bindings = HashMap::new();
+                for ((_, param_type), arg) in params.iter().zip(args) {
+                    let arg_type = self.infer_expression_type(arg)?;
+                    if !param_type.bind(&arg_type, &mut bindings) {
+                        return Err(type_error(
+                            token,
+                            format!(
+                                "Conflicting types '{}' and '{}' for a type parameter of '{}'",
+                                param_type.substitute(&bindings),
+                                arg_type,
+                                name
+                            ),
+                        ));
+                    }
+                }
+                let return_type = return_type.substitute(&bindings);
+                if return_type.is_generic() {
+                    return Err(type_error(
+                        token,
+                        format!(
+                            "Cannot infer the return type '{}' of '{}' from its arguments",
+                            return_type, name
+                        ),
+                    ));
+                }
+                Ok(return_type)
+            }
+            Expr::Borrow {
+                expr, is_mutable, ..
+            } => {
+                let inner = self.infer_expression_type(expr)?;
+                Ok(Type::Ref(Box::new(inner), *is_mutable))
+            }
+            Expr::NullLiteral { token } => Err(type_error(
+                token,
+                "Cannot infer the type of null; annotate it with a '?T' type".to_string(),
+            )),
+            Expr::Deref { expr, token } => {
+                let pointer_type = self.infer_expression_type(expr)?;
+                // Dereferencing a nullable reference is checked at runtime
+                match pointer_type.pointee() {
+                    Some(inner) => Ok(inner.clone()),
+                    None => Err(type_error(
+                        token,
+                        format!("Cannot dereference non-reference type '{}'", pointer_type),
+                    )),
                 }
             }
-            Expr::UnaryOp { op, .. } => match op.kind {
-                crate::token::TokenType::Bang => Ok("bool".to_string()),
-                _ => Ok("i32".to_string()),
+            Expr::Cast {
+                expr,
+                target_type,
+                token,
+            } => {
+                let source_type = self.infer_expression_type(expr)?;
+                if !is_valid_cast(&source_type, target_type) {
+                    return Err(type_error(
+                        token,
+                        format!("Cannot cast '{}' to '{}'", source_type, target_type),
+                    ));
+                }
+                Ok(target_type.clone())
+            }
+            Expr::ArrayRepeat { value, count, .. } => {
+                let element_type = self.infer_expression_type(value)?;
+                Ok(Type::Array(Box::new(element_type), *count))
+            }
+            Expr::IfExpr {
+                condition,
+                then_expr,
+                else_expr,
+                token,
+            } => {
+                let condition_type = self.infer_expression_type(condition)?;
+                if condition_type != Type::Bool {
+                    return Err(type_mismatch(
+                        token,
+                        format!("If condition must be boolean, got '{}'", condition_type),
+                        &Type::Bool,
+                        &condition_type,
+                    ));
+                }
+                let then_type = self.infer_expression_type(then_expr)?;
+                let else_type = self.infer_expression_type(else_expr)?;
+                if then_type != else_type {
+                    let message = format!(
+                        "If branches have incompatible types '{}' and '{}'",
+                        then_type, else_type
+                    );
+                    return Err(type_mismatch(token, message, &then_type, &else_type));
+                }
+                Ok(then_type)
+            }
+            Expr::Block {
+                statements, tail, ..
+            } => {
+                // Names declared in the block are gone once it yields its value
+                self.enter_scope();
+                let result = self
+                    .check_body(statements)
+                    .and_then(|_| self.infer_expression_type(tail));
+                self.exit_scope();
+                result
+            }
+            Expr::Match {
+                value,
+                arms,
+                default,
+                token,
+            } => {
+                let value_type = self.infer_expression_type(value)?;
+                check_match_value(&value_type, token)?;
+                for (pattern, _) in arms {
+                    let pattern_type = self.infer_expression_type(pattern)?;
+                    if pattern_type != value_type {
+                        return Err(type_error(
+                            token,
+                            format!(
+                                "Match pattern of type '{}' does not match value of type '{}'",
+                                pattern_type, value_type
+                            ),
+                        ));
+                    }
+                }
+                if default.is_none() && !covers_bool(&value_type, arms) {
+                    return Err(type_error(
+                        token,
+                        "Match expression is not exhaustive; add a '_' arm".to_string(),
+                    ));
+                }
+
+                // Every arm, including the default, must produce the same type
+                let mut arm_type: Option<Type> = None;
+                for arm_value in arms.iter().map(|(_, v)| v).chain(default.as_deref()) {
+                    let current = self.infer_expression_type(arm_value)?;
+                    match &arm_type {
+                        Some(expected) if *expected != current => {
+                            let message = format!(
+                                "Match arms have incompatible types '{}' and '{}'",
+                                expected, current
+                            );
+                            return Err(type_error(token, message));
+                        }
+                        Some(_) => {}
+                        None => arm_type = Some(current),
+                    }
+                }
+                arm_type
+                    .ok_or_else(|| type_error(token, "Match expression has no arms".to_string()))
+            }
+            Expr::ArrayLiteral { elements, token } => match elements.first() {
+                Some(first) => {
+                    let element_type = self.infer_expression_type(first)?;
+                    for element in &elements[1..] {
+                        let current = self.infer_expression_type(element)?;
+                        if !same_value_kind(&element_type, &current) {
+                            return Err(type_error(
+                                token,
+                                format!(
+                                    "Array literal mixes '{}' and '{}' elements",
+                                    element_type, current
+                                ),
+                            ));
+                        }
+                    }
+                    Ok(Type::Array(Box::new(element_type), elements.len()))
+                }
+                None => Err(type_error(
+                    token,
+                    "Cannot infer the element type of an empty array literal".to_string(),
+                )),
             },
-            Expr::Call { .. } => Ok("i32".to_string()), // Simplified for now
-            _ => Ok("unknown".to_string()),
-        }
-    }
-
-    fn is_valid_type(&self, t: &str) -> bool {
-        matches!(
-            t,
-            "i8" | "i16"
-                | "i32"
-                | "i64"
-                | "u8"
-                | "u16"
-                | "u32"
-                | "u64"
-                | "f32"
-                | "f64"
-                | "bool"
-                | "str"
-                | "char"
-                | "void"
-                | "any"
+            Expr::ArrayAccess {
+                array,
+                index,
+                token,
+            } => {
+                let index_type = self.infer_expression_type(index)?;
+                if !index_type.is_integer() {
+                    return Err(type_error(
+                        token,
+                        format!("Array index must be an integer, got '{}'", index_type),
+                    ));
+                }
+                // A reference to an array is indexed in place
+                let array_type = self.infer_expression_type(array)?;
+                match array_type.pointee().unwrap_or(&array_type) {
+                    // A constant index into a fixed-size array is checked here, not at runtime
+                    Type::Array(_, length)
+                        if constant_index(index).is_some_and(|i| i < 0 || i >= *length as i64) =>
+                    {
+                        let message = format!(
+                            "Index {} is out of bounds for '{}' of length {}",
+                            constant_index(index).unwrap_or_default(),
+                            array_type,
+                            length
+                        );
+                        Err(type_error(token, message))
+                    }
+                    Type::Array(element, _) | Type::Slice(element) => Ok(*element.clone()),
+                    Type::Unknown => Ok(Type::Unknown),
+                    _ => Err(type_error(
+                        token,
+                        format!("Cannot index into '{}'", array_type),
+                    )),
+                }
+            }
+            Expr::FieldAccess {
+                object,
+                field,
+                token,
+            } => {
+                // Fields are reached through a reference as well
+                let object_type = self.infer_expression_type(object)?;
+                let struct_name = match object_type.pointee().unwrap_or(&object_type) {
+                    Type::Struct(name) => name.clone(),
+                    Type::Unknown => return Ok(Type::Unknown),
+                    _ => {
+                        return Err(type_error(
+                            token,
+                            format!(
+                                "Cannot access field '{}' on non-struct type '{}'",
+                                field, object_type
+                            ),
+                        ))
+                    }
+                };
+                self.structs
+                    .get(&struct_name)
+                    .and_then(|fields| fields.iter().find(|(name, _)| name == field))
+                    .map(|(_, field_type)| field_type.clone())
+                    .ok_or_else(|| {
+                        type_error(
+                            token,
+                            format!("Struct '{}' has no field '{}'", struct_name, field),
+                        )
+                    })
+            }
+            Expr::StructLiteral {
+                struct_name,
+                fields,
+                token,
+            } => {
+                let Some(declared) = self.structs.get(struct_name).cloned() else {
+                    return Err(type_error(
+                        token,
+                        format!("Undefined struct '{}'", struct_name),
+                    ));
+                };
+                for (i, (field, value)) in fields.iter().enumerate() {
+                    if fields[..i].iter().any(|(earlier, _)| earlier == field) {
+                        return Err(type_error(
+                            token,
+                            format!(
+                                "Field '{}' is given twice in '{}' literal",
+                                field, struct_name
+                            ),
+                        ));
+                    }
+                    let Some((_, field_type)) = declared.iter().find(|(name, _)| name == field)
+                    else {
+                        return Err(type_error(
+                            token,
+                            format!("Struct '{}' has no field '{}'", struct_name, field),
+                        ));
+                    };
+                    // `null` takes its type from a nullable field
+                    if matches!(value, Expr::NullLiteral { .. })
+                        && matches!(field_type, Type::Nullable(_))
+                    {
+                        continue;
+                    }
+                    let value_type = self.infer_expression_type(value)?;
+                    if !same_value_kind(field_type, &value_type) {
+                        return Err(type_mismatch(
+                            token,
+                            format!(
+                                "Field '{}' of '{}' expects '{}', got '{}'",
+                                field, struct_name, field_type, value_type
+                            ),
+                            field_type,
+                            &value_type,
+                        ));
+                    }
+                }
+                if let Some((missing, _)) = declared
+                    .iter()
+                    .find(|(name, _)| !fields.iter().any(|(given, _)| given == name))
+                {
+                    return Err(type_error(
+                        token,
+                        format!("Missing field '{}' in '{}' literal", missing, struct_name),
+                    ));
+                }
+                Ok(Type::Struct(struct_name.clone()))
+            }
+            Expr::ModuleAccess {
+                module,
+                item,
+                token,
+            } => {
+                // Imported modules are flattened, so their items are top-level names
+                if self.functions.contains_key(item) {
+                    return Err(type_error(
+                        token,
+                        format!("Function '{}::{}' must be called", module, item),
+                    ));
+                }
+                self.variables
+                    .get(item)
+                    .map(|info| info.name.clone())
+                    .ok_or_else(|| {
+                        type_error(token, format!("Undefined item '{}::{}'", module, item))
+                    })
+            }
+            Expr::OwnershipTransfer { expr, .. } => {
+                self.infer_expression_type(expr)?;
+                Ok(Type::Unknown)
+            }
+        }
+    }
+
+    fn is_valid_type(&self, t: &Type) -> bool {
+        match t {
+            Type::Ref(inner, _) | Type::Array(inner, _) | Type::Slice(inner) => {
+                self.is_valid_type(inner)
+            }
+            // Only pointers (references and strings) can be null
+            Type::Nullable(inner) => inner.is_pointer() && self.is_valid_type(inner),
+            Type::Struct(name) => self.structs.contains_key(name),
+            Type::Null | Type::Unknown => false,
+            _ => true,
+        }
+    }
+}
+
+// Scalar conversions permitted by `as`
+fn is_valid_cast(from: &Type, to: &Type) -> bool {
+    match (from, to) {
+        (a, b) if a == b => true,
+        (Type::Int(_), Type::Int(_)) => true,
+        (Type::Int(_), Type::Char) | (Type::Char, Type::Int(_)) => true,
+        (Type::Int(_), Type::Bool) | (Type::Bool, Type::Int(_)) => true,
+        _ => false,
+    }
+}
+
+// The value of an index written as an integer literal, possibly negated
+fn constant_index(index: &Expr) -> Option<i64> {
+    match index {
+        Expr::IntegerLiteral { value, .. } => parse_integer_literal(value).ok(),
+        Expr::UnaryOp { op, operand } if op.kind == TokenType::Minus => {
+            constant_index(operand).map(|value| -value)
+        }
+        _ => None,
+    }
+}
+
+// Whether a value of type `b` fits where a slot of type `a` is expected; integer
+// literals infer as i32, so any two integer widths are accepted together
+fn same_value_kind(a: &Type, b: &Type) -> bool {
+    a == b || (a.is_integer() && b.is_integer()) || (a.is_float() && b.is_float())
+}
+
+// The statement that makes code after `stmt` unreachable, when it ends the block: a
+// `return`, `break` or `continue`, or an `if` every branch of which ends that way
+fn exit_point(stmt: &Stmt) -> Option<(&'static str, &Token)> {
+    match stmt {
+        Stmt::Return { token, .. } => Some(("return", token)),
+        Stmt::Break { token } => Some(("break", token)),
+        Stmt::Continue { token } => Some(("continue", token)),
+        Stmt::If { token, .. } if stmt.ends_block() => Some(("if", token)),
+        Stmt::Block { statements } => statements.iter().find_map(exit_point),
+        _ => None,
+    }
+}
+
+fn check_return_type(
+    name: &str,
+    return_type: &Type,
+    value_type: &Type,
+    token: &Token,
+) -> Result<(), ZenError> {
+    if initializer_fits(return_type, value_type) {
+        return Ok(());
+    }
+    let message = format!(
+        "Function '{}' returns '{}' but a value of type '{}' is returned",
+        name, return_type, value_type
+    );
+    Err(type_mismatch(token, message, return_type, value_type))
+}
+
+// Whether an initializer of type `value` can be bound to a variable declared `slot`;
+// numbers and chars convert into one another, but bools, strings and functions only
+// match themselves
+fn initializer_fits(slot: &Type, value: &Type) -> bool {
+    let is_scalar = |t: &Type| t.is_copy() || *t == Type::Str;
+    let is_exact = |t: &Type| matches!(t, Type::Bool | Type::Str | Type::Function(..));
+    slot == value || !(is_scalar(slot) && is_scalar(value)) || !(is_exact(slot) || is_exact(value))
+}
+
+// Arms are compared with integer equality, so only integers, chars and bools can be matched
+fn check_match_value(value_type: &Type, token: &Token) -> Result<(), ZenError> {
+    match value_type {
+        Type::Int(_) | Type::Char | Type::Bool => Ok(()),
+        _ => Err(type_error(
+            token,
+            format!(
+                "Cannot match on a value of type '{}'; only integers, chars and bools can be matched",
+                value_type
+            ),
+        )),
+    }
+}
+
+// Whether the arm patterns name both `true` and `false` for a bool value
+fn covers_bool(value_type: &Type, arms: &[(Expr, Expr)]) -> bool {
+    let has = |wanted: bool| {
+        arms.iter().any(
+            |(pattern, _)| matches!(pattern, Expr::BooleanLiteral { value, .. } if *value == wanted),
+        )
+    };
+    *value_type == Type::Bool && has(true) && has(false)
+}
+
+// A type error reported at `token`
+fn type_error(token: &Token, message: String) -> ZenError {
+    ZenError::TypeError {
+        message,
+        line: token.line,
+        column: token.column,
+        source_line: None,
+        expected_type: None,
+        found_type: None,
+    }
+}
+
+// A type error for a value of type `found` where `expected` is required
+fn type_mismatch(token: &Token, message: String, expected: &Type, found: &Type) -> ZenError {
+    ZenError::TypeError {
+        message,
+        line: token.line,
+        column: token.column,
+        source_line: None,
+        expected_type: Some(expected.to_string()),
+        found_type: Some(found.to_string()),
+    }
+}
+
+// An error about the program as a whole, such as a missing `--entry` function; line 0
+// marks it as having no source position
+fn unlocated_error(message: String) -> ZenError {
+    ZenError::TypeError {
+        message,
+        line: 0,
+        column: 0,
+        source_line: None,
+        expected_type: None,
+        found_type: None,
+    }
+}
+
+// The struct stored inline by a field of this type, if any; references add indirection
+fn contained_struct(field_type: &Type) -> Option<&str> {
+    match field_type {
+        Type::Struct(name) => Some(name),
+        Type::Array(element, _) => contained_struct(element),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::lexer::Lexer;
+    use crate::parser::parser::Parser;
+
+    fn check(code: &str) -> Result<(), String> {
+        let tokens = Lexer::new(code).tokenize().unwrap();
+        let program = Parser::new(tokens).parse_strict().unwrap();
+        TypeChecker::new().check(&program).map_err(|errors| {
+            let messages: Vec<String> = errors.iter().map(ZenError::format_with_context).collect();
+            format!(
+                "Type checking failed with {} errors:\n{}",
+                errors.len(),
+                messages.join("\n")
+            )
+        })
+    }
+
+    #[test]
+    fn test_self_containing_struct_is_rejected() {
+        let err = check("struct Node { value: i32, next: Node }").unwrap_err();
+        assert!(err.contains("Recursive struct 'Node' has infinite size (Node -> Node)"));
+    }
+
+    #[test]
+    fn test_mutually_recursive_structs_are_rejected() {
+        let err = check("struct A { b: B }\nstruct B { a: A }").unwrap_err();
+        assert!(err.contains("(A -> B -> A)"));
+        assert!(err.starts_with("Type checking failed with 1 errors"));
+    }
+
+    #[test]
+    fn test_nested_structs_without_cycle_are_accepted() {
+        assert!(
+            check("struct Point { x: i32, y: i32 }\nstruct Line { a: Point, b: Point }").is_ok()
+        );
+    }
+
+    #[test]
+    fn test_deref_of_non_reference_is_rejected() {
+        let err = check("fn main() -> i32 {\n    let x = 1\n    let y = *x\n    return 0\n}")
+            .unwrap_err();
+        assert!(err.contains("Cannot dereference non-reference type 'i32'"));
+    }
+
+    #[test]
+    fn test_assign_through_shared_reference_is_rejected() {
+        let err = check("fn set(p: &i32) -> void {\n    *p = 2\n}").unwrap_err();
+        assert!(err.contains("a '&mut' reference is required"));
+        assert!(check("fn set(p: &mut i32) -> void {\n    *p = 2\n}").is_ok());
+    }
+
+    #[test]
+    fn test_code_after_diverging_call_warns() {
+        let tokens = Lexer::new("fn main() -> i32 {\n    exit(0)\n    return 1\n}")
+            .tokenize()
+            .unwrap();
+        let program = Parser::new(tokens).parse_strict().unwrap();
+        let mut checker = TypeChecker::new();
+
+        assert!(checker.check(&program).is_ok());
+        assert_eq!(
+            checker.warnings,
+            vec!["Unreachable code after call to 'exit' at line 2:5".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_null_requires_nullable_pointer_type() {
+        assert!(check("fn main() -> i32 {\n    let p: ?str = null\n    return 0\n}").is_ok());
+        assert!(check("fn f(p: ?&i32) -> i32 {\n    return *p\n}").is_ok());
+
+        let err = check("fn main() -> i32 {\n    let p: str = null\n    return 0\n}").unwrap_err();
+        assert!(err.contains("Cannot initialize non-nullable type 'str' with null"));
+        let err = check("fn main() -> i32 {\n    let p = null\n    return 0\n}").unwrap_err();
+        assert!(err.contains("Cannot infer the type of null"));
+        let err = check("fn main() -> i32 {\n    let p: ?i32 = null\n    return 0\n}").unwrap_err();
+        assert!(err.contains("Invalid type '?i32'"));
+    }
+
+    #[test]
+    fn test_null_takes_the_nullable_type_it_meets() {
+        assert!(check("fn main() -> i32 {\n    let x = 1\n    let mut p: ?&i32 = null\n    if p == null {\n        p = &x\n    }\n    p = null\n    return 0\n}").is_ok());
+        assert!(check("fn f(p: ?&i32) -> ?&i32 {\n    if null != p {\n        return p\n    }\n    return null\n}\nfn main() -> i32 {\n    f(null)\n    return 0\n}").is_ok());
+
+        let err = check("fn main() -> i32 {\n    let x = 1\n    if x == null {\n        return 1\n    }\n    return 0\n}").unwrap_err();
+        assert!(
+            err.contains("Cannot compare non-nullable type 'i32' with null"),
+            "{}",
+            err
+        );
+        let err = check("fn main() -> i32 {\n    let mut x = 1\n    x = null\n    return x\n}")
+            .unwrap_err();
+        assert!(
+            err.contains("Cannot assign null to 'x' of non-nullable type 'i32'"),
+            "{}",
+            err
+        );
+        let err = check("fn f() -> &i32 {\n    return null\n}").unwrap_err();
+        assert!(
+            err.contains("Function 'f' returns non-nullable type '&i32' but null is returned"),
+            "{}",
+            err
+        );
+        let err = check(
+            "fn f(p: &i32) -> i32 {\n    return *p\n}\nfn main() -> i32 {\n    return f(null)\n}",
+        )
+        .unwrap_err();
+        assert!(
+            err.contains("Cannot pass null as parameter 'p' of non-nullable type '&i32'"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_scalar_casts_are_accepted() {
+        for cast in [
+            "65 as char",
+            "'A' as i32",
+            "true as i32",
+            "0 as bool",
+            "300 as u8",
+            "7 as i32",
+        ] {
+            let code = format!(
+                "fn main() -> i32 {{\n    let x = {}\n    return 0\n}}",
+                cast
+            );
+            assert!(check(&code).is_ok(), "{} should type check", cast);
+        }
+    }
+
+    #[test]
+    fn test_string_cast_to_integer_is_rejected() {
+        let err =
+            check("fn main() -> i32 {\n    let x = \"12\" as i32\n    return 0\n}").unwrap_err();
+        assert!(err.contains("Type error at 2:18: Cannot cast 'str' to 'i32'"));
+        let err =
+            check("fn main() -> i32 {\n    let x = 1.5 as char\n    return 0\n}").unwrap_err();
+        assert!(err.contains("Cannot cast 'f64' to 'char'"));
+    }
+
+    #[test]
+    fn test_array_repeat_infers_sized_array() {
+        let tokens = Lexer::new("let a = [1; 5]\nlet s = [\"x\"; 2]")
+            .tokenize()
+            .unwrap();
+        let program = Parser::new(tokens).parse_strict().unwrap();
+        let mut checker = TypeChecker::new();
+
+        assert!(checker.check(&program).is_ok());
+        assert_eq!(checker.variables["a"].name.to_string(), "[i32; 5]");
+        assert_eq!(checker.variables["s"].name.to_string(), "[str; 2]");
+    }
+
+    #[test]
+    fn test_match_expression_unifies_arm_types() {
+        let tokens = Lexer::new("let n = 2\nlet s = match n { 1 => \"one\", _ => \"many\" }")
+            .tokenize()
+            .unwrap();
+        let program = Parser::new(tokens).parse_strict().unwrap();
+        let mut checker = TypeChecker::new();
+
+        assert!(checker.check(&program).is_ok());
+        assert_eq!(checker.variables["s"].name, Type::Str);
+        assert!(check("let b = true\nlet n = match b { true => 1, false => 0 }").is_ok());
+
+        let err = check("let n = 2\nlet s = match n { 1 => \"one\", _ => 2 }").unwrap_err();
+        assert!(err.contains("Match arms have incompatible types 'str' and 'i32'"));
+        let err = check("let n = 2\nlet s = match n { 1 => \"one\" }").unwrap_err();
+        assert!(err.contains("Match expression is not exhaustive"));
+    }
+
+    #[test]
+    fn test_for_init_reuses_or_declares_loop_variable() {
+        let code = "fn main() -> i32 {\n    let mut i: i64 = 5\n    for (i = 0; i < 3; i = i + 1) {}\n    for (j = 0; j < 3; j = j + 1) {}\n    return 0\n}";
+        let tokens = Lexer::new(code).tokenize().unwrap();
+        let program = Parser::new(tokens).parse_strict().unwrap();
+        let mut checker = TypeChecker::new();
+        let Stmt::FunctionDecl { body, .. } = &program.statements[0] else {
+            panic!("Expected a function");
+        };
+
+        checker.check_statement(&body[0]).unwrap();
+        checker.check_statement(&body[1]).unwrap();
+        checker.check_statement(&body[2]).unwrap();
+        // Reusing `i` keeps its declared type instead of shadowing it with an i32
+        assert_eq!(
+            checker.variables["i"].name,
+            Type::Int(crate::types::IntKind::I64)
+        );
+        // A new loop variable is an i32 scoped to the loop
+        assert!(!checker.variables.contains_key("j"));
+        let err = check("fn main() -> i32 {\n    for (j = 0; j < 3; j = j + 1) {\n        let s: str = j\n    }\n    return 0\n}").unwrap_err();
+        assert!(
+            err.contains("Cannot initialize 'str' with a value of type 'i32'"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_for_in_binds_element_type() {
+        let tokens = Lexer::new("let a = [\"x\", \"y\"]\nfor s in a { let t = s }")
+            .tokenize()
+            .unwrap();
+        let program = Parser::new(tokens).parse_strict().unwrap();
+        let mut checker = TypeChecker::new();
+
+        assert!(checker.check(&program).is_ok());
+        assert_eq!(checker.variables["a"].name.to_string(), "[str; 2]");
+        let err = check("let a = [\"x\"]\nfor s in a { let t: i32 = s }").unwrap_err();
+        assert!(
+            err.contains("Cannot initialize 'i32' with a value of type 'str'"),
+            "{}",
+            err
+        );
+
+        let err = check("let n = 3\nfor x in n { let y = x }").unwrap_err();
+        assert!(err.contains("Cannot iterate over 'i32'"));
+    }
+
+    #[test]
+    fn test_char_literal_must_fit_in_a_byte() {
+        assert!(check("let c = '\\xFF'\nlet e = 'é'").is_ok());
+        let err = check("let c = 'λ'").unwrap_err();
+        assert!(err.contains("Char literal 'λ' (U+03BB) does not fit in a byte"));
+    }
+
+    #[test]
+    fn test_inexact_f32_literal_warns() {
+        let code = "fn main() -> i32 {\n    let a: f32 = 0.5f32\n    let b: f32 = 0.5\n    let mut c: f32 = 0.1\n    c = -0.25\n    c = 2.2\n    println(a + b + c)\n    return 0\n}";
+        let tokens = Lexer::new(code).tokenize().unwrap();
+        let program = Parser::new(tokens).parse_strict().unwrap();
+        let mut checker = TypeChecker::new();
+
+        assert!(checker.check(&program).is_ok());
+        assert_eq!(checker.warnings.len(), 2);
+        assert!(checker.warnings[0].starts_with(
+            "Float literal '0.1' is not exactly representable as f32 and will be rounded to 0.10000000149011612"
+        ));
+        assert!(checker.warnings[0].ends_with("at line 4:22"));
+        assert!(checker.warnings[1].contains("'2.2'"));
+    }
+
+    #[test]
+    fn test_constant_overflow_warns_for_the_type_it_is_stored_as() {
+        let code = "fn wide() -> i64 {\n    return 100000 * 100000\n}\nfn main() -> i32 {\n    let w: i64 = 100000 * 100000\n    let big: u32 = 65536 * 65535\n    let x = 2147483647 + 1\n    let b: u8 = 200 + 100\n    println(w + wide())\n    println(big)\n    println(x)\n    println(b)\n    return 0\n}";
+        let tokens = Lexer::new(code).tokenize().unwrap();
+        let program = Parser::new(tokens).parse_strict().unwrap();
+        let mut checker = TypeChecker::new();
+
+        assert!(checker.check(&program).is_ok());
+        assert_eq!(
+            checker.warnings,
+            [
+                "Constant expression overflows i32 (the exact result is 2147483648) at line 7:24; it wraps at run time",
+                "Constant expression overflows u8 (the exact result is 300) at line 8:21; it wraps at run time",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_return_value_from_void_function_is_rejected() {
+        let err = check("fn f() -> void {\n    return 5\n}").unwrap_err();
+        assert!(err.contains("Function 'f' returns void but a value is returned"));
+        assert!(check("fn f() -> void {\n    return\n}").is_ok());
+    }
+
+    #[test]
+    fn test_bare_return_from_non_void_function_is_rejected() {
+        let err =
+            check("fn f(x: i32) -> i32 {\n    if x > 0 {\n        return\n    }\n    return x\n}")
+                .unwrap_err();
+        assert!(err.contains("Function 'f' must return a value of type 'i32'"));
+    }
+
+    #[test]
+    fn test_non_void_function_must_return_on_every_path() {
+        let err =
+            check("fn f(x: i32) -> i32 {\n    if x > 0 {\n        return 1\n    }\n}").unwrap_err();
+        assert!(
+            err.contains("Type error at 5:1: Function 'f' must return a value of type 'i32' on every path, but can reach the end of its body"),
+            "{}",
+            err
+        );
+        assert!(check("fn f() -> i32 {\n    println(1)\n}").is_err());
+
+        assert!(check("fn f(x: i32) -> i32 {\n    if x > 0 {\n        return 1\n    } else {\n        return 2\n    }\n}").is_ok());
+        assert!(check("fn f(x: i32) -> i32 {\n    x + 1\n}").is_ok());
+        assert!(check(
+            "fn f(x: i32) -> i32 {\n    if x > 0 {\n        return 1\n    }\n    abort()\n}"
+        )
+        .is_ok());
+
+        let err = check("fn f() -> i32 {\n    return \"one\"\n}").unwrap_err();
+        assert!(
+            err.contains("Function 'f' returns 'i32' but a value of type 'str' is returned"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_loop_without_break_and_tail_branches_end_a_function() {
+        assert!(check("fn k(x: i32) -> i32 {\n    let mut i = x\n    loop {\n        i += 1\n        if i > 10 {\n            return i\n        }\n    }\n}").is_ok());
+        // A `break` leaving the loop lets control reach the end of the body again
+        assert!(check("fn k(x: i32) -> i32 {\n    loop {\n        if x > 10 {\n            break\n        }\n        return x\n    }\n}").is_err());
+
+        assert!(check("fn h(x: i32) -> i32 {\n    if x > 0 {\n        1\n    } else if x < 0 {\n        let y = x * 2\n        y\n    } else {\n        0\n    }\n}").is_ok());
+        assert!(check(
+            "fn m(x: i32) -> i32 {\n    match x {\n        1 => 10,\n        _ => 20,\n    }\n}"
+        )
+        .is_ok());
+        assert!(check("fn h(x: i32) -> i32 {\n    if x > 0 {\n        1\n    }\n}").is_err());
+        let err =
+            check("fn h(x: i32) -> i32 {\n    if x > 0 {\n        1\n    } else {\n        \"two\"\n    }\n}")
+                .unwrap_err();
+        assert!(
+            err.contains("If branches have incompatible types"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_unused_variables_and_discarded_results_warn() {
+        let code = "fn one() -> i32 {\n    1\n}\nfn main() -> i32 {\n    let y = 5\n    let _y = 5\n    let mut used = 1\n    used = used + 1\n    one()\n    println(used)\n    return 0\n}";
+        let tokens = Lexer::new(code).tokenize().unwrap();
+        let program = Parser::new(tokens).parse_strict().unwrap();
+        let mut checker = TypeChecker::new();
+
+        assert!(checker.check(&program).is_ok());
+        assert_eq!(checker.warnings.len(), 2, "{:?}", checker.warnings);
+        assert!(
+            checker.warnings[0]
+                .starts_with("Unused result of type 'i32' from call to 'one' at line 9:5"),
+            "{}",
+            checker.warnings[0]
+        );
+        assert!(
+            checker.warnings[1].starts_with("Unused variable 'y' at line 5:9"),
+            "{}",
+            checker.warnings[1]
+        );
+    }
+
+    #[test]
+    fn test_calls_through_locals_are_reads_and_failed_functions_do_not_warn() {
+        let code = "fn add(a: i32, b: i32) -> i32 {\n    return a + b\n}\nfn main() -> i32 {\n    let f = add\n    println(f(1, 2))\n    return 0\n}";
+        let tokens = Lexer::new(code).tokenize().unwrap();
+        let program = Parser::new(tokens).parse_strict().unwrap();
+        let mut checker = TypeChecker::new();
+        assert!(checker.check(&program).is_ok());
+        assert!(checker.warnings.is_empty(), "{:?}", checker.warnings);
+
+        let code = "fn main() -> i32 {\n    let mut s = \"a\"\n    s += \"b\"\n    println(s)\n    return 0\n}";
+        let tokens = Lexer::new(code).tokenize().unwrap();
+        let program = Parser::new(tokens).parse_strict().unwrap();
+        let mut checker = TypeChecker::new();
+        assert!(checker.check(&program).is_err());
+        assert!(checker.warnings.is_empty(), "{:?}", checker.warnings);
+    }
+    #[test]
+    fn test_code_after_a_terminator_warns_once_per_block() {
+        let code = "fn main() -> i32 {\n    let mut i = 0\n    while i < 3 {\n        i = i + 1\n        continue\n        println(i)\n    }\n    return i\n    println(1)\n    println(2)\n}";
+        let tokens = Lexer::new(code).tokenize().unwrap();
+        let program = Parser::new(tokens).parse_strict().unwrap();
+        let mut checker = TypeChecker::new();
+
+        assert!(checker.check(&program).is_ok());
+        assert_eq!(
+            checker.warnings,
+            vec![
+                "Unreachable code after 'continue' at line 5:9".to_string(),
+                "Unreachable code after 'return' at line 8:12".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_struct_addition_without_operator_function_is_rejected() {
+        let err = check(
+            "struct Vec2 { x: i32, y: i32 }\nfn main() -> i32 {\n    let a = Vec2 { x: 1, y: 2 }\n    let b = a + a\n    return 0\n}",
+        )
+        .unwrap_err();
+        assert!(err.contains(
+            "Type error at 4:15: Binary '+' is not defined for struct 'Vec2'; define a function 'Vec2_add'"
+        ));
+    }
+
+    #[test]
+    fn test_struct_equality_without_derive_is_rejected() {
+        let err = check(
+            "struct Vec2 { x: i32, y: i32 }\nfn main() -> i32 {\n    let a = Vec2 { x: 1, y: 2 }\n    let same = a == a\n    return 0\n}",
+        )
+        .unwrap_err();
+        assert!(err.contains(
+            "Type error at 4:18: Binary '==' is not defined for struct 'Vec2'; add #[derive(Eq)] or define a function 'Vec2_eq'"
+        ));
+    }
+
+    #[test]
+    fn test_entry_point_must_take_no_parameters() {
+        let tokens = Lexer::new("fn start(n: i32) -> i32 {\n    return n\n}")
+            .tokenize()
+            .unwrap();
+        let program = Parser::new(tokens).parse_strict().unwrap();
+        let err = TypeChecker::new()
+            .with_entry(Some("start".to_string()))
+            .check(&program)
+            .unwrap_err();
+        assert_eq!(
+            err[0].format_with_context(),
+            "Type error: Entry point 'start' must take no parameters and return i32 or void"
+        );
+        // Display agrees that the error has no position
+        assert_eq!(err[0].to_string(), err[0].format_with_context());
+    }
+
+    #[test]
+    fn test_assert_eq_requires_comparable_arguments() {
+        let err =
+            check("fn main() -> i32 {\n    assert_eq(1, \"one\")\n    return 0\n}").unwrap_err();
+        assert!(err.contains("Type error at 2:5: assert_eq cannot compare 'i32' with 'str'"));
+        assert!(check("fn main() -> i32 {\n    assert_eq(2 + 2, 4)\n    return 0\n}").is_ok());
+    }
+
+    #[test]
+    fn test_compound_assignment_requires_mutable_number() {
+        let err =
+            check("fn main() -> i32 {\n    let x = 1\n    x += 1\n    return 0\n}").unwrap_err();
+        assert!(err.contains(
+            "Type error at 3:7: Cannot apply '+=' to immutable variable 'x'; declare it with 'let mut'"
+        ));
+        let err = check("fn main() -> i32 {\n    let mut s = \"a\"\n    s *= 2\n    return 0\n}")
+            .unwrap_err();
+        assert!(err.contains("Type error at 3:7: Cannot apply '*=' to 's' of type 'str'"));
+    }
+
+    #[test]
+    fn test_break_outside_loop_is_rejected() {
+        let err = check("fn main() -> i32 {\n    if true {\n        break\n    }\n    return 0\n}")
+            .unwrap_err();
+        assert!(err.contains("Type error at 3:9: 'break' outside of a loop"));
+        assert!(check("fn main() -> i32 {\n    while true {\n        if true {\n            break\n        }\n    }\n    return 0\n}").is_ok());
+    }
+
+    #[test]
+    fn test_array_elements_must_share_a_type() {
+        let err = check("fn main() -> i32 {\n    let a = [1, true]\n    return 0\n}").unwrap_err();
+        assert!(err.contains("Type error at 2:13: Array literal mixes 'i32' and 'bool' elements"));
+
+        let err =
+            check("fn main() -> i32 {\n    let mut a = [1, 2]\n    a[0] = \"x\"\n    return 0\n}")
+                .unwrap_err();
+        assert!(err.contains("Type error at 3:10: Cannot assign 'str' to an element of type 'i32'"));
+    }
+
+    #[test]
+    fn test_constant_indices_are_bounds_checked() {
+        assert!(check(
+            "fn main() -> i32 {\n    let mut a = [1, 2, 3]\n    a[2] = 4\n    return a[0]\n}"
+        )
+        .is_ok());
+
+        let err =
+            check("fn main() -> i32 {\n    let mut a = [1, 2, 3]\n    a[3] = 4\n    return 0\n}")
+                .unwrap_err();
+        assert!(
+            err.contains("Type error at 3:8: Index 3 is out of bounds for '[i32; 3]' of length 3"),
+            "{}",
+            err
+        );
+
+        let err =
+            check("fn main() -> i32 {\n    let a = [1, 2, 3]\n    let x = a[-1]\n    return 0\n}")
+                .unwrap_err();
+        assert!(err.contains("Index -1 is out of bounds"), "{}", err);
+    }
+
+    #[test]
+    fn test_field_assignment_must_match_the_field_type() {
+        let point = "struct Point {\n    x: i32,\n    label: str,\n}\n";
+        assert!(check(&format!(
+            "{}fn main() -> i32 {{\n    let mut p = Point {{ x: 1, label: \"a\" }}\n    p.x = 2\n    p.label = \"b\"\n    return p.x\n}}",
+            point
+        ))
+        .is_ok());
+
+        let err = check(&format!(
+            "{}fn main() -> i32 {{\n    let mut p = Point {{ x: 1, label: \"a\" }}\n    p.x = true\n    return 0\n}}",
+            point
+        ))
+        .unwrap_err();
+        assert!(
+            err.contains("Type error at 7:9: Cannot assign 'bool' to field 'x' of type 'i32'"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_recursive_functions_are_registered_before_bodies() {
+        assert!(check("fn fib(n: i32) -> i32 {\n    if n < 2 {\n        return n\n    }\n    return fib(n - 1) + fib(n - 2)\n}").is_ok());
+        assert!(check("fn is_even(n: i32) -> bool {\n    if n == 0 {\n        return true\n    }\n    return is_odd(n - 1)\n}\nfn is_odd(n: i32) -> bool {\n    if n == 0 {\n        return false\n    }\n    return is_even(n - 1)\n}").is_ok());
+    }
+
+    #[test]
+    fn test_match_statement_patterns_must_match_value_type() {
+        let err = check("fn main() -> i32 {\n    let x = 1\n    match x {\n        true => println(1)\n    }\n    return 0\n}")
+            .unwrap_err();
+        assert!(
+            err.contains(
+                "Type error at 3:5: Match pattern of type 'bool' does not match value of type 'i32'"
+            ),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_match_value_must_be_an_integer_char_or_bool() {
+        assert!(check("fn main() -> i32 {\n    let c = 'a'\n    match c {\n        'a' => println(1)\n    }\n    return 0\n}").is_ok());
+        let err = check("fn main() -> i32 {\n    let s = \"a\"\n    match s {\n        \"a\" => println(1)\n    }\n    return 0\n}")
+            .unwrap_err();
+        assert!(
+            err.contains("Type error at 3:5: Cannot match on a value of type 'str'"),
+            "{}",
+            err
+        );
+        let err = check("fn main() -> i32 {\n    let f = 1.5\n    return match f {\n        1.5 => 1,\n        _ => 0\n    }\n}")
+            .unwrap_err();
+        assert!(
+            err.contains("Cannot match on a value of type 'f64'"),
+            "{}",
+            err
+        );
+    }
+
+    const POINT: &str = "struct Point {\n    x: i32,\n    y: i32,\n}\n";
+
+    #[test]
+    fn test_borrow_produces_a_reference_type() {
+        assert!(check(
+            "fn main() -> i32 {\n    let x = 5\n    let r = &x\n    let y = *r\n    return y\n}"
+        )
+        .is_ok());
+        let err = check("fn main() -> i32 {\n    let x = 5\n    let r = &x\n    let y = r as i64\n    return 0\n}")
+            .unwrap_err();
+        assert!(
+            err.contains("Type error at 4:15: Cannot cast '&i32' to 'i64'"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_field_access_resolves_struct_fields() {
+        let ok = format!("{}fn get_x(p: &Point) -> i32 {{\n    return p.x\n}}\nfn main() -> i32 {{\n    let p = Point {{ x: 1, y: 2 }}\n    let y: i32 = p.y\n    return get_x(&p) + y\n}}", POINT);
+        assert!(check(&ok).is_ok());
+
+        let err = check(&format!("{}fn main() -> i32 {{\n    let p = Point {{ x: 1, y: 2 }}\n    let z = p.z\n    return 0\n}}", POINT))
+            .unwrap_err();
+        assert!(
+            err.contains("Type error at 7:15: Struct 'Point' has no field 'z'"),
+            "{}",
+            err
+        );
+
+        let err = check("fn main() -> i32 {\n    let n = 1\n    let z = n.z\n    return 0\n}")
+            .unwrap_err();
+        assert!(
+            err.contains("Type error at 3:15: Cannot access field 'z' on non-struct type 'i32'"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_array_access_returns_element_type() {
+        assert!(check("fn main() -> i32 {\n    let a = [true, false]\n    let r = &a\n    let b: bool = r[1]\n    return 0\n}").is_ok());
+
+        let err = check("fn main() -> i32 {\n    let n = 1\n    let v = n[0]\n    return 0\n}")
+            .unwrap_err();
+        assert!(
+            err.contains("Type error at 3:16: Cannot index into 'i32'"),
+            "{}",
+            err
+        );
+
+        let err =
+            check("fn main() -> i32 {\n    let a = [1, 2]\n    let v = a[true]\n    return 0\n}")
+                .unwrap_err();
+        assert!(
+            err.contains("Type error at 3:19: Array index must be an integer, got 'bool'"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_struct_literal_fields_are_validated() {
+        let literal = |fields: &str| {
+            check(&format!(
+                "{}fn main() -> i32 {{\n    let p = Point {{ {} }}\n    return 0\n}}",
+                POINT, fields
+            ))
+        };
+        assert!(literal("y: 2, x: 1").is_ok());
+        assert!(literal("x: 1")
+            .unwrap_err()
+            .contains("Missing field 'y' in 'Point' literal"));
+        assert!(literal("x: 1, y: 2, z: 3")
+            .unwrap_err()
+            .contains("Struct 'Point' has no field 'z'"));
+        assert!(literal("x: 1, x: 2")
+            .unwrap_err()
+            .contains("Field 'x' is given twice in 'Point' literal"));
+        assert!(literal("x: 1, y: true")
+            .unwrap_err()
+            .contains("Field 'y' of 'Point' expects 'i32', got 'bool'"));
+    }
+
+    #[test]
+    fn test_module_access_resolves_flattened_items() {
+        let code = "fn add(a: i32, b: i32) -> i32 {\n    return a + b\n}\nfn main() -> i32 {\n    let n: i32 = math::add(1, 2)\n    return n\n}";
+        assert!(check(code).is_ok());
+
+        let err =
+            check("fn main() -> i32 {\n    let n = math::sub(1, 2)\n    return 0\n}").unwrap_err();
+        assert!(
+            err.contains("Type error at 2:13: Undefined function 'math::sub'"),
+            "{}",
+            err
+        );
+
+        let err = check("fn main() -> i32 {\n    let n = math::pi\n    return 0\n}").unwrap_err();
+        assert!(
+            err.contains("Type error at 2:13: Undefined item 'math::pi'"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_division_by_a_constant_zero_is_rejected() {
+        let err =
+            check("fn main() -> i32 {\n    let x = 7\n    return x / (2 - 2)\n}").unwrap_err();
+        assert!(
+            err.contains("Type error at 3:14: Division by a constant zero"),
+            "{}",
+            err
+        );
+        let err = check("fn main() -> i32 {\n    return 7 % 0\n}").unwrap_err();
+        assert!(err.contains("Remainder by a constant zero"), "{}", err);
+        // Float division by zero is well defined
+        assert!(check("fn main() -> i32 {\n    let f = 1.0 / 0.0\n    return 0\n}").is_ok());
+    }
+
+    #[test]
+    fn test_power_requires_numeric_operands() {
+        assert!(check("fn main() -> i32 {\n    let n: i32 = 2 ^ 10\n    return n\n}").is_ok());
+
+        let err = check("fn main() -> i32 {\n    let n = \"a\" ^ 2\n    return 0\n}").unwrap_err();
+        assert!(
+            err.contains(
+                "Type error at 2:17: Binary '^' requires numeric operands, found 'str' and 'i32'"
+            ),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_bitwise_operators_require_integers() {
+        assert!(check("fn main() -> i32 {\n    let n = 6 & 3 | 1 << 2\n    return n\n}").is_ok());
+
+        let err = check("fn main() -> i32 {\n    let n = 1.5 << 2\n    return 0\n}").unwrap_err();
+        assert!(
+            err.contains(
+                "Type error at 2:17: Binary '<<' requires integer operands, found 'f64' and 'i32'"
+            ),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_interpolated_expressions_are_type_checked() {
+        assert!(check(
+            "fn main() -> i32 {\n    let a = 1\n    println(\"{a * 2}\")\n    return 0\n}"
+        )
+        .is_ok());
+
+        let err = check("fn main() -> i32 {\n    println(\"{missing + 1}\")\n    return 0\n}")
+            .unwrap_err();
+        assert!(err.contains("Undefined variable 'missing'"), "{}", err);
+
+        let err =
+            check("fn main() -> i32 {\n    let a = [1, 2]\n    println(\"{a}\")\n    return 0\n}")
+                .unwrap_err();
+        assert!(
+            err.contains("Type error at 3:13: Cannot interpolate a value of type '[i32; 2]'"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_initializer_must_match_annotation() {
+        let tokens = Lexer::new("fn main() -> i32 {\n    let x: bool = 5\n    return 0\n}")
+            .tokenize()
+            .unwrap();
+        let program = Parser::new(tokens).parse_strict().unwrap();
+        let errors = TypeChecker::new().check(&program).unwrap_err();
+        match &errors[0] {
+            ZenError::TypeError {
+                line,
+                column,
+                expected_type,
+                found_type,
+                ..
+            } => {
+                assert_eq!((*line, *column), (2, 9));
+                assert_eq!(expected_type.as_deref(), Some("bool"));
+                assert_eq!(found_type.as_deref(), Some("i32"));
+            }
+            other => panic!("Expected a type error, got {:?}", other),
+        }
+
+        assert!(check(
+            "fn main() -> i32 {\n    let x: i64 = 5\n    let c: char = 65\n    return 0\n}"
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_constants_need_a_type_and_a_constant_initializer() {
+        assert!(check("fn main() -> i32 {\n    return LIMIT * 2\n}\nconst LIMIT: i32 = 10 + 5\nconst BIG: i64 = LIMIT ^ 2").is_ok());
+
+        let err = check("const A = 1").unwrap_err();
+        assert!(
+            err.contains("Constant 'A' must have a type annotation"),
+            "{}",
+            err
+        );
+
+        let err = check("fn one() -> i32 {\n    return 1\n}\nconst A: i32 = one()").unwrap_err();
+        assert!(
+            err.contains("Initializer of constant 'A' must be a constant expression"),
+            "{}",
+            err
+        );
+
+        let err =
+            check("const A: i32 = 1\nfn main() -> i32 {\n    A += 1\n    return 0\n}").unwrap_err();
+        assert!(
+            err.contains("Type error at 3:7: Cannot assign to constant 'A'"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_function_values_must_match_their_signature() {
+        let add = "fn add(a: i32, b: i32) -> i32 {\n    return a + b\n}\n";
+        assert!(check(&format!(
+            "{}fn main() -> i32 {{\n    let f: fn(i32, i32) -> i32 = add\n    return f(1, 2)\n}}",
+            add
+        ))
+        .is_ok());
+
+        let err = check(&format!(
+            "{}fn main() -> i32 {{\n    let f: fn(i32) -> i32 = add\n    return 0\n}}",
+            add
+        ))
+        .unwrap_err();
+        assert!(
+            err.contains(
+                "Cannot initialize 'fn(i32) -> i32' with a value of type 'fn(i32, i32) -> i32'"
+            ),
+            "{}",
+            err
+        );
+
+        let err = check(&format!(
+            "{}fn main() -> i32 {{\n    let f = add\n    f(1)\n    return 0\n}}",
+            add
+        ))
+        .unwrap_err();
+        assert!(
+            err.contains("Type error at 6:5: 'f' expects 2 arguments, got 1"),
+            "{}",
+            err
+        );
+
+        let err = check("fn main() -> i32 {\n    let p = println\n    return 0\n}").unwrap_err();
+        assert!(
+            err.contains("Function 'println' cannot be used as a value"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_if_expression_branches_share_a_type() {
+        assert!(check("fn main() -> i32 {\n    let a = 1\n    let m = if a > 0 { a } else { 0 }\n    return m\n}").is_ok());
+
+        let err = check(
+            "fn main() -> i32 {\n    let m = if true { 1 } else { \"one\" }\n    return 0\n}",
+        )
+        .unwrap_err();
+        assert!(
+            err.contains("Type error at 2:13: If branches have incompatible types 'i32' and 'str'"),
+            "{}",
+            err
+        );
+
+        let err = check("fn main() -> i32 {\n    let m = if 1 { 1 } else { 2 }\n    return 0\n}")
+            .unwrap_err();
+        assert!(
+            err.contains("If condition must be boolean, got 'i32'"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_block_expression_has_its_tail_type_and_scopes_its_locals() {
+        assert!(check(
+            "fn main() -> i32 {\n    let s: str = { let n = 1; \"one\" }\n    return 0\n}"
+        )
+        .is_ok());
+
+        let err =
+            check("fn main() -> i32 {\n    let x: i32 = { let a = 2; \"six\" }\n    return 0\n}")
+                .unwrap_err();
+        assert!(err.contains("Cannot initialize 'i32'"), "{}", err);
+
+        let err = check(
+            "fn main() -> i32 {\n    let x = { let a = 2; a * 3 }\n    let y = a\n    return 0\n}",
+        )
+        .unwrap_err();
+        assert!(err.contains("'a'"), "{}", err);
+    }
+
+    #[test]
+    fn test_inner_declarations_shadow_until_their_block_ends() {
+        assert!(check("fn main() -> i32 {\n    let x: i32 = 1\n    {\n        let x: str = \"inner\"\n        println(x)\n    }\n    let y: i32 = x + 1\n    println(y)\n    return 0\n}").is_ok());
+
+        let err = check("fn main() -> i32 {\n    if true {\n        let z = 1\n        println(z)\n    }\n    println(z)\n    return 0\n}")
+            .unwrap_err();
+        assert!(err.contains("Undefined variable 'z'"), "{}", err);
+    }
+
+    #[test]
+    fn test_type_parameters_are_opaque() {
+        let err = check("fn add<T>(a: T, b: T) -> T {\n    let sum = a + b\n    return sum\n}")
+            .unwrap_err();
+        assert!(
+            err.contains("Type error at 2:17: Binary '+' is not defined for type parameter 'T'")
+        );
+    }
+
+    #[test]
+    fn test_generic_call_binds_one_type_per_parameter() {
+        let err = check(
+            "fn pick<T>(a: T, b: T) -> T {\n    return a\n}\nfn main() -> i32 {\n    let x = pick(1, \"one\")\n    return 0\n}",
         )
+        .unwrap_err();
+        assert!(err.contains(
+            "Type error at 5:13: Conflicting types 'i32' and 'str' for a type parameter of 'pick'"
+        ));
     }
 }
@@ -0,0 +1,444 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IntKind {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+}
+
+impl IntKind {
+    pub fn bits(self) -> u32 {
+        match self {
+            IntKind::I8 | IntKind::U8 => 8,
+            IntKind::I16 | IntKind::U16 => 16,
+            IntKind::I32 | IntKind::U32 => 32,
+            IntKind::I64 | IntKind::U64 => 64,
+        }
+    }
+
+    pub fn is_signed(self) -> bool {
+        matches!(
+            self,
+            IntKind::I8 | IntKind::I16 | IntKind::I32 | IntKind::I64
+        )
+    }
+
+    /// Whether `value` is in this type's range
+    pub fn contains(self, value: i64) -> bool {
+        let value = i128::from(value);
+        let bits = self.bits();
+        if self.is_signed() {
+            -(1 << (bits - 1)) <= value && value < 1 << (bits - 1)
+        } else {
+            0 <= value && value < 1 << bits
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            IntKind::I8 => "i8",
+            IntKind::I16 => "i16",
+            IntKind::I32 => "i32",
+            IntKind::I64 => "i64",
+            IntKind::U8 => "u8",
+            IntKind::U16 => "u16",
+            IntKind::U32 => "u32",
+            IntKind::U64 => "u64",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FloatKind {
+    F32,
+    F64,
+}
+
+impl FloatKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            FloatKind::F32 => "f32",
+            FloatKind::F64 => "f64",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Type {
+    Int(IntKind),
+    Float(FloatKind),
+    Bool,
+    Char,
+    Str,
+    Void,
+    /// `[T; N]`
+    Array(Box<Type>, usize),
+    /// `[T]`, an array whose length is not part of the type
+    Slice(Box<Type>),
+    /// `&T`, or `&mut T` when the flag is set
+    Ref(Box<Type>, bool),
+    /// `?T`, a pointer that may be null
+    Nullable(Box<Type>),
+    /// A user-defined struct (any name that is not a built-in type)
+    Struct(String),
+    /// `fn(A, B) -> R`, a pointer to a function with exactly this signature
+    Function(Vec<Type>, Box<Type>),
+    /// A type parameter of a generic function, such as `T` in `fn id<T>(x: T) -> T`
+    Param(String),
+    /// The `null` literal before it is given a `?T` type
+    Null,
+    /// Accepts any argument; only used for builtin parameters
+    Any,
+    /// An expression whose type could not be determined
+    Unknown,
+}
+
+impl Type {
+    pub const I32: Type = Type::Int(IntKind::I32);
+    pub const F64: Type = Type::Float(FloatKind::F64);
+
+    /// Resolve a single type name such as `i32` or `Point`
+    pub fn from_name(name: &str) -> Type {
+        match name {
+            "i8" => Type::Int(IntKind::I8),
+            "i16" => Type::Int(IntKind::I16),
+            "i32" => Type::Int(IntKind::I32),
+            "i64" => Type::Int(IntKind::I64),
+            "u8" => Type::Int(IntKind::U8),
+            "u16" => Type::Int(IntKind::U16),
+            "u32" => Type::Int(IntKind::U32),
+            "u64" => Type::Int(IntKind::U64),
+            "f32" => Type::Float(FloatKind::F32),
+            "f64" => Type::Float(FloatKind::F64),
+            "bool" => Type::Bool,
+            "char" => Type::Char,
+            "str" => Type::Str,
+            "void" => Type::Void,
+            "any" => Type::Any,
+            _ => Type::Struct(name.to_string()),
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        matches!(self, Type::Int(_))
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, Type::Float(_))
+    }
+
+    /// Scalars that `<-` copies instead of moving
+    pub fn is_copy(&self) -> bool {
+        matches!(
+            self,
+            Type::Int(_) | Type::Float(_) | Type::Bool | Type::Char | Type::Function(..)
+        )
+    }
+
+    /// The pointee of `&T`/`&mut T`, looking through a nullable wrapper
+    pub fn pointee(&self) -> Option<&Type> {
+        match self {
+            Type::Ref(inner, _) => Some(inner),
+            Type::Nullable(inner) => inner.pointee(),
+            _ => None,
+        }
+    }
+
+    /// Types represented as a pointer, which are the only ones that may be null
+    pub fn is_pointer(&self) -> bool {
+        matches!(self, Type::Str | Type::Ref(..) | Type::Slice(_))
+    }
+
+    /// The LLVM IR type used to store a value of this type
+    pub fn to_llvm(&self) -> String {
+        match self {
+            Type::Int(kind) => format!("i{}", kind.bits()),
+            Type::Float(FloatKind::F32) => "float".to_string(),
+            Type::Float(FloatKind::F64) => "double".to_string(),
+            Type::Bool => "i1".to_string(),
+            Type::Char => "i8".to_string(),
+            Type::Str | Type::Null => "i8*".to_string(),
+            Type::Void => "void".to_string(),
+            Type::Array(element, size) => format!("[{} x {}]", size, element.to_llvm()),
+            Type::Slice(element) | Type::Ref(element, _) => format!("{}*", element.to_llvm()),
+            // A nullable pointer has the same representation as the pointer itself
+            Type::Nullable(inner) => inner.to_llvm(),
+            Type::Struct(name) => format!("%struct.{}", name),
+            Type::Function(params, return_type) => format!(
+                "{} ({})*",
+                return_type.to_llvm(),
+                params
+                    .iter()
+                    .map(Type::to_llvm)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Type::Any | Type::Unknown | Type::Param(_) => "i32".to_string(),
+        }
+    }
+
+    /// Replace type parameters with the types bound to them
+    pub fn substitute(&self, bindings: &HashMap<String, Type>) -> Type {
+        match self {
+            Type::Param(name) => bindings.get(name).cloned().unwrap_or_else(|| self.clone()),
+            Type::Array(element, size) => {
+                Type::Array(Box::new(element.substitute(bindings)), *size)
+            }
+            Type::Slice(element) => Type::Slice(Box::new(element.substitute(bindings))),
+            Type::Ref(inner, is_mutable) => {
+                Type::Ref(Box::new(inner.substitute(bindings)), *is_mutable)
+            }
+            Type::Nullable(inner) => Type::Nullable(Box::new(inner.substitute(bindings))),
+            Type::Function(params, return_type) => Type::Function(
+                params
+                    .iter()
+                    .map(|param| param.substitute(bindings))
+                    .collect(),
+                Box::new(return_type.substitute(bindings)),
+            ),
+            _ => self.clone(),
+        }
+    }
+
+    /// Match a parameter type against the type of an argument, recording the type each
+    /// parameter stands for. Only generic positions are compared; returns false when a
+    /// parameter is bound to two different types.
+    pub fn bind(&self, argument: &Type, bindings: &mut HashMap<String, Type>) -> bool {
+        match (self, argument) {
+            (Type::Param(name), _) => match bindings.get(name) {
+                Some(bound) => bound == argument,
+                None => {
+                    bindings.insert(name.clone(), argument.clone());
+                    true
+                }
+            },
+            (Type::Array(element, _), Type::Array(arg_element, _))
+            | (Type::Slice(element), Type::Slice(arg_element))
+            | (Type::Ref(element, _), Type::Ref(arg_element, _))
+            | (Type::Nullable(element), Type::Nullable(arg_element)) => {
+                element.bind(arg_element, bindings)
+            }
+            _ => true,
+        }
+    }
+
+    pub fn is_generic(&self) -> bool {
+        self.find_param(&|_| true)
+    }
+
+    pub fn uses_param(&self, name: &str) -> bool {
+        self.find_param(&|param| param == name)
+    }
+
+    fn find_param(&self, matches: &impl Fn(&str) -> bool) -> bool {
+        match self {
+            Type::Param(name) => matches(name),
+            Type::Array(inner, _)
+            | Type::Slice(inner)
+            | Type::Ref(inner, _)
+            | Type::Nullable(inner) => inner.find_param(matches),
+            Type::Function(params, return_type) => {
+                params.iter().any(|param| param.find_param(matches))
+                    || return_type.find_param(matches)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Int(kind) => write!(f, "{}", kind.name()),
+            Type::Float(kind) => write!(f, "{}", kind.name()),
+            Type::Bool => write!(f, "bool"),
+            Type::Char => write!(f, "char"),
+            Type::Str => write!(f, "str"),
+            Type::Void => write!(f, "void"),
+            Type::Array(element, size) => write!(f, "[{}; {}]", element, size),
+            Type::Slice(element) => write!(f, "[{}]", element),
+            Type::Ref(inner, false) => write!(f, "&{}", inner),
+            Type::Ref(inner, true) => write!(f, "&mut {}", inner),
+            Type::Nullable(inner) => write!(f, "?{}", inner),
+            Type::Struct(name) | Type::Param(name) => write!(f, "{}", name),
+            Type::Function(params, return_type) => {
+                let params: Vec<String> = params.iter().map(Type::to_string).collect();
+                write!(f, "fn({}) -> {}", params.join(", "), return_type)
+            }
+            Type::Null => write!(f, "null"),
+            Type::Any => write!(f, "any"),
+            Type::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+// Parses the same syntax `Display` produces, e.g. `&mut [i32; 3]`
+impl FromStr for Type {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Type, String> {
+        let s = s.trim();
+        if let Some(inner) = s.strip_prefix('?') {
+            return Ok(Type::Nullable(Box::new(inner.parse()?)));
+        }
+        if let Some(inner) = s.strip_prefix("&mut ") {
+            return Ok(Type::Ref(Box::new(inner.parse()?), true));
+        }
+        if let Some(inner) = s.strip_prefix('&') {
+            return Ok(Type::Ref(Box::new(inner.parse()?), false));
+        }
+        if let Some(inner) = s.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            // The size follows the last `;` so nested arrays keep their own sizes
+            return match inner.rsplit_once(';') {
+                Some((element, size)) if !element.contains('[') || element.ends_with(']') => {
+                    let size = size
+                        .trim()
+                        .parse()
+                        .map_err(|_| format!("Invalid array size in type '{}'", s))?;
+                    Ok(Type::Array(Box::new(element.parse()?), size))
+                }
+                _ => Ok(Type::Slice(Box::new(inner.parse()?))),
+            };
+        }
+        if let Some(rest) = s.strip_prefix("fn(") {
+            let (params, return_type) = split_function_type(rest)
+                .ok_or_else(|| format!("Invalid function type '{}'", s))?;
+            let params = params
+                .iter()
+                .map(|param| param.parse())
+                .collect::<Result<_, _>>()?;
+            return Ok(Type::Function(params, Box::new(return_type.parse()?)));
+        }
+        if s.is_empty() || !s.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return Err(format!("Invalid type '{}'", s));
+        }
+        Ok(Type::from_name(s))
+    }
+}
+
+// Splits `A, fn(B) -> C) -> R` (the text after `fn(`) into its parameter types and `R`
+fn split_function_type(rest: &str) -> Option<(Vec<&str>, &str)> {
+    let mut depth = 0;
+    let mut params = Vec::new();
+    let mut start = 0;
+    for (i, c) in rest.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ']' => depth -= 1,
+            ',' if depth == 0 => {
+                params.push(&rest[start..i]);
+                start = i + 1;
+            }
+            ')' if depth == 0 => {
+                if !rest[start..i].trim().is_empty() {
+                    params.push(&rest[start..i]);
+                }
+                let return_type = rest[i + 1..].trim_start().strip_prefix("->")?;
+                return Some((params, return_type));
+            }
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display_round_trip() {
+        for text in [
+            "i32",
+            "u64",
+            "f32",
+            "bool",
+            "str",
+            "Point",
+            "&i32",
+            "&mut Point",
+            "?str",
+            "?&mut i32",
+            "[i32; 3]",
+            "[&i32; 3]",
+            "&[i32; 3]",
+            "[[i32; 2]; 4]",
+            "[str]",
+            "&[[u8; 4]]",
+            "fn() -> void",
+            "fn(i32, &str) -> bool",
+            "fn(fn(i32) -> i32, [i32; 2]) -> i64",
+        ] {
+            let ty: Type = text.parse().unwrap();
+            assert_eq!(ty.to_string(), text);
+        }
+    }
+
+    #[test]
+    fn test_parse_builds_structured_types() {
+        assert_eq!("i32".parse::<Type>().unwrap(), Type::I32);
+        assert_eq!(
+            "&mut [f64; 2]".parse::<Type>().unwrap(),
+            Type::Ref(Box::new(Type::Array(Box::new(Type::F64), 2)), true)
+        );
+        assert_eq!(
+            "?&Node".parse::<Type>().unwrap(),
+            Type::Nullable(Box::new(Type::Ref(
+                Box::new(Type::Struct("Node".to_string())),
+                false
+            )))
+        );
+        assert!("[i32; x]".parse::<Type>().is_err());
+        assert!("i32 i64".parse::<Type>().is_err());
+    }
+
+    #[test]
+    fn test_llvm_lowering() {
+        let cases = [
+            ("i8", "i8"),
+            ("u16", "i16"),
+            ("f32", "float"),
+            ("f64", "double"),
+            ("bool", "i1"),
+            ("char", "i8"),
+            ("str", "i8*"),
+            ("void", "void"),
+            ("&i32", "i32*"),
+            ("?&i64", "i64*"),
+            ("[i32; 5]", "[5 x i32]"),
+            ("&[Point; 2]", "[2 x %struct.Point]*"),
+            ("[u8]", "i8*"),
+            ("fn(i32, f64) -> bool", "i1 (i32, double)*"),
+        ];
+        for (text, llvm) in cases {
+            assert_eq!(text.parse::<Type>().unwrap().to_llvm(), llvm, "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_pointee_looks_through_nullable() {
+        let ty: Type = "?&mut char".parse().unwrap();
+        assert_eq!(ty.pointee(), Some(&Type::Char));
+        assert_eq!(Type::Str.pointee(), None);
+    }
+
+    #[test]
+    fn test_bind_and_substitute_type_parameters() {
+        let param = Type::Ref(Box::new(Type::Param("T".to_string())), false);
+        let mut bindings = HashMap::new();
+
+        assert!(param.bind(&"&str".parse().unwrap(), &mut bindings));
+        assert_eq!(bindings["T"], Type::Str);
+        assert!(!Type::Param("T".to_string()).bind(&Type::I32, &mut bindings));
+        assert_eq!(param.substitute(&bindings).to_string(), "&str");
+    }
+}
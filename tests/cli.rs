@@ -0,0 +1,51 @@
+use std::process::Command;
+
+#[test]
+fn test_stats_json_on_stdout_is_the_only_output() {
+    let dir = std::env::temp_dir().join(format!("zen_cli_test_{}_stats", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("main.zen");
+    std::fs::write(&input, "fn main() -> i32 {\n    return 0\n}").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zen"))
+        .arg("compile")
+        .arg(&input)
+        .arg("-o")
+        .arg(dir.join("program"))
+        .args(["--stats-json", "-"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let json: serde_json::Value = serde_json::from_str(&stdout)
+        .unwrap_or_else(|e| panic!("stdout is not JSON ({}):\n{}", e, stdout));
+    assert!(json["tokens_count"].is_u64(), "{}", json);
+}
+
+#[test]
+fn test_bench_uses_the_global_tool_options() {
+    let dir = std::env::temp_dir().join(format!("zen_cli_test_{}_bench", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("main.zen");
+    std::fs::write(&input, "fn main() -> i32 {\n    return 2\n}").unwrap();
+
+    let bench = |extra: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_zen"))
+            .arg("bench")
+            .arg(&input)
+            .args(["-n", "2"])
+            .args(extra)
+            .output()
+            .unwrap()
+    };
+    let output = bench(&[]);
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("(2 iterations)"), "{}", stdout);
+    assert!(stdout.contains("exited with code 2"), "{}", stdout);
+
+    let output = bench(&["--llc", "false"]);
+    assert!(!output.status.success(), "{:?}", output);
+}